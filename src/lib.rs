@@ -1,17 +1,25 @@
 pub mod core;
 pub mod platform;
 
-pub use platform::{generic, windows};
+#[cfg(target_os = "linux")]
+pub use platform::linux;
+#[cfg(all(unix, not(target_os = "macos"), feature = "wayland"))]
+pub use platform::wayland;
+#[cfg(all(windows, feature = "win32"))]
+pub use platform::windows;
+#[cfg(all(unix, not(target_os = "macos"), feature = "x11"))]
+pub use platform::x11;
+pub use platform::{current, generic, headless};
 
 pub static mut PUMPING_MESSAGE_OUTSIDE_OF_MAIN_LOOP: bool = true;
 
-/** Thread ID of the main/game thread */
+// Thread ID of the main/game thread
 //pub static uint32 GAME_THREAD_ID: u32 = ;
 
-/** Thread ID of the render thread, if any */
+// Thread ID of the render thread, if any
 //extern CORE_API uint32 GRenderThreadId;
 
-/** Thread ID of the slate thread, if any */
+// Thread ID of the slate thread, if any
 //extern CORE_API uint32 GSlateLoadingThreadId;
 
 /** @return True if called from the game thread. */
@@ -1,40 +1,23 @@
 use crate::generic::application_message_handler::ApplicationMessageHandler;
+use crate::generic::application_proxy::{ApplicationProxy, ProxyWindowId};
 use crate::generic::cursor::ICursor;
+use crate::generic::edid::Edid;
+use crate::generic::input::Key;
+use crate::generic::platform_event::{
+    forward_to_message_handler, PlatformEvent, PlatformEventQueue,
+};
+use crate::generic::timer::{TimerId, TimerQueue};
 use crate::generic::window::GenericWindow;
-use bitflags::bitflags;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::io;
 use std::rc::Rc;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
-pub static mut DEBUG_SAFE_ZONE_RATIO: f32 = 1.0;
-pub static mut DEBUG_ACTION_ZONE_RATIO: f32 = 1.0;
-
-bitflags! {
-    struct ModifierKey: u8 {
-        const NONE = 0;
-        const CONTROL = 1 << 0;
-        const ALT = 1 << 1;
-        const SHIFT = 1 << 2;
-        const COMMAND = 1 << 3;
-    }
-}
-
-impl ModifierKey {
-    pub fn from_bools(control: bool, alt: bool, shift: bool, command: bool) -> ModifierKey {
-        let mut modifier_mask = ModifierKey::NONE;
-        if control {
-            modifier_mask |= ModifierKey::CONTROL
-        };
-        if alt {
-            modifier_mask |= ModifierKey::ALT
-        };
-        if shift {
-            modifier_mask |= ModifierKey::SHIFT
-        };
-        if command {
-            modifier_mask |= ModifierKey::COMMAND
-        };
-
-        modifier_mask
-    }
+lazy_static! {
+    static ref SAFE_ZONE_SETTINGS: RwLock<SafeZoneSettings> =
+        RwLock::new(SafeZoneSettings::default());
 }
 
 pub enum PopUpOrientation {
@@ -42,9 +25,102 @@ pub enum PopUpOrientation {
     Vertical,
 }
 
-//TODO ModifierKeysState struct
+/**
+ * Which modifier keys are held, telling the left and right keys apart, and whether caps lock is on.
+ * Command is the Windows/Super key everywhere but macOS, where it is the Command key.
+ */
+#[derive(PartialEq, Eq, Default, Debug, Clone, Copy)]
+pub struct ModifierKeysState {
+    pub is_left_shift_down: bool,
+    pub is_right_shift_down: bool,
+    pub is_left_control_down: bool,
+    pub is_right_control_down: bool,
+    pub is_left_alt_down: bool,
+    pub is_right_alt_down: bool,
+    pub is_left_command_down: bool,
+    pub is_right_command_down: bool,
+    pub are_caps_locked: bool,
+}
 
-#[derive(PartialEq, Default, Debug, Clone)]
+impl ModifierKeysState {
+    pub fn is_shift_down(&self) -> bool {
+        self.is_left_shift_down || self.is_right_shift_down
+    }
+    pub fn is_control_down(&self) -> bool {
+        self.is_left_control_down || self.is_right_control_down
+    }
+    pub fn is_alt_down(&self) -> bool {
+        self.is_left_alt_down || self.is_right_alt_down
+    }
+    pub fn is_command_down(&self) -> bool {
+        self.is_left_command_down || self.is_right_command_down
+    }
+    pub fn any_modifiers_down(&self) -> bool {
+        self.is_shift_down()
+            || self.is_control_down()
+            || self.is_alt_down()
+            || self.is_command_down()
+    }
+    /** Records a key going down or up. Keys that are not shift, control, alt or command are ignored. */
+    pub fn set_key_down(&mut self, key: Key, is_down: bool) {
+        match key {
+            Key::ShiftLeft => self.is_left_shift_down = is_down,
+            Key::ShiftRight => self.is_right_shift_down = is_down,
+            Key::ControlLeft => self.is_left_control_down = is_down,
+            Key::ControlRight => self.is_right_control_down = is_down,
+            Key::AltLeft => self.is_left_alt_down = is_down,
+            Key::AltRight => self.is_right_alt_down = is_down,
+            Key::MetaLeft => self.is_left_command_down = is_down,
+            Key::MetaRight => self.is_right_command_down = is_down,
+            _ => {}
+        }
+    }
+    /**
+     * Reconciles the tracked keys with a platform that only reports which modifiers are held, not on which side.
+     * A modifier that is no longer held is released on both sides, and one that went down while the application
+     * was not watching the keyboard is put on the left.
+     */
+    pub fn sync_with(
+        &mut self,
+        is_shift_down: bool,
+        is_control_down: bool,
+        is_alt_down: bool,
+        is_command_down: bool,
+        are_caps_locked: bool,
+    ) {
+        fn sync_pair(is_down: bool, is_left_down: &mut bool, is_right_down: &mut bool) {
+            if !is_down {
+                *is_left_down = false;
+                *is_right_down = false;
+            } else if !*is_left_down && !*is_right_down {
+                *is_left_down = true;
+            }
+        }
+        sync_pair(
+            is_shift_down,
+            &mut self.is_left_shift_down,
+            &mut self.is_right_shift_down,
+        );
+        sync_pair(
+            is_control_down,
+            &mut self.is_left_control_down,
+            &mut self.is_right_control_down,
+        );
+        sync_pair(
+            is_alt_down,
+            &mut self.is_left_alt_down,
+            &mut self.is_right_alt_down,
+        );
+        sync_pair(
+            is_command_down,
+            &mut self.is_left_command_down,
+            &mut self.is_right_command_down,
+        );
+        self.are_caps_locked = are_caps_locked;
+    }
+}
+
+#[derive(PartialEq, Default, Debug, Clone, Copy)]
 pub struct PlatformRect {
     pub left: i32,
     pub top: i32,
@@ -52,6 +128,196 @@ pub struct PlatformRect {
     pub bottom: i32,
 }
 
+impl PlatformRect {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> PlatformRect {
+        PlatformRect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+    pub fn get_width(&self) -> i32 {
+        self.right - self.left
+    }
+    pub fn get_height(&self) -> i32 {
+        self.bottom - self.top
+    }
+    pub fn contains_point(&self, x: i32, y: i32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+    /** The area the two rects have in common, 0 if they do not overlap */
+    pub fn get_intersection_area(&self, other: &PlatformRect) -> i64 {
+        let width = self.right.min(other.right) - self.left.max(other.left);
+        let height = self.bottom.min(other.bottom) - self.top.max(other.top);
+        if width <= 0 || height <= 0 {
+            0
+        } else {
+            width as i64 * height as i64
+        }
+    }
+    /** The part the two rects have in common, None if they do not overlap */
+    pub fn intersect(&self, other: &PlatformRect) -> Option<PlatformRect> {
+        let intersection = PlatformRect {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        };
+        if intersection.left < intersection.right && intersection.top < intersection.bottom {
+            Some(intersection)
+        } else {
+            None
+        }
+    }
+    /** The same rect moved by the offset */
+    pub fn offset(&self, x: i32, y: i32) -> PlatformRect {
+        PlatformRect::new(self.left + x, self.top + y, self.right + x, self.bottom + y)
+    }
+    /** The smallest rect that holds both rects */
+    pub fn union(&self, other: &PlatformRect) -> PlatformRect {
+        PlatformRect {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+    /** The squared distance from the point to the nearest point of the rect, 0 if the point is inside */
+    fn get_distance_squared_to_point(&self, x: i32, y: i32) -> i64 {
+        let dx = (self.left - x).max(x - (self.right - 1)).max(0) as i64;
+        let dy = (self.top - y).max(y - (self.bottom - 1)).max(0) as i64;
+        dx * dx + dy * dy
+    }
+}
+
+/** How the desktop is rotated on a monitor, clockwise from the monitor's native orientation */
+#[derive(PartialEq, Eq, Default, Debug, Clone, Copy)]
+pub enum DisplayRotation {
+    #[default]
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+/**
+ * The two safe zones of a TV. Anything outside the action safe zone may be cut off by overscan, and text should
+ * stay inside the smaller title safe zone to be readable.
+ */
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SafeZoneType {
+    TitleSafe,
+    ActionSafe,
+}
+
+/** How far, in pixels, each edge of a monitor is from the edge of a safe zone */
+#[derive(PartialEq, Default, Debug, Clone, Copy)]
+pub struct SafeZoneInsets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl SafeZoneInsets {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> SafeZoneInsets {
+        SafeZoneInsets {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+    /**
+     * Insets that keep the centered safe_zone_ratio of the rect, the way Unreal's r.DebugSafeZone ratios do.
+     * A ratio of 1 or more leaves the whole rect.
+     */
+    pub fn from_ratio(rect: &PlatformRect, safe_zone_ratio: f32) -> SafeZoneInsets {
+        if safe_zone_ratio >= 1.0 {
+            return SafeZoneInsets::default();
+        }
+        let half_unsafe_ratio = (1.0 - safe_zone_ratio.max(0.0)) * 0.5;
+        let horizontal = rect.get_width() as f32 * half_unsafe_ratio;
+        let vertical = rect.get_height() as f32 * half_unsafe_ratio;
+        SafeZoneInsets::new(horizontal, vertical, horizontal, vertical)
+    }
+    pub fn is_zero(&self) -> bool {
+        *self == SafeZoneInsets::default()
+    }
+    /** The part of the rect inside the insets, rounded to whole pixels */
+    pub fn inset_rect(&self, rect: &PlatformRect) -> PlatformRect {
+        let left = rect.left + self.left.round() as i32;
+        let top = rect.top + self.top.round() as i32;
+        PlatformRect {
+            left,
+            top,
+            right: (rect.right - self.right.round() as i32).max(left),
+            bottom: (rect.bottom - self.bottom.round() as i32).max(top),
+        }
+    }
+}
+
+/** Safe zones of a monitor that replace the ones the ratios give, e.g. measured for a particular TV */
+#[derive(PartialEq, Default, Debug, Clone)]
+pub struct SafeZoneOverride {
+    pub title_safe_insets: Option<SafeZoneInsets>,
+    pub action_safe_insets: Option<SafeZoneInsets>,
+}
+
+/** How the safe zones of every monitor are worked out */
+#[derive(PartialEq, Debug, Clone)]
+pub struct SafeZoneSettings {
+    /** The part of each monitor, from 0 to 1, that is title safe. 1 turns the title safe zone off. */
+    pub title_safe_zone_ratio: f32,
+    /** The part of each monitor, from 0 to 1, that is action safe. 1 turns the action safe zone off. */
+    pub action_safe_zone_ratio: f32,
+    /**
+     * Overrides keyed by MonitorInfo::id for one monitor, or by MonitorInfo::name for every monitor of a model.
+     * An override for the id wins over one for the name.
+     */
+    pub monitor_overrides: HashMap<String, SafeZoneOverride>,
+}
+
+impl Default for SafeZoneSettings {
+    fn default() -> SafeZoneSettings {
+        SafeZoneSettings {
+            title_safe_zone_ratio: 1.0,
+            action_safe_zone_ratio: 1.0,
+            monitor_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl SafeZoneSettings {
+    fn get_override(&self, monitor: &MonitorInfo) -> Option<&SafeZoneOverride> {
+        self.monitor_overrides
+            .get(&monitor.id)
+            .or_else(|| self.monitor_overrides.get(&monitor.name))
+    }
+}
+
+/** The settings the backends use when they build DisplayMetrics */
+pub fn get_safe_zone_settings() -> SafeZoneSettings {
+    SAFE_ZONE_SETTINGS
+        .read()
+        .map_or_else(|err| err.into_inner().clone(), |settings| settings.clone())
+}
+
+/**
+ * Replaces the settings the backends use from now on. DisplayMetrics that were already built keep their safe
+ * zones; call apply_safe_zones on them to update them.
+ */
+pub fn set_safe_zone_settings(settings: SafeZoneSettings) {
+    match SAFE_ZONE_SETTINGS.write() {
+        Ok(mut current_settings) => *current_settings = settings,
+        Err(err) => *err.into_inner() = settings,
+    }
+}
+
+/** The DPI of a monitor at 100% scaling */
+pub const DEFAULT_DPI: i32 = 96;
+
 #[derive(PartialEq, Debug, Clone, Default)]
 pub struct MonitorInfo {
     pub name: String,
@@ -59,6 +325,234 @@ pub struct MonitorInfo {
     pub native_width: i32,
     pub native_height: i32,
     pub is_primary: bool,
+    /** The product name from the monitor's EDID, empty if it has none */
+    pub display_name: String,
+    /** The size of the screen in millimeters, 0 if the monitor does not say */
+    pub physical_width_mm: i32,
+    pub physical_height_mm: i32,
+    /** Where the monitor sits on the virtual desktop, in desktop pixels */
+    pub display_rect: PlatformRect,
+    /** The part of display_rect that is not covered by task bars, docks and panels */
+    pub work_area: PlatformRect,
+    /** The effective DPI of the monitor; DEFAULT_DPI is 100% scaling */
+    pub dpi: i32,
+    /** The refresh rate of the current mode in Hz, 0 if the platform does not say */
+    pub refresh_rate: f32,
+    pub rotation: DisplayRotation,
+    /** The title safe zone, set from SafeZoneSettings when the DisplayMetrics are built */
+    pub title_safe_insets: SafeZoneInsets,
+    pub action_safe_insets: SafeZoneInsets,
+}
+
+impl MonitorInfo {
+    /** The safe zone of the monitor in desktop coordinates */
+    pub fn get_safe_rect(&self, safe_zone_type: SafeZoneType) -> PlatformRect {
+        match safe_zone_type {
+            SafeZoneType::TitleSafe => self.title_safe_insets.inset_rect(&self.display_rect),
+            SafeZoneType::ActionSafe => self.action_safe_insets.inset_rect(&self.display_rect),
+        }
+    }
+    /** The DPI as a scale factor, where 1.0 is 100% scaling */
+    pub fn get_dpi_scale_factor(&self) -> f32 {
+        if self.dpi > 0 {
+            self.dpi as f32 / DEFAULT_DPI as f32
+        } else {
+            1.0
+        }
+    }
+    /** Fills in what the monitor's EDID says about it: native resolution, product name and physical size */
+    pub fn apply_edid(&mut self, edid: &Edid) {
+        if let Some((native_width, native_height)) = edid.get_native_resolution() {
+            self.native_width = native_width;
+            self.native_height = native_height;
+        }
+        self.display_name = edid.monitor_name.clone().unwrap_or_default();
+        let (physical_width_mm, physical_height_mm) =
+            edid.get_physical_size_mm().unwrap_or_default();
+        self.physical_width_mm = physical_width_mm;
+        self.physical_height_mm = physical_height_mm;
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct DisplayMetrics {
+    pub primary_display_width: i32,
+    pub primary_display_height: i32,
+    pub monitor_info: Vec<MonitorInfo>,
+    pub primary_display_work_area_rect: PlatformRect,
+    pub virtual_display_rect: PlatformRect,
+}
+
+impl DisplayMetrics {
+    /**
+     * Builds the metrics from the monitors a backend found. The primary display and its work area come from the
+     * monitor flagged as primary, or the first one if none is, and the virtual display rect holds every monitor.
+     */
+    pub fn from_monitors(monitor_info: Vec<MonitorInfo>) -> DisplayMetrics {
+        let mut display_metrics = DisplayMetrics {
+            monitor_info,
+            ..DisplayMetrics::default()
+        };
+        if let Some(primary_monitor) = display_metrics.get_primary_monitor().cloned() {
+            display_metrics.primary_display_width = primary_monitor.display_rect.get_width();
+            display_metrics.primary_display_height = primary_monitor.display_rect.get_height();
+            display_metrics.primary_display_work_area_rect = primary_monitor.work_area;
+        }
+        display_metrics.virtual_display_rect = display_metrics
+            .monitor_info
+            .iter()
+            .map(|monitor| monitor.display_rect)
+            .reduce(|virtual_rect, display_rect| virtual_rect.union(&display_rect))
+            .unwrap_or_default();
+        display_metrics.apply_default_safe_zones();
+        display_metrics
+    }
+    /** The monitor flagged as primary, or the first monitor if the platform flagged none */
+    pub fn get_primary_monitor(&self) -> Option<&MonitorInfo> {
+        self.monitor_info
+            .iter()
+            .find(|monitor| monitor.is_primary)
+            .or_else(|| self.monitor_info.first())
+    }
+    /** The monitor that holds the point, or the one nearest to it */
+    pub fn get_monitor_for_point(&self, x: i32, y: i32) -> Option<&MonitorInfo> {
+        self.monitor_info
+            .iter()
+            .min_by_key(|monitor| monitor.display_rect.get_distance_squared_to_point(x, y))
+    }
+    /**
+     * The monitor that holds most of the rect. A rect that is on no monitor gets the one nearest to its center,
+     * the way MONITOR_DEFAULTTONEAREST does on Windows.
+     */
+    pub fn get_monitor_for_rect(&self, rect: &PlatformRect) -> Option<&MonitorInfo> {
+        let best_monitor = self
+            .monitor_info
+            .iter()
+            .max_by_key(|monitor| monitor.display_rect.get_intersection_area(rect))
+            .filter(|monitor| monitor.display_rect.get_intersection_area(rect) > 0);
+        best_monitor.or_else(|| {
+            self.get_monitor_for_point(
+                rect.left + rect.get_width() / 2,
+                rect.top + rect.get_height() / 2,
+            )
+        })
+    }
+    /** Compares these metrics with newer ones */
+    pub fn diff(&self, new_display_metrics: &DisplayMetrics) -> DisplayMetricsChange {
+        let find_monitor = |monitor_info: &[MonitorInfo], id: &str| {
+            monitor_info
+                .iter()
+                .find(|monitor| monitor.id == id)
+                .cloned()
+        };
+        let mut change = DisplayMetricsChange::default();
+        for old_monitor in &self.monitor_info {
+            match find_monitor(&new_display_metrics.monitor_info, &old_monitor.id) {
+                Some(new_monitor) if new_monitor != *old_monitor => {
+                    change.changed.push((old_monitor.clone(), new_monitor))
+                }
+                Some(_) => {}
+                None => change.removed.push(old_monitor.clone()),
+            }
+        }
+        change.added = new_display_metrics
+            .monitor_info
+            .iter()
+            .filter(|new_monitor| find_monitor(&self.monitor_info, &new_monitor.id).is_none())
+            .cloned()
+            .collect();
+        change
+    }
+    /** The work area of the monitor that holds most of the rect */
+    pub fn get_work_area_for_rect(&self, rect: &PlatformRect) -> PlatformRect {
+        self.get_monitor_for_rect(rect)
+            .map_or(self.primary_display_work_area_rect, |monitor| {
+                monitor.work_area
+            })
+    }
+    /** Sets the safe zones of every monitor from the settings set with set_safe_zone_settings */
+    pub fn apply_default_safe_zones(&mut self) {
+        self.apply_safe_zones(&get_safe_zone_settings());
+    }
+    /** Sets the safe zones of every monitor: its override if it has one, else the ratios */
+    pub fn apply_safe_zones(&mut self, settings: &SafeZoneSettings) {
+        for monitor in self.monitor_info.iter_mut() {
+            let monitor_override = settings.get_override(monitor).cloned().unwrap_or_default();
+            monitor.title_safe_insets = monitor_override.title_safe_insets.unwrap_or_else(|| {
+                SafeZoneInsets::from_ratio(&monitor.display_rect, settings.title_safe_zone_ratio)
+            });
+            monitor.action_safe_insets = monitor_override.action_safe_insets.unwrap_or_else(|| {
+                SafeZoneInsets::from_ratio(&monitor.display_rect, settings.action_safe_zone_ratio)
+            });
+        }
+    }
+    /**
+     * The part of a window's client area that is inside the safe zone of the monitor the window is mostly on,
+     * in client coordinates. client_rect is the client area in desktop coordinates. A window that is entirely
+     * outside the safe zone gets an empty rect.
+     */
+    pub fn get_window_safe_rect(
+        &self,
+        client_rect: &PlatformRect,
+        safe_zone_type: SafeZoneType,
+    ) -> PlatformRect {
+        let safe_rect = match self.get_monitor_for_rect(client_rect) {
+            Some(monitor) => monitor.get_safe_rect(safe_zone_type),
+            None => *client_rect,
+        };
+        client_rect
+            .intersect(&safe_rect)
+            .map_or_else(PlatformRect::default, |safe_client_rect| {
+                safe_client_rect.offset(-client_rect.left, -client_rect.top)
+            })
+    }
+}
+
+/** What changed between two DisplayMetrics. Monitors are matched by id. */
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct DisplayMetricsChange {
+    /** Monitors that were plugged in or turned on */
+    pub added: Vec<MonitorInfo>,
+    /** Monitors that were unplugged or turned off, as they were before */
+    pub removed: Vec<MonitorInfo>,
+    /** Monitors that moved, changed mode, rotated or changed work area or DPI, as (before, after) */
+    pub changed: Vec<(MonitorInfo, MonitorInfo)>,
+}
+
+impl DisplayMetricsChange {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/**
+ * Before a paced frame is due, GenericApplication::run spins instead of waiting for this long, since OS waits can
+ * wake up a scheduler tick late
+ */
+pub const PACED_SPIN_TIME: Duration = Duration::from_millis(2);
+
+/** How GenericApplication::run waits between calls to its tick callback */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RunMode {
+    /** Never waits: messages are pumped and tick is called as fast as the loop goes, for games that render flat out */
+    Poll,
+    /**
+     * Blocks until an OS event, proxy message or timer arrives, or the timeout passes; None waits for as long as it
+     * takes. Editors and tools idle without using the CPU this way; a timer or timeout keeps a caret blinking.
+     */
+    Wait { timeout: Option<Duration> },
+    /**
+     * Calls tick frames_per_second times a second. Messages are pumped as they arrive while waiting for the next
+     * frame, and the last PACED_SPIN_TIME before it is spun to hit it on time.
+     */
+    Paced { frames_per_second: f32 },
+}
+
+/** What the tick callback of GenericApplication::run wants next */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControlFlow {
+    Continue,
+    Exit,
 }
 
 pub enum WindowTitleAlignment {
@@ -73,9 +567,124 @@ pub trait GenericApplication {
 
     fn set_message_handler(&mut self, in_message_handler: &Rc<dyn ApplicationMessageHandler>);
     fn get_message_handler(&self) -> &Rc<dyn ApplicationMessageHandler>;
+    /** The events read by poll_events that have not been drained or dispatched yet */
+    fn get_event_queue(&self) -> &PlatformEventQueue;
+    /**
+     * Reads the pending OS messages into the event queue without calling the message handler. An error means the
+     * connection to the display server failed; what was read before it stays queued.
+     */
+    fn poll_events(&self, time_delta: f32) -> io::Result<()>;
+    /** Takes every queued event, for game loops that pull events instead of being called back */
+    fn drain_events(&self) -> Vec<PlatformEvent> {
+        self.get_event_queue().drain()
+    }
+    /** The timers set with set_timer that have not fired for the last time */
+    fn get_timer_queue(&self) -> &TimerQueue;
+    /**
+     * Starts a timer that fires once after the duration, or every duration if it repeats. Each expiration is
+     * delivered to on_timer on this thread, and ends wait_for_events.
+     */
+    fn set_timer(&self, duration: Duration, repeat: bool) -> TimerId {
+        self.get_timer_queue().set_timer(duration, repeat)
+    }
+    /**
+     * Stops the timer. Returns false if it already fired for the last time or was cancelled before. An expiration
+     * that poll_events already queued is still delivered.
+     */
+    fn cancel_timer(&self, timer_id: TimerId) -> bool {
+        self.get_timer_queue().cancel_timer(timer_id)
+    }
+    /** The modifier keys as of the last event read by poll_events */
+    fn get_modifier_keys(&self) -> ModifierKeysState;
+    /** The monitors as they were when the application was created */
+    fn get_initial_display_metrics(&self) -> DisplayMetrics;
+    /** The monitors as of the last poll_events. A change is also reported with on_display_metrics_changed. */
+    fn get_display_metrics(&self) -> DisplayMetrics;
     //fn poll_game_device_state(&self, time_delta: f32);
-    fn pump_messages(&self, time_delta: f32);
-    //fn process_deferred_events(&self, time_delta: f32);
+    /** A handle other threads can use to queue window commands and user events for this application */
+    fn create_proxy(&self) -> ApplicationProxy;
+    /** The window a proxy created with the ID, once the next poll_events has made it */
+    fn get_proxy_window(&self, id: ProxyWindowId) -> Option<Rc<Self::Window>>;
+    /**
+     * Blocks until an OS event, proxy message or timer is ready for poll_events, or the timeout passes; None waits
+     * for as long as it takes. Returns whether something is ready. Returns at once if events are already queued.
+     * An error means the wait itself failed, and waiting again will most likely fail the same way.
+     */
+    fn wait_for_events(&self, timeout: Option<Duration>) -> io::Result<bool>;
+    /**
+     * Runs the message loop until tick returns ControlFlow::Exit. Each pass pumps the messages, polls the gamepads and
+     * then calls tick with the seconds since the previous call; the mode decides how the loop waits in between.
+     * Returns the first error of pump_messages or wait_for_events, which ends the loop.
+     */
+    fn run(&self, mode: RunMode, mut tick: impl FnMut(f32) -> ControlFlow) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        let mut last_tick = Instant::now();
+        let mut next_frame = last_tick;
+        loop {
+            match mode {
+                RunMode::Poll => {}
+                RunMode::Wait { timeout } => {
+                    self.wait_for_events(timeout)?;
+                }
+                RunMode::Paced { frames_per_second } => {
+                    let frame_time = Duration::from_secs_f32(1.0 / frames_per_second.max(1.0));
+                    next_frame += frame_time;
+                    let now = Instant::now();
+                    if next_frame < now {
+                        // Too far behind to catch up, so start counting frames again from now
+                        next_frame = now;
+                    }
+                    let spin_start = next_frame - PACED_SPIN_TIME.min(frame_time);
+                    while let Some(timeout) = spin_start.checked_duration_since(Instant::now()) {
+                        if timeout.is_zero() {
+                            break;
+                        }
+                        if self.wait_for_events(Some(timeout))? {
+                            self.pump_messages(0.0)?;
+                        }
+                    }
+                    while Instant::now() < next_frame {
+                        std::hint::spin_loop();
+                    }
+                }
+            }
+            let now = Instant::now();
+            let time_delta = now.duration_since(last_tick).as_secs_f32();
+            last_tick = now;
+            self.pump_messages(time_delta)?;
+            self.poll_game_device_state(time_delta);
+            if tick(time_delta) == ControlFlow::Exit {
+                return Ok(());
+            }
+        }
+    }
+    /**
+     * Polls the gamepads and sends their button, analog and connection events straight to the message handler.
+     * Backends without gamepad support keep the default, which does nothing.
+     */
+    fn poll_game_device_state(&self, _time_delta: f32) {}
+    /** Whether a gamepad was connected when poll_game_device_state last ran */
+    fn is_gamepad_attached(&self) -> bool {
+        false
+    }
+    /**
+     * Reads the pending OS messages and forwards them to the message handler. If reading fails, the events read
+     * before the error are still forwarded and then the error is returned.
+     */
+    fn pump_messages(&self, time_delta: f32) -> io::Result<()> {
+        let result = self.poll_events(time_delta);
+        self.process_deferred_events(time_delta);
+        result
+    }
+    /** Forwards the queued events to the message handler, outside of any OS callback */
+    fn process_deferred_events(&self, _time_delta: f32) {
+        // Events the handler causes while it runs are delivered in this same pass, like PeekMessage would
+        while let Some(event) = self.get_event_queue().pop() {
+            forward_to_message_handler(event, self.get_message_handler());
+        }
+    }
     //fn tick(&self, time_delta: f32);
     //fn make_window(&self) -> Rc<Self::Window>;
 }
@@ -1,7 +1,14 @@
+use crate::generic::application::{DisplayMetrics, DisplayMetricsChange, ModifierKeysState};
+use crate::generic::application_proxy::UserEvent;
+use crate::generic::input::Key;
+use crate::generic::timer::TimerId;
 use crate::generic::window::GenericWindow;
+use crate::generic::window_hit_test::WindowChrome;
+use crate::generic::window_sizing::ResizeEdge;
 use glam::{Vec2, Vec3};
 use std::rc::Rc;
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum MouseButtons {
     Left = 0,
     Middle,
@@ -13,7 +20,9 @@ pub enum MouseButtons {
 }
 
 //I came across a similar implementation in the Github repository https://github.com/coeuvre/hammer-rs.git
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct WindowSizeLimits {
     pub min_width: Option<f32>,
     pub min_height: Option<f32>,
@@ -55,6 +64,7 @@ impl WindowSizeLimits {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct GamepadKeyNames(pub &'static str);
 pub const INVALID: GamepadKeyNames = GamepadKeyNames("");
 pub const LEFT_ANALOG_X: GamepadKeyNames = GamepadKeyNames("Gamepad_LeftX");
@@ -178,12 +188,14 @@ pub const MOTION_CONTROLLER_RIGHT_GRIP1_AXIS: GamepadKeyNames =
 pub const MOTION_CONTROLLER_RIGHT_GRIP2_AXIS: GamepadKeyNames =
     GamepadKeyNames("MotionController_Right_Grip2Axis");
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum WindowActivation {
     Activate = 0,
     ActivateByMouse,
     Deactivate,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum WindowZone {
     NotInWindow,
     TopLeftBorder,
@@ -224,8 +236,23 @@ impl WindowZone {
             WindowZone::SysMenu => 14,
         }
     }
+    /** The edge or corner a border zone resizes, None for the zones that do not resize the window */
+    pub fn get_resize_edge(&self) -> Option<ResizeEdge> {
+        match *self {
+            WindowZone::TopLeftBorder => Some(ResizeEdge::TopLeft),
+            WindowZone::TopBorder => Some(ResizeEdge::Top),
+            WindowZone::TopRightBorder => Some(ResizeEdge::TopRight),
+            WindowZone::LeftBorder => Some(ResizeEdge::Left),
+            WindowZone::RightBorder => Some(ResizeEdge::Right),
+            WindowZone::BottomLeftBorder => Some(ResizeEdge::BottomLeft),
+            WindowZone::BottomBorder => Some(ResizeEdge::Bottom),
+            WindowZone::BottomRightBorder => Some(ResizeEdge::BottomRight),
+            _ => None,
+        }
+    }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum WindowAction {
     ClickedNonClientArea = 1,
     Maximize = 2,
@@ -233,6 +260,7 @@ pub enum WindowAction {
     WindowMenu = 4,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum DropEffect {
     None = 0,
     Copy = 1,
@@ -240,6 +268,7 @@ pub enum DropEffect {
     Link = 3,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum GestureEvent {
     None,
     Scroll,
@@ -252,118 +281,401 @@ pub enum GestureEvent {
 //TODO are all the Rc<GenericWindow> variables the best they can be?
 //NOTE the only implementer of the FGenericApplicationMessageHandler class is FSlateApplication.
 //NOTE SWindow (E:\Devel\study\UnrealEngine\Engine\Source\Runtime\SlateCore\Public\Widgets\SWindow.h) holds a FGenericWindow member variable.
+//NOTE the default bodies match FGenericApplicationMessageHandler, so a handler only implements what it cares about.
+//NOTE the one exception is should_process_user_input_messages, see there.
 pub trait ApplicationMessageHandler {
-    fn should_process_user_input_messages(&self, platform_window: &Rc<dyn GenericWindow>) -> bool;
-    fn on_key_char(&self, character: char, is_repeat: bool) -> bool;
-    fn on_key_down(&self, key_code: i32, character_code: u32, is_repeat: bool) -> bool;
-    fn on_key_up(&self, key_code: i32, character_code: u32, is_repeat: bool) -> bool;
-    fn on_mouse_down(&self, window: &Rc<dyn GenericWindow>, button: MouseButtons) -> bool;
+    /**
+     * Whether input for the window is delivered. Unreal's default answers false and leaves it to FSlateApplication to
+     * say yes; here the default is true, so a handler that only overrides a few callbacks still gets input. Answer
+     * false to drop input, e.g. while a modal window blocks the others.
+     */
+    fn should_process_user_input_messages(&self, _platform_window: &Rc<dyn GenericWindow>) -> bool {
+        true
+    }
+    fn on_key_char(
+        &self,
+        _character: char,
+        _is_repeat: bool,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    fn on_key_down(
+        &self,
+        _key: Key,
+        _character_code: u32,
+        _is_repeat: bool,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    fn on_key_up(
+        &self,
+        _key: Key,
+        _character_code: u32,
+        _is_repeat: bool,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    fn on_mouse_down(
+        &self,
+        _window: &Rc<dyn GenericWindow>,
+        _button: MouseButtons,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
     fn on_mouse_down_with_cursor_pos(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        button: MouseButtons,
-        cursor_pos: Vec2,
-    ) -> bool;
-    fn on_mouse_up(&self, button: MouseButtons) -> bool;
-    fn on_mouse_up_with_cursor_pos(&self, button: MouseButtons, cursor_pos: Vec2) -> bool;
-    fn on_mouse_double_click(&self, window: &Rc<dyn GenericWindow>, button: MouseButtons) -> bool;
+        _window: &Rc<dyn GenericWindow>,
+        _button: MouseButtons,
+        _cursor_pos: Vec2,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    fn on_mouse_up(&self, _button: MouseButtons, _modifier_keys: ModifierKeysState) -> bool {
+        false
+    }
+    fn on_mouse_up_with_cursor_pos(
+        &self,
+        _button: MouseButtons,
+        _cursor_pos: Vec2,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    fn on_mouse_double_click(
+        &self,
+        _window: &Rc<dyn GenericWindow>,
+        _button: MouseButtons,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
     fn on_mouse_double_click_with_cursor_pos(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        button: MouseButtons,
-        cursor_pos: Vec2,
-    ) -> bool;
-    fn on_mouse_wheel(&self, delta: f32) -> bool;
-    fn on_mouse_wheel_with_cursor_pos(&self, delta: f32, cursor_pos: Vec2) -> bool;
-    fn on_mouse_move(&self) -> bool;
-    fn on_raw_mouse_move(&self, x: i32, y: i32) -> bool;
-    fn on_cursor_set(&self) -> bool;
+        _window: &Rc<dyn GenericWindow>,
+        _button: MouseButtons,
+        _cursor_pos: Vec2,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    fn on_mouse_wheel(&self, _delta: f32, _modifier_keys: ModifierKeysState) -> bool {
+        false
+    }
+    fn on_mouse_wheel_with_cursor_pos(
+        &self,
+        _delta: f32,
+        _cursor_pos: Vec2,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    /** Horizontal scrolling from a tilting wheel or a touchpad. A positive delta scrolls right. */
+    fn on_mouse_hwheel(&self, _delta: f32, _modifier_keys: ModifierKeysState) -> bool {
+        false
+    }
+    fn on_mouse_hwheel_with_cursor_pos(
+        &self,
+        _delta: f32,
+        _cursor_pos: Vec2,
+        _modifier_keys: ModifierKeysState,
+    ) -> bool {
+        false
+    }
+    fn on_mouse_move(&self) -> bool {
+        false
+    }
+    /** Unaccelerated mouse motion, summed over the frame. Only sent while high precision mouse mode is on. */
+    fn on_raw_mouse_move(&self, _x: i32, _y: i32) -> bool {
+        false
+    }
+    /** A button change reported by the mouse itself, without cursor position or window */
+    fn on_raw_mouse_button(&self, _button: MouseButtons, _is_pressed: bool) -> bool {
+        false
+    }
+    fn on_cursor_set(&self) -> bool {
+        false
+    }
     fn on_controller_analog(
         &self,
-        key_name: GamepadKeyNames,
-        controller_id: i32,
-        analog_value: f32,
-    ) -> bool;
+        _key_name: GamepadKeyNames,
+        _controller_id: i32,
+        _analog_value: f32,
+    ) -> bool {
+        false
+    }
     fn on_controller_button_pressed(
         &self,
-        key_name: GamepadKeyNames,
-        controller_id: i32,
-        is_repeat: bool,
-    ) -> bool;
+        _key_name: GamepadKeyNames,
+        _controller_id: i32,
+        _is_repeat: bool,
+    ) -> bool {
+        false
+    }
     fn on_controller_button_released(
         &self,
-        key_name: GamepadKeyNames,
-        controller_id: i32,
-        is_repeat: bool,
-    ) -> bool;
-    fn on_begin_gesture(&self);
+        _key_name: GamepadKeyNames,
+        _controller_id: i32,
+        _is_repeat: bool,
+    ) -> bool {
+        false
+    }
+    /** A gamepad was plugged in or went away. The buttons it held have been released by the time it is gone. */
+    fn on_controller_connection_changed(&self, _controller_id: i32, _is_connected: bool) {}
+    fn on_begin_gesture(&self) {}
     fn on_touch_gesture(
         &self,
-        gesture_type: GestureEvent,
-        delta: Vec2,
-        wheel_delta: f32,
-        is_direction_inverted_from_device: bool,
-    ) -> bool;
-    fn on_end_gesture(&self);
+        _gesture_type: GestureEvent,
+        _delta: Vec2,
+        _wheel_delta: f32,
+        _is_direction_inverted_from_device: bool,
+    ) -> bool {
+        false
+    }
+    fn on_end_gesture(&self) {}
     fn on_touch_started(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        location: Vec2,
-        touch_index: i32,
-        controller_id: i32,
-    ) -> bool;
-    fn on_touch_moved(&self, location: Vec2, touch_index: i32, controller_id: i32) -> bool;
-    fn on_touch_ended(&self, location: Vec2, touch_index: i32, controller_id: i32) -> bool;
+        _window: &Rc<dyn GenericWindow>,
+        _location: Vec2,
+        _touch_index: i32,
+        _controller_id: i32,
+    ) -> bool {
+        false
+    }
+    fn on_touch_moved(&self, _location: Vec2, _touch_index: i32, _controller_id: i32) -> bool {
+        false
+    }
+    fn on_touch_ended(&self, _location: Vec2, _touch_index: i32, _controller_id: i32) -> bool {
+        false
+    }
     fn on_motion_detected(
         &self,
-        tilt: Vec3,
-        rotation_rate: Vec3,
-        gravity: Vec3,
-        acceleration: Vec3,
-        controller_id: i32,
-    ) -> bool;
+        _tilt: Vec3,
+        _rotation_rate: Vec3,
+        _gravity: Vec3,
+        _acceleration: Vec3,
+        _controller_id: i32,
+    ) -> bool {
+        false
+    }
     fn on_size_changed(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        width: i32,
-        height: i32,
-        was_minimized: bool,
-    ) -> bool;
-    fn on_os_paint(&self, window: &Rc<dyn GenericWindow>);
-    fn get_size_limits_for_window(&self, window: &Rc<dyn GenericWindow>) -> WindowSizeLimits;
-    fn on_resizing_window(&self, window: &Rc<dyn GenericWindow>);
-    fn begin_reshaping_window(&self, window: &Rc<dyn GenericWindow>) -> bool;
-    fn finished_reshaping_window(&self, window: &Rc<dyn GenericWindow>);
-    fn on_moved_window(&self, window: &Rc<dyn GenericWindow>, x: i32, y: i32);
+        _window: &Rc<dyn GenericWindow>,
+        _width: i32,
+        _height: i32,
+        _was_minimized: bool,
+    ) -> bool {
+        false
+    }
+    fn on_os_paint(&self, _window: &Rc<dyn GenericWindow>) {}
+    fn get_size_limits_for_window(&self, _window: &Rc<dyn GenericWindow>) -> WindowSizeLimits {
+        WindowSizeLimits::default()
+    }
+    fn on_resizing_window(&self, _window: &Rc<dyn GenericWindow>) {}
+    fn begin_reshaping_window(&self, _window: &Rc<dyn GenericWindow>) -> bool {
+        true
+    }
+    fn finished_reshaping_window(&self, _window: &Rc<dyn GenericWindow>) {}
+    fn on_moved_window(&self, _window: &Rc<dyn GenericWindow>, _x: i32, _y: i32) {}
     fn on_window_activation_changed(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        activation_type: WindowActivation,
-    ) -> bool;
-    fn on_application_activation_changed(&self, is_active: bool) -> bool;
-    fn on_convertible_laptop_mode_changed(&self) -> bool;
+        _window: &Rc<dyn GenericWindow>,
+        _activation_type: WindowActivation,
+    ) -> bool {
+        false
+    }
+    fn on_application_activation_changed(&self, _is_active: bool) -> bool {
+        false
+    }
+    fn on_convertible_laptop_mode_changed(&self) -> bool {
+        false
+    }
+    /**
+     * Monitors were added, removed, moved, rotated or changed resolution, work area or DPI. display_metrics is the
+     * new configuration and change says which monitors it affected.
+     */
+    fn on_display_metrics_changed(
+        &self,
+        _display_metrics: &DisplayMetrics,
+        _change: &DisplayMetricsChange,
+    ) {
+    }
+    /**
+     * The zone under a point in client coordinates of a window without an OS border. NotInWindow means the
+     * application has no opinion, and window_hit_test falls back to the window chrome.
+     */
     fn get_window_zone_for_point(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        x: i32,
-        y: i32,
-    ) -> WindowZone;
-    fn on_window_close(&self, window: &Rc<dyn GenericWindow>);
-    fn on_drag_enter_text(&self, window: &Rc<dyn GenericWindow>, text: &String) -> DropEffect;
+        _window: &Rc<dyn GenericWindow>,
+        _x: i32,
+        _y: i32,
+    ) -> WindowZone {
+        WindowZone::NotInWindow
+    }
+    /** Where the application draws the resize border, title bar and caption buttons of a window without an OS border */
+    fn get_window_chrome(&self, _window: &Rc<dyn GenericWindow>) -> WindowChrome {
+        WindowChrome::default()
+    }
+    fn on_window_close(&self, _window: &Rc<dyn GenericWindow>) {}
+    fn on_drag_enter_text(&self, _window: &Rc<dyn GenericWindow>, _text: &String) -> DropEffect {
+        DropEffect::None
+    }
     //Change Vec<String> to Vec<PathBuf> or Vec<OsString>?
     fn on_drag_enter_files(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        files: &Vec<String>,
-    ) -> DropEffect;
+        _window: &Rc<dyn GenericWindow>,
+        _files: &Vec<String>,
+    ) -> DropEffect {
+        DropEffect::None
+    }
     fn on_drag_enter_external(
         &self,
-        window: &Rc<dyn GenericWindow>,
-        text: &String,
-        files: &Vec<String>,
-    ) -> DropEffect;
-    fn on_drag_over(&self, window: &Rc<dyn GenericWindow>) -> DropEffect;
-    fn on_drag_leave(&self, window: &Rc<dyn GenericWindow>);
-    fn on_drag_drop(&self, window: &Rc<dyn GenericWindow>) -> DropEffect;
-    fn on_window_action(&self, window: &Rc<dyn GenericWindow>, action_type: WindowAction) -> bool;
+        _window: &Rc<dyn GenericWindow>,
+        _text: &String,
+        _files: &Vec<String>,
+    ) -> DropEffect {
+        DropEffect::None
+    }
+    fn on_drag_over(&self, _window: &Rc<dyn GenericWindow>) -> DropEffect {
+        DropEffect::None
+    }
+    fn on_drag_leave(&self, _window: &Rc<dyn GenericWindow>) {}
+    fn on_drag_drop(&self, _window: &Rc<dyn GenericWindow>) -> DropEffect {
+        DropEffect::None
+    }
+    fn on_window_action(
+        &self,
+        _window: &Rc<dyn GenericWindow>,
+        _action_type: WindowAction,
+    ) -> bool {
+        true
+    }
+    /** An event another thread sent with ApplicationProxy::send_user_event; downcast it to the type that was sent */
+    fn on_user_event(&self, _event: &UserEvent) {}
+    /** A timer made with GenericApplication::set_timer is due */
+    fn on_timer(&self, _timer_id: TimerId) {}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /** Writes down every callback the tests look at, in order, one line each */
+    #[derive(Default)]
+    pub(crate) struct RecordingMessageHandler {
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl RecordingMessageHandler {
+        /** The calls since the last take_calls */
+        pub(crate) fn take_calls(&self) -> Vec<String> {
+            self.calls.take()
+        }
+    }
+
+    impl ApplicationMessageHandler for RecordingMessageHandler {
+        fn on_key_down(
+            &self,
+            key: Key,
+            character_code: u32,
+            is_repeat: bool,
+            _modifier_keys: ModifierKeysState,
+        ) -> bool {
+            self.calls.borrow_mut().push(format!(
+                "key_down {:?} {} {}",
+                key, character_code, is_repeat
+            ));
+            true
+        }
+        fn on_size_changed(
+            &self,
+            _window: &Rc<dyn GenericWindow>,
+            width: i32,
+            height: i32,
+            was_minimized: bool,
+        ) -> bool {
+            self.calls.borrow_mut().push(format!(
+                "size_changed {} {} {}",
+                width, height, was_minimized
+            ));
+            true
+        }
+        fn on_moved_window(&self, _window: &Rc<dyn GenericWindow>, x: i32, y: i32) {
+            self.calls
+                .borrow_mut()
+                .push(format!("moved_window {} {}", x, y));
+        }
+        fn on_window_activation_changed(
+            &self,
+            _window: &Rc<dyn GenericWindow>,
+            activation_type: WindowActivation,
+        ) -> bool {
+            self.calls
+                .borrow_mut()
+                .push(format!("window_activation_changed {:?}", activation_type));
+            true
+        }
+        fn on_display_metrics_changed(
+            &self,
+            display_metrics: &DisplayMetrics,
+            _change: &DisplayMetricsChange,
+        ) {
+            self.calls.borrow_mut().push(format!(
+                "display_metrics_changed {}x{}",
+                display_metrics.primary_display_width, display_metrics.primary_display_height
+            ));
+        }
+        fn on_user_event(&self, event: &UserEvent) {
+            if let Some(text) = event.downcast_ref::<&str>() {
+                self.calls.borrow_mut().push(format!("user_event {}", text));
+            }
+        }
+        fn on_controller_analog(
+            &self,
+            key_name: GamepadKeyNames,
+            controller_id: i32,
+            analog_value: f32,
+        ) -> bool {
+            self.calls.borrow_mut().push(format!(
+                "analog {} {} {}",
+                key_name.0, controller_id, analog_value
+            ));
+            true
+        }
+        fn on_controller_button_pressed(
+            &self,
+            key_name: GamepadKeyNames,
+            controller_id: i32,
+            is_repeat: bool,
+        ) -> bool {
+            self.calls.borrow_mut().push(format!(
+                "pressed {} {} {}",
+                key_name.0, controller_id, is_repeat
+            ));
+            true
+        }
+        fn on_controller_button_released(
+            &self,
+            key_name: GamepadKeyNames,
+            controller_id: i32,
+            is_repeat: bool,
+        ) -> bool {
+            self.calls.borrow_mut().push(format!(
+                "released {} {} {}",
+                key_name.0, controller_id, is_repeat
+            ));
+            true
+        }
+        fn on_controller_connection_changed(&self, controller_id: i32, is_connected: bool) {
+            self.calls.borrow_mut().push(format!(
+                "connection_changed {} {}",
+                controller_id, is_connected
+            ));
+        }
+    }
 }
@@ -0,0 +1,368 @@
+use crate::generic::platform_event::{PlatformEvent, PlatformEventQueue};
+use crate::generic::window::GenericWindow;
+use crate::generic::window_definition::WindowDefinition;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/** An event another thread sent through an ApplicationProxy. The handler downcasts it to the type that was sent. */
+pub type UserEvent = Arc<dyn Any + Send + Sync>;
+
+/**
+ * Names a window that was created through an ApplicationProxy. Windows are tied to the platform thread, so other
+ * threads refer to them by ID; get_proxy_window on the application turns an ID back into the window.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ProxyWindowId(u64);
+
+/** What an ApplicationProxy asks the platform thread to do with a window */
+#[derive(Clone, Debug)]
+pub enum WindowCommand {
+    Create {
+        id: ProxyWindowId,
+        definition: WindowDefinition,
+        parent: Option<ProxyWindowId>,
+        show_immediately: bool,
+    },
+    Reshape {
+        id: ProxyWindowId,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    SetTitle {
+        id: ProxyWindowId,
+        title: String,
+    },
+    Show {
+        id: ProxyWindowId,
+    },
+    Hide {
+        id: ProxyWindowId,
+    },
+    Destroy {
+        id: ProxyWindowId,
+    },
+}
+
+/** Everything a proxy can send, kept in one queue so the platform thread sees it in the order it was sent */
+enum ProxyMessage {
+    WindowCommand(WindowCommand),
+    UserEvent(UserEvent),
+}
+
+/**
+ * Wakes the platform thread if it is blocked waiting for OS messages. Backends that wait on an OS queue have their
+ * own; the others wait with ProxyReceiver::wait_for_messages, which every send ends by itself.
+ */
+pub trait EventLoopWaker: Send + Sync {
+    fn wake(&self);
+}
+
+/** Returned by an ApplicationProxy once the application it was made by has been dropped */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ApplicationClosedError;
+
+impl fmt::Display for ApplicationClosedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the application the proxy was made by has been dropped")
+    }
+}
+
+impl Error for ApplicationClosedError {}
+
+struct ProxyShared {
+    messages: Mutex<VecDeque<ProxyMessage>>,
+    message_sent: Condvar,
+    waker: Option<Box<dyn EventLoopWaker>>,
+    next_window_id: AtomicU64,
+    is_closed: AtomicBool,
+}
+
+impl ProxyShared {
+    fn send(&self, message: ProxyMessage) -> Result<(), ApplicationClosedError> {
+        {
+            let mut messages = self.messages.lock().unwrap_or_else(|err| err.into_inner());
+            // Checked under the lock, so nothing is queued after the receiver has dropped the queue
+            if self.is_closed.load(Ordering::Acquire) {
+                return Err(ApplicationClosedError);
+            }
+            messages.push_back(message);
+        }
+        self.message_sent.notify_all();
+        if let Some(waker) = &self.waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+/**
+ * A handle other threads use to reach the platform thread. It queues window commands and user events, wakes the
+ * platform thread, and the next poll_events runs them there in the order they were sent. Clone it for each thread.
+ *
+ * A thread that creates a window and wants the message handler to know about it can send a user event carrying the
+ * ProxyWindowId right after; the window exists by the time the event is delivered.
+ */
+#[derive(Clone)]
+pub struct ApplicationProxy {
+    shared: Arc<ProxyShared>,
+}
+
+impl fmt::Debug for ApplicationProxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApplicationProxy")
+            .field("is_closed", &self.is_closed())
+            .finish()
+    }
+}
+
+impl ApplicationProxy {
+    /** Whether the application is gone, in which case everything sent is refused */
+    pub fn is_closed(&self) -> bool {
+        self.shared.is_closed.load(Ordering::Acquire)
+    }
+    /** Creates a window from the definition. parent must also have been created through a proxy. */
+    pub fn create_window(
+        &self,
+        definition: WindowDefinition,
+        parent: Option<ProxyWindowId>,
+        show_immediately: bool,
+    ) -> Result<ProxyWindowId, ApplicationClosedError> {
+        let id = ProxyWindowId(self.shared.next_window_id.fetch_add(1, Ordering::Relaxed));
+        self.send_window_command(WindowCommand::Create {
+            id,
+            definition,
+            parent,
+            show_immediately,
+        })?;
+        Ok(id)
+    }
+    /** Moves and resizes the window; the size is of the client area, like GenericWindow::reshape_window */
+    pub fn reshape_window(
+        &self,
+        id: ProxyWindowId,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<(), ApplicationClosedError> {
+        self.send_window_command(WindowCommand::Reshape {
+            id,
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+    pub fn set_window_title(
+        &self,
+        id: ProxyWindowId,
+        title: &str,
+    ) -> Result<(), ApplicationClosedError> {
+        self.send_window_command(WindowCommand::SetTitle {
+            id,
+            title: title.to_string(),
+        })
+    }
+    pub fn show_window(&self, id: ProxyWindowId) -> Result<(), ApplicationClosedError> {
+        self.send_window_command(WindowCommand::Show { id })
+    }
+    pub fn hide_window(&self, id: ProxyWindowId) -> Result<(), ApplicationClosedError> {
+        self.send_window_command(WindowCommand::Hide { id })
+    }
+    pub fn destroy_window(&self, id: ProxyWindowId) -> Result<(), ApplicationClosedError> {
+        self.send_window_command(WindowCommand::Destroy { id })
+    }
+    pub fn send_window_command(
+        &self,
+        command: WindowCommand,
+    ) -> Result<(), ApplicationClosedError> {
+        self.shared.send(ProxyMessage::WindowCommand(command))
+    }
+    /** Delivers the event to ApplicationMessageHandler::on_user_event on the platform thread */
+    pub fn send_user_event<T: Any + Send + Sync>(
+        &self,
+        event: T,
+    ) -> Result<(), ApplicationClosedError> {
+        self.shared.send(ProxyMessage::UserEvent(Arc::new(event)))
+    }
+}
+
+/**
+ * The platform thread's end of the proxies. Every backend application keeps one, makes its proxies from it, and
+ * calls process_messages from poll_events. It also remembers which window each ProxyWindowId stands for.
+ */
+pub struct ProxyReceiver<W: GenericWindow> {
+    shared: Arc<ProxyShared>,
+    windows: RefCell<HashMap<ProxyWindowId, Rc<W>>>,
+}
+
+impl<W: GenericWindow> fmt::Debug for ProxyReceiver<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyReceiver")
+            .field("num_windows", &self.windows.borrow().len())
+            .finish()
+    }
+}
+
+impl<W: GenericWindow> ProxyReceiver<W> {
+    /** waker wakes the OS queue the backend waits on; None if the backend waits with wait_for_messages */
+    pub fn new(waker: Option<Box<dyn EventLoopWaker>>) -> ProxyReceiver<W> {
+        ProxyReceiver {
+            shared: Arc::new(ProxyShared {
+                messages: Mutex::new(VecDeque::new()),
+                message_sent: Condvar::new(),
+                waker,
+                next_window_id: AtomicU64::new(1),
+                is_closed: AtomicBool::new(false),
+            }),
+            windows: RefCell::new(HashMap::new()),
+        }
+    }
+    pub fn create_proxy(&self) -> ApplicationProxy {
+        ApplicationProxy {
+            shared: self.shared.clone(),
+        }
+    }
+    /** The window a proxy created, None if it was destroyed or the command has not run yet */
+    pub fn get_window(&self, id: ProxyWindowId) -> Option<Rc<W>> {
+        self.windows.borrow().get(&id).cloned()
+    }
+    /** Whether a proxy has sent anything that process_messages has not run yet */
+    pub fn has_pending_messages(&self) -> bool {
+        !self
+            .shared
+            .messages
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .is_empty()
+    }
+    /**
+     * Blocks until a proxy sends something or the timeout passes, None waiting for as long as it takes. Returns
+     * whether there is something to process.
+     */
+    pub fn wait_for_messages(&self, timeout: Option<Duration>) -> bool {
+        let messages = self
+            .shared
+            .messages
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let is_empty = |messages: &mut VecDeque<ProxyMessage>| messages.is_empty();
+        let messages = match timeout {
+            Some(timeout) => {
+                self.shared
+                    .message_sent
+                    .wait_timeout_while(messages, timeout, is_empty)
+                    .unwrap_or_else(|err| err.into_inner())
+                    .0
+            }
+            None => self
+                .shared
+                .message_sent
+                .wait_while(messages, is_empty)
+                .unwrap_or_else(|err| err.into_inner()),
+        };
+        !messages.is_empty()
+    }
+    /**
+     * Runs what the proxies sent, in order. make_window creates a window the way the backend does; user events are
+     * queued for the message handler. Commands for windows that are gone are dropped.
+     */
+    pub fn process_messages(
+        &self,
+        events: &PlatformEventQueue,
+        make_window: impl Fn(&Rc<WindowDefinition>, Option<&Rc<W>>, bool) -> Rc<W>,
+    ) {
+        // Taken all at once, so a proxy is never blocked by a command that runs for long, and commands that send
+        // more messages do not keep the loop going forever
+        let messages = std::mem::take(
+            &mut *self
+                .shared
+                .messages
+                .lock()
+                .unwrap_or_else(|err| err.into_inner()),
+        );
+        for message in messages {
+            match message {
+                ProxyMessage::WindowCommand(command) => {
+                    self.run_window_command(command, &make_window)
+                }
+                ProxyMessage::UserEvent(event) => events.push(PlatformEvent::UserEvent { event }),
+            }
+        }
+    }
+    /** Forgets the windows for which keep returns false, such as the ones the user closed */
+    pub fn retain_windows(&self, mut keep: impl FnMut(&Rc<W>) -> bool) {
+        self.windows.borrow_mut().retain(|_, window| keep(window));
+    }
+    fn run_window_command(
+        &self,
+        command: WindowCommand,
+        make_window: &impl Fn(&Rc<WindowDefinition>, Option<&Rc<W>>, bool) -> Rc<W>,
+    ) {
+        match command {
+            WindowCommand::Create {
+                id,
+                definition,
+                parent,
+                show_immediately,
+            } => {
+                let parent = parent.and_then(|parent| self.get_window(parent));
+                let window = make_window(&Rc::new(definition), parent.as_ref(), show_immediately);
+                self.windows.borrow_mut().insert(id, window);
+            }
+            WindowCommand::Reshape {
+                id,
+                mut x,
+                mut y,
+                mut width,
+                mut height,
+            } => {
+                if let Some(window) = self.get_window(id) {
+                    window.reshape_window(&mut x, &mut y, &mut width, &mut height);
+                }
+            }
+            WindowCommand::SetTitle { id, title } => {
+                if let Some(window) = self.get_window(id) {
+                    window.set_text(&mut title.encode_utf16().collect());
+                }
+            }
+            WindowCommand::Show { id } => {
+                if let Some(window) = self.get_window(id) {
+                    window.show();
+                }
+            }
+            WindowCommand::Hide { id } => {
+                if let Some(window) = self.get_window(id) {
+                    window.hide();
+                }
+            }
+            WindowCommand::Destroy { id } => {
+                let window = self.windows.borrow_mut().remove(&id);
+                if let Some(window) = window {
+                    window.destroy();
+                }
+            }
+        }
+    }
+}
+
+impl<W: GenericWindow> Drop for ProxyReceiver<W> {
+    fn drop(&mut self) {
+        let mut messages = self
+            .shared
+            .messages
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        self.shared.is_closed.store(true, Ordering::Release);
+        messages.clear();
+    }
+}
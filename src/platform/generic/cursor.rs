@@ -135,14 +135,14 @@ pub trait ICursor {
     /**
      * Shows or hides the cursor
      *
-     * @param show	true to show the mouse cursor, false to hide it
+     * @param show true to show the mouse cursor, false to hide it
      */
     fn show(&self, show: bool);
 
     /**
      * Locks the cursor to the passed in bounds
      *
-     * @param bounds	The bounds to lock the cursor to.  Pass None to unlock.
+     * @param bounds The bounds to lock the cursor to.  Pass None to unlock.
      */
     fn lock(&self, bounds: Option<*const Self::Rect>);
 
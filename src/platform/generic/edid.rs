@@ -0,0 +1,816 @@
+//EDID data format documented here:
+//http://en.wikipedia.org/wiki/EDID
+//VESA Enhanced Extended Display Identification Data Standard, Release A, Revision 2 (EDID 1.4)
+//CTA-861-G, section 7 (CTA extension blocks)
+use std::error;
+use std::fmt;
+
+/** Every EDID block is this long, the base block and each extension */
+pub const EDID_BLOCK_SIZE: usize = 128;
+
+const EDID_HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+/** Offsets of the four 18 byte descriptors of the base block */
+const DESCRIPTOR_OFFSETS: [usize; 4] = [54, 72, 90, 108];
+const DESCRIPTOR_SIZE: usize = 18;
+const STANDARD_TIMINGS_OFFSET: usize = 38;
+const EXTENSION_COUNT_OFFSET: usize = 126;
+
+const DISPLAY_SERIAL_NUMBER_TAG: u8 = 0xFF;
+const DISPLAY_NAME_TAG: u8 = 0xFC;
+const STANDARD_TIMINGS_TAG: u8 = 0xFA;
+
+const CTA_EXTENSION_TAG: u8 = 0x02;
+const CTA_VIDEO_DATA_BLOCK: u8 = 2;
+const CTA_VENDOR_SPECIFIC_DATA_BLOCK: u8 = 3;
+/** IEEE OUI of HDMI Licensing, LLC, which identifies the HDMI vendor specific data block */
+const HDMI_OUI: u32 = 0x000C03;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EdidError {
+    /** There is less than one block of data */
+    TooShort(usize),
+    /** The data does not start with 00 FF FF FF FF FF FF 00 */
+    BadHeader,
+    /** The bytes of the base block do not add up to zero */
+    BadChecksum,
+    /** Only EDID 1.x exists in the wild. EDID 2.0 was never adopted and has another layout. */
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for EdidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdidError::TooShort(length) => write!(
+                f,
+                "EDID is {} bytes long, shorter than one {} byte block",
+                length, EDID_BLOCK_SIZE
+            ),
+            EdidError::BadHeader => write!(f, "EDID header is missing"),
+            EdidError::BadChecksum => write!(f, "EDID base block checksum does not match"),
+            EdidError::UnsupportedVersion(version) => {
+                write!(f, "EDID version {} is not supported", version)
+            }
+        }
+    }
+}
+
+impl error::Error for EdidError {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ManufactureDate {
+    /** Week of the year, None when the manufacturer did not say */
+    pub week: Option<u8>,
+    pub year: u16,
+    /** EDID 1.4 lets the year be the model year instead of the year it was made */
+    pub is_model_year: bool,
+}
+
+/** A video mode given in full, as in the descriptors of the base block and the CTA extensions */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DetailedTiming {
+    pub pixel_clock_khz: u32,
+    pub horizontal_active: u16,
+    pub horizontal_blanking: u16,
+    pub vertical_active: u16,
+    pub vertical_blanking: u16,
+    pub horizontal_sync_offset: u16,
+    pub horizontal_sync_pulse_width: u16,
+    pub vertical_sync_offset: u16,
+    pub vertical_sync_pulse_width: u16,
+    /** The size of the picture in millimeters, 0 if unknown */
+    pub width_mm: u16,
+    pub height_mm: u16,
+    pub is_interlaced: bool,
+}
+
+impl DetailedTiming {
+    /** Parses an 18 byte descriptor. Returns None for display descriptors, which have a pixel clock of 0. */
+    fn parse(descriptor: &[u8]) -> Option<DetailedTiming> {
+        let d = descriptor.get(..DESCRIPTOR_SIZE)?;
+        let pixel_clock = u16::from_le_bytes([d[0], d[1]]);
+        if pixel_clock == 0 {
+            return None;
+        }
+        let high_bits = |byte: u8, shift: u32| (byte as u16) << shift;
+        Some(DetailedTiming {
+            pixel_clock_khz: pixel_clock as u32 * 10,
+            horizontal_active: d[2] as u16 | high_bits(d[4] & 0xF0, 4),
+            horizontal_blanking: d[3] as u16 | high_bits(d[4] & 0x0F, 8),
+            vertical_active: d[5] as u16 | high_bits(d[7] & 0xF0, 4),
+            vertical_blanking: d[6] as u16 | high_bits(d[7] & 0x0F, 8),
+            horizontal_sync_offset: d[8] as u16 | high_bits(d[11] & 0xC0, 2),
+            horizontal_sync_pulse_width: d[9] as u16 | high_bits(d[11] & 0x30, 4),
+            vertical_sync_offset: (d[10] >> 4) as u16 | high_bits(d[11] & 0x0C, 2),
+            vertical_sync_pulse_width: (d[10] & 0x0F) as u16 | high_bits(d[11] & 0x03, 4),
+            width_mm: d[12] as u16 | high_bits(d[14] & 0xF0, 4),
+            height_mm: d[13] as u16 | high_bits(d[14] & 0x0F, 8),
+            is_interlaced: d[17] & 0x80 != 0,
+        })
+    }
+    /** Frames per second. An interlaced mode's vertical_active is one field, so this is the field rate. */
+    pub fn get_refresh_rate(&self) -> f32 {
+        let horizontal_total = (self.horizontal_active + self.horizontal_blanking) as f32;
+        let vertical_total = (self.vertical_active + self.vertical_blanking) as f32;
+        if horizontal_total == 0.0 || vertical_total == 0.0 {
+            return 0.0;
+        }
+        self.pixel_clock_khz as f32 * 1000.0 / (horizontal_total * vertical_total)
+    }
+}
+
+/** A video mode given as a resolution and refresh rate, the monitor is expected to use the standard timing for it */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StandardTiming {
+    pub width: u16,
+    pub height: u16,
+    pub refresh_rate: u8,
+}
+
+impl StandardTiming {
+    /** Parses a 2 byte standard timing. 01 01 marks an unused slot. */
+    fn parse(bytes: &[u8], revision: u8) -> Option<StandardTiming> {
+        let (first, second) = match bytes {
+            [0x01, 0x01, ..] | [0x00, 0x00, ..] => return None,
+            [first, second, ..] => (*first, *second),
+            _ => return None,
+        };
+        let width = (first as u16 + 31) * 8;
+        let height = match second >> 6 {
+            // Before EDID 1.3 this code meant 1:1
+            0 if revision < 3 => width,
+            0 => width * 10 / 16,
+            1 => width * 3 / 4,
+            2 => width * 4 / 5,
+            _ => width * 9 / 16,
+        };
+        Some(StandardTiming {
+            width,
+            height,
+            refresh_rate: (second & 0x3F) + 60,
+        })
+    }
+}
+
+/** A mode listed in a CTA extension by its Video Identification Code */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ShortVideoDescriptor {
+    pub vic: u8,
+    /** Whether the monitor marked this mode as one of its native modes */
+    pub is_native: bool,
+}
+
+/** A CTA-861 extension block, which TVs and most HDMI monitors add to describe their video and audio support */
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct CtaExtension {
+    pub revision: u8,
+    pub supports_underscan: bool,
+    pub supports_basic_audio: bool,
+    pub supports_ycbcr_444: bool,
+    pub supports_ycbcr_422: bool,
+    /** How many of the detailed timings, counting from the first, are native modes */
+    pub native_detailed_timing_count: u8,
+    pub video_descriptors: Vec<ShortVideoDescriptor>,
+    /** The source physical address from the HDMI vendor specific data block, only present on HDMI sinks */
+    pub hdmi_physical_address: Option<u16>,
+    pub detailed_timings: Vec<DetailedTiming>,
+}
+
+impl CtaExtension {
+    fn parse(block: &[u8]) -> CtaExtension {
+        let mut extension = CtaExtension {
+            revision: block[1],
+            ..CtaExtension::default()
+        };
+        if extension.revision >= 2 {
+            extension.supports_underscan = block[3] & 0x80 != 0;
+            extension.supports_basic_audio = block[3] & 0x40 != 0;
+            extension.supports_ycbcr_444 = block[3] & 0x20 != 0;
+            extension.supports_ycbcr_422 = block[3] & 0x10 != 0;
+            extension.native_detailed_timing_count = block[3] & 0x0F;
+        }
+
+        // Byte 2 is where the detailed timings start, the data blocks fill the space between it and the header.
+        // 0 means the block has neither, and anything else under 4 is invalid. The last byte is the checksum.
+        let detailed_timings_offset = (block[2] as usize).min(EDID_BLOCK_SIZE - 1);
+        if detailed_timings_offset < 4 {
+            return extension;
+        }
+        if extension.revision >= 3 && detailed_timings_offset > 4 {
+            extension.parse_data_blocks(&block[4..detailed_timings_offset]);
+        }
+        extension.detailed_timings = block[detailed_timings_offset..EDID_BLOCK_SIZE - 1]
+            .chunks_exact(DESCRIPTOR_SIZE)
+            .map_while(DetailedTiming::parse)
+            .collect();
+        extension
+    }
+    fn parse_data_blocks(&mut self, mut data_blocks: &[u8]) {
+        while let Some((&header, rest)) = data_blocks.split_first() {
+            let tag = header >> 5;
+            let length = (header & 0x1F) as usize;
+            // A block that runs past the collection means the block is corrupt, keep what was read so far
+            let payload = match rest.get(..length) {
+                Some(payload) => payload,
+                None => return,
+            };
+            match tag {
+                CTA_VIDEO_DATA_BLOCK => {
+                    self.video_descriptors.extend(payload.iter().map(|&byte| {
+                        // Only VICs 1-64 have a native flag, VICs 193 and up use the top bit as a value bit
+                        if (1..=64).contains(&(byte & 0x7F)) && byte & 0x80 != 0 {
+                            ShortVideoDescriptor {
+                                vic: byte & 0x7F,
+                                is_native: true,
+                            }
+                        } else {
+                            ShortVideoDescriptor {
+                                vic: byte,
+                                is_native: false,
+                            }
+                        }
+                    }));
+                }
+                CTA_VENDOR_SPECIFIC_DATA_BLOCK if payload.len() >= 5 => {
+                    let oui = u32::from_le_bytes([payload[0], payload[1], payload[2], 0]);
+                    if oui == HDMI_OUI {
+                        self.hdmi_physical_address =
+                            Some(u16::from_be_bytes([payload[3], payload[4]]));
+                    }
+                }
+                _ => {}
+            }
+            data_blocks = &rest[length..];
+        }
+    }
+}
+
+/** Everything a monitor says about itself in its EDID */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Edid {
+    pub version: u8,
+    pub revision: u8,
+    /** The three letter PNP ID of the manufacturer, such as DEL or SAM */
+    pub manufacturer_id: String,
+    pub product_code: u16,
+    /** The serial number as a number, None when the monitor leaves it out */
+    pub serial_number: Option<u32>,
+    pub manufacture_date: ManufactureDate,
+    pub is_digital: bool,
+    /** The size of the screen in centimeters from the basic display parameters, 0 if unknown */
+    pub width_cm: u8,
+    pub height_cm: u8,
+    /** The mode the monitor looks best in, the first detailed timing */
+    pub preferred_timing: Option<DetailedTiming>,
+    /** The detailed timings of the base block, preferred timing included */
+    pub detailed_timings: Vec<DetailedTiming>,
+    pub standard_timings: Vec<StandardTiming>,
+    /** The product name from the display name descriptor */
+    pub monitor_name: Option<String>,
+    /** The serial number from the serial number descriptor, which many monitors use instead of serial_number */
+    pub monitor_serial: Option<String>,
+    pub cta_extensions: Vec<CtaExtension>,
+}
+
+impl Edid {
+    /**
+     * Parses an EDID and its CTA-861 extension blocks. The base block must be valid. Extension blocks that
+     * are missing from the data, fail their checksum or are of another kind are skipped.
+     */
+    pub fn parse(bytes: &[u8]) -> Result<Edid, EdidError> {
+        let base = bytes
+            .get(..EDID_BLOCK_SIZE)
+            .ok_or(EdidError::TooShort(bytes.len()))?;
+        if base[..EDID_HEADER.len()] != EDID_HEADER {
+            return Err(EdidError::BadHeader);
+        }
+        if !is_checksum_valid(base) {
+            return Err(EdidError::BadChecksum);
+        }
+        let version = base[18];
+        let revision = base[19];
+        if version != 1 {
+            return Err(EdidError::UnsupportedVersion(version));
+        }
+
+        let mut edid = Edid {
+            version,
+            revision,
+            manufacturer_id: parse_manufacturer_id(u16::from_be_bytes([base[8], base[9]])),
+            product_code: u16::from_le_bytes([base[10], base[11]]),
+            serial_number: match u32::from_le_bytes([base[12], base[13], base[14], base[15]]) {
+                0 => None,
+                serial_number => Some(serial_number),
+            },
+            manufacture_date: ManufactureDate {
+                week: match base[16] {
+                    0 | 0xFF => None,
+                    week => Some(week),
+                },
+                year: 1990 + base[17] as u16,
+                is_model_year: base[16] == 0xFF,
+            },
+            is_digital: base[20] & 0x80 != 0,
+            width_cm: base[21],
+            height_cm: base[22],
+            preferred_timing: None,
+            detailed_timings: vec![],
+            standard_timings: base[STANDARD_TIMINGS_OFFSET..STANDARD_TIMINGS_OFFSET + 16]
+                .chunks_exact(2)
+                .filter_map(|bytes| StandardTiming::parse(bytes, revision))
+                .collect(),
+            monitor_name: None,
+            monitor_serial: None,
+            cta_extensions: vec![],
+        };
+        // In EDID 1.4 both sizes being set is a size, one of them being 0 means the other is an aspect ratio
+        if edid.width_cm == 0 || edid.height_cm == 0 {
+            edid.width_cm = 0;
+            edid.height_cm = 0;
+        }
+
+        for &offset in DESCRIPTOR_OFFSETS.iter() {
+            let descriptor = &base[offset..offset + DESCRIPTOR_SIZE];
+            if let Some(detailed_timing) = DetailedTiming::parse(descriptor) {
+                edid.detailed_timings.push(detailed_timing);
+                continue;
+            }
+            match descriptor[3] {
+                DISPLAY_NAME_TAG => edid.monitor_name = parse_descriptor_text(descriptor),
+                DISPLAY_SERIAL_NUMBER_TAG => {
+                    edid.monitor_serial = parse_descriptor_text(descriptor)
+                }
+                STANDARD_TIMINGS_TAG => edid.standard_timings.extend(
+                    descriptor[5..17]
+                        .chunks_exact(2)
+                        .filter_map(|bytes| StandardTiming::parse(bytes, revision)),
+                ),
+                _ => {}
+            }
+        }
+        edid.preferred_timing = edid.detailed_timings.first().copied();
+
+        let extension_count = base[EXTENSION_COUNT_OFFSET] as usize;
+        edid.cta_extensions = bytes[EDID_BLOCK_SIZE..]
+            .chunks_exact(EDID_BLOCK_SIZE)
+            .take(extension_count)
+            .filter(|block| block[0] == CTA_EXTENSION_TAG && is_checksum_valid(block))
+            .map(CtaExtension::parse)
+            .collect();
+
+        Ok(edid)
+    }
+    /**
+     * The hardware ID Windows gives the monitor, the manufacturer followed by the product code in hex, such as
+     * DEL40A2. It is what MonitorInfo::name holds on Windows.
+     */
+    pub fn get_pnp_id(&self) -> String {
+        format!("{}{:04X}", self.manufacturer_id, self.product_code)
+    }
+    /** The resolution of the preferred timing, which is the panel's native resolution */
+    pub fn get_native_resolution(&self) -> Option<(i32, i32)> {
+        self.preferred_timing.map(|timing| {
+            (
+                timing.horizontal_active as i32,
+                timing.vertical_active as i32,
+            )
+        })
+    }
+    /**
+     * The size of the screen in millimeters. The preferred timing is more precise than the
+     * basic display parameters, which are rounded to centimeters.
+     */
+    pub fn get_physical_size_mm(&self) -> Option<(i32, i32)> {
+        match self.preferred_timing {
+            Some(timing) if timing.width_mm != 0 && timing.height_mm != 0 => {
+                Some((timing.width_mm as i32, timing.height_mm as i32))
+            }
+            _ if self.width_cm != 0 => {
+                Some((self.width_cm as i32 * 10, self.height_cm as i32 * 10))
+            }
+            _ => None,
+        }
+    }
+}
+
+/** The bytes of a block, checksum included, add up to a multiple of 256 */
+fn is_checksum_valid(block: &[u8]) -> bool {
+    block.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) == 0
+}
+
+/** Unpacks the three 5 bit letters of a PNP ID, 1 being A */
+fn parse_manufacturer_id(packed: u16) -> String {
+    [10, 5, 0]
+        .iter()
+        .map(|shift| match ((packed >> shift) & 0x1F) as u8 {
+            letter @ 1..=26 => (b'A' + letter - 1) as char,
+            _ => '?',
+        })
+        .collect()
+}
+
+/** The text of a display descriptor, which ends at a line feed and is padded with spaces */
+fn parse_descriptor_text(descriptor: &[u8]) -> Option<String> {
+    let text = &descriptor[5..DESCRIPTOR_SIZE];
+    let text = match text.iter().position(|&byte| byte == b'\n') {
+        Some(end) => &text[..end],
+        None => text,
+    };
+    // The standard says ASCII, code page 437 is what monitors send in practice. Keep the printable part.
+    let text: String = text
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '?'
+            }
+        })
+        .collect();
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /** EDID 1.3 of a 22 inch 1680x1050 desktop monitor, with a serial number, name and range limits */
+    pub(crate) const EDID_1_3: [u8; 128] = [
+        0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x10, 0xAC, 0x3E, 0xA0, 0x41, 0x30, 0x33,
+        0x30, 0x0C, 0x13, 0x01, 0x03, 0x80, 0x2F, 0x1E, 0x78, 0xEE, 0xEE, 0x91, 0xA3, 0x54, 0x4C,
+        0x99, 0x26, 0x0F, 0x50, 0x54, 0xA5, 0x4B, 0x00, 0xB3, 0x00, 0x81, 0x80, 0x71, 0x4F, 0x95,
+        0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x7C, 0x2E, 0x90, 0xA0, 0x60, 0x1A,
+        0x1E, 0x40, 0x30, 0x20, 0x36, 0x00, 0xD9, 0x28, 0x11, 0x00, 0x00, 0x19, 0x00, 0x00, 0x00,
+        0xFF, 0x00, 0x47, 0x34, 0x33, 0x32, 0x48, 0x39, 0x41, 0x34, 0x33, 0x30, 0x31, 0x4C, 0x0A,
+        0x00, 0x00, 0x00, 0xFC, 0x00, 0x44, 0x45, 0x4C, 0x4C, 0x20, 0x32, 0x32, 0x30, 0x39, 0x57,
+        0x41, 0x0A, 0x20, 0x00, 0x00, 0x00, 0xFD, 0x00, 0x38, 0x4C, 0x1E, 0x53, 0x11, 0x00, 0x0A,
+        0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x52,
+    ];
+
+    /** EDID 1.4 of a 15.6 inch 1920x1080 laptop panel, which gives a model year and no serial number */
+    pub(crate) const EDID_1_4: [u8; 128] = [
+        0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x09, 0xE5, 0x47, 0x07, 0x00, 0x00, 0x00,
+        0x00, 0xFF, 0x1D, 0x01, 0x04, 0xA5, 0x22, 0x13, 0x78, 0x02, 0x5F, 0x05, 0x9D, 0x55, 0x50,
+        0x8E, 0x27, 0x22, 0x1C, 0x50, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x1A, 0x36, 0x80, 0xA0, 0x70, 0x38,
+        0x1F, 0x40, 0x30, 0x20, 0x35, 0x00, 0x58, 0xC2, 0x10, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0xFE, 0x00, 0x42, 0x4F, 0x45, 0x20, 0x48, 0x46, 0x0A, 0x20, 0x20, 0x20,
+        0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0xFE, 0x00, 0x4E, 0x56, 0x31, 0x35, 0x36, 0x46, 0x48,
+        0x4D, 0x2D, 0x4E, 0x34, 0x38, 0x0A, 0x00, 0xC8,
+    ];
+
+    /** A TV with a CTA-861 extension holding video, audio, HDMI and speaker data blocks and two more timings */
+    pub(crate) const EDID_CTA: [u8; 256] = [
+        0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x1E, 0x6D, 0x09, 0x5B, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x1C, 0x01, 0x03, 0x80, 0xA0, 0x5A, 0x78, 0x0A, 0xEE, 0x91, 0xA3, 0x54, 0x4C,
+        0x99, 0x26, 0x0F, 0x50, 0x54, 0x21, 0x08, 0x00, 0x81, 0x40, 0xD1, 0xC0, 0x01, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x3A, 0x80, 0x18, 0x71, 0x38,
+        0x2D, 0x40, 0x58, 0x2C, 0x45, 0x00, 0x40, 0x84, 0x63, 0x00, 0x00, 0x1E, 0x01, 0x1D, 0x00,
+        0x72, 0x51, 0xD0, 0x1E, 0x20, 0x6E, 0x28, 0x55, 0x00, 0x40, 0x84, 0x63, 0x00, 0x00, 0x1E,
+        0x00, 0x00, 0x00, 0xFC, 0x00, 0x4C, 0x47, 0x20, 0x54, 0x56, 0x0A, 0x20, 0x20, 0x20, 0x20,
+        0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0xFD, 0x00, 0x3A, 0x3E, 0x1E, 0x53, 0x10, 0x00, 0x0A,
+        0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x01, 0x20, 0x02, 0x03, 0x1E, 0xF1, 0x49, 0x90, 0x1F,
+        0x04, 0x13, 0x05, 0x14, 0x03, 0x61, 0xC1, 0x23, 0x09, 0x07, 0x07, 0x67, 0x03, 0x0C, 0x00,
+        0x10, 0x00, 0xB8, 0x2D, 0x83, 0x01, 0x00, 0x00, 0x8C, 0x0A, 0xD0, 0x8A, 0x20, 0xE0, 0x2D,
+        0x10, 0x10, 0x3E, 0x96, 0x00, 0x40, 0x84, 0x63, 0x00, 0x00, 0x18, 0x01, 0x1D, 0x80, 0x18,
+        0x71, 0x1C, 0x16, 0x20, 0x58, 0x2C, 0x25, 0x00, 0x40, 0x84, 0x63, 0x00, 0x00, 0x9E, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x3F,
+    ];
+
+    /** Makes the bytes of a block add up to zero again after a test changed it */
+    fn fix_checksum(block: &mut [u8]) {
+        let sum = block[..EDID_BLOCK_SIZE - 1]
+            .iter()
+            .fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        block[EDID_BLOCK_SIZE - 1] = 0u8.wrapping_sub(sum);
+    }
+
+    #[test]
+    fn parses_an_edid_1_3_monitor() {
+        let edid = Edid::parse(&EDID_1_3).unwrap();
+        assert_eq!((edid.version, edid.revision), (1, 3));
+        assert_eq!(edid.manufacturer_id, "DEL");
+        assert_eq!(edid.product_code, 0xA03E);
+        assert_eq!(edid.get_pnp_id(), "DELA03E");
+        assert_eq!(edid.serial_number, Some(0x30333041));
+        assert_eq!(
+            edid.manufacture_date,
+            ManufactureDate {
+                week: Some(12),
+                year: 2009,
+                is_model_year: false,
+            }
+        );
+        assert!(edid.is_digital);
+        assert_eq!((edid.width_cm, edid.height_cm), (47, 30));
+        assert_eq!(edid.monitor_name.as_deref(), Some("DELL 2209WA"));
+        assert_eq!(edid.monitor_serial.as_deref(), Some("G432H9A4301L"));
+
+        assert_eq!(
+            edid.preferred_timing,
+            Some(DetailedTiming {
+                pixel_clock_khz: 119000,
+                horizontal_active: 1680,
+                horizontal_blanking: 160,
+                vertical_active: 1050,
+                vertical_blanking: 30,
+                horizontal_sync_offset: 48,
+                horizontal_sync_pulse_width: 32,
+                vertical_sync_offset: 3,
+                vertical_sync_pulse_width: 6,
+                width_mm: 473,
+                height_mm: 296,
+                is_interlaced: false,
+            })
+        );
+        assert_eq!(edid.detailed_timings.len(), 1);
+        let refresh_rate = edid.preferred_timing.unwrap().get_refresh_rate();
+        assert!((refresh_rate - 59.88).abs() < 0.01, "{}", refresh_rate);
+
+        // The 16:10 code is 1:1 before EDID 1.3 and 16:10 from it on
+        let modes: Vec<(u16, u16, u8)> = edid
+            .standard_timings
+            .iter()
+            .map(|timing| (timing.width, timing.height, timing.refresh_rate))
+            .collect();
+        assert_eq!(
+            modes,
+            [
+                (1680, 1050, 60),
+                (1280, 1024, 60),
+                (1152, 864, 75),
+                (1440, 900, 60)
+            ]
+        );
+
+        assert_eq!(edid.get_native_resolution(), Some((1680, 1050)));
+        assert_eq!(edid.get_physical_size_mm(), Some((473, 296)));
+        assert!(edid.cta_extensions.is_empty());
+    }
+
+    #[test]
+    fn standard_timings_before_edid_1_3_use_square_aspect_ratios() {
+        let mut bytes = EDID_1_3;
+        bytes[19] = 2;
+        fix_checksum(&mut bytes);
+        let edid = Edid::parse(&bytes).unwrap();
+        assert_eq!(edid.revision, 2);
+        assert_eq!(
+            edid.standard_timings[0],
+            StandardTiming {
+                width: 1680,
+                height: 1680,
+                refresh_rate: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_an_edid_1_4_laptop_panel() {
+        let edid = Edid::parse(&EDID_1_4).unwrap();
+        assert_eq!((edid.version, edid.revision), (1, 4));
+        assert_eq!(edid.get_pnp_id(), "BOE0747");
+        assert_eq!(edid.serial_number, None);
+        assert_eq!(
+            edid.manufacture_date,
+            ManufactureDate {
+                week: None,
+                year: 2019,
+                is_model_year: true,
+            }
+        );
+        assert!(edid.is_digital);
+        assert_eq!(edid.monitor_name, None);
+        assert_eq!(edid.monitor_serial, None);
+        assert!(edid.standard_timings.is_empty());
+        // The empty descriptor and the two text descriptors are not timings
+        assert_eq!(edid.detailed_timings.len(), 1);
+
+        let timing = edid.preferred_timing.unwrap();
+        assert_eq!(timing.pixel_clock_khz, 138500);
+        assert!((timing.get_refresh_rate() - 59.93).abs() < 0.01);
+        assert_eq!(edid.get_native_resolution(), Some((1920, 1080)));
+        assert_eq!(edid.get_physical_size_mm(), Some((344, 194)));
+    }
+
+    #[test]
+    fn physical_size_falls_back_to_centimeters() {
+        // Without a size in the preferred timing the basic display parameters are used
+        let mut bytes = EDID_1_4;
+        bytes[54 + 12..54 + 15].fill(0);
+        fix_checksum(&mut bytes);
+        let edid = Edid::parse(&bytes).unwrap();
+        assert_eq!(edid.get_physical_size_mm(), Some((340, 190)));
+
+        // One of them being 0 makes the other an aspect ratio, which is no size at all
+        bytes[22] = 0;
+        fix_checksum(&mut bytes);
+        let edid = Edid::parse(&bytes).unwrap();
+        assert_eq!((edid.width_cm, edid.height_cm), (0, 0));
+        assert_eq!(edid.get_physical_size_mm(), None);
+    }
+
+    #[test]
+    fn parses_a_cta_861_extension() {
+        let edid = Edid::parse(&EDID_CTA).unwrap();
+        assert_eq!(edid.get_pnp_id(), "GSM5B09");
+        assert_eq!(edid.monitor_name.as_deref(), Some("LG TV"));
+        assert_eq!(edid.manufacture_date.year, 2018);
+        assert_eq!(
+            edid.standard_timings,
+            [
+                StandardTiming {
+                    width: 1280,
+                    height: 960,
+                    refresh_rate: 60,
+                },
+                StandardTiming {
+                    width: 1920,
+                    height: 1080,
+                    refresh_rate: 60,
+                },
+            ]
+        );
+        assert_eq!(edid.detailed_timings.len(), 2);
+        assert_eq!(edid.get_native_resolution(), Some((1920, 1080)));
+        assert_eq!(edid.get_physical_size_mm(), Some((1600, 900)));
+
+        assert_eq!(edid.cta_extensions.len(), 1);
+        let extension = &edid.cta_extensions[0];
+        assert_eq!(extension.revision, 3);
+        assert!(extension.supports_underscan);
+        assert!(extension.supports_basic_audio);
+        assert!(extension.supports_ycbcr_444);
+        assert!(extension.supports_ycbcr_422);
+        assert_eq!(extension.native_detailed_timing_count, 1);
+
+        // VIC 16 is flagged native. 193 and up have no flag, their top bit is part of the number.
+        let vics: Vec<(u8, bool)> = extension
+            .video_descriptors
+            .iter()
+            .map(|descriptor| (descriptor.vic, descriptor.is_native))
+            .collect();
+        assert_eq!(
+            vics,
+            [
+                (16, true),
+                (31, false),
+                (4, false),
+                (19, false),
+                (5, false),
+                (20, false),
+                (3, false),
+                (97, false),
+                (193, false)
+            ]
+        );
+        assert_eq!(extension.hdmi_physical_address, Some(0x1000));
+
+        assert_eq!(extension.detailed_timings.len(), 2);
+        let timing = extension.detailed_timings[0];
+        assert_eq!(
+            (timing.horizontal_active, timing.vertical_active),
+            (720, 480)
+        );
+        assert!(!timing.is_interlaced);
+        assert!((timing.get_refresh_rate() - 59.94).abs() < 0.01);
+        let timing = extension.detailed_timings[1];
+        assert_eq!(
+            (timing.horizontal_active, timing.vertical_active),
+            (1920, 540)
+        );
+        assert!(timing.is_interlaced);
+    }
+
+    #[test]
+    fn skips_extensions_that_are_missing_or_corrupt() {
+        // Only the base block is there
+        let edid = Edid::parse(&EDID_CTA[..EDID_BLOCK_SIZE + 64]).unwrap();
+        assert!(edid.cta_extensions.is_empty());
+
+        let mut bytes = EDID_CTA;
+        bytes[EDID_BLOCK_SIZE + 10] ^= 0x01;
+        let edid = Edid::parse(&bytes).unwrap();
+        assert!(edid.cta_extensions.is_empty());
+        assert_eq!(edid.get_pnp_id(), "GSM5B09");
+
+        // The base block says there are no extensions, so the block after it is not read
+        let mut bytes = EDID_CTA;
+        bytes[EXTENSION_COUNT_OFFSET] = 0;
+        fix_checksum(&mut bytes);
+        assert!(Edid::parse(&bytes).unwrap().cta_extensions.is_empty());
+
+        // Another kind of extension
+        let mut bytes = EDID_CTA;
+        bytes[EDID_BLOCK_SIZE] = 0x70;
+        fix_checksum(&mut bytes[EDID_BLOCK_SIZE..]);
+        assert!(Edid::parse(&bytes).unwrap().cta_extensions.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_base_blocks() {
+        assert_eq!(Edid::parse(&[]), Err(EdidError::TooShort(0)));
+        assert_eq!(Edid::parse(&EDID_1_3[..100]), Err(EdidError::TooShort(100)));
+        assert_eq!(
+            Edid::parse(&EDID_1_3[..EDID_BLOCK_SIZE - 1]),
+            Err(EdidError::TooShort(EDID_BLOCK_SIZE - 1))
+        );
+
+        let mut bytes = EDID_1_3;
+        bytes[0] = 0xFF;
+        fix_checksum(&mut bytes);
+        assert_eq!(Edid::parse(&bytes), Err(EdidError::BadHeader));
+
+        let mut bytes = EDID_1_3;
+        bytes[10] = bytes[10].wrapping_add(1);
+        assert_eq!(Edid::parse(&bytes), Err(EdidError::BadChecksum));
+        let mut bytes = EDID_CTA;
+        bytes[EDID_BLOCK_SIZE - 1] ^= 0x80;
+        assert_eq!(Edid::parse(&bytes), Err(EdidError::BadChecksum));
+
+        let mut bytes = EDID_1_4;
+        bytes[18] = 2;
+        fix_checksum(&mut bytes);
+        assert_eq!(Edid::parse(&bytes), Err(EdidError::UnsupportedVersion(2)));
+    }
+
+    /** Runs parse and everything that reads its result, which must not panic whatever the bytes are */
+    fn parse_and_read(bytes: &[u8]) {
+        if let Ok(edid) = Edid::parse(bytes) {
+            edid.get_pnp_id();
+            edid.get_native_resolution();
+            edid.get_physical_size_mm();
+            for timing in edid.detailed_timings.iter().chain(
+                edid.cta_extensions
+                    .iter()
+                    .flat_map(|extension| extension.detailed_timings.iter()),
+            ) {
+                timing.get_refresh_rate();
+            }
+        }
+    }
+
+    #[test]
+    fn parse_never_panics_on_arbitrary_bytes() {
+        // A fixed linear congruential generator, so a failure can be reproduced
+        let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next_byte = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as u8
+        };
+
+        for _ in 0..2000 {
+            let length = next_byte() as usize * 2;
+            let bytes: Vec<u8> = (0..length).map(|_| next_byte()).collect();
+            parse_and_read(&bytes);
+        }
+
+        // Random bytes rarely get past the header and checksum. Keep those valid so the rest is exercised too.
+        for _ in 0..2000 {
+            let num_blocks = 1 + next_byte() as usize % 4;
+            let mut bytes: Vec<u8> = (0..num_blocks * EDID_BLOCK_SIZE)
+                .map(|_| next_byte())
+                .collect();
+            bytes[..EDID_HEADER.len()].copy_from_slice(&EDID_HEADER);
+            bytes[18] = 1;
+            if num_blocks > 1 {
+                bytes[EDID_BLOCK_SIZE] = CTA_EXTENSION_TAG;
+            }
+            for block in bytes.chunks_exact_mut(EDID_BLOCK_SIZE) {
+                fix_checksum(block);
+            }
+            // Cut some short in the middle of an extension
+            let length = bytes.len() - next_byte() as usize % 8 * 16;
+            parse_and_read(&bytes[..length]);
+        }
+
+        // Mutated fixtures, which keep most of their structure
+        for fixture in [&EDID_1_3[..], &EDID_1_4[..], &EDID_CTA[..]] {
+            for _ in 0..2000 {
+                let mut bytes = fixture.to_vec();
+                for _ in 0..1 + next_byte() % 8 {
+                    let index = (next_byte() as usize) << 8 | next_byte() as usize;
+                    let index = index % bytes.len();
+                    bytes[index] = next_byte();
+                }
+                bytes[..EDID_HEADER.len()].copy_from_slice(&EDID_HEADER);
+                bytes[18] = 1;
+                for block in bytes.chunks_exact_mut(EDID_BLOCK_SIZE) {
+                    fix_checksum(block);
+                }
+                parse_and_read(&bytes);
+            }
+        }
+    }
+}
@@ -0,0 +1,692 @@
+use crate::generic::application_message_handler::{self as keys, GamepadKeyNames};
+use crate::generic::platform_event::{PlatformEvent, PlatformEventQueue};
+use std::collections::HashMap;
+
+pub const MAX_NUM_CONTROLLER_BUTTONS: usize = 24;
+
+/** How long a button has to be held before it starts repeating, in seconds */
+pub const DEFAULT_INITIAL_BUTTON_REPEAT_DELAY: f32 = 0.2;
+/** How often a held button repeats after that, in seconds */
+pub const DEFAULT_BUTTON_REPEAT_DELAY: f32 = 0.1;
+/** XINPUT_GAMEPAD_LEFT_THUMB_DEADZONE as a fraction of the full stick range */
+pub const DEFAULT_LEFT_STICK_DEADZONE: f32 = 7849.0 / 32767.0;
+/** XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE as a fraction of the full stick range */
+pub const DEFAULT_RIGHT_STICK_DEADZONE: f32 = 8689.0 / 32767.0;
+/** XINPUT_GAMEPAD_TRIGGER_THRESHOLD as a fraction of the full trigger range */
+pub const DEFAULT_TRIGGER_THRESHOLD: f32 = 30.0 / 255.0;
+
+/**
+ * The buttons of a gamepad, in the order the trackers keep them. The first ten and the d-pad are physical buttons a
+ * backend reports; the trigger thresholds and stick directions are made up from the analog values.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GamepadButton {
+    FaceButtonBottom,
+    FaceButtonRight,
+    FaceButtonLeft,
+    FaceButtonTop,
+    LeftShoulder,
+    RightShoulder,
+    SpecialRight,
+    SpecialLeft,
+    LeftThumb,
+    RightThumb,
+    LeftTriggerThreshold,
+    RightTriggerThreshold,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    LeftStickUp,
+    LeftStickDown,
+    LeftStickLeft,
+    LeftStickRight,
+    RightStickUp,
+    RightStickDown,
+    RightStickLeft,
+    RightStickRight,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; MAX_NUM_CONTROLLER_BUTTONS] = [
+        GamepadButton::FaceButtonBottom,
+        GamepadButton::FaceButtonRight,
+        GamepadButton::FaceButtonLeft,
+        GamepadButton::FaceButtonTop,
+        GamepadButton::LeftShoulder,
+        GamepadButton::RightShoulder,
+        GamepadButton::SpecialRight,
+        GamepadButton::SpecialLeft,
+        GamepadButton::LeftThumb,
+        GamepadButton::RightThumb,
+        GamepadButton::LeftTriggerThreshold,
+        GamepadButton::RightTriggerThreshold,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+        GamepadButton::LeftStickUp,
+        GamepadButton::LeftStickDown,
+        GamepadButton::LeftStickLeft,
+        GamepadButton::LeftStickRight,
+        GamepadButton::RightStickUp,
+        GamepadButton::RightStickDown,
+        GamepadButton::RightStickLeft,
+        GamepadButton::RightStickRight,
+    ];
+    pub fn to_usize(self) -> usize {
+        self as usize
+    }
+    pub fn get_key_name(self) -> GamepadKeyNames {
+        match self {
+            GamepadButton::FaceButtonBottom => keys::FACE_BUTTON_BOTTOM,
+            GamepadButton::FaceButtonRight => keys::FACE_BUTTON_RIGHT,
+            GamepadButton::FaceButtonLeft => keys::FACE_BUTTON_LEFT,
+            GamepadButton::FaceButtonTop => keys::FACE_BUTTON_TOP,
+            GamepadButton::LeftShoulder => keys::LEFT_SHOULDER,
+            GamepadButton::RightShoulder => keys::RIGHT_SHOULDER,
+            GamepadButton::SpecialRight => keys::SPECIAL_RIGHT,
+            GamepadButton::SpecialLeft => keys::SPECIAL_LEFT,
+            GamepadButton::LeftThumb => keys::LEFT_THUMB,
+            GamepadButton::RightThumb => keys::RIGHT_THUMB,
+            GamepadButton::LeftTriggerThreshold => keys::LEFT_TRIGGER_THRESHOLD,
+            GamepadButton::RightTriggerThreshold => keys::RIGHT_TRIGGER_THRESHOLD,
+            GamepadButton::DPadUp => keys::DPAD_UP,
+            GamepadButton::DPadDown => keys::DPAD_DOWN,
+            GamepadButton::DPadLeft => keys::DPAD_LEFT,
+            GamepadButton::DPadRight => keys::DPAD_RIGHT,
+            GamepadButton::LeftStickUp => keys::LEFT_STICK_UP,
+            GamepadButton::LeftStickDown => keys::LEFT_STICK_DOWN,
+            GamepadButton::LeftStickLeft => keys::LEFT_STICK_LEFT,
+            GamepadButton::LeftStickRight => keys::LEFT_STICK_RIGHT,
+            GamepadButton::RightStickUp => keys::RIGHT_STICK_UP,
+            GamepadButton::RightStickDown => keys::RIGHT_STICK_DOWN,
+            GamepadButton::RightStickLeft => keys::RIGHT_STICK_LEFT,
+            GamepadButton::RightStickRight => keys::RIGHT_STICK_RIGHT,
+        }
+    }
+    /** Whether the tracker makes the button up from the analog values, ignoring what the sample says about it */
+    pub fn is_virtual(self) -> bool {
+        matches!(
+            self,
+            GamepadButton::LeftTriggerThreshold
+                | GamepadButton::RightTriggerThreshold
+                | GamepadButton::LeftStickUp
+                | GamepadButton::LeftStickDown
+                | GamepadButton::LeftStickLeft
+                | GamepadButton::LeftStickRight
+                | GamepadButton::RightStickUp
+                | GamepadButton::RightStickDown
+                | GamepadButton::RightStickLeft
+                | GamepadButton::RightStickRight
+        )
+    }
+}
+
+/** The analog inputs of a gamepad, in the order the trackers keep them */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+pub const NUM_GAMEPAD_AXES: usize = 6;
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; NUM_GAMEPAD_AXES] = [
+        GamepadAxis::LeftX,
+        GamepadAxis::LeftY,
+        GamepadAxis::RightX,
+        GamepadAxis::RightY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+    pub fn get_key_name(self) -> GamepadKeyNames {
+        match self {
+            GamepadAxis::LeftX => keys::LEFT_ANALOG_X,
+            GamepadAxis::LeftY => keys::LEFT_ANALOG_Y,
+            GamepadAxis::RightX => keys::RIGHT_ANALOG_X,
+            GamepadAxis::RightY => keys::RIGHT_ANALOG_Y,
+            GamepadAxis::LeftTrigger => keys::LEFT_TRIGGER_ANALOG,
+            GamepadAxis::RightTrigger => keys::RIGHT_TRIGGER_ANALOG,
+        }
+    }
+}
+
+/**
+ * What a gamepad reported in one poll, already converted from the backend's raw format. Sticks go from -1 to 1 with
+ * y growing upward, triggers from 0 to 1.
+ */
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct GamepadSample {
+    /** Indexed by GamepadButton; only the physical buttons are read */
+    pub buttons: [bool; MAX_NUM_CONTROLLER_BUTTONS],
+    /** Indexed by GamepadAxis */
+    pub axes: [f32; NUM_GAMEPAD_AXES],
+}
+
+impl GamepadSample {
+    pub fn new() -> GamepadSample {
+        GamepadSample::default()
+    }
+    pub fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons[button.to_usize()]
+    }
+    pub fn set_button(&mut self, button: GamepadButton, is_pressed: bool) -> &mut GamepadSample {
+        self.buttons[button.to_usize()] = is_pressed;
+        self
+    }
+    pub fn get_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+    pub fn set_axis(&mut self, axis: GamepadAxis, value: f32) -> &mut GamepadSample {
+        self.axes[axis as usize] = value;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ControllerState {
+    /** Last sample's button states, so we only send events on edges */
+    button_states: [bool; MAX_NUM_CONTROLLER_BUTTONS],
+    /** Next time a repeat event should be generated for each button */
+    next_repeat_time: [f64; MAX_NUM_CONTROLLER_BUTTONS],
+    /** Last sample's analog values, so we only send events on changes */
+    axes: [f32; NUM_GAMEPAD_AXES],
+}
+
+impl Default for ControllerState {
+    fn default() -> ControllerState {
+        ControllerState {
+            button_states: [false; MAX_NUM_CONTROLLER_BUTTONS],
+            next_repeat_time: [0.0; MAX_NUM_CONTROLLER_BUTTONS],
+            axes: [0.0; NUM_GAMEPAD_AXES],
+        }
+    }
+}
+
+/**
+ * Turns the samples a backend polls from its gamepads into controller events. It sends a press or release when a
+ * button changes, repeats held buttons, sends the analog values that changed, and makes up the trigger threshold and
+ * stick direction buttons. Time is passed in, so any sequence of samples can be replayed.
+ */
+#[derive(Debug)]
+pub struct GamepadStateTracker {
+    controller_states: HashMap<i32, ControllerState>,
+    pub initial_button_repeat_delay: f32,
+    pub button_repeat_delay: f32,
+    /** How far a left stick axis has to be pushed before its direction button is pressed, from 0 to 1 */
+    pub left_stick_deadzone: f32,
+    pub right_stick_deadzone: f32,
+    /** How far a trigger has to be pulled before its threshold button is pressed, from 0 to 1 */
+    pub trigger_threshold: f32,
+}
+
+impl Default for GamepadStateTracker {
+    fn default() -> GamepadStateTracker {
+        GamepadStateTracker::new()
+    }
+}
+
+impl GamepadStateTracker {
+    pub fn new() -> GamepadStateTracker {
+        GamepadStateTracker {
+            controller_states: HashMap::new(),
+            initial_button_repeat_delay: DEFAULT_INITIAL_BUTTON_REPEAT_DELAY,
+            button_repeat_delay: DEFAULT_BUTTON_REPEAT_DELAY,
+            left_stick_deadzone: DEFAULT_LEFT_STICK_DEADZONE,
+            right_stick_deadzone: DEFAULT_RIGHT_STICK_DEADZONE,
+            trigger_threshold: DEFAULT_TRIGGER_THRESHOLD,
+        }
+    }
+    /** Whether the button was down in the last sample of the controller, virtual buttons included */
+    pub fn is_button_pressed(&self, controller_id: i32, button: GamepadButton) -> bool {
+        self.controller_states
+            .get(&controller_id)
+            .is_some_and(|state| state.button_states[button.to_usize()])
+    }
+    /**
+     * Compares the sample with the last one of the controller and queues the events for what changed: analog values
+     * first, then buttons in GamepadButton order. current_time is in seconds and only has to grow steadily.
+     */
+    pub fn update(
+        &mut self,
+        controller_id: i32,
+        sample: &GamepadSample,
+        current_time: f64,
+        events: &PlatformEventQueue,
+    ) {
+        let button_states = self.get_button_states(sample);
+        let state = self.controller_states.entry(controller_id).or_default();
+
+        for axis in GamepadAxis::ALL {
+            let value = sample.get_axis(axis);
+            if value != state.axes[axis as usize] {
+                state.axes[axis as usize] = value;
+                events.push(PlatformEvent::ControllerAnalog {
+                    key_name: axis.get_key_name(),
+                    controller_id,
+                    analog_value: value,
+                });
+            }
+        }
+
+        for button in GamepadButton::ALL {
+            let index = button.to_usize();
+            let is_pressed = button_states[index];
+            if is_pressed != state.button_states[index] {
+                state.button_states[index] = is_pressed;
+                if is_pressed {
+                    events.push(PlatformEvent::ControllerButtonPressed {
+                        key_name: button.get_key_name(),
+                        controller_id,
+                        is_repeat: false,
+                    });
+                    // This button was pressed - set the button's NextRepeatTime to the InitialButtonRepeatDelay
+                    state.next_repeat_time[index] =
+                        current_time + self.initial_button_repeat_delay as f64;
+                } else {
+                    events.push(PlatformEvent::ControllerButtonReleased {
+                        key_name: button.get_key_name(),
+                        controller_id,
+                        is_repeat: false,
+                    });
+                }
+            } else if is_pressed && state.next_repeat_time[index] <= current_time {
+                events.push(PlatformEvent::ControllerButtonPressed {
+                    key_name: button.get_key_name(),
+                    controller_id,
+                    is_repeat: true,
+                });
+                // Set the button's NextRepeatTime to the ButtonRepeatDelay
+                state.next_repeat_time[index] = current_time + self.button_repeat_delay as f64;
+            }
+        }
+    }
+    /**
+     * Releases everything the controller held and returns its analog values to rest, then forgets it. Call it when
+     * the backend sees the controller go away, so nothing stays pressed.
+     */
+    pub fn disconnect(
+        &mut self,
+        controller_id: i32,
+        current_time: f64,
+        events: &PlatformEventQueue,
+    ) {
+        if self.controller_states.contains_key(&controller_id) {
+            self.update(
+                controller_id,
+                &GamepadSample::default(),
+                current_time,
+                events,
+            );
+            self.controller_states.remove(&controller_id);
+        }
+    }
+    /** The sample's physical buttons together with the virtual buttons made from its analog values */
+    fn get_button_states(&self, sample: &GamepadSample) -> [bool; MAX_NUM_CONTROLLER_BUTTONS] {
+        let mut button_states = sample.buttons;
+        let left_x = sample.get_axis(GamepadAxis::LeftX);
+        let left_y = sample.get_axis(GamepadAxis::LeftY);
+        let right_x = sample.get_axis(GamepadAxis::RightX);
+        let right_y = sample.get_axis(GamepadAxis::RightY);
+        let virtual_buttons = [
+            (
+                GamepadButton::LeftTriggerThreshold,
+                sample.get_axis(GamepadAxis::LeftTrigger) > self.trigger_threshold,
+            ),
+            (
+                GamepadButton::RightTriggerThreshold,
+                sample.get_axis(GamepadAxis::RightTrigger) > self.trigger_threshold,
+            ),
+            (
+                GamepadButton::LeftStickUp,
+                left_y > self.left_stick_deadzone,
+            ),
+            (
+                GamepadButton::LeftStickDown,
+                left_y < -self.left_stick_deadzone,
+            ),
+            (
+                GamepadButton::LeftStickLeft,
+                left_x < -self.left_stick_deadzone,
+            ),
+            (
+                GamepadButton::LeftStickRight,
+                left_x > self.left_stick_deadzone,
+            ),
+            (
+                GamepadButton::RightStickUp,
+                right_y > self.right_stick_deadzone,
+            ),
+            (
+                GamepadButton::RightStickDown,
+                right_y < -self.right_stick_deadzone,
+            ),
+            (
+                GamepadButton::RightStickLeft,
+                right_x < -self.right_stick_deadzone,
+            ),
+            (
+                GamepadButton::RightStickRight,
+                right_x > self.right_stick_deadzone,
+            ),
+        ];
+        for (button, is_pressed) in virtual_buttons {
+            button_states[button.to_usize()] = is_pressed;
+        }
+        button_states
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /** The parts of a controller event the tracker decides, so whole sequences can be compared */
+    #[derive(PartialEq, Debug)]
+    enum ControllerEvent {
+        Analog(GamepadKeyNames, i32, f32),
+        Pressed(GamepadKeyNames, i32, bool),
+        Released(GamepadKeyNames, i32, bool),
+    }
+    use ControllerEvent::{Analog, Pressed, Released};
+
+    fn take_events(events: &PlatformEventQueue) -> Vec<ControllerEvent> {
+        events
+            .drain()
+            .into_iter()
+            .map(|event| match event {
+                PlatformEvent::ControllerAnalog {
+                    key_name,
+                    controller_id,
+                    analog_value,
+                } => Analog(key_name, controller_id, analog_value),
+                PlatformEvent::ControllerButtonPressed {
+                    key_name,
+                    controller_id,
+                    is_repeat,
+                } => Pressed(key_name, controller_id, is_repeat),
+                PlatformEvent::ControllerButtonReleased {
+                    key_name,
+                    controller_id,
+                    is_repeat,
+                } => Released(key_name, controller_id, is_repeat),
+                _ => panic!("the tracker queued an event that is not a controller event"),
+            })
+            .collect()
+    }
+
+    /** A tracker with limits that floats hold exactly, so samples can sit right on them */
+    fn make_tracker() -> GamepadStateTracker {
+        GamepadStateTracker {
+            initial_button_repeat_delay: 0.25,
+            button_repeat_delay: 0.125,
+            left_stick_deadzone: 0.25,
+            right_stick_deadzone: 0.5,
+            trigger_threshold: 0.125,
+            ..GamepadStateTracker::new()
+        }
+    }
+
+    #[test]
+    fn sends_presses_and_releases_on_edges() {
+        let events = PlatformEventQueue::new();
+        let mut tracker = make_tracker();
+        let mut sample = GamepadSample::new();
+        sample
+            .set_button(GamepadButton::DPadUp, true)
+            .set_button(GamepadButton::FaceButtonBottom, true);
+
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Pressed(keys::FACE_BUTTON_BOTTOM, 0, false),
+                Pressed(keys::DPAD_UP, 0, false),
+            ]
+        );
+        assert!(tracker.is_button_pressed(0, GamepadButton::DPadUp));
+
+        tracker.update(0, &sample, 0.1, &events);
+        assert_eq!(take_events(&events), []);
+
+        sample.set_button(GamepadButton::FaceButtonBottom, false);
+        tracker.update(0, &sample, 0.2, &events);
+        assert_eq!(
+            take_events(&events),
+            [Released(keys::FACE_BUTTON_BOTTOM, 0, false)]
+        );
+        assert!(!tracker.is_button_pressed(0, GamepadButton::FaceButtonBottom));
+
+        // Every controller has its own state
+        tracker.update(3, &sample, 0.2, &events);
+        assert_eq!(take_events(&events), [Pressed(keys::DPAD_UP, 3, false)]);
+        assert!(!tracker.is_button_pressed(1, GamepadButton::DPadUp));
+    }
+
+    #[test]
+    fn repeats_held_buttons() {
+        let events = PlatformEventQueue::new();
+        let mut tracker = make_tracker();
+        let mut sample = GamepadSample::new();
+        sample.set_button(GamepadButton::RightShoulder, true);
+
+        tracker.update(0, &sample, 1.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [Pressed(keys::RIGHT_SHOULDER, 0, false)]
+        );
+
+        // The first repeat comes after initial_button_repeat_delay, the next ones every button_repeat_delay
+        let expected_repeats = [
+            (1.2, false),
+            (1.25, true),
+            (1.3, false),
+            (1.375, true),
+            (1.45, false),
+            (1.5, true),
+        ];
+        for (time, is_repeated) in expected_repeats {
+            tracker.update(0, &sample, time, &events);
+            let expected: &[ControllerEvent] = if is_repeated {
+                &[Pressed(keys::RIGHT_SHOULDER, 0, true)]
+            } else {
+                &[]
+            };
+            assert_eq!(take_events(&events), expected, "at {}", time);
+        }
+
+        // A long gap gives one repeat, not one for every delay that passed
+        tracker.update(0, &sample, 3.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [Pressed(keys::RIGHT_SHOULDER, 0, true)]
+        );
+
+        // Pressing again starts over from the initial delay
+        sample.set_button(GamepadButton::RightShoulder, false);
+        tracker.update(0, &sample, 3.5, &events);
+        assert_eq!(
+            take_events(&events),
+            [Released(keys::RIGHT_SHOULDER, 0, false)]
+        );
+        sample.set_button(GamepadButton::RightShoulder, true);
+        tracker.update(0, &sample, 4.0, &events);
+        tracker.update(0, &sample, 4.125, &events);
+        assert_eq!(
+            take_events(&events),
+            [Pressed(keys::RIGHT_SHOULDER, 0, false)]
+        );
+        tracker.update(0, &sample, 4.25, &events);
+        assert_eq!(
+            take_events(&events),
+            [Pressed(keys::RIGHT_SHOULDER, 0, true)]
+        );
+    }
+
+    #[test]
+    fn sends_only_the_axes_that_changed() {
+        let events = PlatformEventQueue::new();
+        let mut tracker = make_tracker();
+        let mut sample = GamepadSample::new();
+
+        // A controller at rest has nothing to say
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(take_events(&events), []);
+
+        sample.set_axis(GamepadAxis::LeftX, 0.125);
+        tracker.update(0, &sample, 0.1, &events);
+        assert_eq!(
+            take_events(&events),
+            [Analog(keys::LEFT_ANALOG_X, 0, 0.125)]
+        );
+
+        tracker.update(0, &sample, 0.2, &events);
+        assert_eq!(take_events(&events), []);
+
+        sample
+            .set_axis(GamepadAxis::RightY, -0.0625)
+            .set_axis(GamepadAxis::RightTrigger, 0.0625);
+        tracker.update(0, &sample, 0.3, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::RIGHT_ANALOG_Y, 0, -0.0625),
+                Analog(keys::RIGHT_TRIGGER_ANALOG, 0, 0.0625),
+            ]
+        );
+
+        sample.set_axis(GamepadAxis::LeftX, 0.0);
+        tracker.update(0, &sample, 0.4, &events);
+        assert_eq!(take_events(&events), [Analog(keys::LEFT_ANALOG_X, 0, 0.0)]);
+    }
+
+    #[test]
+    fn makes_up_stick_and_trigger_buttons_past_the_deadzone() {
+        let events = PlatformEventQueue::new();
+        let mut tracker = make_tracker();
+        let mut sample = GamepadSample::new();
+
+        // What the sample says about a virtual button is ignored
+        sample.set_button(GamepadButton::LeftStickUp, true);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(take_events(&events), []);
+
+        // Right at the deadzone is still inside it
+        sample.set_axis(GamepadAxis::LeftY, 0.25);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(take_events(&events), [Analog(keys::LEFT_ANALOG_Y, 0, 0.25)]);
+
+        sample.set_axis(GamepadAxis::LeftY, 0.3);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::LEFT_ANALOG_Y, 0, 0.3),
+                Pressed(keys::LEFT_STICK_UP, 0, false),
+            ]
+        );
+
+        // Flicking the stick over releases one direction and presses the other
+        sample.set_axis(GamepadAxis::LeftY, -1.0);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::LEFT_ANALOG_Y, 0, -1.0),
+                Released(keys::LEFT_STICK_UP, 0, false),
+                Pressed(keys::LEFT_STICK_DOWN, 0, false),
+            ]
+        );
+
+        // The right stick has its own deadzone
+        sample
+            .set_axis(GamepadAxis::RightX, -0.5)
+            .set_axis(GamepadAxis::RightY, 0.3);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::RIGHT_ANALOG_X, 0, -0.5),
+                Analog(keys::RIGHT_ANALOG_Y, 0, 0.3),
+            ]
+        );
+        sample.set_axis(GamepadAxis::RightX, -0.75);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::RIGHT_ANALOG_X, 0, -0.75),
+                Pressed(keys::RIGHT_STICK_LEFT, 0, false),
+            ]
+        );
+
+        sample
+            .set_axis(GamepadAxis::LeftTrigger, 0.125)
+            .set_axis(GamepadAxis::RightTrigger, 0.5);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::LEFT_TRIGGER_ANALOG, 0, 0.125),
+                Analog(keys::RIGHT_TRIGGER_ANALOG, 0, 0.5),
+                Pressed(keys::RIGHT_TRIGGER_THRESHOLD, 0, false),
+            ]
+        );
+        sample.set_axis(GamepadAxis::LeftTrigger, 0.25);
+        tracker.update(0, &sample, 0.0, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::LEFT_TRIGGER_ANALOG, 0, 0.25),
+                Pressed(keys::LEFT_TRIGGER_THRESHOLD, 0, false),
+            ]
+        );
+        assert!(tracker.is_button_pressed(0, GamepadButton::LeftTriggerThreshold));
+    }
+
+    #[test]
+    fn disconnect_releases_buttons_and_zeroes_axes() {
+        let events = PlatformEventQueue::new();
+        let mut tracker = make_tracker();
+        let mut sample = GamepadSample::new();
+        sample
+            .set_button(GamepadButton::FaceButtonBottom, true)
+            .set_axis(GamepadAxis::LeftX, -1.0)
+            .set_axis(GamepadAxis::LeftTrigger, 1.0);
+        tracker.update(2, &sample, 0.0, &events);
+        let pressed = [
+            Analog(keys::LEFT_ANALOG_X, 2, -1.0),
+            Analog(keys::LEFT_TRIGGER_ANALOG, 2, 1.0),
+            Pressed(keys::FACE_BUTTON_BOTTOM, 2, false),
+            Pressed(keys::LEFT_TRIGGER_THRESHOLD, 2, false),
+            Pressed(keys::LEFT_STICK_LEFT, 2, false),
+        ];
+        assert_eq!(take_events(&events), pressed);
+
+        tracker.disconnect(2, 0.5, &events);
+        assert_eq!(
+            take_events(&events),
+            [
+                Analog(keys::LEFT_ANALOG_X, 2, 0.0),
+                Analog(keys::LEFT_TRIGGER_ANALOG, 2, 0.0),
+                Released(keys::FACE_BUTTON_BOTTOM, 2, false),
+                Released(keys::LEFT_TRIGGER_THRESHOLD, 2, false),
+                Released(keys::LEFT_STICK_LEFT, 2, false),
+            ]
+        );
+        assert!(!tracker.is_button_pressed(2, GamepadButton::FaceButtonBottom));
+
+        // It is forgotten, so a second disconnect says nothing and coming back presses everything again
+        tracker.disconnect(2, 0.6, &events);
+        tracker.disconnect(0, 0.6, &events);
+        assert_eq!(take_events(&events), []);
+        tracker.update(2, &sample, 0.7, &events);
+        assert_eq!(take_events(&events), pressed);
+    }
+}
@@ -7,6 +7,7 @@ pub enum ForceFeedbackChannelType {
     RightSmall,
 }
 
+#[allow(dead_code)]
 pub struct ForceFeedbackValues {
     left_large: f32,
     left_small: f32,
@@ -14,6 +15,12 @@ pub struct ForceFeedbackValues {
     right_small: f32,
 }
 
+impl Default for ForceFeedbackValues {
+    fn default() -> ForceFeedbackValues {
+        ForceFeedbackValues::new()
+    }
+}
+
 impl ForceFeedbackValues {
     pub fn new() -> ForceFeedbackValues {
         ForceFeedbackValues {
@@ -25,6 +32,7 @@ impl ForceFeedbackValues {
     }
 }
 
+#[allow(dead_code)]
 pub struct HapticFeedbackBuffer {
     raw_data: Vec<u8>,
     current_ptr: u32,
@@ -35,6 +43,12 @@ pub struct HapticFeedbackBuffer {
     scale_factor: f32,
 }
 
+impl Default for HapticFeedbackBuffer {
+    fn default() -> HapticFeedbackBuffer {
+        HapticFeedbackBuffer::new()
+    }
+}
+
 impl HapticFeedbackBuffer {
     pub fn new() -> HapticFeedbackBuffer {
         HapticFeedbackBuffer {
@@ -52,12 +66,19 @@ impl HapticFeedbackBuffer {
     }
 }
 
+#[allow(dead_code)]
 pub struct HapticFeedbackValues {
     frequency: f32,
     amplitude: f32,
     haptic_buffer: *mut HapticFeedbackBuffer,
 }
 
+impl Default for HapticFeedbackValues {
+    fn default() -> HapticFeedbackValues {
+        HapticFeedbackValues::new()
+    }
+}
+
 impl HapticFeedbackValues {
     pub fn new() -> HapticFeedbackValues {
         HapticFeedbackValues {
@@ -68,20 +89,8 @@ impl HapticFeedbackValues {
     }
     pub fn from_freq_and_amplitude(in_frequency: f32, in_amplitude: f32) -> HapticFeedbackValues {
         HapticFeedbackValues {
-            frequency: if in_frequency < 0.0f32 {
-                0.0f32
-            } else if in_frequency > 1.0f32 {
-                1.0f32
-            } else {
-                in_frequency
-            },
-            amplitude: if in_amplitude < 0.0f32 {
-                0.0f32
-            } else if in_amplitude > 1.0f32 {
-                1.0f32
-            } else {
-                in_amplitude
-            },
+            frequency: in_frequency.clamp(0.0f32, 1.0f32),
+            amplitude: in_amplitude.clamp(0.0f32, 1.0f32),
             haptic_buffer: ptr::null_mut(),
         }
     }
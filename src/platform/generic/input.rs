@@ -0,0 +1,378 @@
+/**
+ * A physical key, named after what it types on a US layout. The same key reports the same Key whatever layout is
+ * active, which is what hotkeys and game controls want; the character it types comes with on_key_char instead.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Key {
+    Unknown,
+
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+
+    Backquote,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Digit0,
+    Minus,
+    Equal,
+    Backspace,
+    Tab,
+    KeyQ,
+    KeyW,
+    KeyE,
+    KeyR,
+    KeyT,
+    KeyY,
+    KeyU,
+    KeyI,
+    KeyO,
+    KeyP,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    CapsLock,
+    KeyA,
+    KeyS,
+    KeyD,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyJ,
+    KeyK,
+    KeyL,
+    Semicolon,
+    Quote,
+    Enter,
+    ShiftLeft,
+    IntlBackslash,
+    KeyZ,
+    KeyX,
+    KeyC,
+    KeyV,
+    KeyB,
+    KeyN,
+    KeyM,
+    Comma,
+    Period,
+    Slash,
+    ShiftRight,
+    ControlLeft,
+    MetaLeft,
+    AltLeft,
+    Space,
+    AltRight,
+    MetaRight,
+    ContextMenu,
+    ControlRight,
+
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+
+    NumLock,
+    NumpadDivide,
+    NumpadMultiply,
+    NumpadSubtract,
+    NumpadAdd,
+    NumpadEnter,
+    NumpadDecimal,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+}
+
+/**
+ * PC/AT (set 1) scan codes. Keys sent with an 0xE0 or 0xE1 prefix keep the prefix in the high byte,
+ * so Right Control is 0xE01D and Pause is 0xE11D.
+ */
+const SCAN_CODES: [(u32, Key); 105] = [
+    (0x01, Key::Escape),
+    (0x02, Key::Digit1),
+    (0x03, Key::Digit2),
+    (0x04, Key::Digit3),
+    (0x05, Key::Digit4),
+    (0x06, Key::Digit5),
+    (0x07, Key::Digit6),
+    (0x08, Key::Digit7),
+    (0x09, Key::Digit8),
+    (0x0A, Key::Digit9),
+    (0x0B, Key::Digit0),
+    (0x0C, Key::Minus),
+    (0x0D, Key::Equal),
+    (0x0E, Key::Backspace),
+    (0x0F, Key::Tab),
+    (0x10, Key::KeyQ),
+    (0x11, Key::KeyW),
+    (0x12, Key::KeyE),
+    (0x13, Key::KeyR),
+    (0x14, Key::KeyT),
+    (0x15, Key::KeyY),
+    (0x16, Key::KeyU),
+    (0x17, Key::KeyI),
+    (0x18, Key::KeyO),
+    (0x19, Key::KeyP),
+    (0x1A, Key::BracketLeft),
+    (0x1B, Key::BracketRight),
+    (0x1C, Key::Enter),
+    (0x1D, Key::ControlLeft),
+    (0x1E, Key::KeyA),
+    (0x1F, Key::KeyS),
+    (0x20, Key::KeyD),
+    (0x21, Key::KeyF),
+    (0x22, Key::KeyG),
+    (0x23, Key::KeyH),
+    (0x24, Key::KeyJ),
+    (0x25, Key::KeyK),
+    (0x26, Key::KeyL),
+    (0x27, Key::Semicolon),
+    (0x28, Key::Quote),
+    (0x29, Key::Backquote),
+    (0x2A, Key::ShiftLeft),
+    (0x2B, Key::Backslash),
+    (0x2C, Key::KeyZ),
+    (0x2D, Key::KeyX),
+    (0x2E, Key::KeyC),
+    (0x2F, Key::KeyV),
+    (0x30, Key::KeyB),
+    (0x31, Key::KeyN),
+    (0x32, Key::KeyM),
+    (0x33, Key::Comma),
+    (0x34, Key::Period),
+    (0x35, Key::Slash),
+    (0x36, Key::ShiftRight),
+    (0x37, Key::NumpadMultiply),
+    (0x38, Key::AltLeft),
+    (0x39, Key::Space),
+    (0x3A, Key::CapsLock),
+    (0x3B, Key::F1),
+    (0x3C, Key::F2),
+    (0x3D, Key::F3),
+    (0x3E, Key::F4),
+    (0x3F, Key::F5),
+    (0x40, Key::F6),
+    (0x41, Key::F7),
+    (0x42, Key::F8),
+    (0x43, Key::F9),
+    (0x44, Key::F10),
+    (0x45, Key::NumLock),
+    (0x46, Key::ScrollLock),
+    (0x47, Key::Numpad7),
+    (0x48, Key::Numpad8),
+    (0x49, Key::Numpad9),
+    (0x4A, Key::NumpadSubtract),
+    (0x4B, Key::Numpad4),
+    (0x4C, Key::Numpad5),
+    (0x4D, Key::Numpad6),
+    (0x4E, Key::NumpadAdd),
+    (0x4F, Key::Numpad1),
+    (0x50, Key::Numpad2),
+    (0x51, Key::Numpad3),
+    (0x52, Key::Numpad0),
+    (0x53, Key::NumpadDecimal),
+    (0x56, Key::IntlBackslash),
+    (0x57, Key::F11),
+    (0x58, Key::F12),
+    (0xE01C, Key::NumpadEnter),
+    (0xE01D, Key::ControlRight),
+    (0xE035, Key::NumpadDivide),
+    (0xE037, Key::PrintScreen),
+    (0xE038, Key::AltRight),
+    (0xE047, Key::Home),
+    (0xE048, Key::ArrowUp),
+    (0xE049, Key::PageUp),
+    (0xE04B, Key::ArrowLeft),
+    (0xE04D, Key::ArrowRight),
+    (0xE04F, Key::End),
+    (0xE050, Key::ArrowDown),
+    (0xE051, Key::PageDown),
+    (0xE052, Key::Insert),
+    (0xE053, Key::Delete),
+    (0xE05B, Key::MetaLeft),
+    (0xE05C, Key::MetaRight),
+    (0xE05D, Key::ContextMenu),
+    (0xE11D, Key::Pause),
+];
+
+/** Linux evdev codes (linux/input-event-codes.h) of the keys whose evdev code is not their scan code */
+const EVDEV_EXTENDED_CODES: [(u32, Key); 19] = [
+    (96, Key::NumpadEnter),
+    (97, Key::ControlRight),
+    (98, Key::NumpadDivide),
+    (99, Key::PrintScreen),
+    (100, Key::AltRight),
+    (102, Key::Home),
+    (103, Key::ArrowUp),
+    (104, Key::PageUp),
+    (105, Key::ArrowLeft),
+    (106, Key::ArrowRight),
+    (107, Key::End),
+    (108, Key::ArrowDown),
+    (109, Key::PageDown),
+    (110, Key::Insert),
+    (111, Key::Delete),
+    (119, Key::Pause),
+    (125, Key::MetaLeft),
+    (126, Key::MetaRight),
+    (127, Key::ContextMenu),
+];
+/** evdev codes below this are the set 1 scan codes of the keys without a prefix */
+const EVDEV_FIRST_EXTENDED_CODE: u32 = 89;
+
+impl Key {
+    /** Looks up a key by its set 1 scan code, with any 0xE0/0xE1 prefix in the high byte */
+    pub fn from_scan_code(scan_code: u32) -> Key {
+        SCAN_CODES
+            .iter()
+            .find(|(code, _)| *code == scan_code)
+            .map_or(Key::Unknown, |&(_, key)| key)
+    }
+    /** The set 1 scan code of the key, or None for Key::Unknown */
+    pub fn get_scan_code(&self) -> Option<u32> {
+        SCAN_CODES
+            .iter()
+            .find(|(_, key)| key == self)
+            .map(|&(code, _)| code)
+    }
+    /**
+     * Looks up a key by its Linux evdev code, as delivered by Wayland's wl_keyboard.key.
+     * X11 keycodes are evdev codes plus 8 when the X server uses the evdev or libinput driver.
+     */
+    pub fn from_evdev_code(evdev_code: u32) -> Key {
+        if evdev_code < EVDEV_FIRST_EXTENDED_CODE {
+            return Key::from_scan_code(evdev_code);
+        }
+        EVDEV_EXTENDED_CODES
+            .iter()
+            .find(|(code, _)| *code == evdev_code)
+            .map_or(Key::Unknown, |&(_, key)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_scan_code_round_trips() {
+        for &(scan_code, key) in SCAN_CODES.iter() {
+            assert_eq!(Key::from_scan_code(scan_code), key, "{:#X}", scan_code);
+            assert_eq!(key.get_scan_code(), Some(scan_code), "{:?}", key);
+        }
+        assert_eq!(Key::Unknown.get_scan_code(), None);
+        assert_eq!(Key::from_scan_code(0), Key::Unknown);
+        assert_eq!(Key::from_scan_code(0x54), Key::Unknown);
+    }
+
+    #[test]
+    fn prefixed_scan_codes_are_different_keys() {
+        for (scan_code, key, extended_key) in [
+            (0x1D, Key::ControlLeft, Key::ControlRight),
+            (0x38, Key::AltLeft, Key::AltRight),
+            (0x1C, Key::Enter, Key::NumpadEnter),
+            (0x35, Key::Slash, Key::NumpadDivide),
+            (0x48, Key::Numpad8, Key::ArrowUp),
+            (0x4B, Key::Numpad4, Key::ArrowLeft),
+            (0x4D, Key::Numpad6, Key::ArrowRight),
+            (0x50, Key::Numpad2, Key::ArrowDown),
+            (0x53, Key::NumpadDecimal, Key::Delete),
+        ] {
+            assert_eq!(Key::from_scan_code(scan_code), key);
+            assert_eq!(Key::from_scan_code(0xE000 | scan_code), extended_key);
+        }
+        // The 0xE0 prefix on a key that has no extended version is not a key
+        assert_eq!(Key::from_scan_code(0xE01E), Key::Unknown);
+    }
+
+    #[test]
+    fn pause_and_num_lock_are_told_apart_by_their_prefix() {
+        // Pause sends E1 1D 45, NumLock sends a plain 45
+        assert_eq!(Key::from_scan_code(0xE11D), Key::Pause);
+        assert_eq!(Key::from_scan_code(0x45), Key::NumLock);
+        assert_eq!(Key::from_scan_code(0xE045), Key::Unknown);
+        assert_eq!(Key::Pause.get_scan_code(), Some(0xE11D));
+        assert_eq!(Key::from_evdev_code(119), Key::Pause);
+        assert_eq!(Key::from_evdev_code(69), Key::NumLock);
+    }
+
+    #[test]
+    fn evdev_codes_below_the_extended_codes_are_scan_codes() {
+        for evdev_code in 0..EVDEV_FIRST_EXTENDED_CODE {
+            assert_eq!(
+                Key::from_evdev_code(evdev_code),
+                Key::from_scan_code(evdev_code),
+                "{}",
+                evdev_code
+            );
+        }
+        assert_eq!(Key::from_evdev_code(1), Key::Escape);
+        assert_eq!(Key::from_evdev_code(29), Key::ControlLeft);
+        assert_eq!(Key::from_evdev_code(88), Key::F12);
+    }
+
+    #[test]
+    fn evdev_codes_beyond_the_scan_code_table() {
+        for (evdev_code, key) in [
+            (96, Key::NumpadEnter),
+            (97, Key::ControlRight),
+            (100, Key::AltRight),
+            (103, Key::ArrowUp),
+            (105, Key::ArrowLeft),
+            (106, Key::ArrowRight),
+            (108, Key::ArrowDown),
+            (111, Key::Delete),
+            (125, Key::MetaLeft),
+            (127, Key::ContextMenu),
+        ] {
+            assert_eq!(Key::from_evdev_code(evdev_code), key, "{}", evdev_code);
+        }
+        // Neither scan codes with a prefix nor codes the table does not list are keys
+        for evdev_code in [89, 95, 101, 120, 128, 0xE01D, 0xE11D] {
+            assert_eq!(
+                Key::from_evdev_code(evdev_code),
+                Key::Unknown,
+                "{}",
+                evdev_code
+            );
+        }
+        // Every extended evdev code is a key with a prefixed scan code
+        for &(evdev_code, key) in EVDEV_EXTENDED_CODES.iter() {
+            let scan_code = key.get_scan_code().unwrap();
+            assert!(scan_code > 0xFF, "{} {:#X}", evdev_code, scan_code);
+        }
+    }
+}
@@ -3,7 +3,19 @@ pub use self::window_definition::{WindowDefinition, WindowTransparency, WindowTy
 
 pub mod application;
 pub mod application_message_handler;
+pub mod application_proxy;
 pub mod cursor;
+pub mod edid;
+pub mod gamepad;
 pub mod iinputinterface;
+pub mod input;
+pub mod platform_event;
+pub mod timer;
+#[cfg(unix)]
+pub mod wakeup_pipe;
 pub mod window;
 pub mod window_definition;
+pub mod window_hierarchy;
+pub mod window_hit_test;
+pub mod window_sizing;
+pub mod xinputinterface;
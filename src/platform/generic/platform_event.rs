@@ -0,0 +1,503 @@
+use crate::generic::application::{DisplayMetrics, DisplayMetricsChange, ModifierKeysState};
+use crate::generic::application_message_handler::{
+    ApplicationMessageHandler, DropEffect, GamepadKeyNames, GestureEvent, MouseButtons,
+    WindowAction, WindowActivation,
+};
+use crate::generic::application_proxy::UserEvent;
+use crate::generic::input::Key;
+use crate::generic::timer::TimerId;
+use crate::generic::window::GenericWindow;
+use glam::{Vec2, Vec3};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/**
+ * An event read from the OS. Every variant matches one ApplicationMessageHandler callback; the callbacks that
+ * answer a question (should_process_user_input_messages, get_size_limits_for_window, get_window_zone_for_point,
+ * get_window_chrome) are still asked directly, since the OS needs the answer before the event can be queued.
+ */
+#[derive(Clone)]
+pub enum PlatformEvent {
+    /** The modifier_keys of the key and mouse events are the keys held when the event happened, not when it is dispatched */
+    KeyChar {
+        character: char,
+        is_repeat: bool,
+        modifier_keys: ModifierKeysState,
+    },
+    KeyDown {
+        key: Key,
+        character_code: u32,
+        is_repeat: bool,
+        modifier_keys: ModifierKeysState,
+    },
+    KeyUp {
+        key: Key,
+        character_code: u32,
+        is_repeat: bool,
+        modifier_keys: ModifierKeysState,
+    },
+    /**
+     * cursor_pos is in the client coordinates of the window the event was sent to.
+     * It is None when the backend did not report where the click happened.
+     */
+    MouseDown {
+        window: Rc<dyn GenericWindow>,
+        button: MouseButtons,
+        cursor_pos: Option<Vec2>,
+        modifier_keys: ModifierKeysState,
+    },
+    MouseUp {
+        button: MouseButtons,
+        cursor_pos: Option<Vec2>,
+        modifier_keys: ModifierKeysState,
+    },
+    MouseDoubleClick {
+        window: Rc<dyn GenericWindow>,
+        button: MouseButtons,
+        cursor_pos: Option<Vec2>,
+        modifier_keys: ModifierKeysState,
+    },
+    MouseWheel {
+        delta: f32,
+        cursor_pos: Option<Vec2>,
+        modifier_keys: ModifierKeysState,
+    },
+    /** A positive delta scrolls right */
+    MouseHWheel {
+        delta: f32,
+        cursor_pos: Option<Vec2>,
+        modifier_keys: ModifierKeysState,
+    },
+    MouseMove,
+    RawMouseMove {
+        x: i32,
+        y: i32,
+    },
+    RawMouseButton {
+        button: MouseButtons,
+        is_pressed: bool,
+    },
+    CursorSet,
+    ControllerAnalog {
+        key_name: GamepadKeyNames,
+        controller_id: i32,
+        analog_value: f32,
+    },
+    ControllerButtonPressed {
+        key_name: GamepadKeyNames,
+        controller_id: i32,
+        is_repeat: bool,
+    },
+    ControllerButtonReleased {
+        key_name: GamepadKeyNames,
+        controller_id: i32,
+        is_repeat: bool,
+    },
+    ControllerConnectionChanged {
+        controller_id: i32,
+        is_connected: bool,
+    },
+    BeginGesture,
+    TouchGesture {
+        gesture_type: GestureEvent,
+        delta: Vec2,
+        wheel_delta: f32,
+        is_direction_inverted_from_device: bool,
+    },
+    EndGesture,
+    TouchStarted {
+        window: Rc<dyn GenericWindow>,
+        location: Vec2,
+        touch_index: i32,
+        controller_id: i32,
+    },
+    TouchMoved {
+        location: Vec2,
+        touch_index: i32,
+        controller_id: i32,
+    },
+    TouchEnded {
+        location: Vec2,
+        touch_index: i32,
+        controller_id: i32,
+    },
+    MotionDetected {
+        tilt: Vec3,
+        rotation_rate: Vec3,
+        gravity: Vec3,
+        acceleration: Vec3,
+        controller_id: i32,
+    },
+    SizeChanged {
+        window: Rc<dyn GenericWindow>,
+        width: i32,
+        height: i32,
+        was_minimized: bool,
+    },
+    OsPaint {
+        window: Rc<dyn GenericWindow>,
+    },
+    ResizingWindow {
+        window: Rc<dyn GenericWindow>,
+    },
+    BeginReshapingWindow {
+        window: Rc<dyn GenericWindow>,
+    },
+    FinishedReshapingWindow {
+        window: Rc<dyn GenericWindow>,
+    },
+    MovedWindow {
+        window: Rc<dyn GenericWindow>,
+        x: i32,
+        y: i32,
+    },
+    WindowActivationChanged {
+        window: Rc<dyn GenericWindow>,
+        activation_type: WindowActivation,
+    },
+    ApplicationActivationChanged {
+        is_active: bool,
+    },
+    ConvertibleLaptopModeChanged,
+    DisplayMetricsChanged {
+        display_metrics: DisplayMetrics,
+        change: DisplayMetricsChange,
+    },
+    WindowClose {
+        window: Rc<dyn GenericWindow>,
+    },
+    DragEnterText {
+        window: Rc<dyn GenericWindow>,
+        text: String,
+    },
+    DragEnterFiles {
+        window: Rc<dyn GenericWindow>,
+        files: Vec<String>,
+    },
+    DragEnterExternal {
+        window: Rc<dyn GenericWindow>,
+        text: String,
+        files: Vec<String>,
+    },
+    DragOver {
+        window: Rc<dyn GenericWindow>,
+    },
+    DragLeave {
+        window: Rc<dyn GenericWindow>,
+    },
+    DragDrop {
+        window: Rc<dyn GenericWindow>,
+    },
+    WindowAction {
+        window: Rc<dyn GenericWindow>,
+        action_type: WindowAction,
+    },
+    UserEvent {
+        event: UserEvent,
+    },
+    Timer {
+        timer_id: TimerId,
+    },
+}
+
+impl PlatformEvent {
+    /** The window the event is about, if it names one */
+    pub fn get_window(&self) -> Option<&Rc<dyn GenericWindow>> {
+        match self {
+            PlatformEvent::MouseDown { window, .. }
+            | PlatformEvent::MouseDoubleClick { window, .. }
+            | PlatformEvent::TouchStarted { window, .. }
+            | PlatformEvent::SizeChanged { window, .. }
+            | PlatformEvent::OsPaint { window }
+            | PlatformEvent::ResizingWindow { window }
+            | PlatformEvent::BeginReshapingWindow { window }
+            | PlatformEvent::FinishedReshapingWindow { window }
+            | PlatformEvent::MovedWindow { window, .. }
+            | PlatformEvent::WindowActivationChanged { window, .. }
+            | PlatformEvent::WindowClose { window }
+            | PlatformEvent::DragEnterText { window, .. }
+            | PlatformEvent::DragEnterFiles { window, .. }
+            | PlatformEvent::DragEnterExternal { window, .. }
+            | PlatformEvent::DragOver { window }
+            | PlatformEvent::DragLeave { window }
+            | PlatformEvent::DragDrop { window }
+            | PlatformEvent::WindowAction { window, .. } => Some(window),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Forwards an event to the matching ApplicationMessageHandler callback.
+ * Returns what the callback returned; callbacks without a result count as handled, drag and drop counts as handled
+ * unless the handler answered DropEffect::None.
+ */
+pub fn forward_to_message_handler(
+    event: PlatformEvent,
+    message_handler: &Rc<dyn ApplicationMessageHandler>,
+) -> bool {
+    match event {
+        PlatformEvent::KeyChar {
+            character,
+            is_repeat,
+            modifier_keys,
+        } => message_handler.on_key_char(character, is_repeat, modifier_keys),
+        PlatformEvent::KeyDown {
+            key,
+            character_code,
+            is_repeat,
+            modifier_keys,
+        } => message_handler.on_key_down(key, character_code, is_repeat, modifier_keys),
+        PlatformEvent::KeyUp {
+            key,
+            character_code,
+            is_repeat,
+            modifier_keys,
+        } => message_handler.on_key_up(key, character_code, is_repeat, modifier_keys),
+        PlatformEvent::MouseDown {
+            window,
+            button,
+            cursor_pos,
+            modifier_keys,
+        } => match cursor_pos {
+            Some(cursor_pos) => message_handler.on_mouse_down_with_cursor_pos(
+                &window,
+                button,
+                cursor_pos,
+                modifier_keys,
+            ),
+            None => message_handler.on_mouse_down(&window, button, modifier_keys),
+        },
+        PlatformEvent::MouseUp {
+            button,
+            cursor_pos,
+            modifier_keys,
+        } => match cursor_pos {
+            Some(cursor_pos) => {
+                message_handler.on_mouse_up_with_cursor_pos(button, cursor_pos, modifier_keys)
+            }
+            None => message_handler.on_mouse_up(button, modifier_keys),
+        },
+        PlatformEvent::MouseDoubleClick {
+            window,
+            button,
+            cursor_pos,
+            modifier_keys,
+        } => match cursor_pos {
+            Some(cursor_pos) => message_handler.on_mouse_double_click_with_cursor_pos(
+                &window,
+                button,
+                cursor_pos,
+                modifier_keys,
+            ),
+            None => message_handler.on_mouse_double_click(&window, button, modifier_keys),
+        },
+        PlatformEvent::MouseWheel {
+            delta,
+            cursor_pos,
+            modifier_keys,
+        } => match cursor_pos {
+            Some(cursor_pos) => {
+                message_handler.on_mouse_wheel_with_cursor_pos(delta, cursor_pos, modifier_keys)
+            }
+            None => message_handler.on_mouse_wheel(delta, modifier_keys),
+        },
+        PlatformEvent::MouseHWheel {
+            delta,
+            cursor_pos,
+            modifier_keys,
+        } => match cursor_pos {
+            Some(cursor_pos) => {
+                message_handler.on_mouse_hwheel_with_cursor_pos(delta, cursor_pos, modifier_keys)
+            }
+            None => message_handler.on_mouse_hwheel(delta, modifier_keys),
+        },
+        PlatformEvent::MouseMove => message_handler.on_mouse_move(),
+        PlatformEvent::RawMouseMove { x, y } => message_handler.on_raw_mouse_move(x, y),
+        PlatformEvent::RawMouseButton { button, is_pressed } => {
+            message_handler.on_raw_mouse_button(button, is_pressed)
+        }
+        PlatformEvent::CursorSet => message_handler.on_cursor_set(),
+        PlatformEvent::ControllerAnalog {
+            key_name,
+            controller_id,
+            analog_value,
+        } => message_handler.on_controller_analog(key_name, controller_id, analog_value),
+        PlatformEvent::ControllerButtonPressed {
+            key_name,
+            controller_id,
+            is_repeat,
+        } => message_handler.on_controller_button_pressed(key_name, controller_id, is_repeat),
+        PlatformEvent::ControllerButtonReleased {
+            key_name,
+            controller_id,
+            is_repeat,
+        } => message_handler.on_controller_button_released(key_name, controller_id, is_repeat),
+        PlatformEvent::ControllerConnectionChanged {
+            controller_id,
+            is_connected,
+        } => {
+            message_handler.on_controller_connection_changed(controller_id, is_connected);
+            true
+        }
+        PlatformEvent::BeginGesture => {
+            message_handler.on_begin_gesture();
+            true
+        }
+        PlatformEvent::TouchGesture {
+            gesture_type,
+            delta,
+            wheel_delta,
+            is_direction_inverted_from_device,
+        } => message_handler.on_touch_gesture(
+            gesture_type,
+            delta,
+            wheel_delta,
+            is_direction_inverted_from_device,
+        ),
+        PlatformEvent::EndGesture => {
+            message_handler.on_end_gesture();
+            true
+        }
+        PlatformEvent::TouchStarted {
+            window,
+            location,
+            touch_index,
+            controller_id,
+        } => message_handler.on_touch_started(&window, location, touch_index, controller_id),
+        PlatformEvent::TouchMoved {
+            location,
+            touch_index,
+            controller_id,
+        } => message_handler.on_touch_moved(location, touch_index, controller_id),
+        PlatformEvent::TouchEnded {
+            location,
+            touch_index,
+            controller_id,
+        } => message_handler.on_touch_ended(location, touch_index, controller_id),
+        PlatformEvent::MotionDetected {
+            tilt,
+            rotation_rate,
+            gravity,
+            acceleration,
+            controller_id,
+        } => message_handler.on_motion_detected(
+            tilt,
+            rotation_rate,
+            gravity,
+            acceleration,
+            controller_id,
+        ),
+        PlatformEvent::SizeChanged {
+            window,
+            width,
+            height,
+            was_minimized,
+        } => message_handler.on_size_changed(&window, width, height, was_minimized),
+        PlatformEvent::OsPaint { window } => {
+            message_handler.on_os_paint(&window);
+            true
+        }
+        PlatformEvent::ResizingWindow { window } => {
+            message_handler.on_resizing_window(&window);
+            true
+        }
+        PlatformEvent::BeginReshapingWindow { window } => {
+            message_handler.begin_reshaping_window(&window)
+        }
+        PlatformEvent::FinishedReshapingWindow { window } => {
+            message_handler.finished_reshaping_window(&window);
+            true
+        }
+        PlatformEvent::MovedWindow { window, x, y } => {
+            message_handler.on_moved_window(&window, x, y);
+            true
+        }
+        PlatformEvent::WindowActivationChanged {
+            window,
+            activation_type,
+        } => message_handler.on_window_activation_changed(&window, activation_type),
+        PlatformEvent::ApplicationActivationChanged { is_active } => {
+            message_handler.on_application_activation_changed(is_active)
+        }
+        PlatformEvent::ConvertibleLaptopModeChanged => {
+            message_handler.on_convertible_laptop_mode_changed()
+        }
+        PlatformEvent::DisplayMetricsChanged {
+            display_metrics,
+            change,
+        } => {
+            message_handler.on_display_metrics_changed(&display_metrics, &change);
+            true
+        }
+        PlatformEvent::WindowClose { window } => {
+            message_handler.on_window_close(&window);
+            true
+        }
+        PlatformEvent::DragEnterText { window, text } => {
+            message_handler.on_drag_enter_text(&window, &text) != DropEffect::None
+        }
+        PlatformEvent::DragEnterFiles { window, files } => {
+            message_handler.on_drag_enter_files(&window, &files) != DropEffect::None
+        }
+        PlatformEvent::DragEnterExternal {
+            window,
+            text,
+            files,
+        } => message_handler.on_drag_enter_external(&window, &text, &files) != DropEffect::None,
+        PlatformEvent::DragOver { window } => {
+            message_handler.on_drag_over(&window) != DropEffect::None
+        }
+        PlatformEvent::DragLeave { window } => {
+            message_handler.on_drag_leave(&window);
+            true
+        }
+        PlatformEvent::DragDrop { window } => {
+            message_handler.on_drag_drop(&window) != DropEffect::None
+        }
+        PlatformEvent::WindowAction {
+            window,
+            action_type,
+        } => message_handler.on_window_action(&window, action_type),
+        PlatformEvent::UserEvent { event } => {
+            message_handler.on_user_event(&event);
+            true
+        }
+        PlatformEvent::Timer { timer_id } => {
+            message_handler.on_timer(timer_id);
+            true
+        }
+    }
+}
+
+/** The events an application has read from the OS but not handed out yet */
+#[derive(Default)]
+pub struct PlatformEventQueue {
+    events: RefCell<VecDeque<PlatformEvent>>,
+}
+
+impl PlatformEventQueue {
+    pub fn new() -> PlatformEventQueue {
+        PlatformEventQueue {
+            events: RefCell::new(VecDeque::new()),
+        }
+    }
+    pub fn push(&self, event: PlatformEvent) {
+        self.events.borrow_mut().push_back(event);
+    }
+    /** Takes the oldest event. The queue is not borrowed afterwards, so handlers may push while events are popped. */
+    pub fn pop(&self) -> Option<PlatformEvent> {
+        self.events.borrow_mut().pop_front()
+    }
+    /** Takes every queued event, oldest first */
+    pub fn drain(&self) -> Vec<PlatformEvent> {
+        self.events.borrow_mut().drain(..).collect()
+    }
+    pub fn len(&self) -> usize {
+        self.events.borrow().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.events.borrow().is_empty()
+    }
+}
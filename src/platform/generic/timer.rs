@@ -0,0 +1,296 @@
+use crate::generic::platform_event::{PlatformEvent, PlatformEventQueue};
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+/** The shortest interval of a repeating timer, so that one set to zero does not fire on every pass of the loop */
+pub const MIN_TIMER_INTERVAL: Duration = Duration::from_millis(1);
+
+/** Names a timer made by GenericApplication::set_timer */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct TimerId(u64);
+
+#[derive(Clone, Copy, Debug)]
+struct Timer {
+    id: TimerId,
+    deadline: Instant,
+    interval: Duration,
+    is_repeating: bool,
+}
+
+/**
+ * The timers of an application. Every backend keeps one: wait_for_events waits no longer than get_wait_timeout,
+ * and poll_events calls fire_expired_timers, which queues a PlatformEvent::Timer for each timer that is due.
+ */
+#[derive(Debug)]
+pub struct TimerQueue {
+    timers: RefCell<Vec<Timer>>,
+    next_id: Cell<u64>,
+}
+
+impl Default for TimerQueue {
+    fn default() -> TimerQueue {
+        TimerQueue::new()
+    }
+}
+
+impl TimerQueue {
+    pub fn new() -> TimerQueue {
+        TimerQueue {
+            timers: RefCell::new(vec![]),
+            next_id: Cell::new(1),
+        }
+    }
+    /** Starts a timer that fires once after the duration, or every duration if it repeats */
+    pub fn set_timer(&self, duration: Duration, repeat: bool) -> TimerId {
+        let id = TimerId(self.next_id.get());
+        self.next_id.set(id.0 + 1);
+        let interval = if repeat {
+            duration.max(MIN_TIMER_INTERVAL)
+        } else {
+            duration
+        };
+        self.timers.borrow_mut().push(Timer {
+            id,
+            deadline: Instant::now() + interval,
+            interval,
+            is_repeating: repeat,
+        });
+        id
+    }
+    /** Stops the timer. Returns false if it already fired for the last time or was cancelled before. */
+    pub fn cancel_timer(&self, timer_id: TimerId) -> bool {
+        let mut timers = self.timers.borrow_mut();
+        let num_timers = timers.len();
+        timers.retain(|timer| timer.id != timer_id);
+        timers.len() != num_timers
+    }
+    pub fn is_empty(&self) -> bool {
+        self.timers.borrow().is_empty()
+    }
+    /** When the next timer is due, None if there are no timers */
+    pub fn get_next_deadline(&self) -> Option<Instant> {
+        self.timers
+            .borrow()
+            .iter()
+            .map(|timer| timer.deadline)
+            .min()
+    }
+    pub fn has_expired_timers(&self) -> bool {
+        self.get_next_deadline()
+            .is_some_and(|deadline| deadline <= Instant::now())
+    }
+    /** Shortens the timeout of a wait so it ends when the next timer is due. None waits for as long as it takes. */
+    pub fn get_wait_timeout(&self, timeout: Option<Duration>) -> Option<Duration> {
+        match self.get_next_deadline() {
+            Some(deadline) => {
+                let until_deadline = deadline.saturating_duration_since(Instant::now());
+                Some(timeout.map_or(until_deadline, |timeout| timeout.min(until_deadline)))
+            }
+            None => timeout,
+        }
+    }
+    /**
+     * Queues an event for every timer that is due, earliest first. A repeating timer that fell more than one
+     * interval behind, for example while the machine was asleep, fires once and then keeps its rhythm from now.
+     */
+    pub fn fire_expired_timers(&self, events: &PlatformEventQueue) {
+        let now = Instant::now();
+        let mut expired_timers: Vec<Timer> = vec![];
+        self.timers.borrow_mut().retain_mut(|timer| {
+            if timer.deadline > now {
+                return true;
+            }
+            expired_timers.push(*timer);
+            if !timer.is_repeating {
+                return false;
+            }
+            timer.deadline += timer.interval;
+            if timer.deadline <= now {
+                timer.deadline = now + timer.interval;
+            }
+            true
+        });
+        expired_timers.sort_by_key(|timer| (timer.deadline, timer.id));
+        for timer in expired_timers {
+            events.push(PlatformEvent::Timer { timer_id: timer.id });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    /** The ids of the timer events, in the order they were queued */
+    fn take_fired_timers(events: &PlatformEventQueue) -> Vec<TimerId> {
+        events
+            .drain()
+            .into_iter()
+            .map(|event| match event {
+                PlatformEvent::Timer { timer_id } => timer_id,
+                _ => panic!("the timer queue queued an event that is not a timer event"),
+            })
+            .collect()
+    }
+
+    /** Moves the deadline of a timer, since the tests cannot wait for the clock */
+    fn set_deadline(timers: &TimerQueue, timer_id: TimerId, deadline: Instant) {
+        timers
+            .timers
+            .borrow_mut()
+            .iter_mut()
+            .find(|timer| timer.id == timer_id)
+            .unwrap()
+            .deadline = deadline;
+    }
+
+    fn get_deadline(timers: &TimerQueue, timer_id: TimerId) -> Option<Instant> {
+        timers
+            .timers
+            .borrow()
+            .iter()
+            .find(|timer| timer.id == timer_id)
+            .map(|timer| timer.deadline)
+    }
+
+    #[test]
+    fn one_shot_timer_fires_once() {
+        let timers = TimerQueue::new();
+        let events = PlatformEventQueue::new();
+        let timer_id = timers.set_timer(Duration::ZERO, false);
+        assert!(timers.has_expired_timers());
+
+        timers.fire_expired_timers(&events);
+        assert_eq!(take_fired_timers(&events), [timer_id]);
+        assert!(timers.is_empty());
+        assert_eq!(timers.get_next_deadline(), None);
+
+        timers.fire_expired_timers(&events);
+        assert_eq!(take_fired_timers(&events), []);
+        assert!(!timers.cancel_timer(timer_id));
+    }
+
+    #[test]
+    fn repeating_timer_keeps_its_rhythm() {
+        let timers = TimerQueue::new();
+        let events = PlatformEventQueue::new();
+        let timer_id = timers.set_timer(HOUR, true);
+        timers.fire_expired_timers(&events);
+        assert_eq!(take_fired_timers(&events), []);
+
+        // Due a little while ago, so the next deadline is one interval after the old one and not after now
+        let deadline = Instant::now() - Duration::from_secs(1);
+        set_deadline(&timers, timer_id, deadline);
+        timers.fire_expired_timers(&events);
+        assert_eq!(take_fired_timers(&events), [timer_id]);
+        assert_eq!(get_deadline(&timers, timer_id), Some(deadline + HOUR));
+        assert!(!timers.has_expired_timers());
+    }
+
+    #[test]
+    fn timer_that_fell_behind_fires_once() {
+        let timers = TimerQueue::new();
+        let events = PlatformEventQueue::new();
+        let interval = Duration::from_secs(1);
+        let timer_id = timers.set_timer(interval, true);
+        // Five intervals behind, as if the machine had been asleep. Instant counts from boot, so stay close to now.
+        set_deadline(&timers, timer_id, Instant::now() - interval * 5);
+
+        let before = Instant::now();
+        timers.fire_expired_timers(&events);
+        let after = Instant::now();
+        assert_eq!(take_fired_timers(&events), [timer_id]);
+        let deadline = get_deadline(&timers, timer_id).unwrap();
+        assert!(before + interval <= deadline && deadline <= after + interval);
+
+        timers.fire_expired_timers(&events);
+        assert_eq!(take_fired_timers(&events), []);
+    }
+
+    #[test]
+    fn repeating_intervals_are_clamped() {
+        let timers = TimerQueue::new();
+        let repeating = timers.set_timer(Duration::ZERO, true);
+        let one_shot = timers.set_timer(Duration::ZERO, false);
+        let intervals: Vec<(TimerId, Duration)> = timers
+            .timers
+            .borrow()
+            .iter()
+            .map(|timer| (timer.id, timer.interval))
+            .collect();
+        assert_eq!(
+            intervals,
+            [(repeating, MIN_TIMER_INTERVAL), (one_shot, Duration::ZERO)]
+        );
+    }
+
+    #[test]
+    fn cancel_timer_stops_a_timer_once() {
+        let timers = TimerQueue::new();
+        let events = PlatformEventQueue::new();
+        let cancelled = timers.set_timer(Duration::ZERO, true);
+        let kept = timers.set_timer(Duration::ZERO, false);
+        assert_ne!(cancelled, kept);
+
+        assert!(timers.cancel_timer(cancelled));
+        assert!(!timers.cancel_timer(cancelled));
+        assert!(!timers.cancel_timer(TimerId(u64::MAX)));
+
+        timers.fire_expired_timers(&events);
+        assert_eq!(take_fired_timers(&events), [kept]);
+    }
+
+    #[test]
+    fn expired_timers_fire_earliest_first() {
+        let timers = TimerQueue::new();
+        let events = PlatformEventQueue::new();
+        let now = Instant::now();
+        let last = timers.set_timer(HOUR, false);
+        let first = timers.set_timer(HOUR, true);
+        let middle = timers.set_timer(HOUR, false);
+        let pending = timers.set_timer(HOUR, false);
+        set_deadline(&timers, last, now - Duration::from_secs(1));
+        set_deadline(&timers, first, now - Duration::from_secs(3));
+        set_deadline(&timers, middle, now - Duration::from_secs(2));
+
+        timers.fire_expired_timers(&events);
+        assert_eq!(take_fired_timers(&events), [first, middle, last]);
+        assert!(get_deadline(&timers, pending).is_some());
+    }
+
+    #[test]
+    fn wait_timeout_ends_at_the_next_deadline() {
+        let timers = TimerQueue::new();
+        assert_eq!(timers.get_wait_timeout(None), None);
+        assert_eq!(
+            timers.get_wait_timeout(Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+
+        timers.set_timer(HOUR, false);
+        // The timer is sooner than no timeout or a day
+        for timeout in [None, Some(HOUR * 24)] {
+            let wait_timeout = timers.get_wait_timeout(timeout).unwrap();
+            assert!(
+                HOUR - Duration::from_secs(60) < wait_timeout && wait_timeout <= HOUR,
+                "{:?}",
+                wait_timeout
+            );
+        }
+        // A shorter timeout is kept
+        assert_eq!(
+            timers.get_wait_timeout(Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+
+        // A timer that is already due does not wait at all
+        timers.set_timer(Duration::ZERO, false);
+        assert_eq!(
+            timers.get_wait_timeout(Some(Duration::from_secs(5))),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(timers.get_wait_timeout(None), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::generic::application_proxy::EventLoopWaker;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+/**
+ * The read end of a self-pipe, for backends that block in poll on the display connection. Polling this too lets
+ * an ApplicationProxy end the wait: its WakeupPipeWaker writes a byte, which makes the pipe readable.
+ */
+#[derive(Debug)]
+pub struct WakeupPipe {
+    reader: UnixStream,
+}
+
+/** The write end of a WakeupPipe, which proxies on any thread use */
+#[derive(Debug)]
+pub struct WakeupPipeWaker {
+    writer: UnixStream,
+}
+
+impl WakeupPipe {
+    pub fn new() -> io::Result<(WakeupPipe, WakeupPipeWaker)> {
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+        Ok((WakeupPipe { reader }, WakeupPipeWaker { writer }))
+    }
+    /** Reads every pending wake, so the next poll blocks again */
+    pub fn drain(&self) {
+        let mut buffer = [0u8; 64];
+        while let Ok(read) = (&self.reader).read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+        }
+    }
+    /**
+     * Blocks until fd is readable, a waker wakes the pipe, or the timeout passes; None waits for as long as it
+     * takes. Returns whether fd is readable or the pipe was woken. Wakes are not drained, see drain.
+     */
+    pub fn wait(&self, fd: RawFd, timeout: Option<Duration>) -> io::Result<bool> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let mut poll_fds = [
+                libc::pollfd {
+                    fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+                libc::pollfd {
+                    fd: self.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+            ];
+            let timeout_ms = match deadline {
+                // Rounded up, so the wait does not end just before the deadline and leave the caller to spin
+                Some(deadline) => deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_micros()
+                    .div_ceil(1000)
+                    .min(libc::c_int::MAX as u128) as libc::c_int,
+                None => -1,
+            };
+            let result = unsafe {
+                libc::poll(
+                    poll_fds.as_mut_ptr(),
+                    poll_fds.len() as libc::nfds_t,
+                    timeout_ms,
+                )
+            };
+            if result >= 0 {
+                return Ok(result > 0);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+}
+
+impl AsRawFd for WakeupPipe {
+    fn as_raw_fd(&self) -> RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl EventLoopWaker for WakeupPipeWaker {
+    fn wake(&self) {
+        // When the buffer is full the reader is already woken, so a write that would block is not needed
+        let _ = (&self.writer).write(&[1]);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::generic::window_definition::WindowDefinition;
+use crate::generic::window_hierarchy::WindowHierarchy;
 use std::os::raw::c_void;
 use std::rc::Rc;
 
@@ -44,13 +45,13 @@ pub trait GenericWindow {
     fn move_window_to(&self, x: &mut i32, y: &mut i32);
     fn bring_to_front(&self, force: bool);
     //fn HACK_force_to_front(&mut self);
-    fn destroy(&mut self);
+    fn destroy(&self);
     fn minimize(&self);
     fn maximize(&self);
     fn restore(&self);
     fn show(&self);
     fn hide(&self);
-    fn set_window_mode(&mut self, new_window_mode: WindowMode);
+    fn set_window_mode(&self, new_window_mode: WindowMode);
     fn get_window_mode(&self) -> WindowMode;
     fn is_maximized(&self) -> bool;
     fn is_minimized(&self) -> bool;
@@ -62,7 +63,7 @@ pub trait GenericWindow {
         width: &mut i32,
         height: &mut i32,
     ) -> bool;
-    fn set_window_focus(&mut self);
+    fn set_window_focus(&self);
     fn set_opacity(&self, opacity: f32);
     fn enable(&self, enable: bool);
     fn is_point_in_window(&self, x: i32, y: i32) -> bool;
@@ -76,9 +77,19 @@ pub trait GenericWindow {
     fn is_definition_valid(&self) -> bool;
     fn adjust_cached_size(&self, size: &mut (i32, i32));
     fn get_dpi_scale_factor(&self) -> f32;
-    fn set_dpi_scale_factor(&mut self, factor: f32);
+    fn set_dpi_scale_factor(&self, factor: f32);
     fn is_manual_manage_dpi_change(&self) -> bool;
-    fn set_manual_manage_dpi_change(&mut self, auto_handle: bool);
+    fn set_manual_manage_dpi_change(&self, auto_handle: bool);
     fn draw_attention(&self, parameters: WindowDrawAttentionRequestType);
-    fn set_native_window_buttons_visibility(&mut self, visible: bool);
+    fn set_native_window_buttons_visibility(&self, visible: bool);
+    /** The window's place in the ownership tree, see window_hierarchy */
+    fn get_hierarchy(&self) -> &WindowHierarchy;
+    /** The window that owns this one, None for a top-level window */
+    fn get_parent(&self) -> Option<Rc<dyn GenericWindow>> {
+        self.get_hierarchy().get_parent()
+    }
+    /** The windows this one owns */
+    fn get_children(&self) -> Vec<Rc<dyn GenericWindow>> {
+        self.get_hierarchy().get_children()
+    }
 }
@@ -1,7 +1,8 @@
 use crate::generic::application_message_handler::WindowSizeLimits;
-use std::default;
+use std::{default, error, fmt, io};
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WindowType {
     /** Value indicating that this is a standard, general-purpose window */
     Normal,
@@ -18,6 +19,7 @@ pub enum WindowType {
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WindowTransparency {
     /** Value indicating that a window does not support transparency */
     None = 0,
@@ -31,6 +33,7 @@ pub enum WindowTransparency {
 
 /** Enumeration to specify whether the window gets activated upon showing it */
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WindowActivationPolicy {
     /** Value indicating that a window never activates when it is shown */
     Never,
@@ -42,7 +45,14 @@ pub enum WindowActivationPolicy {
     FirstShown,
 }
 
+/**
+ * Describes a window before it is created. Start from WindowDefinition::builder, which fills in the usual values
+ * for the window type and checks the result, or load one with WindowDefinition::load. Fields missing from a file
+ * keep their Default value.
+ */
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct WindowDefinition {
     /** Window type */
     pub window_type: WindowType,
@@ -89,9 +99,9 @@ pub struct WindowDefinition {
     pub size_will_change_often: bool,
     /** true if the window should preserve its aspect ratio when resized by user */
     pub should_preserve_aspect_ratio: bool,
-    /** The expected maximum width of the window.  May be used for performance optimization when SizeWillChangeOften is set. */
+    /** The expected maximum width of the window, -1 if unknown.  May be used for performance optimization when SizeWillChangeOften is set. */
     pub expected_max_width: i32,
-    /** The expected maximum height of the window.  May be used for performance optimization when SizeWillChangeOften is set. */
+    /** The expected maximum height of the window, -1 if unknown.  May be used for performance optimization when SizeWillChangeOften is set. */
     pub expected_max_height: i32,
 
     /** the title of the window */
@@ -107,6 +117,7 @@ pub struct WindowDefinition {
     pub manual_dpi: bool,
 }
 
+/** A plain, resizable window with an OS border */
 impl default::Default for WindowDefinition {
     fn default() -> WindowDefinition {
         WindowDefinition {
@@ -115,10 +126,10 @@ impl default::Default for WindowDefinition {
             y_desired_position_on_screen: 0.0,
             width_desired_on_screen: 800.0,
             height_desired_on_screen: 600.0,
-            transparency_support: WindowTransparency::PerWindow,
+            transparency_support: WindowTransparency::None,
             has_os_window_border: true,
             appears_in_taskbar: true,
-            is_topmost_window: true,
+            is_topmost_window: false,
             accepts_input: true,
             activation_policy: WindowActivationPolicy::Always,
             focus_when_first_shown: true,
@@ -129,19 +140,577 @@ impl default::Default for WindowDefinition {
             is_regular_window: true,
             has_sizing_frame: true,
             size_will_change_often: false,
-            should_preserve_aspect_ratio: true,
-            expected_max_width: 1280,
-            expected_max_height: 640,
-            title: "Cormac's Test Window".to_string(),
+            should_preserve_aspect_ratio: false,
+            expected_max_width: -1,
+            expected_max_height: -1,
+            title: String::new(),
             opacity: 1.0,
-            corner_radius: 1,
-            size_limits: WindowSizeLimits {
-                min_width: Some(50.0),
-                min_height: Some(50.0),
-                max_width: Some(1366.0),
-                max_height: Some(705.0),
-            },
+            corner_radius: 0,
+            size_limits: WindowSizeLimits::default(),
             manual_dpi: false,
         }
     }
 }
+
+/** Why a window definition was rejected */
+#[derive(Debug)]
+pub enum WindowDefinitionError {
+    /** Only regular windows can be resized by the user; a tooltip, menu, notification or cursor decorator can not */
+    SizingFrameNotSupported(WindowType),
+    /** The OS border is drawn opaque, so it can not be combined with per-pixel alpha */
+    PerPixelTransparencyWithOsBorder,
+    /** The desired width or height is not positive */
+    InvalidSize(f32, f32),
+    /** The opacity is not between 0 and 1 */
+    InvalidOpacity(f32),
+    /** A minimum size in the size limits is larger than the maximum */
+    InvertedSizeLimits,
+    /** The file could not be read */
+    Io(io::Error),
+    /** The file is not valid TOML or RON, or does not describe a window definition */
+    Parse(String),
+    /** The file name does not end in .toml or .ron */
+    UnknownFormat,
+}
+
+impl fmt::Display for WindowDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowDefinitionError::SizingFrameNotSupported(window_type) => {
+                write!(f, "a {:?} window can not have a sizing frame", window_type)
+            }
+            WindowDefinitionError::PerPixelTransparencyWithOsBorder => write!(
+                f,
+                "per-pixel transparency can not be used with an OS window border"
+            ),
+            WindowDefinitionError::InvalidSize(width, height) => {
+                write!(f, "window size {}x{} is not positive", width, height)
+            }
+            WindowDefinitionError::InvalidOpacity(opacity) => {
+                write!(f, "opacity {} is not between 0 and 1", opacity)
+            }
+            WindowDefinitionError::InvertedSizeLimits => {
+                write!(f, "a minimum size limit is larger than the maximum")
+            }
+            WindowDefinitionError::Io(error) => {
+                write!(f, "could not read the window definition: {}", error)
+            }
+            WindowDefinitionError::Parse(message) => {
+                write!(f, "could not parse the window definition: {}", message)
+            }
+            WindowDefinitionError::UnknownFormat => write!(
+                f,
+                "window definition files must have a .toml or .ron extension"
+            ),
+        }
+    }
+}
+
+impl error::Error for WindowDefinitionError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            WindowDefinitionError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WindowDefinitionError {
+    fn from(error: io::Error) -> WindowDefinitionError {
+        WindowDefinitionError::Io(error)
+    }
+}
+
+impl WindowDefinition {
+    /**
+     * The usual definition of a window of the given type: a tooltip or cursor decorator never takes input or focus,
+     * menus and notifications float above other windows without a border, and a game window is a regular window
+     * that keeps its aspect ratio.
+     */
+    pub fn preset(window_type: WindowType) -> WindowDefinition {
+        let popup = WindowDefinition {
+            window_type: window_type.clone(),
+            transparency_support: WindowTransparency::PerWindow,
+            has_os_window_border: false,
+            appears_in_taskbar: false,
+            is_topmost_window: true,
+            has_close_button: false,
+            supports_minimize: false,
+            supports_maximize: false,
+            is_regular_window: false,
+            has_sizing_frame: false,
+            ..WindowDefinition::default()
+        };
+        match window_type {
+            WindowType::Normal => WindowDefinition::default(),
+            WindowType::Menu => popup,
+            WindowType::ToolTip | WindowType::CursorDecorator => WindowDefinition {
+                accepts_input: false,
+                activation_policy: WindowActivationPolicy::Never,
+                focus_when_first_shown: false,
+                size_will_change_often: true,
+                expected_max_width: 1024,
+                expected_max_height: 512,
+                ..popup
+            },
+            WindowType::Notification => WindowDefinition {
+                activation_policy: WindowActivationPolicy::Never,
+                focus_when_first_shown: false,
+                size_will_change_often: true,
+                ..popup
+            },
+            WindowType::GameWindow => WindowDefinition {
+                window_type: WindowType::GameWindow,
+                should_preserve_aspect_ratio: true,
+                ..WindowDefinition::default()
+            },
+        }
+    }
+    /** Starts a definition from the preset for the window type */
+    pub fn builder(window_type: WindowType) -> WindowDefinitionBuilder {
+        WindowDefinitionBuilder {
+            definition: WindowDefinition::preset(window_type),
+        }
+    }
+    /** Checks for settings that contradict each other or that no backend can honour */
+    pub fn validate(&self) -> Result<(), WindowDefinitionError> {
+        if self.has_sizing_frame
+            && matches!(
+                self.window_type,
+                WindowType::Menu
+                    | WindowType::ToolTip
+                    | WindowType::Notification
+                    | WindowType::CursorDecorator
+            )
+        {
+            return Err(WindowDefinitionError::SizingFrameNotSupported(
+                self.window_type.clone(),
+            ));
+        }
+        if self.has_os_window_border && self.transparency_support == WindowTransparency::PerPixel {
+            return Err(WindowDefinitionError::PerPixelTransparencyWithOsBorder);
+        }
+        if !(self.width_desired_on_screen > 0.0 && self.height_desired_on_screen > 0.0) {
+            return Err(WindowDefinitionError::InvalidSize(
+                self.width_desired_on_screen,
+                self.height_desired_on_screen,
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(WindowDefinitionError::InvalidOpacity(self.opacity));
+        }
+        let size_limits = &self.size_limits;
+        let is_inverted = |min: Option<f32>, max: Option<f32>| match (min, max) {
+            (Some(min), Some(max)) => min > max,
+            _ => false,
+        };
+        if is_inverted(size_limits.get_min_width(), size_limits.get_max_width())
+            || is_inverted(size_limits.get_min_height(), size_limits.get_max_height())
+        {
+            return Err(WindowDefinitionError::InvertedSizeLimits);
+        }
+        Ok(())
+    }
+    /** Parses and validates a definition written in TOML */
+    #[cfg(feature = "serde")]
+    pub fn from_toml_str(text: &str) -> Result<WindowDefinition, WindowDefinitionError> {
+        let definition: WindowDefinition = toml::from_str(text)
+            .map_err(|error| WindowDefinitionError::Parse(error.to_string()))?;
+        definition.validate()?;
+        Ok(definition)
+    }
+    /** Parses and validates a definition written in RON */
+    #[cfg(feature = "serde")]
+    pub fn from_ron_str(text: &str) -> Result<WindowDefinition, WindowDefinitionError> {
+        let definition: WindowDefinition =
+            ron::from_str(text).map_err(|error| WindowDefinitionError::Parse(error.to_string()))?;
+        definition.validate()?;
+        Ok(definition)
+    }
+    /** Reads a .toml or .ron file and validates the definition in it */
+    #[cfg(feature = "serde")]
+    pub fn load(path: &std::path::Path) -> Result<WindowDefinition, WindowDefinitionError> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("toml") => WindowDefinition::from_toml_str(&std::fs::read_to_string(path)?),
+            Some("ron") => WindowDefinition::from_ron_str(&std::fs::read_to_string(path)?),
+            _ => Err(WindowDefinitionError::UnknownFormat),
+        }
+    }
+}
+
+/** Builds a WindowDefinition from a preset, checking it with WindowDefinition::validate at the end */
+#[derive(Clone, Debug)]
+pub struct WindowDefinitionBuilder {
+    definition: WindowDefinition,
+}
+
+impl WindowDefinitionBuilder {
+    pub fn position(mut self, x: f32, y: f32) -> WindowDefinitionBuilder {
+        self.definition.x_desired_position_on_screen = x;
+        self.definition.y_desired_position_on_screen = y;
+        self
+    }
+    pub fn size(mut self, width: f32, height: f32) -> WindowDefinitionBuilder {
+        self.definition.width_desired_on_screen = width;
+        self.definition.height_desired_on_screen = height;
+        self
+    }
+    pub fn title<S: Into<String>>(mut self, title: S) -> WindowDefinitionBuilder {
+        self.definition.title = title.into();
+        self
+    }
+    pub fn transparency_support(
+        mut self,
+        transparency_support: WindowTransparency,
+    ) -> WindowDefinitionBuilder {
+        self.definition.transparency_support = transparency_support;
+        self
+    }
+    pub fn has_os_window_border(mut self, has_os_window_border: bool) -> WindowDefinitionBuilder {
+        self.definition.has_os_window_border = has_os_window_border;
+        self
+    }
+    pub fn appears_in_taskbar(mut self, appears_in_taskbar: bool) -> WindowDefinitionBuilder {
+        self.definition.appears_in_taskbar = appears_in_taskbar;
+        self
+    }
+    pub fn is_topmost_window(mut self, is_topmost_window: bool) -> WindowDefinitionBuilder {
+        self.definition.is_topmost_window = is_topmost_window;
+        self
+    }
+    pub fn accepts_input(mut self, accepts_input: bool) -> WindowDefinitionBuilder {
+        self.definition.accepts_input = accepts_input;
+        self
+    }
+    pub fn activation_policy(
+        mut self,
+        activation_policy: WindowActivationPolicy,
+    ) -> WindowDefinitionBuilder {
+        self.definition.activation_policy = activation_policy;
+        self
+    }
+    pub fn focus_when_first_shown(
+        mut self,
+        focus_when_first_shown: bool,
+    ) -> WindowDefinitionBuilder {
+        self.definition.focus_when_first_shown = focus_when_first_shown;
+        self
+    }
+    pub fn has_close_button(mut self, has_close_button: bool) -> WindowDefinitionBuilder {
+        self.definition.has_close_button = has_close_button;
+        self
+    }
+    pub fn supports_minimize(mut self, supports_minimize: bool) -> WindowDefinitionBuilder {
+        self.definition.supports_minimize = supports_minimize;
+        self
+    }
+    pub fn supports_maximize(mut self, supports_maximize: bool) -> WindowDefinitionBuilder {
+        self.definition.supports_maximize = supports_maximize;
+        self
+    }
+    pub fn is_modal_window(mut self, is_modal_window: bool) -> WindowDefinitionBuilder {
+        self.definition.is_modal_window = is_modal_window;
+        self
+    }
+    pub fn has_sizing_frame(mut self, has_sizing_frame: bool) -> WindowDefinitionBuilder {
+        self.definition.has_sizing_frame = has_sizing_frame;
+        self
+    }
+    pub fn size_will_change_often(
+        mut self,
+        size_will_change_often: bool,
+    ) -> WindowDefinitionBuilder {
+        self.definition.size_will_change_often = size_will_change_often;
+        self
+    }
+    pub fn should_preserve_aspect_ratio(
+        mut self,
+        should_preserve_aspect_ratio: bool,
+    ) -> WindowDefinitionBuilder {
+        self.definition.should_preserve_aspect_ratio = should_preserve_aspect_ratio;
+        self
+    }
+    pub fn expected_max_size(mut self, width: i32, height: i32) -> WindowDefinitionBuilder {
+        self.definition.expected_max_width = width;
+        self.definition.expected_max_height = height;
+        self
+    }
+    pub fn opacity(mut self, opacity: f32) -> WindowDefinitionBuilder {
+        self.definition.opacity = opacity;
+        self
+    }
+    pub fn corner_radius(mut self, corner_radius: i32) -> WindowDefinitionBuilder {
+        self.definition.corner_radius = corner_radius;
+        self
+    }
+    pub fn size_limits(mut self, size_limits: WindowSizeLimits) -> WindowDefinitionBuilder {
+        self.definition.size_limits = size_limits;
+        self
+    }
+    pub fn manual_dpi(mut self, manual_dpi: bool) -> WindowDefinitionBuilder {
+        self.definition.manual_dpi = manual_dpi;
+        self
+    }
+    pub fn build(self) -> Result<WindowDefinition, WindowDefinitionError> {
+        self.definition.validate()?;
+        Ok(self.definition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_WINDOW_TYPES: [WindowType; 6] = [
+        WindowType::Normal,
+        WindowType::Menu,
+        WindowType::ToolTip,
+        WindowType::Notification,
+        WindowType::CursorDecorator,
+        WindowType::GameWindow,
+    ];
+
+    #[test]
+    fn every_preset_is_valid() {
+        for window_type in ALL_WINDOW_TYPES {
+            let definition = WindowDefinition::preset(window_type.clone());
+            assert_eq!(definition.window_type, window_type);
+            assert!(definition.validate().is_ok(), "{:?}", window_type);
+            assert_eq!(
+                WindowDefinition::builder(window_type).build().unwrap(),
+                definition
+            );
+        }
+        assert_eq!(
+            WindowDefinition::preset(WindowType::Normal),
+            WindowDefinition::default()
+        );
+        assert!(!WindowDefinition::preset(WindowType::ToolTip).accepts_input);
+        assert!(WindowDefinition::preset(WindowType::GameWindow).should_preserve_aspect_ratio);
+    }
+
+    #[test]
+    fn popups_can_not_have_a_sizing_frame() {
+        for window_type in ALL_WINDOW_TYPES {
+            let result = WindowDefinition::builder(window_type.clone())
+                .has_sizing_frame(true)
+                .build();
+            match window_type {
+                WindowType::Normal | WindowType::GameWindow => assert!(result.is_ok()),
+                _ => assert!(
+                    matches!(
+                        &result,
+                        Err(WindowDefinitionError::SizingFrameNotSupported(rejected_type))
+                            if *rejected_type == window_type
+                    ),
+                    "{:?}",
+                    result
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_per_pixel_transparency_with_an_os_border() {
+        let result = WindowDefinition::builder(WindowType::Normal)
+            .transparency_support(WindowTransparency::PerPixel)
+            .build();
+        assert!(matches!(
+            result,
+            Err(WindowDefinitionError::PerPixelTransparencyWithOsBorder)
+        ));
+        assert!(WindowDefinition::builder(WindowType::Normal)
+            .transparency_support(WindowTransparency::PerPixel)
+            .has_os_window_border(false)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_sizes_that_are_not_positive() {
+        for (width, height) in [(0.0, 600.0), (800.0, -1.0), (f32::NAN, 600.0)] {
+            let result = WindowDefinition::builder(WindowType::Normal)
+                .size(width, height)
+                .build();
+            assert!(
+                matches!(result, Err(WindowDefinitionError::InvalidSize(..))),
+                "{}x{}",
+                width,
+                height
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_opacity_outside_zero_to_one() {
+        for opacity in [-0.1, 1.5, f32::NAN] {
+            let result = WindowDefinition::builder(WindowType::Normal)
+                .opacity(opacity)
+                .build();
+            assert!(
+                matches!(result, Err(WindowDefinitionError::InvalidOpacity(..))),
+                "{}",
+                opacity
+            );
+        }
+        for opacity in [0.0, 1.0] {
+            assert!(WindowDefinition::builder(WindowType::Normal)
+                .opacity(opacity)
+                .build()
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn rejects_inverted_size_limits() {
+        let make_limits = |min_width, max_width, min_height, max_height| WindowSizeLimits {
+            min_width,
+            min_height,
+            max_width,
+            max_height,
+        };
+        for size_limits in [
+            make_limits(Some(500.0), Some(400.0), None, None),
+            make_limits(None, None, Some(300.0), Some(200.0)),
+        ] {
+            let result = WindowDefinition::builder(WindowType::Normal)
+                .size_limits(size_limits)
+                .build();
+            assert!(matches!(
+                result,
+                Err(WindowDefinitionError::InvertedSizeLimits)
+            ));
+        }
+        // Equal limits, or a limit on one side only, are fine
+        for size_limits in [
+            make_limits(Some(400.0), Some(400.0), None, Some(200.0)),
+            make_limits(Some(500.0), None, None, Some(100.0)),
+        ] {
+            assert!(WindowDefinition::builder(WindowType::Normal)
+                .size_limits(size_limits)
+                .build()
+                .is_ok());
+        }
+    }
+
+    /** A definition with every kind of field set away from its default */
+    #[cfg(feature = "serde")]
+    fn make_custom_definition() -> WindowDefinition {
+        WindowDefinition::builder(WindowType::GameWindow)
+            .position(10.0, 20.5)
+            .size(1280.0, 720.0)
+            .title("Seraph \"test\" window")
+            .transparency_support(WindowTransparency::PerWindow)
+            .activation_policy(WindowActivationPolicy::FirstShown)
+            .opacity(0.75)
+            .corner_radius(8)
+            .size_limits(WindowSizeLimits {
+                min_width: Some(640.0),
+                min_height: Some(360.0),
+                max_width: None,
+                max_height: Some(2160.0),
+            })
+            .build()
+            .unwrap()
+    }
+
+    /** A file under the temp directory, removed again when the test is done */
+    #[cfg(feature = "serde")]
+    struct TempFile(std::path::PathBuf);
+
+    #[cfg(feature = "serde")]
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> TempFile {
+            let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn toml_round_trips() {
+        let definition = make_custom_definition();
+        let text = toml::to_string(&definition).unwrap();
+        assert_eq!(WindowDefinition::from_toml_str(&text).unwrap(), definition);
+
+        // Missing fields keep their default
+        let definition =
+            WindowDefinition::from_toml_str("title = \"Tools\"\nopacity = 0.5\n").unwrap();
+        assert_eq!(
+            definition,
+            WindowDefinition {
+                title: "Tools".to_string(),
+                opacity: 0.5,
+                ..WindowDefinition::default()
+            }
+        );
+
+        assert!(matches!(
+            WindowDefinition::from_toml_str("opacity = 2.0"),
+            Err(WindowDefinitionError::InvalidOpacity(..))
+        ));
+        assert!(matches!(
+            WindowDefinition::from_toml_str("opacity = \"opaque\""),
+            Err(WindowDefinitionError::Parse(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ron_round_trips() {
+        let definition = make_custom_definition();
+        let text = ron::to_string(&definition).unwrap();
+        assert_eq!(WindowDefinition::from_ron_str(&text).unwrap(), definition);
+
+        let definition =
+            WindowDefinition::from_ron_str("(window_type: Menu, has_sizing_frame: false)").unwrap();
+        assert_eq!(definition.window_type, WindowType::Menu);
+        assert!(matches!(
+            WindowDefinition::from_ron_str("(window_type: Menu)"),
+            Err(WindowDefinitionError::SizingFrameNotSupported(
+                WindowType::Menu
+            ))
+        ));
+        assert!(matches!(
+            WindowDefinition::from_ron_str("(window_type: Sideways)"),
+            Err(WindowDefinitionError::Parse(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn load_picks_the_format_from_the_extension() {
+        let definition = make_custom_definition();
+        let toml_file = TempFile::new("window.TOML", &toml::to_string(&definition).unwrap());
+        assert_eq!(WindowDefinition::load(&toml_file.0).unwrap(), definition);
+        let ron_file = TempFile::new("window.ron", &ron::to_string(&definition).unwrap());
+        assert_eq!(WindowDefinition::load(&ron_file.0).unwrap(), definition);
+
+        // The contents do not matter when the extension is unknown
+        for name in ["window.json", "window"] {
+            let file = TempFile::new(name, &toml::to_string(&definition).unwrap());
+            assert!(matches!(
+                WindowDefinition::load(&file.0),
+                Err(WindowDefinitionError::UnknownFormat)
+            ));
+        }
+
+        let missing_file =
+            std::env::temp_dir().join(format!("{}-missing.toml", std::process::id()));
+        assert!(matches!(
+            WindowDefinition::load(&missing_file),
+            Err(WindowDefinitionError::Io(_))
+        ));
+    }
+}
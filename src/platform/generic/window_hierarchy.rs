@@ -0,0 +1,302 @@
+use crate::generic::application_message_handler::ApplicationMessageHandler;
+use crate::generic::window::GenericWindow;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::ptr;
+use std::rc::{Rc, Weak};
+
+/**
+ * Where a window sits in the ownership tree. Every backend window keeps one and returns it from
+ * GenericWindow::get_hierarchy; set_parent links two windows. Owned windows stay above their owner, minimize and
+ * restore with it, and a visible modal window blocks user input to its whole owner chain.
+ */
+#[derive(Default)]
+pub struct WindowHierarchy {
+    parent: RefCell<Option<Weak<dyn GenericWindow>>>,
+    children: RefCell<Vec<Weak<dyn GenericWindow>>>,
+    /** Set when the window was minimized because its parent was, so restoring the parent restores it too */
+    is_minimized_with_parent: Cell<bool>,
+}
+
+impl fmt::Debug for WindowHierarchy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowHierarchy")
+            .field("has_parent", &self.get_parent().is_some())
+            .field("num_children", &self.get_children().len())
+            .field("is_minimized_with_parent", &self.is_minimized_with_parent)
+            .finish()
+    }
+}
+
+impl WindowHierarchy {
+    pub fn new() -> WindowHierarchy {
+        WindowHierarchy::default()
+    }
+    /** The owner of the window, None for a top-level window or once the owner is gone */
+    pub fn get_parent(&self) -> Option<Rc<dyn GenericWindow>> {
+        self.parent.borrow().as_ref().and_then(Weak::upgrade)
+    }
+    /** The windows the window owns, oldest first. Windows that were dropped are left out. */
+    pub fn get_children(&self) -> Vec<Rc<dyn GenericWindow>> {
+        let mut children = self.children.borrow_mut();
+        children.retain(|child| child.strong_count() > 0);
+        children.iter().filter_map(Weak::upgrade).collect()
+    }
+}
+
+/** Whether two handles point at the same window. The vtable is ignored, since one type can have several. */
+pub fn is_same_window(a: &Rc<dyn GenericWindow>, b: &Rc<dyn GenericWindow>) -> bool {
+    ptr::addr_eq(Rc::as_ptr(a), Rc::as_ptr(b))
+}
+
+/**
+ * Makes parent the owner of child, or makes child a top-level window if parent is None. Returns false and changes
+ * nothing if parent is child itself or one of the windows child owns, since the owner chain must not loop.
+ */
+pub fn set_parent(child: &Rc<dyn GenericWindow>, parent: Option<&Rc<dyn GenericWindow>>) -> bool {
+    if let Some(parent) = parent {
+        if is_same_window(parent, child) || is_descendant_of(parent, child) {
+            return false;
+        }
+    }
+    let hierarchy = child.get_hierarchy();
+    if let Some(old_parent) = hierarchy.get_parent() {
+        old_parent
+            .get_hierarchy()
+            .children
+            .borrow_mut()
+            .retain(|sibling| !ptr::addr_eq(sibling.as_ptr(), Rc::as_ptr(child)));
+    }
+    *hierarchy.parent.borrow_mut() = parent.map(Rc::downgrade);
+    if let Some(parent) = parent {
+        parent
+            .get_hierarchy()
+            .children
+            .borrow_mut()
+            .push(Rc::downgrade(child));
+    }
+    true
+}
+
+/** Whether ancestor owns window, directly or through other owned windows */
+pub fn is_descendant_of(window: &Rc<dyn GenericWindow>, ancestor: &Rc<dyn GenericWindow>) -> bool {
+    let mut parent = window.get_parent();
+    while let Some(window) = parent {
+        if is_same_window(&window, ancestor) {
+            return true;
+        }
+        parent = window.get_parent();
+    }
+    false
+}
+
+/**
+ * The visible modal window that blocks input to the window, None if nothing does. A modal window blocks its owner
+ * and everything above it in the owner chain, but not itself or its siblings. When modal windows are nested, the
+ * innermost one is returned, since that is the one the user has to deal with first.
+ */
+pub fn get_blocking_modal_window(window: &Rc<dyn GenericWindow>) -> Option<Rc<dyn GenericWindow>> {
+    let mut blocking_window = None;
+    for child in window.get_children() {
+        if !child.is_visible() {
+            continue;
+        }
+        if let Some(nested_window) = get_blocking_modal_window(&child) {
+            return Some(nested_window);
+        }
+        if child.get_definition().is_modal_window && blocking_window.is_none() {
+            blocking_window = Some(child);
+        }
+    }
+    blocking_window
+}
+
+/**
+ * Whether the backends should deliver user input to the window: it must not be blocked by a modal window, and the
+ * message handler must want it.
+ */
+pub fn should_process_user_input(
+    message_handler: &Rc<dyn ApplicationMessageHandler>,
+    window: &Rc<dyn GenericWindow>,
+) -> bool {
+    get_blocking_modal_window(window).is_none()
+        && message_handler.should_process_user_input_messages(window)
+}
+
+/**
+ * Minimizes the visible windows the window owns, for backends where the OS does not do it. Each child remembers
+ * that it went with its parent, so restore_children only brings back those.
+ */
+pub fn minimize_children(window: &dyn GenericWindow) {
+    for child in window.get_children() {
+        if child.is_visible() && !child.is_minimized() {
+            child.get_hierarchy().is_minimized_with_parent.set(true);
+            child.minimize();
+        }
+    }
+}
+
+/** Raises the visible owned windows above the window, which has just been raised, for backends where the OS does not */
+pub fn bring_children_to_front(window: &dyn GenericWindow) {
+    for child in window.get_children() {
+        if child.is_visible() && !child.is_minimized() {
+            child.bring_to_front(false);
+        }
+    }
+}
+
+/** Restores the owned windows that minimize_children minimized */
+pub fn restore_children(window: &dyn GenericWindow) {
+    for child in window.get_children() {
+        if child
+            .get_hierarchy()
+            .is_minimized_with_parent
+            .replace(false)
+        {
+            child.restore();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::window_definition::WindowDefinition;
+    use crate::headless::application::HeadlessApplication;
+    use crate::headless::window::HeadlessWindow;
+
+    /** Keeps every default, like a handler that only cares about a few callbacks */
+    struct DefaultMessageHandler;
+
+    impl ApplicationMessageHandler for DefaultMessageHandler {}
+
+    #[test]
+    fn default_handler_takes_input_unless_a_modal_window_blocks_it() {
+        let message_handler: Rc<dyn ApplicationMessageHandler> = Rc::new(DefaultMessageHandler);
+        let application = HeadlessApplication::new(&message_handler);
+        let parent = application.make_window(&Rc::new(WindowDefinition::default()), None, true);
+        let generic_parent: Rc<dyn GenericWindow> = parent.clone();
+        assert!(should_process_user_input(&message_handler, &generic_parent));
+
+        let modal_definition = WindowDefinition {
+            is_modal_window: true,
+            ..WindowDefinition::default()
+        };
+        let modal = application.make_window(&Rc::new(modal_definition), Some(&parent), true);
+        let generic_modal: Rc<dyn GenericWindow> = modal.clone();
+        assert!(!should_process_user_input(
+            &message_handler,
+            &generic_parent
+        ));
+        assert!(should_process_user_input(&message_handler, &generic_modal));
+    }
+
+    fn make_modal_definition() -> Rc<WindowDefinition> {
+        Rc::new(WindowDefinition {
+            is_modal_window: true,
+            ..WindowDefinition::default()
+        })
+    }
+
+    fn as_generic(window: &Rc<HeadlessWindow>) -> Rc<dyn GenericWindow> {
+        window.clone()
+    }
+
+    fn get_blocking_handle(window: &Rc<HeadlessWindow>) -> Option<usize> {
+        get_blocking_modal_window(&as_generic(window))
+            .map(|modal| modal.get_os_window_handle() as usize)
+    }
+
+    #[test]
+    fn set_parent_rejects_cycles() {
+        let message_handler: Rc<dyn ApplicationMessageHandler> = Rc::new(DefaultMessageHandler);
+        let application = HeadlessApplication::new(&message_handler);
+        let definition = Rc::new(WindowDefinition::default());
+        let root = as_generic(&application.make_window(&definition, None, true));
+        let child = as_generic(&application.make_window(&definition, None, true));
+        let grandchild = as_generic(&application.make_window(&definition, None, true));
+        assert!(set_parent(&child, Some(&root)));
+        assert!(set_parent(&grandchild, Some(&child)));
+
+        assert!(!set_parent(&root, Some(&root)));
+        assert!(!set_parent(&root, Some(&child)));
+        assert!(!set_parent(&root, Some(&grandchild)));
+        assert!(!set_parent(&child, Some(&grandchild)));
+        // A rejected call leaves the tree as it was
+        assert!(root.get_parent().is_none());
+        assert!(is_same_window(&child.get_parent().unwrap(), &root));
+        assert_eq!(root.get_children().len(), 1);
+        assert!(get_blocking_modal_window(&root).is_none());
+
+        // Moving a window elsewhere in the tree, or making it top-level, is fine
+        assert!(set_parent(&grandchild, Some(&root)));
+        assert!(set_parent(&child, Some(&grandchild)));
+        assert!(is_descendant_of(&child, &root));
+        assert!(set_parent(&child, None));
+        assert!(!is_descendant_of(&child, &root));
+        assert_eq!(root.get_children().len(), 1);
+        assert!(grandchild.get_children().is_empty());
+    }
+
+    #[test]
+    fn innermost_visible_modal_window_blocks_the_owner_chain() {
+        let message_handler: Rc<dyn ApplicationMessageHandler> = Rc::new(DefaultMessageHandler);
+        let application = HeadlessApplication::new(&message_handler);
+        let definition = Rc::new(WindowDefinition::default());
+        let root = application.make_window(&definition, None, true);
+        let modal = application.make_window(&make_modal_definition(), Some(&root), true);
+        let sibling = application.make_window(&definition, Some(&root), true);
+        let nested_modal = application.make_window(&make_modal_definition(), Some(&modal), true);
+
+        assert_eq!(get_blocking_handle(&root), Some(nested_modal.get_handle()));
+        assert_eq!(get_blocking_handle(&modal), Some(nested_modal.get_handle()));
+        assert_eq!(get_blocking_handle(&nested_modal), None);
+        // A modal window only blocks its owners, not the other windows they own
+        assert_eq!(get_blocking_handle(&sibling), None);
+
+        // Hidden modal windows do not block
+        nested_modal.hide();
+        assert_eq!(get_blocking_handle(&root), Some(modal.get_handle()));
+        assert_eq!(get_blocking_handle(&modal), None);
+        modal.hide();
+        assert_eq!(get_blocking_handle(&root), None);
+
+        // Nor do visible windows that are not modal
+        let _invisible_modal =
+            application.make_window(&make_modal_definition(), Some(&sibling), false);
+        let _owned_window = application.make_window(&definition, Some(&sibling), true);
+        assert_eq!(get_blocking_handle(&sibling), None);
+        assert_eq!(get_blocking_handle(&root), None);
+    }
+
+    #[test]
+    fn restore_brings_back_only_the_windows_minimized_with_the_parent() {
+        let message_handler: Rc<dyn ApplicationMessageHandler> = Rc::new(DefaultMessageHandler);
+        let application = HeadlessApplication::new(&message_handler);
+        let definition = Rc::new(WindowDefinition::default());
+        let root = application.make_window(&definition, None, true);
+        let child = application.make_window(&definition, Some(&root), true);
+        let grandchild = application.make_window(&definition, Some(&child), true);
+        let minimized_child = application.make_window(&definition, Some(&root), true);
+        minimized_child.minimize();
+        let hidden_child = application.make_window(&definition, Some(&root), false);
+
+        root.minimize();
+        assert!(child.is_minimized());
+        assert!(grandchild.is_minimized());
+        assert!(minimized_child.is_minimized());
+        assert!(!hidden_child.is_minimized());
+
+        root.restore();
+        assert!(!root.is_minimized());
+        assert!(!child.is_minimized());
+        assert!(!grandchild.is_minimized());
+        // The user minimized it before the parent, so it stays minimized
+        assert!(minimized_child.is_minimized());
+        assert!(!hidden_child.is_minimized());
+
+        // The flags were cleared, so a second restore changes nothing
+        restore_children(&*root);
+        assert!(minimized_child.is_minimized());
+    }
+}
@@ -0,0 +1,314 @@
+use crate::generic::application::PlatformRect;
+use crate::generic::application_message_handler::{ApplicationMessageHandler, WindowZone};
+use crate::generic::window::{GenericWindow, WindowMode};
+use std::rc::Rc;
+
+/** How wide the resize border of a window without an OS border is when the application does not say */
+pub const DEFAULT_RESIZE_BORDER_THICKNESS: i32 = 5;
+
+/** A minimize, maximize, close or system menu button the application draws itself */
+#[derive(Clone, PartialEq, Debug)]
+pub struct CaptionButton {
+    pub zone: WindowZone,
+    /** Where the button is, in client coordinates */
+    pub rect: PlatformRect,
+}
+
+/** The parts of a custom window frame that the OS should treat like its own. All sizes are in client pixels. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct WindowChrome {
+    /** How far in from each edge the window can be resized, 0 for no resize border */
+    pub resize_border_thickness: i32,
+    /** How far down from the top the window can be dragged, 0 for no title bar */
+    pub title_bar_height: i32,
+    /** Buttons inside the title bar, which take precedence over everything else */
+    pub caption_buttons: Vec<CaptionButton>,
+}
+
+impl Default for WindowChrome {
+    fn default() -> WindowChrome {
+        WindowChrome {
+            resize_border_thickness: DEFAULT_RESIZE_BORDER_THICKNESS,
+            title_bar_height: 0,
+            caption_buttons: Vec::new(),
+        }
+    }
+}
+
+impl WindowChrome {
+    /** The border zone under a point of a client area of the given size, None away from the border */
+    fn get_border_zone(&self, width: i32, height: i32, x: i32, y: i32) -> Option<WindowZone> {
+        let thickness = self.resize_border_thickness;
+        if thickness <= 0 {
+            return None;
+        }
+        let is_left = x < thickness;
+        let is_right = x >= width - thickness;
+        let is_top = y < thickness;
+        let is_bottom = y >= height - thickness;
+        match (is_left, is_right, is_top, is_bottom) {
+            (true, _, true, _) => Some(WindowZone::TopLeftBorder),
+            (_, true, true, _) => Some(WindowZone::TopRightBorder),
+            (true, _, _, true) => Some(WindowZone::BottomLeftBorder),
+            (_, true, _, true) => Some(WindowZone::BottomRightBorder),
+            (true, _, _, _) => Some(WindowZone::LeftBorder),
+            (_, true, _, _) => Some(WindowZone::RightBorder),
+            (_, _, true, _) => Some(WindowZone::TopBorder),
+            (_, _, _, true) => Some(WindowZone::BottomBorder),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Finds the zone under a point in client coordinates of a window with a client area of the given size.
+ * Caption buttons come first, then the resize border when can_resize is set, then the zone get_handler_zone
+ * returns, and finally the title bar. get_handler_zone is only called if the chrome does not decide, and its
+ * NotInWindow means no opinion; whatever is left is client area.
+ */
+pub fn hit_test(
+    chrome: &WindowChrome,
+    client_width: i32,
+    client_height: i32,
+    x: i32,
+    y: i32,
+    can_resize: bool,
+    get_handler_zone: impl FnOnce() -> WindowZone,
+) -> WindowZone {
+    if x < 0 || y < 0 || x >= client_width || y >= client_height {
+        return WindowZone::NotInWindow;
+    }
+    if let Some(button) = chrome
+        .caption_buttons
+        .iter()
+        .find(|button| button.rect.contains_point(x, y))
+    {
+        return button.zone;
+    }
+    if can_resize {
+        if let Some(zone) = chrome.get_border_zone(client_width, client_height, x, y) {
+            return zone;
+        }
+    }
+    match get_handler_zone() {
+        WindowZone::NotInWindow => {}
+        zone => return zone,
+    }
+    if y < chrome.title_bar_height {
+        WindowZone::TitleBar
+    } else {
+        WindowZone::ClientArea
+    }
+}
+
+/**
+ * Finds the zone under a point in client coordinates of a window without an OS border, asking the message handler
+ * for the chrome and for its own answer. Only windows with a sizing frame that are neither maximized nor fullscreen
+ * get a resize border.
+ */
+pub fn get_window_zone_for_point(
+    message_handler: &Rc<dyn ApplicationMessageHandler>,
+    window: &Rc<dyn GenericWindow>,
+    client_width: i32,
+    client_height: i32,
+    x: i32,
+    y: i32,
+) -> WindowZone {
+    let chrome = message_handler.get_window_chrome(window);
+    let can_resize = window.get_definition().has_sizing_frame
+        && window.get_window_mode() == WindowMode::Windowed
+        && !window.is_maximized();
+    hit_test(
+        &chrome,
+        client_width,
+        client_height,
+        x,
+        y,
+        can_resize,
+        || message_handler.get_window_zone_for_point(window, x, y),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    const CLIENT_WIDTH: i32 = 200;
+    const CLIENT_HEIGHT: i32 = 150;
+
+    /** A 200x150 window with a 30 pixel title bar and three buttons that stop short of the right border */
+    fn make_chrome() -> WindowChrome {
+        WindowChrome {
+            resize_border_thickness: 5,
+            title_bar_height: 30,
+            caption_buttons: vec![
+                CaptionButton {
+                    zone: WindowZone::MinimizeButton,
+                    rect: PlatformRect::new(105, 0, 135, 30),
+                },
+                CaptionButton {
+                    zone: WindowZone::MaximizeButton,
+                    rect: PlatformRect::new(135, 0, 165, 30),
+                },
+                CaptionButton {
+                    zone: WindowZone::CloseButton,
+                    rect: PlatformRect::new(165, 0, 195, 30),
+                },
+            ],
+        }
+    }
+
+    /** Checks every point against the zone it expects, with the handler giving handler_zone when it is asked */
+    fn check_table(chrome: &WindowChrome, table: &[(i32, i32, bool, WindowZone, WindowZone)]) {
+        for &(x, y, can_resize, handler_zone, expected_zone) in table {
+            let zone = hit_test(
+                chrome,
+                CLIENT_WIDTH,
+                CLIENT_HEIGHT,
+                x,
+                y,
+                can_resize,
+                || handler_zone,
+            );
+            assert_eq!(
+                zone, expected_zone,
+                "({}, {}) can_resize {} handler {:?}",
+                x, y, can_resize, handler_zone
+            );
+        }
+    }
+
+    #[test]
+    fn points_outside_the_client_area_are_not_in_the_window() {
+        use WindowZone::*;
+        check_table(
+            &make_chrome(),
+            &[
+                (-1, 10, true, ClientArea, NotInWindow),
+                (10, -1, true, ClientArea, NotInWindow),
+                (CLIENT_WIDTH, 10, true, ClientArea, NotInWindow),
+                (10, CLIENT_HEIGHT, true, ClientArea, NotInWindow),
+                (-50, -50, false, TitleBar, NotInWindow),
+            ],
+        );
+    }
+
+    #[test]
+    fn resizable_windows_have_borders_and_corners() {
+        use WindowZone::*;
+        check_table(
+            &make_chrome(),
+            &[
+                (0, 0, true, NotInWindow, TopLeftBorder),
+                (4, 4, true, NotInWindow, TopLeftBorder),
+                (197, 2, true, NotInWindow, TopRightBorder),
+                (0, 149, true, NotInWindow, BottomLeftBorder),
+                (199, 149, true, NotInWindow, BottomRightBorder),
+                (195, 145, true, NotInWindow, BottomRightBorder),
+                (0, 75, true, NotInWindow, LeftBorder),
+                (199, 75, true, NotInWindow, RightBorder),
+                (50, 0, true, NotInWindow, TopBorder),
+                (50, 149, true, NotInWindow, BottomBorder),
+                // Just inside the border
+                (5, 5, true, NotInWindow, TitleBar),
+                (194, 144, true, NotInWindow, ClientArea),
+            ],
+        );
+    }
+
+    #[test]
+    fn zones_follow_the_documented_precedence() {
+        use WindowZone::*;
+        check_table(
+            &make_chrome(),
+            &[
+                // Caption buttons beat the resize border and the handler
+                (190, 2, true, ClientArea, CloseButton),
+                (120, 0, true, TitleBar, MinimizeButton),
+                (150, 29, false, NotInWindow, MaximizeButton),
+                // The border beats the handler, but only when the window can be resized
+                (0, 0, true, ClientArea, TopLeftBorder),
+                (0, 0, false, NotInWindow, TitleBar),
+                (0, 75, false, NotInWindow, ClientArea),
+                (50, 149, false, NotInWindow, ClientArea),
+                // The handler beats the title bar and the client area, unless it has no opinion
+                (50, 10, true, ClientArea, ClientArea),
+                (50, 100, true, TitleBar, TitleBar),
+                (50, 100, true, CloseButton, CloseButton),
+                (50, 10, true, NotInWindow, TitleBar),
+                (50, 29, true, NotInWindow, TitleBar),
+                (50, 30, true, NotInWindow, ClientArea),
+            ],
+        );
+    }
+
+    #[test]
+    fn handler_is_asked_only_when_the_chrome_does_not_decide() {
+        let chrome = make_chrome();
+        for (x, y, can_resize, is_handler_asked) in [
+            (190, 2, true, false),
+            (0, 75, true, false),
+            (0, 75, false, true),
+            (50, 10, true, true),
+            (-1, 10, true, false),
+        ] {
+            let was_asked = Cell::new(false);
+            hit_test(
+                &chrome,
+                CLIENT_WIDTH,
+                CLIENT_HEIGHT,
+                x,
+                y,
+                can_resize,
+                || {
+                    was_asked.set(true);
+                    WindowZone::NotInWindow
+                },
+            );
+            assert_eq!(was_asked.get(), is_handler_asked, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn chrome_without_border_or_title_bar_is_all_client_area() {
+        use WindowZone::*;
+        let chrome = WindowChrome {
+            resize_border_thickness: 0,
+            title_bar_height: 0,
+            caption_buttons: vec![],
+        };
+        check_table(
+            &chrome,
+            &[
+                (0, 0, true, NotInWindow, ClientArea),
+                (199, 149, true, NotInWindow, ClientArea),
+                (50, 10, true, NotInWindow, ClientArea),
+            ],
+        );
+        // The default chrome has a border but no title bar
+        check_table(
+            &WindowChrome::default(),
+            &[
+                (0, 0, true, NotInWindow, TopLeftBorder),
+                (50, 10, true, NotInWindow, ClientArea),
+            ],
+        );
+    }
+
+    #[test]
+    fn border_wider_than_the_window_picks_a_corner() {
+        // Every point of a 6x6 window is near two opposite borders, the top left corner is checked first
+        let chrome = make_chrome();
+        for (x, y, expected_zone) in [
+            (0, 0, WindowZone::TopLeftBorder),
+            (3, 3, WindowZone::TopLeftBorder),
+            (5, 5, WindowZone::BottomRightBorder),
+        ] {
+            assert_eq!(
+                hit_test(&chrome, 6, 6, x, y, true, || WindowZone::NotInWindow),
+                expected_zone
+            );
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use crate::generic::application::PlatformRect;
+use crate::generic::application_message_handler::WindowSizeLimits;
+
+/** The edge or corner of a window the user is dragging to resize it */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ResizeEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/** Which way an axis grows while resizing: from the dragged side, or evenly from the center */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Anchor {
+    /** The left or top edge is dragged, so the right or bottom edge stays put */
+    End,
+    /** The right or bottom edge is dragged, so the left or top edge stays put */
+    Start,
+    /** The other axis is dragged; this one grows or shrinks around its center to keep the aspect ratio */
+    Center,
+}
+
+impl ResizeEdge {
+    fn get_horizontal_anchor(self) -> Anchor {
+        match self {
+            ResizeEdge::Left | ResizeEdge::TopLeft | ResizeEdge::BottomLeft => Anchor::End,
+            ResizeEdge::Right | ResizeEdge::TopRight | ResizeEdge::BottomRight => Anchor::Start,
+            ResizeEdge::Top | ResizeEdge::Bottom => Anchor::Center,
+        }
+    }
+    fn get_vertical_anchor(self) -> Anchor {
+        match self {
+            ResizeEdge::Top | ResizeEdge::TopLeft | ResizeEdge::TopRight => Anchor::End,
+            ResizeEdge::Bottom | ResizeEdge::BottomLeft | ResizeEdge::BottomRight => Anchor::Start,
+            ResizeEdge::Left | ResizeEdge::Right => Anchor::Center,
+        }
+    }
+}
+
+/** How far the window frame (border and title bar) reaches past each side of the client area, in pixels */
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct FrameInsets {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+/** Everything that limits the size of a window while the user resizes it */
+#[derive(Clone, PartialEq, Debug)]
+pub struct SizingConstraints {
+    /** The frame around the client area. Limits and aspect ratio apply to the client area only. */
+    pub frame: FrameInsets,
+    /** Client size limits in DPI independent units, like WindowDefinition::size_limits */
+    pub size_limits: WindowSizeLimits,
+    /** Client width divided by client height to keep, None to resize freely */
+    pub aspect_ratio: Option<f32>,
+    /** Pixels per DPI independent unit, which scales the size limits. Anything but a positive number counts as 1. */
+    pub dpi_scale: f32,
+}
+
+impl Default for SizingConstraints {
+    fn default() -> SizingConstraints {
+        SizingConstraints {
+            frame: FrameInsets::default(),
+            size_limits: WindowSizeLimits::default(),
+            aspect_ratio: None,
+            dpi_scale: 1.0,
+        }
+    }
+}
+
+/** The smallest and largest client sizes in pixels. A minimum above the maximum wins, and sizes are at least 1. */
+fn get_size_range(min: Option<f32>, max: Option<f32>, dpi_scale: f32) -> (f32, f32) {
+    let min = min.map_or(1.0, |min| (min * dpi_scale).max(1.0));
+    let max = max.map_or(f32::MAX, |max| (max * dpi_scale).max(min));
+    (min, max)
+}
+
+/** Places a span of the given length on the axis so the anchored side of the proposed span stays where it is */
+fn place_span(start: i32, end: i32, length: i32, anchor: Anchor) -> (i32, i32) {
+    match anchor {
+        Anchor::Start => (start, start + length),
+        Anchor::End => (end - length, end),
+        Anchor::Center => {
+            let new_start = start + (end - start - length).div_euclid(2);
+            (new_start, new_start + length)
+        }
+    }
+}
+
+/**
+ * Constrains the window rect the OS proposes while the user drags the edge. The client area is kept within the
+ * size limits and, when there is one, at the aspect ratio; the side opposite the dragged edge stays put.
+ *
+ * With an aspect ratio, dragging a side edge sets the width and the height follows, centered, and the other way
+ * round for the top and bottom edges. Dragging a corner uses whichever of width and height needs the larger
+ * window, so the corner does not fall behind the cursor. When the limits and the aspect ratio cannot all be met,
+ * the minimum size is kept and the aspect ratio before the maximum.
+ */
+pub fn constrain_window_rect(
+    edge: ResizeEdge,
+    proposed_window_rect: &PlatformRect,
+    constraints: &SizingConstraints,
+) -> PlatformRect {
+    let frame = &constraints.frame;
+    let client_rect = PlatformRect::new(
+        proposed_window_rect.left + frame.left,
+        proposed_window_rect.top + frame.top,
+        proposed_window_rect.right - frame.right,
+        proposed_window_rect.bottom - frame.bottom,
+    );
+    let proposed_width = client_rect.get_width().max(1) as f32;
+    let proposed_height = client_rect.get_height().max(1) as f32;
+
+    let size_limits = &constraints.size_limits;
+    let dpi_scale = if constraints.dpi_scale.is_finite() && constraints.dpi_scale > 0.0 {
+        constraints.dpi_scale
+    } else {
+        1.0
+    };
+    let (min_width, max_width) = get_size_range(
+        size_limits.get_min_width(),
+        size_limits.get_max_width(),
+        dpi_scale,
+    );
+    let (min_height, max_height) = get_size_range(
+        size_limits.get_min_height(),
+        size_limits.get_max_height(),
+        dpi_scale,
+    );
+
+    let (width, height) = match constraints
+        .aspect_ratio
+        .filter(|aspect_ratio| aspect_ratio.is_finite() && *aspect_ratio > 0.0)
+    {
+        Some(aspect_ratio) => {
+            let desired_width = match edge {
+                ResizeEdge::Left | ResizeEdge::Right => proposed_width,
+                ResizeEdge::Top | ResizeEdge::Bottom => proposed_height * aspect_ratio,
+                _ => proposed_width.max(proposed_height * aspect_ratio),
+            };
+            // The widths whose matching height is also within its limits
+            let lowest_width = min_width.max(min_height * aspect_ratio);
+            let highest_width = max_width.min(max_height * aspect_ratio).max(lowest_width);
+            let width = desired_width.clamp(lowest_width, highest_width).round();
+            let height = (width / aspect_ratio).round().max(1.0);
+            (width as i32, height as i32)
+        }
+        None => (
+            proposed_width.clamp(min_width, max_width).round() as i32,
+            proposed_height.clamp(min_height, max_height).round() as i32,
+        ),
+    };
+
+    let (left, right) = place_span(
+        client_rect.left,
+        client_rect.right,
+        width,
+        edge.get_horizontal_anchor(),
+    );
+    let (top, bottom) = place_span(
+        client_rect.top,
+        client_rect.bottom,
+        height,
+        edge.get_vertical_anchor(),
+    );
+    PlatformRect::new(
+        left - frame.left,
+        top - frame.top,
+        right + frame.right,
+        bottom + frame.bottom,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_EDGES: [ResizeEdge; 8] = [
+        ResizeEdge::Left,
+        ResizeEdge::Right,
+        ResizeEdge::Top,
+        ResizeEdge::Bottom,
+        ResizeEdge::TopLeft,
+        ResizeEdge::TopRight,
+        ResizeEdge::BottomLeft,
+        ResizeEdge::BottomRight,
+    ];
+
+    /** A frame with a title bar, uneven so that mixing up two sides shows */
+    const FRAME: FrameInsets = FrameInsets {
+        left: 8,
+        top: 31,
+        right: 6,
+        bottom: 4,
+    };
+
+    /** The window rect around a client rect given as left, top, right, bottom */
+    fn window_rect((left, top, right, bottom): (i32, i32, i32, i32)) -> PlatformRect {
+        PlatformRect::new(
+            left - FRAME.left,
+            top - FRAME.top,
+            right + FRAME.right,
+            bottom + FRAME.bottom,
+        )
+    }
+
+    fn make_constraints(
+        min_size: (Option<f32>, Option<f32>),
+        max_size: (Option<f32>, Option<f32>),
+        aspect_ratio: Option<f32>,
+        dpi_scale: f32,
+    ) -> SizingConstraints {
+        SizingConstraints {
+            frame: FRAME,
+            size_limits: WindowSizeLimits {
+                min_width: min_size.0,
+                min_height: min_size.1,
+                max_width: max_size.0,
+                max_height: max_size.1,
+            },
+            aspect_ratio,
+            dpi_scale,
+        }
+    }
+
+    /** Constrains the window around the proposed client rect and returns the client rect of the result */
+    fn constrain_client(
+        edge: ResizeEdge,
+        proposed_client_rect: (i32, i32, i32, i32),
+        constraints: &SizingConstraints,
+    ) -> PlatformRect {
+        let window = constrain_window_rect(edge, &window_rect(proposed_client_rect), constraints);
+        PlatformRect::new(
+            window.left + FRAME.left,
+            window.top + FRAME.top,
+            window.right - FRAME.right,
+            window.bottom - FRAME.bottom,
+        )
+    }
+
+    /** Checks every edge against its expected client rect, comparing whole window rects so the frame counts too */
+    fn check_table(
+        proposed_client_rect: (i32, i32, i32, i32),
+        constraints: &SizingConstraints,
+        table: [(ResizeEdge, (i32, i32, i32, i32)); 8],
+    ) {
+        for (edge, expected_client_rect) in table {
+            assert_eq!(
+                constrain_window_rect(edge, &window_rect(proposed_client_rect), constraints),
+                window_rect(expected_client_rect),
+                "{:?}",
+                edge
+            );
+        }
+    }
+
+    #[test]
+    fn sizes_within_the_limits_are_kept() {
+        let constraints = make_constraints(
+            (Some(200.0), Some(100.0)),
+            (Some(800.0), Some(600.0)),
+            None,
+            1.0,
+        );
+        for edge in ALL_EDGES {
+            assert_eq!(
+                constrain_window_rect(edge, &window_rect((10, 20, 410, 320)), &constraints),
+                window_rect((10, 20, 410, 320)),
+                "{:?}",
+                edge
+            );
+        }
+    }
+
+    #[test]
+    fn growing_to_the_minimum_keeps_the_anchored_side() {
+        // 50x50 against a 200x100 minimum. The dragged side moves, an undragged axis grows around its center.
+        let constraints = make_constraints((Some(200.0), Some(100.0)), (None, None), None, 1.0);
+        check_table(
+            (100, 100, 150, 150),
+            &constraints,
+            [
+                (ResizeEdge::Left, (-50, 75, 150, 175)),
+                (ResizeEdge::Right, (100, 75, 300, 175)),
+                (ResizeEdge::Top, (25, 50, 225, 150)),
+                (ResizeEdge::Bottom, (25, 100, 225, 200)),
+                (ResizeEdge::TopLeft, (-50, 50, 150, 150)),
+                (ResizeEdge::TopRight, (100, 50, 300, 150)),
+                (ResizeEdge::BottomLeft, (-50, 100, 150, 200)),
+                (ResizeEdge::BottomRight, (100, 100, 300, 200)),
+            ],
+        );
+    }
+
+    #[test]
+    fn shrinking_to_the_maximum_keeps_the_anchored_side() {
+        // 1000x800 against an 800x600 maximum
+        let constraints = make_constraints((None, None), (Some(800.0), Some(600.0)), None, 1.0);
+        check_table(
+            (100, 100, 1100, 900),
+            &constraints,
+            [
+                (ResizeEdge::Left, (300, 200, 1100, 800)),
+                (ResizeEdge::Right, (100, 200, 900, 800)),
+                (ResizeEdge::Top, (200, 300, 1000, 900)),
+                (ResizeEdge::Bottom, (200, 100, 1000, 700)),
+                (ResizeEdge::TopLeft, (300, 300, 1100, 900)),
+                (ResizeEdge::TopRight, (100, 300, 900, 900)),
+                (ResizeEdge::BottomLeft, (300, 100, 1100, 700)),
+                (ResizeEdge::BottomRight, (100, 100, 900, 700)),
+            ],
+        );
+    }
+
+    #[test]
+    fn keeps_the_aspect_ratio() {
+        // 640x400 at 16:9. Side edges keep the width, top and bottom the height, corners the larger window.
+        let constraints = make_constraints((None, None), (None, None), Some(16.0 / 9.0), 1.0);
+        check_table(
+            (100, 100, 740, 500),
+            &constraints,
+            [
+                (ResizeEdge::Left, (100, 120, 740, 480)),
+                (ResizeEdge::Right, (100, 120, 740, 480)),
+                (ResizeEdge::Top, (64, 100, 775, 500)),
+                (ResizeEdge::Bottom, (64, 100, 775, 500)),
+                (ResizeEdge::TopLeft, (29, 100, 740, 500)),
+                (ResizeEdge::TopRight, (100, 100, 811, 500)),
+                (ResizeEdge::BottomLeft, (29, 100, 740, 500)),
+                (ResizeEdge::BottomRight, (100, 100, 811, 500)),
+            ],
+        );
+    }
+
+    #[test]
+    fn aspect_ratio_is_kept_in_floating_point() {
+        // Rounding each size on its own would let the ratio drift by a pixel every step. Both sizes come from
+        // the same float, so the ratio stays within the rounding of one pixel whatever the size.
+        for aspect_ratio in [4.0 / 3.0, 16.0 / 9.0, 21.0 / 9.0, 1.0 / 3.0, 0.7] {
+            let constraints = make_constraints((None, None), (None, None), Some(aspect_ratio), 1.0);
+            for size in (101..1000).step_by(37) {
+                for edge in ALL_EDGES {
+                    let client = constrain_client(edge, (0, 0, size, size + 13), &constraints);
+                    let (width, height) = (client.get_width() as f32, client.get_height() as f32);
+                    assert!(
+                        (width / aspect_ratio - height).abs() <= 0.5,
+                        "{:?} {} gave {}x{} at {}",
+                        edge,
+                        size,
+                        width,
+                        height,
+                        aspect_ratio
+                    );
+                }
+            }
+        }
+        // 401 / (4 / 3) is 300.75, which rounds up instead of truncating to 300
+        let constraints = make_constraints((None, None), (None, None), Some(4.0 / 3.0), 1.0);
+        let client = constrain_client(ResizeEdge::Right, (0, 0, 401, 500), &constraints);
+        assert_eq!((client.get_width(), client.get_height()), (401, 301));
+    }
+
+    #[test]
+    fn limits_are_scaled_by_the_dpi_scale() {
+        let constraints = make_constraints(
+            (Some(200.0), Some(100.0)),
+            (Some(800.0), Some(600.0)),
+            None,
+            2.0,
+        );
+        let client = constrain_client(ResizeEdge::BottomRight, (0, 0, 50, 50), &constraints);
+        assert_eq!((client.get_width(), client.get_height()), (400, 200));
+        let client = constrain_client(ResizeEdge::BottomRight, (0, 0, 3000, 3000), &constraints);
+        assert_eq!((client.get_width(), client.get_height()), (1600, 1200));
+
+        let constraints = make_constraints((Some(100.0), None), (None, None), Some(2.0), 1.5);
+        let client = constrain_client(ResizeEdge::Bottom, (0, 0, 10, 10), &constraints);
+        assert_eq!((client.get_width(), client.get_height()), (150, 75));
+    }
+
+    #[test]
+    fn minimum_and_aspect_ratio_win_over_the_maximum() {
+        // A 500 pixel minimum height at 16:9 needs 889 pixels of width, more than the 600 allowed
+        let constraints = make_constraints(
+            (None, Some(500.0)),
+            (Some(600.0), None),
+            Some(16.0 / 9.0),
+            1.0,
+        );
+        for edge in ALL_EDGES {
+            let client = constrain_client(edge, (0, 0, 300, 300), &constraints);
+            assert_eq!(
+                (client.get_width(), client.get_height()),
+                (889, 500),
+                "{:?}",
+                edge
+            );
+        }
+
+        // The minimum width and the aspect ratio decide the height, over the maximum height
+        let constraints =
+            make_constraints((Some(400.0), None), (None, Some(100.0)), Some(2.0), 1.0);
+        let client = constrain_client(ResizeEdge::Top, (0, 0, 50, 50), &constraints);
+        assert_eq!((client.get_width(), client.get_height()), (400, 200));
+    }
+
+    #[test]
+    fn minimum_above_the_maximum_wins() {
+        let constraints = make_constraints(
+            (Some(500.0), Some(400.0)),
+            (Some(300.0), Some(200.0)),
+            None,
+            1.0,
+        );
+        for proposed_size in [100, 350, 1000] {
+            for edge in ALL_EDGES {
+                let client =
+                    constrain_client(edge, (0, 0, proposed_size, proposed_size), &constraints);
+                assert_eq!((client.get_width(), client.get_height()), (500, 400));
+            }
+        }
+        let constraints = SizingConstraints {
+            aspect_ratio: Some(1.0),
+            ..constraints
+        };
+        for edge in ALL_EDGES {
+            let client = constrain_client(edge, (0, 0, 1000, 1000), &constraints);
+            assert_eq!((client.get_width(), client.get_height()), (500, 500));
+        }
+    }
+
+    #[test]
+    fn unusable_aspect_ratios_resize_freely() {
+        let free = make_constraints((Some(200.0), Some(100.0)), (Some(800.0), None), None, 1.0);
+        for aspect_ratio in [0.0, -1.5, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let constraints = SizingConstraints {
+                aspect_ratio: Some(aspect_ratio),
+                ..free.clone()
+            };
+            for edge in ALL_EDGES {
+                for proposed_client_rect in [(0, 0, 50, 50), (0, 0, 640, 400), (0, 0, 1000, 700)] {
+                    assert_eq!(
+                        constrain_client(edge, proposed_client_rect, &constraints),
+                        constrain_client(edge, proposed_client_rect, &free),
+                        "{:?} {}",
+                        edge,
+                        aspect_ratio
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unusable_dpi_scales_count_as_one() {
+        let unscaled = make_constraints(
+            (Some(200.0), Some(100.0)),
+            (Some(800.0), Some(600.0)),
+            Some(1.5),
+            1.0,
+        );
+        for dpi_scale in [0.0, -2.0, f32::NAN, f32::INFINITY] {
+            let constraints = SizingConstraints {
+                dpi_scale,
+                ..unscaled.clone()
+            };
+            for edge in ALL_EDGES {
+                for proposed_client_rect in [(0, 0, 50, 50), (0, 0, 1000, 1000)] {
+                    assert_eq!(
+                        constrain_client(edge, proposed_client_rect, &constraints),
+                        constrain_client(edge, proposed_client_rect, &unscaled),
+                        "{:?} {}",
+                        edge,
+                        dpi_scale
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn window_smaller_than_its_frame_keeps_a_client_pixel() {
+        let constraints = make_constraints((None, None), (None, None), None, 1.0);
+        let client = constrain_client(ResizeEdge::BottomRight, (100, 100, 80, 90), &constraints);
+        assert_eq!(client, PlatformRect::new(100, 100, 101, 101));
+    }
+}
@@ -0,0 +1,477 @@
+use crate::generic::application_message_handler::ApplicationMessageHandler;
+use crate::generic::gamepad::{
+    GamepadAxis, GamepadButton, GamepadSample, GamepadStateTracker, MAX_NUM_CONTROLLER_BUTTONS,
+};
+use crate::generic::iinputinterface::ForceFeedbackValues;
+use crate::generic::platform_event::{
+    forward_to_message_handler, PlatformEvent, PlatformEventQueue,
+};
+use std::cell::{Cell, RefCell, RefMut};
+use std::fmt;
+use std::rc::Rc;
+use std::time::Instant;
+
+pub const MAX_NUM_XINPUT_CONTROLLERS: usize = 4;
+
+pub const XINPUT_GAMEPAD_DPAD_UP: u16 = 0x0001;
+pub const XINPUT_GAMEPAD_DPAD_DOWN: u16 = 0x0002;
+pub const XINPUT_GAMEPAD_DPAD_LEFT: u16 = 0x0004;
+pub const XINPUT_GAMEPAD_DPAD_RIGHT: u16 = 0x0008;
+pub const XINPUT_GAMEPAD_START: u16 = 0x0010;
+pub const XINPUT_GAMEPAD_BACK: u16 = 0x0020;
+pub const XINPUT_GAMEPAD_LEFT_THUMB: u16 = 0x0040;
+pub const XINPUT_GAMEPAD_RIGHT_THUMB: u16 = 0x0080;
+pub const XINPUT_GAMEPAD_LEFT_SHOULDER: u16 = 0x0100;
+pub const XINPUT_GAMEPAD_RIGHT_SHOULDER: u16 = 0x0200;
+pub const XINPUT_GAMEPAD_A: u16 = 0x1000;
+pub const XINPUT_GAMEPAD_B: u16 = 0x2000;
+pub const XINPUT_GAMEPAD_X: u16 = 0x4000;
+pub const XINPUT_GAMEPAD_Y: u16 = 0x8000;
+
+/** The buttons XInput reports, in the order the Xbox 360 controller numbers them. Back comes before Start. */
+const X360_BUTTON_FLAGS: [u16; 14] = [
+    XINPUT_GAMEPAD_A,
+    XINPUT_GAMEPAD_B,
+    XINPUT_GAMEPAD_X,
+    XINPUT_GAMEPAD_Y,
+    XINPUT_GAMEPAD_LEFT_SHOULDER,
+    XINPUT_GAMEPAD_RIGHT_SHOULDER,
+    XINPUT_GAMEPAD_BACK,
+    XINPUT_GAMEPAD_START,
+    XINPUT_GAMEPAD_LEFT_THUMB,
+    XINPUT_GAMEPAD_RIGHT_THUMB,
+    XINPUT_GAMEPAD_DPAD_UP,
+    XINPUT_GAMEPAD_DPAD_DOWN,
+    XINPUT_GAMEPAD_DPAD_LEFT,
+    XINPUT_GAMEPAD_DPAD_RIGHT,
+];
+
+/** The Xbox 360 button number of each of X360_BUTTON_FLAGS; 10 and 11 are the trigger thresholds */
+const X360_BUTTON_INDICES: [usize; 14] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 12, 13, 14, 15];
+
+/** XINPUT_GAMEPAD, the state of the buttons, triggers and sticks of a controller */
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct XInputGamepad {
+    /** XINPUT_GAMEPAD_* flags of the buttons that are down */
+    pub buttons: u16,
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+    pub thumb_lx: i16,
+    pub thumb_ly: i16,
+    pub thumb_rx: i16,
+    pub thumb_ry: i16,
+}
+
+/** XINPUT_STATE, what XInputGetState reads for a connected controller */
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct XInputState {
+    /** Changes whenever the state of the controller does */
+    pub packet_number: u32,
+    pub gamepad: XInputGamepad,
+}
+
+/**
+ * Reads the raw state of the XInput controller slots. On Windows this calls XInputGetState; anything else can
+ * stand in for it, so the mapping and the events can be checked with made-up states on any OS.
+ */
+pub trait XInputStateSource {
+    /** The state of the controller in the slot, None if no controller is connected there */
+    fn get_state(&self, user_index: u32) -> Option<XInputState>;
+}
+
+/** A stick axis from -32768..32767 to -1..1 */
+fn short_to_normalized_float(axis_value: i16) -> f32 {
+    // normalize [-32768..32767] -> [-1..1]
+    let norm = if axis_value <= 0 { 32768.0 } else { 32767.0 };
+    axis_value as f32 / norm
+}
+
+struct ControllerState {
+    /** Id of the controller */
+    controller_id: i32,
+
+    /** If the controller is currently connected */
+    is_connected: bool,
+
+    /** Current force feedback values */
+    force_feedback: ForceFeedbackValues,
+}
+
+/**
+ * Polls the four XInput controller slots and sends their events to the message handler. Button states, repeats and
+ * analog changes are tracked by a GamepadStateTracker; this turns XInput states into its samples.
+ */
+pub struct XInputInterface {
+    state_source: Box<dyn XInputStateSource>,
+    /** Set when a device was plugged in or removed. Empty slots are slow to read, so they are only read then. */
+    needs_controller_state_update: Cell<bool>,
+    is_gamepad_attached: Cell<bool>,
+    /** Which GamepadButton each Xbox 360 button number is reported as */
+    x360_to_xbox_controller_mapping: [u8; MAX_NUM_CONTROLLER_BUTTONS],
+    controller_states: RefCell<[ControllerState; MAX_NUM_XINPUT_CONTROLLERS]>,
+    gamepad_state_tracker: RefCell<GamepadStateTracker>,
+    /** The events of one poll, sent to the message handler once the poll is done */
+    events: PlatformEventQueue,
+    /** What the repeat times count from */
+    start_time: Instant,
+    message_handler: RefCell<Rc<dyn ApplicationMessageHandler>>,
+}
+
+impl fmt::Debug for XInputInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XInputInterface")
+            .field(
+                "needs_controller_state_update",
+                &self.needs_controller_state_update,
+            )
+            .field("is_gamepad_attached", &self.is_gamepad_attached)
+            .field(
+                "connected_controllers",
+                &self
+                    .controller_states
+                    .borrow()
+                    .iter()
+                    .filter(|state| state.is_connected)
+                    .map(|state| state.controller_id)
+                    .collect::<Vec<i32>>(),
+            )
+            .field("gamepad_state_tracker", &self.gamepad_state_tracker)
+            .finish()
+    }
+}
+
+impl XInputInterface {
+    pub fn new(
+        state_source: Box<dyn XInputStateSource>,
+        message_handler: &Rc<dyn ApplicationMessageHandler>,
+    ) -> XInputInterface {
+        let mut x360_to_xbox_controller_mapping = [0; MAX_NUM_CONTROLLER_BUTTONS];
+        for (index, mapping) in x360_to_xbox_controller_mapping.iter_mut().enumerate() {
+            *mapping = index as u8;
+        }
+        // The Xbox 360 controller numbers Back before Start, the other way round from the generic buttons
+        x360_to_xbox_controller_mapping[6] = GamepadButton::SpecialLeft.to_usize() as u8;
+        x360_to_xbox_controller_mapping[7] = GamepadButton::SpecialRight.to_usize() as u8;
+
+        XInputInterface {
+            state_source,
+            // Nothing is known about the slots yet, so read them all on the first poll
+            needs_controller_state_update: Cell::new(true),
+            is_gamepad_attached: Cell::new(false),
+            x360_to_xbox_controller_mapping,
+            controller_states: RefCell::new(std::array::from_fn(|index| ControllerState {
+                controller_id: index as i32,
+                is_connected: false,
+                force_feedback: ForceFeedbackValues::new(),
+            })),
+            gamepad_state_tracker: RefCell::new(GamepadStateTracker::new()),
+            events: PlatformEventQueue::new(),
+            start_time: Instant::now(),
+            message_handler: RefCell::new(message_handler.clone()),
+        }
+    }
+    pub fn set_message_handler(&self, message_handler: &Rc<dyn ApplicationMessageHandler>) {
+        *self.message_handler.borrow_mut() = message_handler.clone();
+    }
+    /** Makes the next poll read the empty slots too. Call it when Windows reports a device change. */
+    pub fn set_needs_controller_state_update(&self) {
+        self.needs_controller_state_update.set(true);
+    }
+    /** Whether a controller was connected at the last poll */
+    pub fn is_gamepad_attached(&self) -> bool {
+        self.is_gamepad_attached.get()
+    }
+    /** The deadzones, trigger threshold and repeat delays the events are made with */
+    pub fn get_gamepad_state_tracker(&self) -> RefMut<'_, GamepadStateTracker> {
+        self.gamepad_state_tracker.borrow_mut()
+    }
+    /** Turns an XInput state into a sample of the generic buttons and axes */
+    pub fn get_gamepad_sample(&self, gamepad: &XInputGamepad) -> GamepadSample {
+        let mut sample = GamepadSample::new();
+        for (flag, x360_index) in X360_BUTTON_FLAGS.iter().zip(X360_BUTTON_INDICES) {
+            let button_index = self.x360_to_xbox_controller_mapping[x360_index] as usize;
+            sample.buttons[button_index] = gamepad.buttons & flag != 0;
+        }
+        sample.set_axis(
+            GamepadAxis::LeftX,
+            short_to_normalized_float(gamepad.thumb_lx),
+        );
+        sample.set_axis(
+            GamepadAxis::LeftY,
+            short_to_normalized_float(gamepad.thumb_ly),
+        );
+        sample.set_axis(
+            GamepadAxis::RightX,
+            short_to_normalized_float(gamepad.thumb_rx),
+        );
+        sample.set_axis(
+            GamepadAxis::RightY,
+            short_to_normalized_float(gamepad.thumb_ry),
+        );
+        sample.set_axis(
+            GamepadAxis::LeftTrigger,
+            gamepad.left_trigger as f32 / 255.0,
+        );
+        sample.set_axis(
+            GamepadAxis::RightTrigger,
+            gamepad.right_trigger as f32 / 255.0,
+        );
+        sample
+    }
+    /** Polls the controllers and sends what changed to the message handler */
+    pub fn send_controller_events(&self) {
+        self.send_controller_events_at(self.start_time.elapsed().as_secs_f64());
+    }
+    /**
+     * send_controller_events with the time given in seconds, for replaying recorded states. The time only has to
+     * grow steadily.
+     */
+    pub fn send_controller_events_at(&self, current_time: f64) {
+        self.poll_controller_states(current_time);
+        // Nothing is borrowed any more, so the handler may poll again or change the settings
+        let message_handler = self.message_handler.borrow().clone();
+        while let Some(event) = self.events.pop() {
+            forward_to_message_handler(event, &message_handler);
+        }
+    }
+    fn poll_controller_states(&self, current_time: f64) {
+        let mut controller_states = self.controller_states.borrow_mut();
+        let mut gamepad_state_tracker = self.gamepad_state_tracker.borrow_mut();
+        let needs_controller_state_update = self.needs_controller_state_update.take();
+        let mut is_gamepad_attached = false;
+        for controller_state in controller_states.iter_mut() {
+            let controller_id = controller_state.controller_id;
+            let was_connected = controller_state.is_connected;
+
+            // Reading a slot with nothing in it stalls, so do it only when devices changed
+            let state = if needs_controller_state_update || was_connected {
+                self.state_source.get_state(controller_id as u32)
+            } else {
+                None
+            };
+            controller_state.is_connected = state.is_some();
+
+            if controller_state.is_connected != was_connected {
+                if !controller_state.is_connected {
+                    // Release whatever it held, so no button stays pressed for good
+                    gamepad_state_tracker.disconnect(controller_id, current_time, &self.events);
+                    controller_state.force_feedback = ForceFeedbackValues::new();
+                }
+                self.events
+                    .push(PlatformEvent::ControllerConnectionChanged {
+                        controller_id,
+                        is_connected: controller_state.is_connected,
+                    });
+            }
+
+            if let Some(state) = state {
+                is_gamepad_attached = true;
+                let sample = self.get_gamepad_sample(&state.gamepad);
+                gamepad_state_tracker.update(controller_id, &sample, current_time, &self.events);
+            }
+        }
+        self.is_gamepad_attached.set(is_gamepad_attached);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::application_message_handler::tests::RecordingMessageHandler;
+
+    /** Stands in for XInputGetState with states the test sets, and writes down which slots were read */
+    #[derive(Default)]
+    struct ScriptedStateSource {
+        states: Rc<RefCell<[Option<XInputState>; MAX_NUM_XINPUT_CONTROLLERS]>>,
+        reads: Rc<RefCell<Vec<u32>>>,
+    }
+
+    impl XInputStateSource for ScriptedStateSource {
+        fn get_state(&self, user_index: u32) -> Option<XInputState> {
+            self.reads.borrow_mut().push(user_index);
+            self.states.borrow()[user_index as usize]
+        }
+    }
+
+    struct TestSetup {
+        xinput: XInputInterface,
+        message_handler: Rc<RecordingMessageHandler>,
+        states: Rc<RefCell<[Option<XInputState>; MAX_NUM_XINPUT_CONTROLLERS]>>,
+        reads: Rc<RefCell<Vec<u32>>>,
+    }
+
+    fn make_setup() -> TestSetup {
+        let state_source = ScriptedStateSource::default();
+        let states = state_source.states.clone();
+        let reads = state_source.reads.clone();
+        let message_handler = Rc::new(RecordingMessageHandler::default());
+        let handler: Rc<dyn ApplicationMessageHandler> = message_handler.clone();
+        TestSetup {
+            xinput: XInputInterface::new(Box::new(state_source), &handler),
+            message_handler,
+            states,
+            reads,
+        }
+    }
+
+    fn make_state(buttons: u16) -> XInputState {
+        XInputState {
+            packet_number: 1,
+            gamepad: XInputGamepad {
+                buttons,
+                ..XInputGamepad::default()
+            },
+        }
+    }
+
+    #[test]
+    fn back_and_start_swap_places() {
+        let setup = make_setup();
+        let mapping = &setup.xinput.x360_to_xbox_controller_mapping;
+        assert_eq!(mapping[6] as usize, GamepadButton::SpecialLeft.to_usize());
+        assert_eq!(mapping[7] as usize, GamepadButton::SpecialRight.to_usize());
+        for (index, &button_index) in mapping.iter().enumerate() {
+            if index != 6 && index != 7 {
+                assert_eq!(button_index as usize, index);
+            }
+        }
+
+        let sample = setup
+            .xinput
+            .get_gamepad_sample(&make_state(XINPUT_GAMEPAD_BACK).gamepad);
+        assert!(sample.is_button_pressed(GamepadButton::SpecialLeft));
+        assert!(!sample.is_button_pressed(GamepadButton::SpecialRight));
+        let sample = setup
+            .xinput
+            .get_gamepad_sample(&make_state(XINPUT_GAMEPAD_START).gamepad);
+        assert!(sample.is_button_pressed(GamepadButton::SpecialRight));
+        assert!(!sample.is_button_pressed(GamepadButton::SpecialLeft));
+    }
+
+    #[test]
+    fn each_button_sets_its_own_gamepad_button() {
+        let setup = make_setup();
+        let expected_buttons = [
+            (XINPUT_GAMEPAD_A, GamepadButton::FaceButtonBottom),
+            (XINPUT_GAMEPAD_B, GamepadButton::FaceButtonRight),
+            (XINPUT_GAMEPAD_X, GamepadButton::FaceButtonLeft),
+            (XINPUT_GAMEPAD_Y, GamepadButton::FaceButtonTop),
+            (XINPUT_GAMEPAD_LEFT_SHOULDER, GamepadButton::LeftShoulder),
+            (XINPUT_GAMEPAD_RIGHT_SHOULDER, GamepadButton::RightShoulder),
+            (XINPUT_GAMEPAD_LEFT_THUMB, GamepadButton::LeftThumb),
+            (XINPUT_GAMEPAD_RIGHT_THUMB, GamepadButton::RightThumb),
+            (XINPUT_GAMEPAD_DPAD_UP, GamepadButton::DPadUp),
+            (XINPUT_GAMEPAD_DPAD_DOWN, GamepadButton::DPadDown),
+            (XINPUT_GAMEPAD_DPAD_LEFT, GamepadButton::DPadLeft),
+            (XINPUT_GAMEPAD_DPAD_RIGHT, GamepadButton::DPadRight),
+        ];
+        for (flag, button) in expected_buttons {
+            let sample = setup.xinput.get_gamepad_sample(&make_state(flag).gamepad);
+            let pressed: Vec<usize> = (0..MAX_NUM_CONTROLLER_BUTTONS)
+                .filter(|&index| sample.buttons[index])
+                .collect();
+            assert_eq!(pressed, [button.to_usize()], "{:?}", button);
+        }
+        // The d-pad sits after the two trigger thresholds
+        assert_eq!(GamepadButton::DPadUp.to_usize(), 12);
+        assert_eq!(GamepadButton::DPadDown.to_usize(), 13);
+        assert_eq!(GamepadButton::DPadLeft.to_usize(), 14);
+        assert_eq!(GamepadButton::DPadRight.to_usize(), 15);
+    }
+
+    #[test]
+    fn normalizes_sticks_and_triggers() {
+        assert_eq!(short_to_normalized_float(-32768), -1.0);
+        assert_eq!(short_to_normalized_float(0), 0.0);
+        assert_eq!(short_to_normalized_float(32767), 1.0);
+
+        let setup = make_setup();
+        let gamepad = XInputGamepad {
+            left_trigger: 255,
+            right_trigger: 0,
+            thumb_lx: -32768,
+            thumb_ly: 32767,
+            thumb_rx: 0,
+            thumb_ry: -16384,
+            ..XInputGamepad::default()
+        };
+        let sample = setup.xinput.get_gamepad_sample(&gamepad);
+        assert_eq!(sample.get_axis(GamepadAxis::LeftX), -1.0);
+        assert_eq!(sample.get_axis(GamepadAxis::LeftY), 1.0);
+        assert_eq!(sample.get_axis(GamepadAxis::RightX), 0.0);
+        assert_eq!(sample.get_axis(GamepadAxis::RightY), -0.5);
+        assert_eq!(sample.get_axis(GamepadAxis::LeftTrigger), 1.0);
+        assert_eq!(sample.get_axis(GamepadAxis::RightTrigger), 0.0);
+    }
+
+    #[test]
+    fn connecting_and_disconnecting_tell_the_handler() {
+        let setup = make_setup();
+        let mut state = make_state(XINPUT_GAMEPAD_A | XINPUT_GAMEPAD_BACK);
+        state.gamepad.thumb_lx = 32767;
+        setup.states.borrow_mut()[1] = Some(state);
+
+        setup.xinput.send_controller_events_at(0.0);
+        assert_eq!(
+            setup.message_handler.take_calls(),
+            [
+                "connection_changed 1 true",
+                "analog Gamepad_LeftX 1 1",
+                "pressed Gamepad_FaceButton_Bottom 1 false",
+                "pressed Gamepad_Special_Left 1 false",
+                "pressed Gamepad_LeftStick_Right 1 false",
+            ]
+        );
+        assert!(setup.xinput.is_gamepad_attached());
+
+        // Unplugging it releases what it held before it goes
+        setup.states.borrow_mut()[1] = None;
+        setup.xinput.send_controller_events_at(0.1);
+        assert_eq!(
+            setup.message_handler.take_calls(),
+            [
+                "analog Gamepad_LeftX 1 0",
+                "released Gamepad_FaceButton_Bottom 1 false",
+                "released Gamepad_Special_Left 1 false",
+                "released Gamepad_LeftStick_Right 1 false",
+                "connection_changed 1 false",
+            ]
+        );
+        assert!(!setup.xinput.is_gamepad_attached());
+        assert!(!setup
+            .xinput
+            .get_gamepad_state_tracker()
+            .is_button_pressed(1, GamepadButton::FaceButtonBottom));
+    }
+
+    #[test]
+    fn empty_slots_are_read_only_after_a_device_change() {
+        let setup = make_setup();
+
+        // Nothing is known at first, so every slot is read once
+        setup.xinput.send_controller_events_at(0.0);
+        assert_eq!(setup.reads.take(), [0, 1, 2, 3]);
+        setup.xinput.send_controller_events_at(0.1);
+        assert_eq!(setup.reads.take(), []);
+
+        // A controller plugged in unnoticed stays unseen
+        setup.states.borrow_mut()[2] = Some(make_state(0));
+        setup.xinput.send_controller_events_at(0.2);
+        assert_eq!(setup.reads.take(), []);
+        assert_eq!(setup.message_handler.take_calls(), Vec::<String>::new());
+
+        setup.xinput.set_needs_controller_state_update();
+        setup.xinput.send_controller_events_at(0.3);
+        assert_eq!(setup.reads.take(), [0, 1, 2, 3]);
+        assert_eq!(
+            setup.message_handler.take_calls(),
+            ["connection_changed 2 true"]
+        );
+
+        // From then on only the connected slot is read
+        setup.xinput.send_controller_events_at(0.4);
+        assert_eq!(setup.reads.take(), [2]);
+    }
+}
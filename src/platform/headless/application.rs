@@ -0,0 +1,369 @@
+use crate::generic::application::{
+    DisplayMetrics, GenericApplication, ModifierKeysState, MonitorInfo, PlatformRect, DEFAULT_DPI,
+};
+use crate::generic::application_message_handler::ApplicationMessageHandler;
+use crate::generic::application_proxy::{ApplicationProxy, ProxyReceiver, ProxyWindowId};
+use crate::generic::platform_event::{PlatformEvent, PlatformEventQueue};
+use crate::generic::timer::TimerQueue;
+use crate::generic::window::GenericWindow;
+use crate::generic::window_definition::WindowDefinition;
+use crate::generic::window_hierarchy;
+use crate::headless::cursor::HeadlessCursor;
+use crate::headless::window::{HeadlessDesktop, HeadlessWindow};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+
+pub const DEFAULT_HEADLESS_DISPLAY_RECT: PlatformRect = PlatformRect {
+    left: 0,
+    top: 0,
+    right: 1920,
+    bottom: 1080,
+};
+
+/** A single 60 Hz monitor covering the display rect */
+fn get_display_metrics_for_rect(display_rect: &PlatformRect) -> DisplayMetrics {
+    DisplayMetrics::from_monitors(vec![MonitorInfo {
+        name: "Headless".to_string(),
+        id: "Headless".to_string(),
+        native_width: display_rect.get_width(),
+        native_height: display_rect.get_height(),
+        is_primary: true,
+        display_rect: *display_rect,
+        work_area: *display_rect,
+        dpi: DEFAULT_DPI,
+        refresh_rate: 60.0,
+        ..MonitorInfo::default()
+    }])
+}
+
+/** An application that never talks to a display server. Windows live in memory and input is scripted. */
+pub struct HeadlessApplication {
+    cursor: Rc<RefCell<HeadlessCursor>>,
+    desktop: Rc<HeadlessDesktop>,
+    pub windows: RefCell<Vec<Rc<HeadlessWindow>>>,
+    events: PlatformEventQueue,
+    message_handler: Rc<dyn ApplicationMessageHandler>,
+    modifier_keys: Cell<ModifierKeysState>,
+    initial_display_metrics: DisplayMetrics,
+    display_metrics: RefCell<DisplayMetrics>,
+    /** There is no OS queue to wake, so a waiting headless application waits on the proxies themselves */
+    proxy_receiver: ProxyReceiver<HeadlessWindow>,
+    timers: TimerQueue,
+}
+
+impl fmt::Debug for HeadlessApplication {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeadlessApplication")
+            .field("cursor", &self.cursor)
+            .field("desktop", &self.desktop)
+            .field("windows", &self.windows)
+            .field("num_queued_events", &self.events.len())
+            .field("modifier_keys", &self.modifier_keys)
+            .field("display_metrics", &self.display_metrics)
+            .field("proxy_receiver", &self.proxy_receiver)
+            .field("timers", &self.timers)
+            .finish()
+    }
+}
+
+impl HeadlessApplication {
+    pub fn new(message_handler: &Rc<dyn ApplicationMessageHandler>) -> HeadlessApplication {
+        HeadlessApplication::with_display_rect(message_handler, &DEFAULT_HEADLESS_DISPLAY_RECT)
+    }
+    pub fn with_display_rect(
+        message_handler: &Rc<dyn ApplicationMessageHandler>,
+        display_rect: &PlatformRect,
+    ) -> HeadlessApplication {
+        let display_metrics = get_display_metrics_for_rect(display_rect);
+        HeadlessApplication {
+            cursor: Rc::new(RefCell::new(HeadlessCursor::new())),
+            desktop: Rc::new(HeadlessDesktop::new(display_rect)),
+            windows: RefCell::new(vec![]),
+            events: PlatformEventQueue::new(),
+            message_handler: message_handler.clone(),
+            modifier_keys: Cell::new(ModifierKeysState::default()),
+            initial_display_metrics: display_metrics.clone(),
+            display_metrics: RefCell::new(display_metrics),
+            proxy_receiver: ProxyReceiver::new(None),
+            timers: TimerQueue::new(),
+        }
+    }
+    /** Creates a window from the definition and registers it with the application. parent becomes its owner. */
+    pub fn make_window(
+        &self,
+        definition: &Rc<WindowDefinition>,
+        parent: Option<&Rc<HeadlessWindow>>,
+        show_immediately: bool,
+    ) -> Rc<HeadlessWindow> {
+        let window = Rc::new(HeadlessWindow::new(&self.desktop, definition));
+        if let Some(parent) = parent {
+            let generic_parent: Rc<dyn GenericWindow> = parent.clone();
+            let generic_window: Rc<dyn GenericWindow> = window.clone();
+            window_hierarchy::set_parent(&generic_window, Some(&generic_parent));
+        }
+        self.windows.borrow_mut().push(window.clone());
+        if show_immediately {
+            window.show();
+        }
+        window
+    }
+    pub fn find_window_by_handle(&self, handle_to_find: usize) -> Option<Rc<HeadlessWindow>> {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|window| window.get_handle() == handle_to_find)
+            .cloned()
+    }
+    /** The window that currently has keyboard focus */
+    pub fn get_focused_window(&self) -> Option<Rc<HeadlessWindow>> {
+        self.find_window_by_handle(self.desktop.focused_window.get())
+    }
+    pub fn get_cursor(&self) -> &Rc<RefCell<HeadlessCursor>> {
+        &self.cursor
+    }
+    pub fn get_display_rect(&self) -> PlatformRect {
+        self.desktop.display_rect.get()
+    }
+    /** Resizes the display and queues on_display_metrics_changed, like a resolution change would */
+    pub fn set_display_rect(&self, display_rect: &PlatformRect) {
+        self.desktop.display_rect.set(*display_rect);
+        let display_metrics = get_display_metrics_for_rect(display_rect);
+        let change = self.display_metrics.borrow().diff(&display_metrics);
+        if !change.is_empty() {
+            *self.display_metrics.borrow_mut() = display_metrics.clone();
+            self.events.push(PlatformEvent::DisplayMetricsChanged {
+                display_metrics,
+                change,
+            });
+        }
+    }
+    /** Queues a scripted event. It is handed out by the next drain_events or pump_messages. */
+    pub fn queue_event(&self, event: PlatformEvent) {
+        self.events.push(event);
+    }
+    /** Sets what get_modifier_keys reports. Scripted events carry their own modifier state. */
+    pub fn set_modifier_keys(&self, modifier_keys: ModifierKeysState) {
+        self.modifier_keys.set(modifier_keys);
+    }
+}
+
+impl GenericApplication for HeadlessApplication {
+    type Cursor = HeadlessCursor;
+    type Window = HeadlessWindow;
+
+    fn set_message_handler(&mut self, in_message_handler: &Rc<dyn ApplicationMessageHandler>) {
+        self.message_handler = in_message_handler.clone();
+    }
+    fn get_message_handler(&self) -> &Rc<dyn ApplicationMessageHandler> {
+        &self.message_handler
+    }
+    fn get_event_queue(&self) -> &PlatformEventQueue {
+        &self.events
+    }
+    fn get_timer_queue(&self) -> &TimerQueue {
+        &self.timers
+    }
+    fn get_modifier_keys(&self) -> ModifierKeysState {
+        self.modifier_keys.get()
+    }
+    fn get_initial_display_metrics(&self) -> DisplayMetrics {
+        self.initial_display_metrics.clone()
+    }
+    fn get_display_metrics(&self) -> DisplayMetrics {
+        self.display_metrics.borrow().clone()
+    }
+    fn create_proxy(&self) -> ApplicationProxy {
+        self.proxy_receiver.create_proxy()
+    }
+    fn get_proxy_window(&self, id: ProxyWindowId) -> Option<Rc<HeadlessWindow>> {
+        self.proxy_receiver.get_window(id)
+    }
+    fn wait_for_events(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        // Scripted events are queued on this thread, so while it waits only a proxy or a timer can end the wait
+        if !self.events.is_empty() || self.timers.has_expired_timers() {
+            return Ok(true);
+        }
+        Ok(self
+            .proxy_receiver
+            .wait_for_messages(self.timers.get_wait_timeout(timeout))
+            || self.timers.has_expired_timers())
+    }
+    fn poll_events(&self, _time_delta: f32) -> io::Result<()> {
+        // There is no OS to read from; scripted events are already queued
+        self.proxy_receiver.process_messages(
+            &self.events,
+            |definition, parent, show_immediately| {
+                self.make_window(definition, parent, show_immediately)
+            },
+        );
+        self.proxy_receiver
+            .retain_windows(|window| !window.is_destroyed());
+        self.windows
+            .borrow_mut()
+            .retain(|window| !window.is_destroyed());
+        self.timers.fire_expired_timers(&self.events);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::application_message_handler::tests::RecordingMessageHandler;
+    use crate::generic::application_message_handler::WindowActivation;
+    use crate::generic::input::Key;
+    use crate::generic::window::WindowMode;
+
+    fn make_application() -> (Rc<RecordingMessageHandler>, HeadlessApplication) {
+        let handler = Rc::new(RecordingMessageHandler::default());
+        let message_handler: Rc<dyn ApplicationMessageHandler> = handler.clone();
+        (handler, HeadlessApplication::new(&message_handler))
+    }
+
+    #[test]
+    fn scripted_events_reach_the_handler_in_order() {
+        let (handler, application) = make_application();
+        let window = application.make_window(&Rc::new(WindowDefinition::default()), None, true);
+        let generic_window: Rc<dyn GenericWindow> = window.clone();
+
+        application.queue_event(PlatformEvent::WindowActivationChanged {
+            window: generic_window.clone(),
+            activation_type: WindowActivation::Activate,
+        });
+        application.queue_event(PlatformEvent::MovedWindow {
+            window: generic_window.clone(),
+            x: 10,
+            y: 20,
+        });
+        application.queue_event(PlatformEvent::SizeChanged {
+            window: generic_window,
+            width: 640,
+            height: 480,
+            was_minimized: false,
+        });
+        application.queue_event(PlatformEvent::KeyDown {
+            key: Key::Escape,
+            character_code: 0x1b,
+            is_repeat: false,
+            modifier_keys: ModifierKeysState::default(),
+        });
+        assert!(handler.take_calls().is_empty());
+
+        application.pump_messages(0.0).unwrap();
+        assert_eq!(
+            handler.take_calls(),
+            [
+                "window_activation_changed Activate",
+                "moved_window 10 20",
+                "size_changed 640 480 false",
+                "key_down Escape 27 false",
+            ]
+        );
+        assert!(application.get_event_queue().is_empty());
+
+        application.pump_messages(0.0).unwrap();
+        assert!(handler.take_calls().is_empty());
+    }
+
+    #[test]
+    fn proxy_commands_change_the_window_state() {
+        let (handler, application) = make_application();
+        let proxy = application.create_proxy();
+        let definition = WindowDefinition {
+            title: "Scripted".to_string(),
+            opacity: 0.5,
+            ..WindowDefinition::default()
+        };
+        let id = proxy.create_window(definition, None, false).unwrap();
+        proxy.reshape_window(id, 100, 50, 300, 200).unwrap();
+        proxy.set_window_title(id, "Renamed").unwrap();
+        proxy.show_window(id).unwrap();
+        proxy.send_user_event("shown").unwrap();
+        assert!(application.get_proxy_window(id).is_none());
+
+        application.pump_messages(0.0).unwrap();
+        let window = application.get_proxy_window(id).unwrap();
+        assert_eq!(handler.take_calls(), ["user_event shown"]);
+        assert_eq!(
+            window.get_window_rect(),
+            PlatformRect {
+                left: 100,
+                top: 50,
+                right: 400,
+                bottom: 250,
+            }
+        );
+        assert_eq!(window.get_title(), "Renamed");
+        assert_eq!(window.get_opacity(), 0.5);
+        assert_eq!(window.get_window_mode(), WindowMode::Windowed);
+        assert!(window.is_visible());
+        assert_eq!(
+            application
+                .get_focused_window()
+                .map(|focused| focused.get_handle()),
+            Some(window.get_handle())
+        );
+
+        proxy.hide_window(id).unwrap();
+        application.pump_messages(0.0).unwrap();
+        assert!(!window.is_visible());
+        assert!(application.get_focused_window().is_none());
+
+        proxy.destroy_window(id).unwrap();
+        application.pump_messages(0.0).unwrap();
+        assert!(window.is_destroyed());
+        assert!(application.get_proxy_window(id).is_none());
+        assert!(application.windows.borrow().is_empty());
+    }
+
+    #[test]
+    fn window_mode_and_opacity_follow_the_display() {
+        let (handler, application) = make_application();
+        let window = application.make_window(&Rc::new(WindowDefinition::default()), None, true);
+        let (mut x, mut y, mut width, mut height) = (30, 40, 500, 400);
+        window.reshape_window(&mut x, &mut y, &mut width, &mut height);
+
+        window.set_window_mode(WindowMode::Fullscreen);
+        assert_eq!(window.get_window_mode(), WindowMode::Fullscreen);
+        assert_eq!(window.get_window_rect(), DEFAULT_HEADLESS_DISPLAY_RECT);
+
+        window.set_window_mode(WindowMode::Windowed);
+        assert_eq!(
+            window.get_window_rect(),
+            PlatformRect {
+                left: 30,
+                top: 40,
+                right: 530,
+                bottom: 440,
+            }
+        );
+
+        let display_rect = PlatformRect {
+            left: 0,
+            top: 0,
+            right: 1280,
+            bottom: 720,
+        };
+        application.set_display_rect(&display_rect);
+        application.pump_messages(0.0).unwrap();
+        assert_eq!(handler.take_calls(), ["display_metrics_changed 1280x720"]);
+        window.maximize();
+        assert!(window.is_maximized());
+        assert_eq!(window.get_window_rect(), display_rect);
+        window.minimize();
+        assert!(window.is_minimized());
+        assert!(application.get_focused_window().is_none());
+        window.restore();
+        assert!(window.is_maximized());
+        window.restore();
+        assert_eq!(window.get_window_rect().left, 30);
+
+        window.set_opacity(1.5);
+        assert_eq!(window.get_opacity(), 1.0);
+        window.set_opacity(-0.5);
+        assert_eq!(window.get_opacity(), 0.0);
+    }
+}
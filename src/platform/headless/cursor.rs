@@ -0,0 +1,118 @@
+use crate::{
+    core::math::color::Color,
+    generic::{
+        application::PlatformRect,
+        cursor::{ICursor, MouseCursor, TagRect},
+    },
+};
+use glam::Vec2;
+use std::{cell::Cell, error::Error, ffi::c_void, io, path::Path, ptr};
+
+impl TagRect for PlatformRect {}
+
+/** A cursor that only exists in memory. It remembers its position, shape, visibility and clip rect. */
+#[derive(PartialEq, Debug)]
+pub struct HeadlessCursor {
+    pub current_type: MouseCursor,
+    pub cursor_override_handles: [*const c_void; 15],
+    position: Vec2,
+    is_visible: Cell<bool>,
+    clip_rect: Cell<Option<PlatformRect>>,
+}
+
+impl Default for HeadlessCursor {
+    fn default() -> HeadlessCursor {
+        HeadlessCursor::new()
+    }
+}
+
+impl HeadlessCursor {
+    pub fn new() -> HeadlessCursor {
+        HeadlessCursor {
+            current_type: MouseCursor::Default,
+            cursor_override_handles: [ptr::null(); 15],
+            position: Vec2::ZERO,
+            is_visible: Cell::new(true),
+            clip_rect: Cell::new(None),
+        }
+    }
+    pub fn is_visible(&self) -> bool {
+        self.is_visible.get()
+    }
+    /** The rect the cursor is currently locked to, if any */
+    pub fn get_clip_rect(&self) -> Option<PlatformRect> {
+        self.clip_rect.get()
+    }
+}
+
+impl ICursor for HeadlessCursor {
+    type Rect = PlatformRect;
+
+    fn create_cursor_from_file<P: AsRef<Path>>(
+        path_to_cursor_without_extension: P,
+        _hotspot: Vec2,
+    ) -> Result<Self, Box<dyn Error>> {
+        let msg = format!(
+            "hardware cursors are not available on the headless platform ({})",
+            path_to_cursor_without_extension.as_ref().display()
+        );
+        Err(Box::new(io::Error::new(io::ErrorKind::Unsupported, msg)))
+    }
+
+    fn is_create_cursor_from_rgba_buffer_supported() -> bool {
+        false
+    }
+
+    fn create_cursor_from_rgba_buffer(
+        _pixels: Color,
+        _width: i32,
+        _height: i32,
+        _hotspot: Vec2,
+    ) -> Option<Self> {
+        None
+    }
+
+    fn get_position(&self) -> Vec2 {
+        self.position
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) {
+        let (mut x, mut y) = (x, y);
+        // Mimic ClipCursor: the cursor can never leave the locked rect
+        if let Some(clip_rect) = self.clip_rect.get() {
+            x = x.max(clip_rect.left).min(clip_rect.right - 1);
+            y = y.max(clip_rect.top).min(clip_rect.bottom - 1);
+        }
+        self.position = Vec2::new(x as f32, y as f32);
+    }
+
+    fn set_type(&mut self, new_cursor: MouseCursor) {
+        self.current_type = new_cursor;
+    }
+
+    fn get_type(&self) -> &MouseCursor {
+        &self.current_type
+    }
+
+    fn get_size(&self, width: &mut i32, height: &mut i32) {
+        *width = 32;
+        *height = 32;
+    }
+
+    fn show(&self, show: bool) {
+        self.is_visible.set(show);
+    }
+
+    fn lock(&self, bounds: Option<*const Self::Rect>) {
+        match bounds {
+            Some(rect) if !rect.is_null() => {
+                self.clip_rect.set(Some(unsafe { *rect }));
+            }
+            _ => self.clip_rect.set(None),
+        }
+    }
+
+    fn set_type_shape(&mut self, cursor_type: MouseCursor, cursor_handle: *const c_void) {
+        self.cursor_override_handles[cursor_type.to_usize()] = cursor_handle;
+    }
+}
@@ -0,0 +1,3 @@
+pub mod application;
+pub mod cursor;
+pub mod window;
@@ -0,0 +1,381 @@
+use crate::generic::{
+    application::PlatformRect,
+    window::{GenericWindow, WindowDrawAttentionRequestType, WindowMode},
+    window_definition::{WindowActivationPolicy, WindowDefinition},
+    window_hierarchy::{self, WindowHierarchy},
+};
+use std::{
+    cell::{Cell, RefCell},
+    os::raw::c_void,
+    rc::Rc,
+};
+
+/** The fake desktop that the windows of one HeadlessApplication live on */
+#[derive(Debug)]
+pub struct HeadlessDesktop {
+    /** The area covered by the single virtual monitor */
+    pub display_rect: Cell<PlatformRect>,
+    /** Handle of the window that currently has keyboard focus, 0 if none */
+    pub focused_window: Cell<usize>,
+    next_window_handle: Cell<usize>,
+    next_z_order: Cell<u64>,
+}
+
+impl HeadlessDesktop {
+    pub fn new(display_rect: &PlatformRect) -> HeadlessDesktop {
+        HeadlessDesktop {
+            display_rect: Cell::new(*display_rect),
+            focused_window: Cell::new(0),
+            next_window_handle: Cell::new(1),
+            next_z_order: Cell::new(1),
+        }
+    }
+    fn allocate_window_handle(&self) -> usize {
+        let handle = self.next_window_handle.get();
+        self.next_window_handle.set(handle + 1);
+        handle
+    }
+    fn allocate_z_order(&self) -> u64 {
+        let z_order = self.next_z_order.get();
+        self.next_z_order.set(z_order + 1);
+        z_order
+    }
+}
+
+/** A window that only exists in memory. Every GenericWindow operation updates its cached state. */
+#[derive(Debug)]
+pub struct HeadlessWindow {
+    handle: usize,
+    desktop: Rc<HeadlessDesktop>,
+    x: Cell<i32>,
+    y: Cell<i32>,
+    width: Cell<i32>,
+    height: Cell<i32>,
+    /** Client rect to return to when leaving the maximized, minimized or fullscreen state */
+    restored_rect: Cell<(i32, i32, i32, i32)>,
+    window_mode: Cell<WindowMode>,
+    z_order: Cell<u64>,
+    is_visible: Cell<bool>,
+    is_first_time_visible: Cell<bool>,
+    is_maximized: Cell<bool>,
+    is_minimized: Cell<bool>,
+    is_enabled: Cell<bool>,
+    is_destroyed: Cell<bool>,
+    is_drawing_attention: Cell<bool>,
+    native_window_buttons_visible: Cell<bool>,
+    opacity: Cell<f32>,
+    title: RefCell<String>,
+    dpi_scale_factor: Cell<f32>,
+    handle_manual_dpi_changes: Cell<bool>,
+    window_definitions: Rc<WindowDefinition>,
+    hierarchy: WindowHierarchy,
+}
+
+impl HeadlessWindow {
+    pub fn new(desktop: &Rc<HeadlessDesktop>, definition: &Rc<WindowDefinition>) -> HeadlessWindow {
+        let x = definition.x_desired_position_on_screen as i32;
+        let y = definition.y_desired_position_on_screen as i32;
+        let width = definition.width_desired_on_screen as i32;
+        let height = definition.height_desired_on_screen as i32;
+        HeadlessWindow {
+            handle: desktop.allocate_window_handle(),
+            desktop: desktop.clone(),
+            x: Cell::new(x),
+            y: Cell::new(y),
+            width: Cell::new(width),
+            height: Cell::new(height),
+            restored_rect: Cell::new((x, y, width, height)),
+            window_mode: Cell::new(WindowMode::Windowed),
+            z_order: Cell::new(desktop.allocate_z_order()),
+            is_visible: Cell::new(false),
+            is_first_time_visible: Cell::new(true),
+            is_maximized: Cell::new(false),
+            is_minimized: Cell::new(false),
+            is_enabled: Cell::new(true),
+            is_destroyed: Cell::new(false),
+            is_drawing_attention: Cell::new(false),
+            native_window_buttons_visible: Cell::new(true),
+            opacity: Cell::new(definition.opacity),
+            title: RefCell::new(definition.title.clone()),
+            dpi_scale_factor: Cell::new(1.0),
+            handle_manual_dpi_changes: Cell::new(definition.manual_dpi),
+            window_definitions: definition.clone(),
+            hierarchy: WindowHierarchy::new(),
+        }
+    }
+    pub fn get_handle(&self) -> usize {
+        self.handle
+    }
+    /** The current client rect in screen coordinates */
+    pub fn get_window_rect(&self) -> PlatformRect {
+        PlatformRect {
+            left: self.x.get(),
+            top: self.y.get(),
+            right: self.x.get() + self.width.get(),
+            bottom: self.y.get() + self.height.get(),
+        }
+    }
+    pub fn get_title(&self) -> String {
+        self.title.borrow().clone()
+    }
+    pub fn get_opacity(&self) -> f32 {
+        self.opacity.get()
+    }
+    pub fn get_z_order(&self) -> u64 {
+        self.z_order.get()
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled.get()
+    }
+    pub fn is_destroyed(&self) -> bool {
+        self.is_destroyed.get()
+    }
+    pub fn is_drawing_attention(&self) -> bool {
+        self.is_drawing_attention.get()
+    }
+    pub fn are_native_window_buttons_visible(&self) -> bool {
+        self.native_window_buttons_visible.get()
+    }
+    fn set_rect(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.x.set(x);
+        self.y.set(y);
+        self.width.set(width);
+        self.height.set(height);
+    }
+    fn save_restored_rect(&self) {
+        if !self.is_maximized.get()
+            && !self.is_minimized.get()
+            && self.window_mode.get() == WindowMode::Windowed
+        {
+            self.restored_rect.set((
+                self.x.get(),
+                self.y.get(),
+                self.width.get(),
+                self.height.get(),
+            ));
+        }
+    }
+    fn apply_restored_rect(&self) {
+        let (x, y, width, height) = self.restored_rect.get();
+        self.set_rect(x, y, width, height);
+    }
+    fn fill_display_rect(&self) {
+        let display_rect = self.desktop.display_rect.get();
+        self.set_rect(
+            display_rect.left,
+            display_rect.top,
+            display_rect.right - display_rect.left,
+            display_rect.bottom - display_rect.top,
+        );
+    }
+}
+
+impl GenericWindow for HeadlessWindow {
+    fn reshape_window(
+        &self,
+        new_x: &mut i32,
+        new_y: &mut i32,
+        new_width: &mut i32,
+        new_height: &mut i32,
+    ) {
+        if self.is_maximized() {
+            self.restore();
+        }
+        self.set_rect(*new_x, *new_y, *new_width, *new_height);
+    }
+    fn get_fullscreen_info(
+        &self,
+        x: &mut i32,
+        y: &mut i32,
+        width: &mut i32,
+        height: &mut i32,
+    ) -> bool {
+        let display_rect = self.desktop.display_rect.get();
+        *x = display_rect.left;
+        *y = display_rect.top;
+        *width = display_rect.right - display_rect.left;
+        *height = display_rect.bottom - display_rect.top;
+        true
+    }
+    fn move_window_to(&self, x: &mut i32, y: &mut i32) {
+        self.x.set(*x);
+        self.y.set(*y);
+    }
+    fn bring_to_front(&self, _force: bool) {
+        if self.is_minimized() && self.window_definitions.is_regular_window {
+            self.restore();
+        }
+        self.z_order.set(self.desktop.allocate_z_order());
+        window_hierarchy::bring_children_to_front(self);
+    }
+    fn destroy(&self) {
+        self.is_destroyed.set(true);
+        self.is_visible.set(false);
+        if self.is_foreground_window() {
+            self.desktop.focused_window.set(0);
+        }
+    }
+    fn minimize(&self) {
+        self.save_restored_rect();
+        self.is_minimized.set(true);
+        if self.is_foreground_window() {
+            self.desktop.focused_window.set(0);
+        }
+        window_hierarchy::minimize_children(self);
+    }
+    fn maximize(&self) {
+        self.save_restored_rect();
+        self.is_minimized.set(false);
+        self.is_maximized.set(true);
+        self.fill_display_rect();
+    }
+    fn restore(&self) {
+        // Like SW_RESTORE, a minimized window goes back to being maximized if it was maximized before
+        if self.is_minimized.get() {
+            self.is_minimized.set(false);
+            window_hierarchy::restore_children(self);
+        } else if self.is_maximized.get() {
+            self.is_maximized.set(false);
+            self.apply_restored_rect();
+        }
+    }
+    fn show(&self) {
+        if !self.is_visible.get() {
+            self.is_visible.set(true);
+
+            // Do not activate windows that do not take input; e.g. tool-tips and cursor decorators
+            let windef = &self.window_definitions;
+            let mut should_activate = false;
+            if windef.accepts_input {
+                should_activate = windef.activation_policy == WindowActivationPolicy::Always;
+                if self.is_first_time_visible.get()
+                    && windef.activation_policy == WindowActivationPolicy::FirstShown
+                {
+                    should_activate = true;
+                }
+            }
+            self.is_first_time_visible.set(false);
+
+            if should_activate {
+                self.set_window_focus();
+            }
+        }
+    }
+    fn hide(&self) {
+        if self.is_visible.get() {
+            self.is_visible.set(false);
+            if self.is_foreground_window() {
+                self.desktop.focused_window.set(0);
+            }
+        }
+    }
+    fn set_window_mode(&self, new_window_mode: WindowMode) {
+        if new_window_mode == self.window_mode.get() {
+            return;
+        }
+        if new_window_mode == WindowMode::Windowed {
+            self.window_mode.set(new_window_mode);
+            self.apply_restored_rect();
+        } else {
+            self.save_restored_rect();
+            self.window_mode.set(new_window_mode);
+            self.is_minimized.set(false);
+            self.is_maximized.set(false);
+            self.fill_display_rect();
+        }
+    }
+    fn get_window_mode(&self) -> WindowMode {
+        self.window_mode.get()
+    }
+    fn is_maximized(&self) -> bool {
+        self.is_maximized.get() && !self.is_minimized.get()
+    }
+    fn is_minimized(&self) -> bool {
+        self.is_minimized.get()
+    }
+    fn is_visible(&self) -> bool {
+        self.is_visible.get()
+    }
+    fn get_restored_dimensions(
+        &self,
+        x: &mut i32,
+        y: &mut i32,
+        width: &mut i32,
+        height: &mut i32,
+    ) -> bool {
+        self.save_restored_rect();
+        let (restored_x, restored_y, restored_width, restored_height) = self.restored_rect.get();
+        *x = restored_x;
+        *y = restored_y;
+        *width = restored_width;
+        *height = restored_height;
+        true
+    }
+    fn set_window_focus(&self) {
+        self.desktop.focused_window.set(self.handle);
+        self.is_drawing_attention.set(false);
+    }
+    fn set_opacity(&self, opacity: f32) {
+        self.opacity.set(opacity.clamp(0.0, 1.0));
+    }
+    fn enable(&self, enable: bool) {
+        self.is_enabled.set(enable);
+    }
+    fn is_point_in_window(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width.get() && y < self.height.get()
+    }
+    fn get_window_border_size(&self) -> u32 {
+        0
+    }
+    fn get_window_title_bar_size(&self) -> i32 {
+        0
+    }
+    fn get_os_window_handle(&self) -> *const c_void {
+        self.handle as *const c_void
+    }
+    fn is_foreground_window(&self) -> bool {
+        self.desktop.focused_window.get() == self.handle
+    }
+    fn is_fullscreen_supported(&self) -> bool {
+        true
+    }
+    fn set_text(&self, text: &mut Vec<u16>) {
+        let len = text.iter().position(|&c| c == 0).unwrap_or(text.len());
+        *self.title.borrow_mut() = String::from_utf16_lossy(&text[..len]);
+    }
+    fn get_definition(&self) -> &Rc<WindowDefinition> {
+        &self.window_definitions
+    }
+    fn is_definition_valid(&self) -> bool {
+        true
+    }
+    fn adjust_cached_size(&self, size: &mut (i32, i32)) {
+        *size = (self.width.get(), self.height.get());
+    }
+    fn get_dpi_scale_factor(&self) -> f32 {
+        self.dpi_scale_factor.get()
+    }
+    fn set_dpi_scale_factor(&self, factor: f32) {
+        self.dpi_scale_factor.set(factor);
+    }
+    fn is_manual_manage_dpi_change(&self) -> bool {
+        self.handle_manual_dpi_changes.get()
+    }
+    fn set_manual_manage_dpi_change(&self, manual_dpi_changes: bool) {
+        self.handle_manual_dpi_changes.set(manual_dpi_changes);
+    }
+    fn draw_attention(&self, parameters: WindowDrawAttentionRequestType) {
+        match parameters {
+            WindowDrawAttentionRequestType::UntilActivated => {
+                self.is_drawing_attention.set(!self.is_foreground_window())
+            }
+            WindowDrawAttentionRequestType::Stop => self.is_drawing_attention.set(false),
+        }
+    }
+    fn set_native_window_buttons_visibility(&self, visible: bool) {
+        self.native_window_buttons_visible.set(visible);
+    }
+    fn get_hierarchy(&self) -> &WindowHierarchy {
+        &self.hierarchy
+    }
+}
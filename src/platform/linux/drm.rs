@@ -0,0 +1,421 @@
+//Connectors are documented in the kernel's Documentation/ABI/testing/sysfs-class-drm.
+//Each one is a directory named card<N>-<connector type>-<index>, next to the card<N> directories themselves.
+use crate::generic::application::{DisplayMetrics, MonitorInfo, PlatformRect, DEFAULT_DPI};
+use crate::generic::edid::Edid;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/** Where the kernel lists DRM devices and their connectors */
+pub const DEFAULT_SYSFS_DRM_PATH: &str = "/sys/class/drm";
+
+/** Connector types of panels that are built into the device, which is where the desktop usually starts */
+const INTERNAL_CONNECTOR_TYPES: [&str; 3] = ["eDP", "LVDS", "DSI"];
+
+/** One output of a graphics card, as the kernel describes it in sysfs */
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct DrmConnector {
+    /** The name of the connector's directory, such as card0-HDMI-A-1 */
+    pub name: String,
+    /** The card the connector belongs to, such as card0 */
+    pub card: String,
+    /** The connector without its card, such as HDMI-A-1 */
+    pub connector: String,
+    pub path: PathBuf,
+    /** Whether the status file says a monitor is plugged in */
+    pub is_connected: bool,
+    /** Whether the enabled file says the output is lit. Older kernels do not have the file. */
+    pub is_enabled: bool,
+    /** The modes from the modes file, in the kernel's order, which puts the preferred mode first */
+    pub modes: Vec<(i32, i32)>,
+    /** The monitor's EDID, None if the connector has none or it does not parse */
+    pub edid: Option<Edid>,
+}
+
+impl DrmConnector {
+    /** Reads a connector directory. Files that are missing are treated as empty. */
+    pub fn read(path: &Path) -> DrmConnector {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (card, connector) = split_connector_name(&name).unwrap_or_default();
+        let read_trimmed = |file_name: &str| {
+            fs::read_to_string(path.join(file_name))
+                .map(|contents| contents.trim().to_string())
+                .unwrap_or_default()
+        };
+        let status = read_trimmed("status");
+        let enabled = read_trimmed("enabled");
+        DrmConnector {
+            card: card.to_string(),
+            connector: connector.to_string(),
+            path: path.to_path_buf(),
+            is_connected: status == "connected",
+            is_enabled: enabled.is_empty() || enabled == "enabled",
+            modes: read_trimmed("modes")
+                .lines()
+                .filter_map(parse_mode)
+                .collect(),
+            edid: fs::read(path.join("edid"))
+                .ok()
+                .and_then(|bytes| Edid::parse(&bytes).ok()),
+            name,
+        }
+    }
+    pub fn is_internal(&self) -> bool {
+        INTERNAL_CONNECTOR_TYPES.iter().any(|connector_type| {
+            self.connector
+                .strip_prefix(connector_type)
+                .is_some_and(|rest| rest.starts_with('-'))
+        })
+    }
+    /**
+     * Describes the connected monitor. name is the monitor's hardware ID when it has an EDID, like on Windows,
+     * else the connector; id is the connector's directory. The native size comes from the EDID, or the
+     * preferred mode when there is no EDID.
+     */
+    pub fn get_monitor_info(&self) -> MonitorInfo {
+        let mut info = MonitorInfo {
+            name: self.connector.clone(),
+            id: self.name.clone(),
+            ..MonitorInfo::default()
+        };
+        if let Some(&(width, height)) = self.modes.first() {
+            info.native_width = width;
+            info.native_height = height;
+        }
+        if let Some(edid) = &self.edid {
+            info.name = edid.get_pnp_id();
+            info.apply_edid(edid);
+        }
+        info
+    }
+}
+
+/** Splits card0-HDMI-A-1 into card0 and HDMI-A-1. Anything else, such as card0 or renderD128, is not a connector. */
+fn split_connector_name(name: &str) -> Option<(&str, &str)> {
+    let (card, connector) = name.split_once('-')?;
+    let card_index = card.strip_prefix("card")?;
+    if card_index.is_empty() || !card_index.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    if connector.is_empty() {
+        return None;
+    }
+    Some((card, connector))
+}
+
+/** Parses a line of a modes file, such as 1920x1080 or 1920x1080i */
+fn parse_mode(line: &str) -> Option<(i32, i32)> {
+    let (width, height) = line.trim().split_once('x')?;
+    let height = height.trim_end_matches(|c: char| !c.is_ascii_digit());
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/** Reads every connector under a sysfs DRM directory, sorted by name. Pass DEFAULT_SYSFS_DRM_PATH for the real one. */
+pub fn read_connectors(sysfs_drm_path: &Path) -> io::Result<Vec<DrmConnector>> {
+    let mut connectors: Vec<DrmConnector> = fs::read_dir(sysfs_drm_path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| split_connector_name(&entry.file_name().to_string_lossy()).is_some())
+        .map(|entry| DrmConnector::read(&entry.path()))
+        .collect();
+    connectors.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(connectors)
+}
+
+/**
+ * Describes every monitor that is plugged in and lit. sysfs has no notion of a primary display, so the first
+ * built-in panel is primary, or the first monitor if there is none.
+ */
+pub fn get_monitor_info(sysfs_drm_path: &Path) -> io::Result<Vec<MonitorInfo>> {
+    let connectors: Vec<DrmConnector> = read_connectors(sysfs_drm_path)?
+        .into_iter()
+        .filter(|connector| connector.is_connected && connector.is_enabled)
+        .collect();
+    let primary_index = connectors
+        .iter()
+        .position(|connector| connector.is_internal())
+        .unwrap_or(0);
+    Ok(connectors
+        .iter()
+        .enumerate()
+        .map(|(index, connector)| MonitorInfo {
+            is_primary: index == primary_index,
+            ..connector.get_monitor_info()
+        })
+        .collect())
+}
+
+/**
+ * Builds display metrics from sysfs. The kernel does not know how a desktop arranges its monitors, so they are
+ * placed left to right at their native resolution, primary first, and each one's work area is all of it.
+ * Scaling is a desktop setting, so every monitor reports DEFAULT_DPI.
+ */
+pub fn get_display_metrics(sysfs_drm_path: &Path) -> io::Result<DisplayMetrics> {
+    let mut monitor_info = get_monitor_info(sysfs_drm_path)?;
+    monitor_info.sort_by_key(|monitor| !monitor.is_primary);
+    let mut left = 0;
+    for monitor in monitor_info.iter_mut() {
+        monitor.display_rect =
+            PlatformRect::new(left, 0, left + monitor.native_width, monitor.native_height);
+        monitor.work_area = monitor.display_rect;
+        monitor.dpi = DEFAULT_DPI;
+        left += monitor.native_width;
+    }
+    Ok(DisplayMetrics::from_monitors(monitor_info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::edid::tests::{EDID_1_3, EDID_1_4};
+    use std::process;
+
+    /** A made-up /sys/class/drm under the temp directory, removed again when the test is done */
+    struct FakeSysfsDrm {
+        path: PathBuf,
+    }
+
+    impl FakeSysfsDrm {
+        fn new(test_name: &str) -> FakeSysfsDrm {
+            let path = std::env::temp_dir().join(format!("drm-{}-{}", test_name, process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            FakeSysfsDrm { path }
+        }
+        /** Adds a directory such as card0 or renderD128, with the files the kernel puts in a card */
+        fn add_device(&self, name: &str) {
+            let path = self.path.join(name);
+            fs::create_dir(&path).unwrap();
+            fs::write(path.join("dev"), "226:0\n").unwrap();
+        }
+        /** Adds a connector directory. A file that is None is left out, like older kernels do. */
+        fn add_connector(
+            &self,
+            name: &str,
+            status: &str,
+            enabled: Option<&str>,
+            modes: &str,
+            edid: &[u8],
+        ) {
+            let path = self.path.join(name);
+            fs::create_dir(&path).unwrap();
+            fs::write(path.join("status"), format!("{}\n", status)).unwrap();
+            if let Some(enabled) = enabled {
+                fs::write(path.join("enabled"), format!("{}\n", enabled)).unwrap();
+            }
+            fs::write(path.join("modes"), modes).unwrap();
+            fs::write(path.join("edid"), edid).unwrap();
+        }
+    }
+
+    impl Drop for FakeSysfsDrm {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    /** A laptop with its panel on eDP-1 and a monitor without an EDID on HDMI-A-1 */
+    fn make_laptop_with_monitor(test_name: &str) -> FakeSysfsDrm {
+        let sysfs = FakeSysfsDrm::new(test_name);
+        sysfs.add_device("card0");
+        sysfs.add_device("renderD128");
+        fs::write(sysfs.path.join("version"), "drm 1.1.0 20060810\n").unwrap();
+        // The modes file lists a lower mode first, the EDID's preferred timing wins over it
+        sysfs.add_connector(
+            "card0-eDP-1",
+            "connected",
+            Some("enabled"),
+            "1366x768\n1920x1080\n",
+            &EDID_1_4,
+        );
+        // Sorts before eDP-1, so the panel is not simply the first connector
+        sysfs.add_connector(
+            "card0-HDMI-A-1",
+            "connected",
+            Some("enabled"),
+            "3840x2160\n1920x1080\n1920x1080i\n",
+            &[],
+        );
+        sysfs.add_connector("card0-DP-1", "disconnected", Some("disabled"), "", &[]);
+        // Plugged in but switched off
+        sysfs.add_connector(
+            "card0-DP-2",
+            "connected",
+            Some("disabled"),
+            "2560x1440\n",
+            &EDID_1_3,
+        );
+        sysfs
+    }
+
+    #[test]
+    fn splits_connector_names() {
+        assert_eq!(
+            split_connector_name("card0-HDMI-A-1"),
+            Some(("card0", "HDMI-A-1"))
+        );
+        assert_eq!(
+            split_connector_name("card12-eDP-1"),
+            Some(("card12", "eDP-1"))
+        );
+        assert_eq!(split_connector_name("card0"), None);
+        assert_eq!(split_connector_name("card0-"), None);
+        assert_eq!(split_connector_name("card-DP-1"), None);
+        assert_eq!(split_connector_name("cardA-DP-1"), None);
+        assert_eq!(split_connector_name("renderD128"), None);
+        assert_eq!(split_connector_name("version"), None);
+    }
+
+    #[test]
+    fn parses_modes() {
+        assert_eq!(parse_mode("1920x1080"), Some((1920, 1080)));
+        assert_eq!(parse_mode("1920x1080i"), Some((1920, 1080)));
+        assert_eq!(parse_mode(" 640x480 "), Some((640, 480)));
+        assert_eq!(parse_mode("card0"), None);
+        assert_eq!(parse_mode(""), None);
+        assert_eq!(parse_mode("x1080"), None);
+        assert_eq!(parse_mode("1920x"), None);
+    }
+
+    #[test]
+    fn reads_only_connectors() {
+        let sysfs = make_laptop_with_monitor("read_connectors");
+        let connectors = read_connectors(&sysfs.path).unwrap();
+        let names: Vec<&str> = connectors
+            .iter()
+            .map(|connector| connector.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["card0-DP-1", "card0-DP-2", "card0-HDMI-A-1", "card0-eDP-1"]
+        );
+
+        let hdmi = &connectors[2];
+        assert_eq!(
+            (hdmi.card.as_str(), hdmi.connector.as_str()),
+            ("card0", "HDMI-A-1")
+        );
+        assert!(hdmi.is_connected && hdmi.is_enabled);
+        assert!(!hdmi.is_internal());
+        assert_eq!(hdmi.modes, [(3840, 2160), (1920, 1080), (1920, 1080)]);
+        assert_eq!(hdmi.edid, None);
+
+        let panel = &connectors[3];
+        assert!(panel.is_internal());
+        assert_eq!(
+            panel.edid.as_ref().map(Edid::get_pnp_id).as_deref(),
+            Some("BOE0747")
+        );
+
+        assert!(!connectors[0].is_connected);
+        assert!(connectors[1].is_connected && !connectors[1].is_enabled);
+
+        assert!(read_connectors(&sysfs.path.join("missing")).is_err());
+    }
+
+    #[test]
+    fn missing_enabled_file_counts_as_enabled() {
+        let sysfs = FakeSysfsDrm::new("enabled_file");
+        sysfs.add_connector("card0-VGA-1", "connected", None, "1024x768\n", &[]);
+        let connectors = read_connectors(&sysfs.path).unwrap();
+        assert!(connectors[0].is_enabled);
+    }
+
+    #[test]
+    fn monitor_info_prefers_the_edid_over_the_modes() {
+        let sysfs = make_laptop_with_monitor("monitor_info");
+        let monitor_info = get_monitor_info(&sysfs.path).unwrap();
+        assert_eq!(monitor_info.len(), 2);
+
+        // No EDID, so the connector names it and the first mode is its native size
+        let hdmi = &monitor_info[0];
+        assert_eq!(hdmi.name, "HDMI-A-1");
+        assert_eq!(hdmi.id, "card0-HDMI-A-1");
+        assert_eq!((hdmi.native_width, hdmi.native_height), (3840, 2160));
+        assert_eq!((hdmi.physical_width_mm, hdmi.physical_height_mm), (0, 0));
+        assert!(!hdmi.is_primary);
+
+        // The built-in panel is primary even though it is not the first connector
+        let panel = &monitor_info[1];
+        assert_eq!(panel.name, "BOE0747");
+        assert_eq!(panel.id, "card0-eDP-1");
+        assert_eq!((panel.native_width, panel.native_height), (1920, 1080));
+        assert_eq!(
+            (panel.physical_width_mm, panel.physical_height_mm),
+            (344, 194)
+        );
+        assert!(panel.is_primary);
+    }
+
+    #[test]
+    fn first_monitor_is_primary_without_a_built_in_panel() {
+        let sysfs = FakeSysfsDrm::new("no_panel");
+        sysfs.add_device("card0");
+        sysfs.add_connector(
+            "card0-DP-1",
+            "connected",
+            Some("enabled"),
+            "2560x1440\n",
+            &[],
+        );
+        sysfs.add_connector("card0-DP-2", "connected", Some("enabled"), "", &EDID_1_3);
+        let monitor_info = get_monitor_info(&sysfs.path).unwrap();
+        let primary: Vec<bool> = monitor_info
+            .iter()
+            .map(|monitor| monitor.is_primary)
+            .collect();
+        assert_eq!(primary, [true, false]);
+        assert_eq!(monitor_info[1].display_name, "DELL 2209WA");
+        assert_eq!(
+            (monitor_info[1].native_width, monitor_info[1].native_height),
+            (1680, 1050)
+        );
+    }
+
+    #[test]
+    fn display_metrics_place_monitors_left_to_right_from_the_primary() {
+        let sysfs = make_laptop_with_monitor("display_metrics");
+        let display_metrics = get_display_metrics(&sysfs.path).unwrap();
+
+        let panel = &display_metrics.monitor_info[0];
+        assert_eq!(panel.id, "card0-eDP-1");
+        assert_eq!(panel.display_rect, PlatformRect::new(0, 0, 1920, 1080));
+        assert_eq!(panel.work_area, panel.display_rect);
+        assert_eq!(panel.dpi, DEFAULT_DPI);
+
+        let hdmi = &display_metrics.monitor_info[1];
+        assert_eq!(hdmi.id, "card0-HDMI-A-1");
+        assert_eq!(hdmi.display_rect, PlatformRect::new(1920, 0, 5760, 2160));
+        assert_eq!(hdmi.dpi, DEFAULT_DPI);
+
+        assert_eq!(
+            (
+                display_metrics.primary_display_width,
+                display_metrics.primary_display_height
+            ),
+            (1920, 1080)
+        );
+        assert_eq!(
+            display_metrics.primary_display_work_area_rect,
+            PlatformRect::new(0, 0, 1920, 1080)
+        );
+        assert_eq!(
+            display_metrics.virtual_display_rect,
+            PlatformRect::new(0, 0, 5760, 2160)
+        );
+    }
+
+    #[test]
+    fn no_connectors_give_empty_metrics() {
+        let sysfs = FakeSysfsDrm::new("empty");
+        sysfs.add_device("card0");
+        let display_metrics = get_display_metrics(&sysfs.path).unwrap();
+        assert!(display_metrics.monitor_info.is_empty());
+        assert_eq!(
+            display_metrics.virtual_display_rect,
+            PlatformRect::default()
+        );
+    }
+}
@@ -0,0 +1,2 @@
+pub mod drm;
+pub mod monitors;
@@ -0,0 +1,26 @@
+use crate::generic::application::DisplayMetrics;
+use crate::linux::drm::{self, DEFAULT_SYSFS_DRM_PATH};
+#[cfg(feature = "x11")]
+use crate::x11::display::X11Display;
+use std::io;
+use std::path::Path;
+
+/**
+ * Describes the monitors without creating an application. When an X server with XRandR is running it knows the
+ * desktop layout, work areas and refresh rates, so it is asked first; otherwise the DRM connectors in sysfs are
+ * read, which also works on a console or a kiosk running a bare compositor.
+ */
+pub fn get_display_metrics() -> io::Result<DisplayMetrics> {
+    #[cfg(feature = "x11")]
+    if let Some(display_metrics) = get_xrandr_display_metrics() {
+        return Ok(display_metrics);
+    }
+    drm::get_display_metrics(Path::new(DEFAULT_SYSFS_DRM_PATH))
+}
+
+#[cfg(feature = "x11")]
+fn get_xrandr_display_metrics() -> Option<DisplayMetrics> {
+    let display = X11Display::open().ok()?;
+    display.xrandr.as_ref()?;
+    Some(DisplayMetrics::from_monitors(display.get_monitor_info()))
+}
@@ -1,5 +1,54 @@
 pub mod generic;
+pub mod headless;
+#[cfg(target_os = "linux")]
+pub mod linux;
+#[cfg(all(unix, not(target_os = "macos"), feature = "wayland"))]
+pub mod wayland;
+#[cfg(all(windows, feature = "win32"))]
 pub mod windows;
+#[cfg(all(unix, not(target_os = "macos"), feature = "x11"))]
+pub mod x11;
 
+#[cfg(all(windows, feature = "win32"))]
 pub use crate::windows::application::WindowsApplication;
+#[cfg(all(windows, feature = "win32"))]
 pub use crate::windows::window::WindowsWindow;
+
+/** The backend that is used by default when building for the target platform */
+pub mod current {
+    #[cfg(all(windows, feature = "win32"))]
+    pub use crate::platform::windows::{
+        application::WindowsApplication as Application, cursor::WindowsCursor as Cursor,
+        window::WindowsWindow as Window,
+    };
+
+    #[cfg(all(unix, not(target_os = "macos"), feature = "x11"))]
+    pub use crate::platform::x11::{
+        application::X11Application as Application, cursor::X11Cursor as Cursor,
+        window::X11Window as Window,
+    };
+
+    #[cfg(all(
+        unix,
+        not(target_os = "macos"),
+        feature = "wayland",
+        not(feature = "x11")
+    ))]
+    pub use crate::platform::wayland::{
+        application::WaylandApplication as Application, cursor::WaylandCursor as Cursor,
+        window::WaylandWindow as Window,
+    };
+
+    #[cfg(not(any(
+        all(windows, feature = "win32"),
+        all(
+            unix,
+            not(target_os = "macos"),
+            any(feature = "x11", feature = "wayland")
+        )
+    )))]
+    pub use crate::platform::headless::{
+        application::HeadlessApplication as Application, cursor::HeadlessCursor as Cursor,
+        window::HeadlessWindow as Window,
+    };
+}
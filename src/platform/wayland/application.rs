@@ -0,0 +1,1119 @@
+use crate::generic::application::{
+    DisplayMetrics, DisplayRotation, GenericApplication, ModifierKeysState, MonitorInfo,
+    PlatformRect, DEFAULT_DPI,
+};
+use crate::generic::application_message_handler::{
+    ApplicationMessageHandler, MouseButtons, WindowActivation,
+};
+use crate::generic::application_proxy::{ApplicationProxy, ProxyReceiver, ProxyWindowId};
+use crate::generic::input::Key;
+use crate::generic::platform_event::{PlatformEvent, PlatformEventQueue};
+use crate::generic::timer::TimerQueue;
+use crate::generic::wakeup_pipe::WakeupPipe;
+use crate::generic::window::GenericWindow;
+use crate::generic::window_definition::WindowDefinition;
+use crate::generic::window_hierarchy;
+use crate::wayland::cursor::WaylandCursor;
+use crate::wayland::keymap::{self, BTN_EXTRA, BTN_LEFT, BTN_MIDDLE, BTN_RIGHT, BTN_SIDE};
+use crate::wayland::window::{WaylandGlobals, WaylandWindow};
+use crate::wayland::xkb::XkbKeyboard;
+use glam::Vec2;
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt, io, mem,
+    os::{raw::c_void, unix::io::AsRawFd},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use wayland_client::{
+    delegate_noop,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{
+        wl_compositor, wl_keyboard, wl_output, wl_pointer, wl_registry, wl_seat, wl_surface,
+    },
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum, WaylandError,
+};
+use wayland_protocols::xdg::{
+    decoration::zv1::client::{zxdg_decoration_manager_v1, zxdg_toplevel_decoration_v1},
+    shell::client::{xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base},
+};
+
+/** Wayland has no system double-click time, so use the Windows default */
+const DOUBLE_CLICK_TIME_MS: u32 = 500;
+/** How far, in pixels, the second click of a double-click may be from the first */
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+/** One notch of a wheel mouse is reported as 10 units of wl_pointer.axis */
+const AXIS_UNITS_PER_WHEEL_NOTCH: f64 = 10.0;
+
+fn translate_mouse_button(button: u32) -> MouseButtons {
+    match button {
+        BTN_LEFT => MouseButtons::Left,
+        BTN_MIDDLE => MouseButtons::Middle,
+        BTN_RIGHT => MouseButtons::Right,
+        BTN_SIDE => MouseButtons::Thumb01,
+        BTN_EXTRA => MouseButtons::Thumb02,
+        _ => MouseButtons::Invalid,
+    }
+}
+
+/** A monitor as wl_output describes it. The info is rebuilt from the latest events on every wl_output.done. */
+#[derive(Debug)]
+struct WaylandOutput {
+    output: wl_output::WlOutput,
+    /** The registry name of the global, which wl_registry.global_remove refers to */
+    global_name: u32,
+    position: (i32, i32),
+    /** The size of the current mode, before the output's transform is applied */
+    mode_size: (i32, i32),
+    scale: i32,
+    info: MonitorInfo,
+}
+
+impl WaylandOutput {
+    fn bind(
+        registry: &wl_registry::WlRegistry,
+        global_name: u32,
+        version: u32,
+        queue_handle: &QueueHandle<WaylandState>,
+    ) -> WaylandOutput {
+        // Version 4 adds the output name and description
+        WaylandOutput {
+            output: registry.bind(global_name, version.min(4), queue_handle, ()),
+            global_name,
+            position: (0, 0),
+            mode_size: (0, 0),
+            scale: 1,
+            info: MonitorInfo {
+                name: format!("wl_output{}", global_name),
+                id: format!("wl_output{}", global_name),
+                ..MonitorInfo::default()
+            },
+        }
+    }
+    fn update_display_rect(&mut self) {
+        let (width, height) = match self.info.rotation {
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                (self.mode_size.1, self.mode_size.0)
+            }
+            _ => self.mode_size,
+        };
+        let (x, y) = self.position;
+        self.info.display_rect = PlatformRect::new(x, y, x + width, y + height);
+        // Panels and docks are not visible to clients, so the whole output is the work area
+        self.info.work_area = self.info.display_rect;
+        self.info.dpi = DEFAULT_DPI * self.scale;
+    }
+}
+
+/** The key that is currently being repeated. The client repeats keys itself on Wayland. */
+#[derive(Clone, Copy, Debug)]
+struct KeyRepeat {
+    key: u32,
+    character: Option<char>,
+    next_repeat: Instant,
+}
+
+/** Everything the event dispatch needs to mutate. Owned by WaylandApplication, passed to the event queue. */
+pub struct WaylandState {
+    globals: Rc<WaylandGlobals>,
+    cursor: Rc<RefCell<WaylandCursor>>,
+    windows: Vec<Rc<WaylandWindow>>,
+    pointer: Option<wl_pointer::WlPointer>,
+    keyboard: Option<wl_keyboard::WlKeyboard>,
+    pointer_focus: Option<Rc<WaylandWindow>>,
+    pointer_position: Vec2,
+    keyboard_focus: Option<Rc<WaylandWindow>>,
+    /** Button, time and position of the last click, used to detect double-clicks */
+    last_click: Option<(u32, u32, Vec2)>,
+    modifier_keys: ModifierKeysState,
+    /**
+     * Interprets keys with the compositor's keymap. None if libxkbcommon could not be loaded, and then, like before
+     * the compositor sends a keymap, keys are read with a US layout.
+     */
+    xkb: Option<XkbKeyboard>,
+    /** Every wl_output the compositor advertises */
+    outputs: Vec<WaylandOutput>,
+    /** Set when an output was added, removed or sent wl_output.done, so poll_events reports new metrics */
+    display_metrics_dirty: bool,
+    /** Keys per second and delay before the first repeat, from wl_keyboard.repeat_info */
+    repeat_rate: i32,
+    repeat_delay: i32,
+    key_repeat: Option<KeyRepeat>,
+    /**
+     * Events produced while dispatching, moved to the application's queue once dispatch is done.
+     * Input events carry their window so they can be dropped if it is disabled or the handler does not want input.
+     */
+    events: VecDeque<(Option<Rc<WaylandWindow>>, PlatformEvent)>,
+}
+
+impl WaylandState {
+    /** Wayland has no primary output; the first one the compositor listed is treated as primary */
+    fn get_display_metrics(&self) -> DisplayMetrics {
+        let mut monitor_info: Vec<MonitorInfo> = self
+            .outputs
+            .iter()
+            .map(|wayland_output| wayland_output.info.clone())
+            .collect();
+        if let Some(first_monitor) = monitor_info.first_mut() {
+            first_monitor.is_primary = true;
+        }
+        DisplayMetrics::from_monitors(monitor_info)
+    }
+    fn find_window_by_surface(&self, surface: &wl_surface::WlSurface) -> Option<Rc<WaylandWindow>> {
+        self.windows
+            .iter()
+            .find(|window| window.get_surface() == surface)
+            .cloned()
+    }
+    fn queue_event(&mut self, event: PlatformEvent) {
+        self.events.push_back((None, event));
+    }
+    /** Queues an input event that is dropped if the window is disabled or the handler does not want input */
+    fn queue_input_event(&mut self, window: &Rc<WaylandWindow>, event: PlatformEvent) {
+        self.events.push_back((Some(window.clone()), event));
+    }
+    /** character is what the key types on its own; text is what it typed this time, which compose may change */
+    fn queue_key_down(
+        &mut self,
+        key: u32,
+        character: Option<char>,
+        text: Option<&str>,
+        is_repeat: bool,
+    ) {
+        let window = match &self.keyboard_focus {
+            Some(window) => window.clone(),
+            None => return,
+        };
+        self.queue_input_event(
+            &window,
+            PlatformEvent::KeyDown {
+                key: Key::from_evdev_code(key),
+                character_code: character.map_or(0, |c| c as u32),
+                is_repeat,
+                modifier_keys: self.modifier_keys,
+            },
+        );
+        for character in text.unwrap_or_default().chars() {
+            self.queue_input_event(
+                &window,
+                PlatformEvent::KeyChar {
+                    character,
+                    is_repeat,
+                    modifier_keys: self.modifier_keys,
+                },
+            );
+        }
+    }
+    fn update_key_repeat(&mut self, now: Instant) {
+        if self.repeat_rate <= 0 {
+            return;
+        }
+        let interval = Duration::from_secs_f64(1.0 / self.repeat_rate as f64);
+        while let Some(mut key_repeat) = self.key_repeat {
+            if now < key_repeat.next_repeat {
+                break;
+            }
+            let text = key_repeat.character.map(String::from);
+            self.queue_key_down(key_repeat.key, key_repeat.character, text.as_deref(), true);
+            key_repeat.next_repeat += interval;
+            self.key_repeat = Some(key_repeat);
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandState {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        // Monitors are hot-plugged; other globals that appear later (e.g. seats) are not picked up yet
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == wl_output::WlOutput::interface().name => {
+                // The output is reported once its first wl_output.done arrives
+                state
+                    .outputs
+                    .push(WaylandOutput::bind(registry, name, version, qh));
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(index) = state
+                    .outputs
+                    .iter()
+                    .position(|wayland_output| wayland_output.global_name == name)
+                {
+                    let wayland_output = state.outputs.remove(index);
+                    if wayland_output.output.version() >= 3 {
+                        wayland_output.output.release();
+                    }
+                    state.display_metrics_dirty = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<xdg_wm_base::XdgWmBase, ()> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        wm_base: &xdg_wm_base::XdgWmBase,
+        event: xdg_wm_base::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<xdg_surface::XdgSurface, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &xdg_surface::XdgSurface,
+        event: xdg_surface::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            let window = state
+                .windows
+                .iter()
+                .find(|window| window.is_xdg_surface(xdg_surface))
+                .cloned();
+            if let Some(window) = window {
+                if let Some((width, height)) = window.on_surface_configure(serial) {
+                    state.queue_event(PlatformEvent::SizeChanged {
+                        window,
+                        width,
+                        height,
+                        was_minimized: false,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<xdg_toplevel::XdgToplevel, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        toplevel: &xdg_toplevel::XdgToplevel,
+        event: xdg_toplevel::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let window = match state
+            .windows
+            .iter()
+            .find(|window| window.is_toplevel(toplevel))
+        {
+            Some(window) => window.clone(),
+            None => return,
+        };
+        match event {
+            xdg_toplevel::Event::Configure {
+                width,
+                height,
+                states,
+            } => window.on_toplevel_configure(width, height, &states),
+            xdg_toplevel::Event::Close => {
+                state.queue_event(PlatformEvent::WindowClose { window });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<xdg_popup::XdgPopup, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        popup: &xdg_popup::XdgPopup,
+        event: xdg_popup::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let window = match state.windows.iter().find(|window| window.is_popup(popup)) {
+            Some(window) => window.clone(),
+            None => return,
+        };
+        match event {
+            xdg_popup::Event::Configure {
+                x,
+                y,
+                width,
+                height,
+            } => window.on_popup_configure(x, y, width, height),
+            xdg_popup::Event::PopupDone => {
+                window.on_popup_done();
+                state.queue_event(PlatformEvent::WindowClose { window });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        decoration: &zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1,
+        event: zxdg_toplevel_decoration_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zxdg_toplevel_decoration_v1::Event::Configure {
+            mode: WEnum::Value(mode),
+        } = event
+        {
+            if let Some(window) = state
+                .windows
+                .iter()
+                .find(|window| window.is_decoration(decoration))
+            {
+                window.on_decoration_configure(mode);
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let Some(wayland_output) = state
+            .outputs
+            .iter_mut()
+            .find(|wayland_output| &wayland_output.output == output)
+        else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry {
+                x,
+                y,
+                physical_width,
+                physical_height,
+                make,
+                model,
+                transform,
+                ..
+            } => {
+                wayland_output.position = (x, y);
+                wayland_output.info.physical_width_mm = physical_width;
+                wayland_output.info.physical_height_mm = physical_height;
+                if wayland_output.info.display_name.is_empty() {
+                    wayland_output.info.display_name = format!("{} {}", make, model);
+                }
+                wayland_output.info.rotation = match transform {
+                    WEnum::Value(wl_output::Transform::_90)
+                    | WEnum::Value(wl_output::Transform::Flipped90) => DisplayRotation::Rotate90,
+                    WEnum::Value(wl_output::Transform::_180)
+                    | WEnum::Value(wl_output::Transform::Flipped180) => DisplayRotation::Rotate180,
+                    WEnum::Value(wl_output::Transform::_270)
+                    | WEnum::Value(wl_output::Transform::Flipped270) => DisplayRotation::Rotate270,
+                    _ => DisplayRotation::Rotate0,
+                };
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                refresh,
+            } => {
+                if flags.contains(wl_output::Mode::Current) {
+                    wayland_output.mode_size = (width, height);
+                    // wl_output reports the refresh rate in mHz
+                    wayland_output.info.refresh_rate = refresh as f32 / 1000.0;
+                }
+                if flags.contains(wl_output::Mode::Preferred) {
+                    wayland_output.info.native_width = width;
+                    wayland_output.info.native_height = height;
+                }
+            }
+            wl_output::Event::Scale { factor } => wayland_output.scale = factor,
+            wl_output::Event::Name { name } => {
+                wayland_output.info.id = name.clone();
+                wayland_output.info.name = name;
+            }
+            wl_output::Event::Description { description } => {
+                wayland_output.info.display_name = description;
+            }
+            wl_output::Event::Done => {
+                wayland_output.update_display_rect();
+                state.display_metrics_dirty = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        seat: &wl_seat::WlSeat,
+        event: wl_seat::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_seat::Event::Capabilities {
+            capabilities: WEnum::Value(capabilities),
+        } = event
+        {
+            let has_pointer = capabilities.contains(wl_seat::Capability::Pointer);
+            if has_pointer && state.pointer.is_none() {
+                let pointer = seat.get_pointer(qh, ());
+                state.cursor.borrow().set_pointer(Some(pointer.clone()));
+                state.pointer = Some(pointer);
+            } else if !has_pointer {
+                if let Some(pointer) = state.pointer.take() {
+                    state.cursor.borrow().set_pointer(None);
+                    if pointer.version() >= 3 {
+                        pointer.release();
+                    }
+                }
+            }
+
+            let has_keyboard = capabilities.contains(wl_seat::Capability::Keyboard);
+            if has_keyboard && state.keyboard.is_none() {
+                state.keyboard = Some(seat.get_keyboard(qh, ()));
+            } else if !has_keyboard {
+                if let Some(keyboard) = state.keyboard.take() {
+                    if keyboard.version() >= 3 {
+                        keyboard.release();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_pointer::WlPointer, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _pointer: &wl_pointer::WlPointer,
+        event: wl_pointer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_pointer::Event::Enter {
+                serial,
+                surface,
+                surface_x,
+                surface_y,
+            } => {
+                state.pointer_focus = state.find_window_by_surface(&surface);
+                state.pointer_position = Vec2::new(surface_x as f32, surface_y as f32);
+                state
+                    .cursor
+                    .borrow()
+                    .on_pointer_enter(serial, state.pointer_position);
+                state.queue_event(PlatformEvent::CursorSet);
+            }
+            wl_pointer::Event::Leave { .. } => {
+                state.pointer_focus = None;
+            }
+            wl_pointer::Event::Motion {
+                surface_x,
+                surface_y,
+                ..
+            } => {
+                state.pointer_position = Vec2::new(surface_x as f32, surface_y as f32);
+                state
+                    .cursor
+                    .borrow()
+                    .on_pointer_motion(state.pointer_position);
+                if let Some(window) = state.pointer_focus.clone() {
+                    state.queue_input_event(&window, PlatformEvent::MouseMove);
+                }
+            }
+            wl_pointer::Event::Button {
+                serial,
+                time,
+                button,
+                state: WEnum::Value(button_state),
+            } => {
+                let window = match state.pointer_focus.clone() {
+                    Some(window) => window,
+                    None => return,
+                };
+                let cursor_pos = state.pointer_position;
+                let mouse_button = translate_mouse_button(button);
+                let modifier_keys = state.modifier_keys;
+                if button_state == wl_pointer::ButtonState::Pressed {
+                    state.globals.last_input_serial.set(serial);
+                    let is_double_click = match state.last_click {
+                        Some((last_button, last_time, last_position)) => {
+                            last_button == button
+                                && time.wrapping_sub(last_time) <= DOUBLE_CLICK_TIME_MS
+                                && (cursor_pos.x - last_position.x).abs() <= DOUBLE_CLICK_DISTANCE
+                                && (cursor_pos.y - last_position.y).abs() <= DOUBLE_CLICK_DISTANCE
+                        }
+                        None => false,
+                    };
+                    if is_double_click {
+                        state.last_click = None;
+                        state.queue_input_event(
+                            &window,
+                            PlatformEvent::MouseDoubleClick {
+                                window: window.clone(),
+                                button: mouse_button,
+                                cursor_pos: Some(cursor_pos),
+                                modifier_keys,
+                            },
+                        );
+                    } else {
+                        state.last_click = Some((button, time, cursor_pos));
+                        state.queue_input_event(
+                            &window,
+                            PlatformEvent::MouseDown {
+                                window: window.clone(),
+                                button: mouse_button,
+                                cursor_pos: Some(cursor_pos),
+                                modifier_keys,
+                            },
+                        );
+                    }
+                } else {
+                    state.queue_input_event(
+                        &window,
+                        PlatformEvent::MouseUp {
+                            button: mouse_button,
+                            cursor_pos: Some(cursor_pos),
+                            modifier_keys,
+                        },
+                    );
+                }
+            }
+            wl_pointer::Event::Axis {
+                axis: WEnum::Value(wl_pointer::Axis::VerticalScroll),
+                value,
+                ..
+            } => {
+                if let Some(window) = state.pointer_focus.clone() {
+                    // Positive axis values scroll down, while a positive wheel delta means up
+                    let delta = (-value / AXIS_UNITS_PER_WHEEL_NOTCH) as f32;
+                    let cursor_pos = state.pointer_position;
+                    let modifier_keys = state.modifier_keys;
+                    state.queue_input_event(
+                        &window,
+                        PlatformEvent::MouseWheel {
+                            delta,
+                            cursor_pos: Some(cursor_pos),
+                            modifier_keys,
+                        },
+                    );
+                }
+            }
+            wl_pointer::Event::Axis {
+                axis: WEnum::Value(wl_pointer::Axis::HorizontalScroll),
+                value,
+                ..
+            } => {
+                if let Some(window) = state.pointer_focus.clone() {
+                    // Positive axis values scroll right, like a positive horizontal wheel delta
+                    let delta = (value / AXIS_UNITS_PER_WHEEL_NOTCH) as f32;
+                    let cursor_pos = state.pointer_position;
+                    let modifier_keys = state.modifier_keys;
+                    state.queue_input_event(
+                        &window,
+                        PlatformEvent::MouseHWheel {
+                            delta,
+                            cursor_pos: Some(cursor_pos),
+                            modifier_keys,
+                        },
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        event: wl_keyboard::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_keyboard::Event::Enter { surface, .. } => {
+                state.keyboard_focus = state.find_window_by_surface(&surface);
+                if let Some(window) = state.keyboard_focus.clone() {
+                    state.queue_event(PlatformEvent::WindowActivationChanged {
+                        window,
+                        activation_type: WindowActivation::Activate,
+                    });
+                }
+            }
+            wl_keyboard::Event::Keymap { format, fd, size } => {
+                if let Some(xkb) = &mut state.xkb {
+                    // A keymap that does not compile leaves the previous one, or the US fallback, in place
+                    let _ = match format {
+                        WEnum::Value(wl_keyboard::KeymapFormat::XkbV1) => {
+                            xkb.set_keymap_from_fd(fd, size)
+                        }
+                        // Without a keymap from the compositor, use the one $XKB_DEFAULT_LAYOUT names
+                        _ => xkb.set_keymap_from_names("", ""),
+                    };
+                }
+            }
+            wl_keyboard::Event::Leave { .. } => {
+                state.key_repeat = None;
+                if let Some(xkb) = &mut state.xkb {
+                    xkb.reset_compose();
+                }
+                // The releases of keys held when focus left go to the new focus
+                let are_caps_locked = state.modifier_keys.are_caps_locked;
+                state
+                    .modifier_keys
+                    .sync_with(false, false, false, false, are_caps_locked);
+                if let Some(window) = state.keyboard_focus.take() {
+                    state.queue_event(PlatformEvent::WindowActivationChanged {
+                        window,
+                        activation_type: WindowActivation::Deactivate,
+                    });
+                }
+            }
+            wl_keyboard::Event::Key {
+                serial,
+                key,
+                state: WEnum::Value(key_state),
+                ..
+            } => {
+                let is_press = key_state == wl_keyboard::KeyState::Pressed;
+                // wl_keyboard.modifiers follows the key, so the side of a modifier is only known from here
+                state
+                    .modifier_keys
+                    .set_key_down(Key::from_evdev_code(key), is_press);
+                let xkb = state.xkb.as_mut().filter(|xkb| xkb.has_keymap());
+                let character = match &xkb {
+                    Some(xkb) => xkb.get_character(key),
+                    None => keymap::key_to_char(
+                        key,
+                        state.modifier_keys.is_shift_down(),
+                        state.modifier_keys.are_caps_locked,
+                    ),
+                };
+                if is_press {
+                    let (text, repeats) = match xkb {
+                        Some(xkb) => (xkb.press_key(key), xkb.key_repeats(key)),
+                        None => (character.map(String::from), true),
+                    };
+                    state.globals.last_input_serial.set(serial);
+                    state.queue_key_down(key, character, text.as_deref(), false);
+                    state.key_repeat = repeats.then(|| KeyRepeat {
+                        key,
+                        character,
+                        next_repeat: Instant::now()
+                            + Duration::from_millis(state.repeat_delay.max(0) as u64),
+                    });
+                } else {
+                    if matches!(state.key_repeat, Some(key_repeat) if key_repeat.key == key) {
+                        state.key_repeat = None;
+                    }
+                    if let Some(window) = state.keyboard_focus.clone() {
+                        state.queue_input_event(
+                            &window,
+                            PlatformEvent::KeyUp {
+                                key: Key::from_evdev_code(key),
+                                character_code: character.map_or(0, |c| c as u32),
+                                is_repeat: false,
+                                modifier_keys: state.modifier_keys,
+                            },
+                        );
+                    }
+                }
+            }
+            wl_keyboard::Event::Modifiers {
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+                ..
+            } => match state.xkb.as_mut().filter(|xkb| xkb.has_keymap()) {
+                Some(xkb) => {
+                    xkb.update_modifiers(mods_depressed, mods_latched, mods_locked, group);
+                    let modifiers = xkb.get_modifiers();
+                    state.modifier_keys.sync_with(
+                        modifiers.is_shift_down,
+                        modifiers.is_control_down,
+                        modifiers.is_alt_down,
+                        modifiers.is_command_down,
+                        modifiers.are_caps_locked,
+                    );
+                }
+                None => {
+                    // Shift, Lock, Control, Mod1 (Alt) and Mod4 (Super) have the same bits in every stock xkb keymap
+                    state.modifier_keys.sync_with(
+                        mods_depressed & 0x1 != 0,
+                        mods_depressed & 0x4 != 0,
+                        mods_depressed & 0x8 != 0,
+                        mods_depressed & 0x40 != 0,
+                        mods_locked & 0x2 != 0,
+                    );
+                }
+            },
+            wl_keyboard::Event::RepeatInfo { rate, delay } => {
+                state.repeat_rate = rate;
+                state.repeat_delay = delay;
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(WaylandState: wl_compositor::WlCompositor);
+delegate_noop!(WaylandState: ignore wl_surface::WlSurface);
+delegate_noop!(WaylandState: xdg_positioner::XdgPositioner);
+delegate_noop!(WaylandState: zxdg_decoration_manager_v1::ZxdgDecorationManagerV1);
+
+pub struct WaylandApplication {
+    connection: Connection,
+    event_queue: RefCell<EventQueue<WaylandState>>,
+    queue_handle: QueueHandle<WaylandState>,
+    globals: Rc<WaylandGlobals>,
+    state: RefCell<WaylandState>,
+    cursor: Rc<RefCell<WaylandCursor>>,
+    message_handler: Rc<dyn ApplicationMessageHandler>,
+    events: PlatformEventQueue,
+    is_active: Cell<bool>,
+    initial_display_metrics: DisplayMetrics,
+    display_metrics: RefCell<DisplayMetrics>,
+    proxy_receiver: ProxyReceiver<WaylandWindow>,
+    /** Polled next to the Wayland connection, so a proxy can wake the application while it waits */
+    wakeup_pipe: WakeupPipe,
+    timers: TimerQueue,
+}
+
+impl fmt::Debug for WaylandApplication {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaylandApplication")
+            .field("globals", &self.globals)
+            .field("cursor", &self.cursor)
+            .field("num_windows", &self.state.borrow().windows.len())
+            .field("is_active", &self.is_active)
+            .field("display_metrics", &self.display_metrics)
+            .field("proxy_receiver", &self.proxy_receiver)
+            .field("timers", &self.timers)
+            .finish()
+    }
+}
+
+impl WaylandApplication {
+    /** Connects to the compositor named by $WAYLAND_DISPLAY and binds the globals the backend needs */
+    pub fn new(
+        message_handler: &Rc<dyn ApplicationMessageHandler>,
+    ) -> io::Result<WaylandApplication> {
+        let to_io_error = |err: &dyn std::error::Error| io::Error::other(err.to_string());
+
+        let connection = Connection::connect_to_env()
+            .map_err(|err| io::Error::new(io::ErrorKind::ConnectionRefused, err))?;
+        let (global_list, mut event_queue) =
+            registry_queue_init::<WaylandState>(&connection).map_err(|err| to_io_error(&err))?;
+        let queue_handle = event_queue.handle();
+
+        let compositor: wl_compositor::WlCompositor = global_list
+            .bind(&queue_handle, 1..=4, ())
+            .map_err(|err| to_io_error(&err))?;
+        let wm_base: xdg_wm_base::XdgWmBase = global_list
+            .bind(&queue_handle, 1..=3, ())
+            .map_err(|err| to_io_error(&err))?;
+        let decoration_manager = global_list.bind(&queue_handle, 1..=1, ()).ok();
+        let seat: Option<wl_seat::WlSeat> = global_list.bind(&queue_handle, 1..=5, ()).ok();
+        let outputs = global_list.contents().with_list(|list| {
+            list.iter()
+                .filter(|global| global.interface == wl_output::WlOutput::interface().name)
+                .map(|global| {
+                    WaylandOutput::bind(
+                        global_list.registry(),
+                        global.name,
+                        global.version,
+                        &queue_handle,
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let globals = Rc::new(WaylandGlobals {
+            compositor,
+            wm_base,
+            decoration_manager,
+            seat,
+            last_input_serial: Cell::new(0),
+        });
+        let cursor = Rc::new(RefCell::new(WaylandCursor::new()));
+        let mut state = WaylandState {
+            globals: globals.clone(),
+            cursor: cursor.clone(),
+            windows: vec![],
+            pointer: None,
+            keyboard: None,
+            pointer_focus: None,
+            pointer_position: Vec2::ZERO,
+            keyboard_focus: None,
+            last_click: None,
+            modifier_keys: ModifierKeysState::default(),
+            xkb: XkbKeyboard::new().ok(),
+            outputs,
+            display_metrics_dirty: false,
+            repeat_rate: 25,
+            repeat_delay: 600,
+            key_repeat: None,
+            events: VecDeque::new(),
+        };
+        // Receive the seat capabilities and outputs so input devices and monitors exist before the first pump
+        event_queue
+            .roundtrip(&mut state)
+            .map_err(|err| to_io_error(&err))?;
+        let initial_display_metrics = state.get_display_metrics();
+        state.display_metrics_dirty = false;
+        let (wakeup_pipe, waker) = WakeupPipe::new()?;
+
+        Ok(WaylandApplication {
+            connection,
+            event_queue: RefCell::new(event_queue),
+            queue_handle,
+            globals,
+            state: RefCell::new(state),
+            cursor,
+            message_handler: message_handler.clone(),
+            events: PlatformEventQueue::new(),
+            is_active: Cell::new(false),
+            display_metrics: RefCell::new(initial_display_metrics.clone()),
+            initial_display_metrics,
+            proxy_receiver: ProxyReceiver::new(Some(Box::new(waker))),
+            wakeup_pipe,
+            timers: TimerQueue::new(),
+        })
+    }
+    /**
+     * Creates a window from the definition and registers it with the application. parent becomes its owner.
+     * Menus and tool-tips without one become popups of the window with keyboard focus, or of the newest visible
+     * toplevel.
+     */
+    pub fn make_window(
+        &self,
+        definition: &Rc<WindowDefinition>,
+        parent: Option<&Rc<WaylandWindow>>,
+        show_immediately: bool,
+    ) -> Rc<WaylandWindow> {
+        let parent = if let Some(parent) = parent {
+            Some(parent.clone())
+        } else if WaylandWindow::is_popup_type(definition) {
+            let state = self.state.borrow();
+            state.keyboard_focus.clone().or_else(|| {
+                state
+                    .windows
+                    .iter()
+                    .rev()
+                    .find(|window| {
+                        window.is_visible()
+                            && !WaylandWindow::is_popup_type(window.get_definition())
+                    })
+                    .cloned()
+            })
+        } else {
+            None
+        };
+        let window = Rc::new(WaylandWindow::new(
+            &self.globals,
+            &self.queue_handle,
+            definition,
+            parent.as_ref(),
+        ));
+        if let Some(parent) = &parent {
+            let generic_parent: Rc<dyn GenericWindow> = parent.clone();
+            let generic_window: Rc<dyn GenericWindow> = window.clone();
+            window_hierarchy::set_parent(&generic_window, Some(&generic_parent));
+        }
+        self.state.borrow_mut().windows.push(window.clone());
+        if show_immediately {
+            window.show();
+        }
+        let _ = self.connection.flush();
+        window
+    }
+    pub fn get_windows(&self) -> Vec<Rc<WaylandWindow>> {
+        self.state.borrow().windows.clone()
+    }
+    pub fn get_cursor(&self) -> &Rc<RefCell<WaylandCursor>> {
+        &self.cursor
+    }
+    /** The wl_display, for renderers that need to create a surface for get_os_window_handle */
+    pub fn get_display_ptr(&self) -> *const c_void {
+        self.connection.backend().display_ptr() as *const c_void
+    }
+    /** Sends the queued requests. A full socket is not an error; what did not fit is sent by the next flush. */
+    fn flush_connection(&self) -> io::Result<()> {
+        match self.connection.flush() {
+            Ok(()) => Ok(()),
+            Err(WaylandError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            Err(WaylandError::Io(err)) => Err(err),
+            Err(err) => Err(io::Error::other(err.to_string())),
+        }
+    }
+    /** Sends queued requests and reads whatever the compositor has sent, without blocking */
+    fn read_events(&self) -> io::Result<()> {
+        self.flush_connection()?;
+        let mut event_queue = self.event_queue.borrow_mut();
+        if let Some(guard) = event_queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => {}
+                Err(WaylandError::Io(err)) => return Err(err),
+                Err(err) => return Err(io::Error::other(err.to_string())),
+            }
+        }
+        let mut state = self.state.borrow_mut();
+        event_queue
+            .dispatch_pending(&mut state)
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        state.update_key_repeat(Instant::now());
+        Ok(())
+    }
+}
+
+impl GenericApplication for WaylandApplication {
+    type Cursor = WaylandCursor;
+    type Window = WaylandWindow;
+
+    fn set_message_handler(&mut self, in_message_handler: &Rc<dyn ApplicationMessageHandler>) {
+        self.message_handler = in_message_handler.clone();
+    }
+    fn get_message_handler(&self) -> &Rc<dyn ApplicationMessageHandler> {
+        &self.message_handler
+    }
+    fn get_event_queue(&self) -> &PlatformEventQueue {
+        &self.events
+    }
+    fn get_timer_queue(&self) -> &TimerQueue {
+        &self.timers
+    }
+    fn get_modifier_keys(&self) -> ModifierKeysState {
+        self.state.borrow().modifier_keys
+    }
+    fn get_initial_display_metrics(&self) -> DisplayMetrics {
+        self.initial_display_metrics.clone()
+    }
+    fn get_display_metrics(&self) -> DisplayMetrics {
+        self.display_metrics.borrow().clone()
+    }
+    fn create_proxy(&self) -> ApplicationProxy {
+        self.proxy_receiver.create_proxy()
+    }
+    fn get_proxy_window(&self, id: ProxyWindowId) -> Option<Rc<WaylandWindow>> {
+        self.proxy_receiver.get_window(id)
+    }
+    fn wait_for_events(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        if !self.events.is_empty()
+            || self.proxy_receiver.has_pending_messages()
+            || self.timers.has_expired_timers()
+        {
+            return Ok(true);
+        }
+        self.flush_connection()?;
+        // Key repeats are made here rather than sent by the compositor, so stop waiting when the next one is due
+        let next_repeat = {
+            let state = self.state.borrow();
+            state
+                .key_repeat
+                .filter(|_| state.repeat_rate > 0)
+                .map(|key_repeat| key_repeat.next_repeat)
+        };
+        let timeout = self.timers.get_wait_timeout(timeout);
+        let timeout = match next_repeat {
+            Some(next_repeat) => {
+                let until_repeat = next_repeat.saturating_duration_since(Instant::now());
+                Some(timeout.map_or(until_repeat, |timeout| timeout.min(until_repeat)))
+            }
+            None => timeout,
+        };
+        let event_queue = self.event_queue.borrow();
+        let guard = match event_queue.prepare_read() {
+            Some(guard) => guard,
+            // Events were read already and are waiting to be dispatched
+            None => return Ok(true),
+        };
+        // The guard is dropped without reading, which leaves the events on the socket for poll_events
+        let is_ready = self
+            .wakeup_pipe
+            .wait(guard.connection_fd().as_raw_fd(), timeout)?;
+        Ok(is_ready
+            || next_repeat.is_some_and(|next_repeat| Instant::now() >= next_repeat)
+            || self.timers.has_expired_timers())
+    }
+    fn poll_events(&self, _time_delta: f32) -> io::Result<()> {
+        self.wakeup_pipe.drain();
+        self.proxy_receiver.process_messages(
+            &self.events,
+            |definition, parent, show_immediately| {
+                self.make_window(definition, parent, show_immediately)
+            },
+        );
+        let result = self.read_events();
+
+        let mut state = self.state.borrow_mut();
+        for (window, event) in state.events.drain(..) {
+            if let Some(window) = window {
+                let generic_window: Rc<dyn GenericWindow> = window.clone();
+                if !window.is_enabled()
+                    || !window_hierarchy::should_process_user_input(
+                        &self.message_handler,
+                        &generic_window,
+                    )
+                {
+                    continue;
+                }
+            }
+            self.events.push(event);
+        }
+        if mem::take(&mut state.display_metrics_dirty) {
+            let display_metrics = state.get_display_metrics();
+            let change = self.display_metrics.borrow().diff(&display_metrics);
+            if !change.is_empty() {
+                *self.display_metrics.borrow_mut() = display_metrics.clone();
+                self.events.push(PlatformEvent::DisplayMetricsChanged {
+                    display_metrics,
+                    change,
+                });
+            }
+        }
+        state.windows.retain(|window| !window.is_destroyed());
+        self.proxy_receiver
+            .retain_windows(|window| !window.is_destroyed());
+        let is_active = state.keyboard_focus.is_some();
+        drop(state);
+        if is_active != self.is_active.get() {
+            self.is_active.set(is_active);
+            self.events
+                .push(PlatformEvent::ApplicationActivationChanged { is_active });
+        }
+        self.timers.fire_expired_timers(&self.events);
+        // The events dispatched before a failed read are still queued, so report the read error after them
+        result.and_then(|()| self.flush_connection())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    core::math::color::Color,
+    generic::{
+        application::PlatformRect,
+        cursor::{ICursor, MouseCursor},
+    },
+};
+use glam::Vec2;
+use std::{
+    cell::{Cell, RefCell},
+    error::Error,
+    ffi::c_void,
+    io,
+    path::Path,
+};
+use wayland_client::protocol::wl_pointer;
+
+/**
+ * The Wayland cursor. Clients can not move the pointer, so the position is the last one the compositor reported,
+ * relative to the window under the pointer. Cursor images need a cursor theme, which is left to the renderer;
+ * hiding the cursor is done here because it only needs the pointer.
+ */
+#[derive(Debug)]
+pub struct WaylandCursor {
+    pub current_type: MouseCursor,
+    pub cursor_override_handles: [*const c_void; 15],
+    position: Cell<Vec2>,
+    is_visible: Cell<bool>,
+    clip_rect: Cell<Option<PlatformRect>>,
+    pointer: RefCell<Option<wl_pointer::WlPointer>>,
+    /** Serial of the last wl_pointer.enter. set_cursor is ignored unless it quotes it. */
+    enter_serial: Cell<u32>,
+}
+
+impl WaylandCursor {
+    pub fn new() -> WaylandCursor {
+        WaylandCursor {
+            current_type: MouseCursor::Default,
+            cursor_override_handles: [std::ptr::null(); 15],
+            position: Cell::new(Vec2::ZERO),
+            is_visible: Cell::new(true),
+            clip_rect: Cell::new(None),
+            pointer: RefCell::new(None),
+            enter_serial: Cell::new(0),
+        }
+    }
+    pub fn is_visible(&self) -> bool {
+        self.is_visible.get()
+    }
+    /** The rect the cursor is currently locked to, if any */
+    pub fn get_clip_rect(&self) -> Option<PlatformRect> {
+        self.clip_rect.get()
+    }
+    /** Called by the application when the seat's pointer is created or goes away */
+    pub fn set_pointer(&self, pointer: Option<wl_pointer::WlPointer>) {
+        *self.pointer.borrow_mut() = pointer;
+    }
+    /** Called by the application on wl_pointer.enter */
+    pub fn on_pointer_enter(&self, serial: u32, position: Vec2) {
+        self.enter_serial.set(serial);
+        self.position.set(position);
+        if !self.is_visible.get() {
+            self.apply_visibility();
+        }
+    }
+    /** Called by the application on wl_pointer.motion */
+    pub fn on_pointer_motion(&self, position: Vec2) {
+        self.position.set(position);
+    }
+    fn apply_visibility(&self) {
+        if let Some(pointer) = &*self.pointer.borrow() {
+            if !self.is_visible.get() {
+                pointer.set_cursor(self.enter_serial.get(), None, 0, 0);
+            }
+        }
+    }
+}
+
+impl ICursor for WaylandCursor {
+    type Rect = PlatformRect;
+
+    fn create_cursor_from_file<P: AsRef<Path>>(
+        path_to_cursor_without_extension: P,
+        _hotspot: Vec2,
+    ) -> Result<Self, Box<dyn Error>> {
+        let msg = format!(
+            "loading cursors from file is not supported by the Wayland backend ({})",
+            path_to_cursor_without_extension.as_ref().display()
+        );
+        Err(Box::new(io::Error::new(io::ErrorKind::Unsupported, msg)))
+    }
+
+    fn is_create_cursor_from_rgba_buffer_supported() -> bool {
+        false
+    }
+
+    fn create_cursor_from_rgba_buffer(
+        _pixels: Color,
+        _width: i32,
+        _height: i32,
+        _hotspot: Vec2,
+    ) -> Option<Self> {
+        None
+    }
+
+    fn get_position(&self) -> Vec2 {
+        self.position.get()
+    }
+
+    fn set_position(&mut self, _x: i32, _y: i32) {
+        // Wayland does not let clients warp the pointer
+    }
+
+    fn set_type(&mut self, new_cursor: MouseCursor) {
+        self.current_type = new_cursor;
+    }
+
+    fn get_type(&self) -> &MouseCursor {
+        &self.current_type
+    }
+
+    fn get_size(&self, width: &mut i32, height: &mut i32) {
+        *width = 24;
+        *height = 24;
+    }
+
+    fn show(&self, show: bool) {
+        self.is_visible.set(show);
+        self.apply_visibility();
+    }
+
+    /** Confining the pointer needs the pointer-constraints protocol. The rect is kept for the application to honor. */
+    fn lock(&self, bounds: Option<*const Self::Rect>) {
+        match bounds {
+            Some(rect) if !rect.is_null() => {
+                self.clip_rect.set(Some(unsafe { *rect }));
+            }
+            _ => self.clip_rect.set(None),
+        }
+    }
+
+    fn set_type_shape(&mut self, cursor_type: MouseCursor, cursor_handle: *const c_void) {
+        self.cursor_override_handles[cursor_type.to_usize()] = cursor_handle;
+    }
+}
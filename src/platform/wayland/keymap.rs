@@ -0,0 +1,96 @@
+/** Linux evdev key codes (linux/input-event-codes.h) for the keys the backend has to know about */
+pub const KEY_ESC: u32 = 1;
+pub const KEY_BACKSPACE: u32 = 14;
+pub const KEY_TAB: u32 = 15;
+pub const KEY_ENTER: u32 = 28;
+pub const KEY_SPACE: u32 = 57;
+pub const KEY_KPENTER: u32 = 96;
+
+/** Linux evdev mouse buttons */
+pub const BTN_LEFT: u32 = 0x110;
+pub const BTN_RIGHT: u32 = 0x111;
+pub const BTN_MIDDLE: u32 = 0x112;
+pub const BTN_SIDE: u32 = 0x113;
+pub const BTN_EXTRA: u32 = 0x114;
+
+/** The unshifted and shifted characters of the printable keys on a US layout, indexed by evdev code */
+const US_LAYOUT: [(u32, char, char); 47] = [
+    (2, '1', '!'),
+    (3, '2', '@'),
+    (4, '3', '#'),
+    (5, '4', '$'),
+    (6, '5', '%'),
+    (7, '6', '^'),
+    (8, '7', '&'),
+    (9, '8', '*'),
+    (10, '9', '('),
+    (11, '0', ')'),
+    (12, '-', '_'),
+    (13, '=', '+'),
+    (16, 'q', 'Q'),
+    (17, 'w', 'W'),
+    (18, 'e', 'E'),
+    (19, 'r', 'R'),
+    (20, 't', 'T'),
+    (21, 'y', 'Y'),
+    (22, 'u', 'U'),
+    (23, 'i', 'I'),
+    (24, 'o', 'O'),
+    (25, 'p', 'P'),
+    (26, '[', '{'),
+    (27, ']', '}'),
+    (30, 'a', 'A'),
+    (31, 's', 'S'),
+    (32, 'd', 'D'),
+    (33, 'f', 'F'),
+    (34, 'g', 'G'),
+    (35, 'h', 'H'),
+    (36, 'j', 'J'),
+    (37, 'k', 'K'),
+    (38, 'l', 'L'),
+    (39, ';', ':'),
+    (40, '\'', '"'),
+    (41, '`', '~'),
+    (43, '\\', '|'),
+    (44, 'z', 'Z'),
+    (45, 'x', 'X'),
+    (46, 'c', 'C'),
+    (47, 'v', 'V'),
+    (48, 'b', 'B'),
+    (49, 'n', 'N'),
+    (50, 'm', 'M'),
+    (51, ',', '<'),
+    (52, '.', '>'),
+    (53, '/', '?'),
+];
+
+/**
+ * Maps an evdev key code to the character it types on a US layout. Keys are read with the compositor's keymap
+ * through XkbKeyboard; this is the fallback for when libxkbcommon is missing or no keymap compiled yet.
+ */
+pub fn key_to_char(key: u32, shift_down: bool, caps_lock: bool) -> Option<char> {
+    match key {
+        KEY_ESC => return Some('\u{1b}'),
+        KEY_BACKSPACE => return Some('\u{8}'),
+        KEY_TAB => return Some('\t'),
+        KEY_ENTER | KEY_KPENTER => return Some('\r'),
+        KEY_SPACE => return Some(' '),
+        _ => {}
+    }
+    US_LAYOUT
+        .iter()
+        .find(|(code, _, _)| *code == key)
+        .map(|&(_, unshifted, shifted)| {
+            // Caps lock only affects letters, and shift undoes it
+            let shift = if unshifted.is_ascii_alphabetic() {
+                shift_down != caps_lock
+            } else {
+                shift_down
+            };
+            if shift {
+                shifted
+            } else {
+                unshifted
+            }
+        })
+}
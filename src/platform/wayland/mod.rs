@@ -0,0 +1,5 @@
+pub mod application;
+pub mod cursor;
+pub mod keymap;
+pub mod window;
+pub mod xkb;
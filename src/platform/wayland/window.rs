@@ -0,0 +1,678 @@
+use crate::generic::{
+    application::PlatformRect,
+    window::{GenericWindow, WindowDrawAttentionRequestType, WindowMode},
+    window_definition::{WindowDefinition, WindowType},
+    window_hierarchy::{self, WindowHierarchy},
+    window_sizing::{self, ResizeEdge, SizingConstraints},
+};
+use crate::wayland::application::WaylandState;
+use std::{
+    cell::{Cell, RefCell},
+    os::raw::c_void,
+    rc::{Rc, Weak},
+};
+use wayland_client::{
+    protocol::{wl_compositor, wl_seat, wl_surface},
+    Proxy, QueueHandle,
+};
+use wayland_protocols::xdg::{
+    decoration::zv1::client::{zxdg_decoration_manager_v1, zxdg_toplevel_decoration_v1},
+    shell::client::{xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base},
+};
+
+/** xdg_toplevel states, as they appear in the configure event's state array */
+const XDG_TOPLEVEL_STATE_MAXIMIZED: u32 = 1;
+const XDG_TOPLEVEL_STATE_FULLSCREEN: u32 = 2;
+const XDG_TOPLEVEL_STATE_ACTIVATED: u32 = 4;
+
+/** The globals the windows need, shared with the application */
+#[derive(Debug)]
+pub struct WaylandGlobals {
+    pub compositor: wl_compositor::WlCompositor,
+    pub wm_base: xdg_wm_base::XdgWmBase,
+    /** Not every compositor implements xdg-decoration; without it windows draw their own border */
+    pub decoration_manager: Option<zxdg_decoration_manager_v1::ZxdgDecorationManagerV1>,
+    pub seat: Option<wl_seat::WlSeat>,
+    /** Serial of the latest button or key press. Popup grabs have to quote one. */
+    pub last_input_serial: Cell<u32>,
+}
+
+#[derive(Debug)]
+enum WaylandRole {
+    Toplevel {
+        toplevel: xdg_toplevel::XdgToplevel,
+        decoration: Option<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1>,
+    },
+    Popup {
+        popup: xdg_popup::XdgPopup,
+    },
+}
+
+/** The xdg-shell objects of a mapped window. They only exist while the window is shown. */
+#[derive(Debug)]
+struct WaylandShellSurface {
+    xdg_surface: xdg_surface::XdgSurface,
+    role: WaylandRole,
+}
+
+/** A configure sequence that has not been acknowledged yet */
+#[derive(Clone, Copy, Debug, Default)]
+struct PendingConfigure {
+    width: i32,
+    height: i32,
+    is_maximized: bool,
+    is_fullscreen: bool,
+    is_activated: bool,
+}
+
+/**
+ * A Wayland window. Normal and game windows are xdg_toplevels, menus and tool-tips are xdg_popups of their parent.
+ * Wayland has no global coordinates, so positions are only meaningful for popups, relative to the parent.
+ * The window never attaches a buffer itself; the renderer draws into the wl_surface from get_os_window_handle.
+ */
+#[derive(Debug)]
+pub struct WaylandWindow {
+    globals: Rc<WaylandGlobals>,
+    queue_handle: QueueHandle<WaylandState>,
+    surface: wl_surface::WlSurface,
+    shell_surface: RefCell<Option<WaylandShellSurface>>,
+    parent: Option<Weak<WaylandWindow>>,
+    x: Cell<i32>,
+    y: Cell<i32>,
+    width: Cell<i32>,
+    height: Cell<i32>,
+    /** Size to return to when leaving the maximized or fullscreen state */
+    restored_size: Cell<(i32, i32)>,
+    /** Width over height to keep while the user resizes the window, taken from the last size the application set */
+    aspect_ratio: Cell<f32>,
+    pending_configure: Cell<PendingConfigure>,
+    window_mode: Cell<WindowMode>,
+    is_visible: Cell<bool>,
+    is_maximized: Cell<bool>,
+    is_minimized: Cell<bool>,
+    is_activated: Cell<bool>,
+    is_enabled: Cell<bool>,
+    is_destroyed: Cell<bool>,
+    is_server_side_decorated: Cell<bool>,
+    is_drawing_attention: Cell<bool>,
+    native_window_buttons_visible: Cell<bool>,
+    opacity: Cell<f32>,
+    title: RefCell<String>,
+    dpi_scale_factor: Cell<f32>,
+    handle_manual_dpi_changes: Cell<bool>,
+    window_definitions: Rc<WindowDefinition>,
+    hierarchy: WindowHierarchy,
+}
+
+impl WaylandWindow {
+    pub fn new(
+        globals: &Rc<WaylandGlobals>,
+        queue_handle: &QueueHandle<WaylandState>,
+        definition: &Rc<WindowDefinition>,
+        parent: Option<&Rc<WaylandWindow>>,
+    ) -> WaylandWindow {
+        let width = (definition.width_desired_on_screen as i32).max(1);
+        let height = (definition.height_desired_on_screen as i32).max(1);
+        // Popups are placed relative to their parent
+        let (parent_x, parent_y) = parent.map_or((0, 0), |parent| (parent.x.get(), parent.y.get()));
+        WaylandWindow {
+            globals: globals.clone(),
+            queue_handle: queue_handle.clone(),
+            surface: globals.compositor.create_surface(queue_handle, ()),
+            shell_surface: RefCell::new(None),
+            parent: parent.map(Rc::downgrade),
+            x: Cell::new(definition.x_desired_position_on_screen as i32 - parent_x),
+            y: Cell::new(definition.y_desired_position_on_screen as i32 - parent_y),
+            width: Cell::new(width),
+            height: Cell::new(height),
+            restored_size: Cell::new((width, height)),
+            aspect_ratio: Cell::new(width as f32 / height as f32),
+            pending_configure: Cell::new(PendingConfigure::default()),
+            window_mode: Cell::new(WindowMode::Windowed),
+            is_visible: Cell::new(false),
+            is_maximized: Cell::new(false),
+            is_minimized: Cell::new(false),
+            is_activated: Cell::new(false),
+            is_enabled: Cell::new(true),
+            is_destroyed: Cell::new(false),
+            is_server_side_decorated: Cell::new(false),
+            is_drawing_attention: Cell::new(false),
+            native_window_buttons_visible: Cell::new(true),
+            opacity: Cell::new(definition.opacity),
+            title: RefCell::new(definition.title.clone()),
+            dpi_scale_factor: Cell::new(1.0),
+            handle_manual_dpi_changes: Cell::new(definition.manual_dpi),
+            window_definitions: definition.clone(),
+            hierarchy: WindowHierarchy::new(),
+        }
+    }
+    /** Whether the window type maps to an xdg_popup rather than an xdg_toplevel */
+    pub fn is_popup_type(definition: &WindowDefinition) -> bool {
+        matches!(
+            definition.window_type,
+            WindowType::Menu | WindowType::ToolTip
+        )
+    }
+    pub fn get_surface(&self) -> &wl_surface::WlSurface {
+        &self.surface
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled.get()
+    }
+    pub fn is_destroyed(&self) -> bool {
+        self.is_destroyed.get()
+    }
+    pub fn is_drawing_attention(&self) -> bool {
+        self.is_drawing_attention.get()
+    }
+    /** Whether the compositor agreed to draw the border through xdg-decoration */
+    pub fn is_server_side_decorated(&self) -> bool {
+        self.is_server_side_decorated.get()
+    }
+    pub fn get_opacity(&self) -> f32 {
+        self.opacity.get()
+    }
+    pub fn is_xdg_surface(&self, xdg_surface: &xdg_surface::XdgSurface) -> bool {
+        matches!(&*self.shell_surface.borrow(), Some(shell) if shell.xdg_surface == *xdg_surface)
+    }
+    pub fn is_toplevel(&self, toplevel: &xdg_toplevel::XdgToplevel) -> bool {
+        matches!(
+            &*self.shell_surface.borrow(),
+            Some(WaylandShellSurface { role: WaylandRole::Toplevel { toplevel: candidate, .. }, .. })
+                if candidate == toplevel
+        )
+    }
+    pub fn is_popup(&self, popup: &xdg_popup::XdgPopup) -> bool {
+        matches!(
+            &*self.shell_surface.borrow(),
+            Some(WaylandShellSurface { role: WaylandRole::Popup { popup: candidate }, .. })
+                if candidate == popup
+        )
+    }
+    pub fn is_decoration(
+        &self,
+        decoration: &zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1,
+    ) -> bool {
+        matches!(
+            &*self.shell_surface.borrow(),
+            Some(WaylandShellSurface {
+                role: WaylandRole::Toplevel { decoration: Some(candidate), .. },
+                ..
+            }) if candidate == decoration
+        )
+    }
+    /** Stores the state of an xdg_toplevel.configure until the xdg_surface.configure that completes it */
+    pub fn on_toplevel_configure(&self, width: i32, height: i32, states: &[u8]) {
+        let states: Vec<u32> = states
+            .chunks_exact(4)
+            .map(|state| u32::from_ne_bytes([state[0], state[1], state[2], state[3]]))
+            .collect();
+        self.pending_configure.set(PendingConfigure {
+            width,
+            height,
+            is_maximized: states.contains(&XDG_TOPLEVEL_STATE_MAXIMIZED),
+            is_fullscreen: states.contains(&XDG_TOPLEVEL_STATE_FULLSCREEN),
+            is_activated: states.contains(&XDG_TOPLEVEL_STATE_ACTIVATED),
+        });
+    }
+    pub fn on_popup_configure(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.x.set(x);
+        self.y.set(y);
+        self.pending_configure.set(PendingConfigure {
+            width,
+            height,
+            ..PendingConfigure::default()
+        });
+    }
+    pub fn on_decoration_configure(&self, mode: zxdg_toplevel_decoration_v1::Mode) {
+        self.is_server_side_decorated
+            .set(mode == zxdg_toplevel_decoration_v1::Mode::ServerSide);
+    }
+    /**
+     * Acknowledges the configure sequence and applies it.
+     * Returns the new size if it changed, so the application can report it.
+     */
+    pub fn on_surface_configure(&self, serial: u32) -> Option<(i32, i32)> {
+        if let Some(shell) = &*self.shell_surface.borrow() {
+            shell.xdg_surface.ack_configure(serial);
+        }
+        let configure = self.pending_configure.get();
+        self.is_maximized.set(configure.is_maximized);
+        if configure.is_activated {
+            // There is no "restored" event, but a minimized window can not be active
+            self.is_minimized.set(false);
+            self.is_drawing_attention.set(false);
+        }
+        self.is_activated.set(configure.is_activated);
+        if !configure.is_maximized && !configure.is_fullscreen {
+            self.save_restored_size();
+        }
+
+        // A zero size means the client picks. Use the restored size when leaving maximized or fullscreen.
+        let (mut width, mut height) = (configure.width, configure.height);
+        if width <= 0 || height <= 0 {
+            let (restored_width, restored_height) = self.restored_size.get();
+            width = restored_width;
+            height = restored_height;
+        }
+        self.apply_size_limits(&mut width, &mut height, true);
+        if width != self.width.get() || height != self.height.get() {
+            self.width.set(width);
+            self.height.set(height);
+            Some((width, height))
+        } else {
+            None
+        }
+    }
+    /** Called when the compositor dismisses a popup, e.g. because the user clicked outside of it */
+    pub fn on_popup_done(&self) {
+        self.hide();
+    }
+    /**
+     * Keeps a size within the size limits. A size the user picked by resizing the window also keeps the aspect ratio
+     * of the last reshape if the window preserves it, like WM_SIZING does on Windows. Surface coordinates are already
+     * logical, so the limits are not scaled, and the compositor draws any decorations outside the surface.
+     */
+    fn apply_size_limits(&self, width: &mut i32, height: &mut i32, is_user_resize: bool) {
+        let windef = &self.window_definitions;
+        let should_preserve_aspect_ratio = is_user_resize
+            && windef.should_preserve_aspect_ratio
+            && self.window_mode.get() == WindowMode::Windowed
+            && !self.is_maximized.get();
+        let constraints = SizingConstraints {
+            size_limits: windef.size_limits.clone(),
+            aspect_ratio: should_preserve_aspect_ratio.then(|| self.aspect_ratio.get()),
+            ..SizingConstraints::default()
+        };
+        let constrained_rect = window_sizing::constrain_window_rect(
+            ResizeEdge::BottomRight,
+            &PlatformRect::new(0, 0, *width, *height),
+            &constraints,
+        );
+        *width = constrained_rect.get_width();
+        *height = constrained_rect.get_height();
+    }
+    fn save_restored_size(&self) {
+        if !self.is_maximized.get() && self.window_mode.get() == WindowMode::Windowed {
+            self.restored_size
+                .set((self.width.get(), self.height.get()));
+        }
+    }
+    fn with_toplevel<F: FnOnce(&xdg_toplevel::XdgToplevel)>(&self, f: F) {
+        if let Some(WaylandShellSurface {
+            role: WaylandRole::Toplevel { toplevel, .. },
+            ..
+        }) = &*self.shell_surface.borrow()
+        {
+            f(toplevel);
+        }
+    }
+    fn create_positioner(&self) -> xdg_positioner::XdgPositioner {
+        let positioner = self
+            .globals
+            .wm_base
+            .create_positioner(&self.queue_handle, ());
+        positioner.set_size(self.width.get(), self.height.get());
+        positioner.set_anchor_rect(self.x.get(), self.y.get(), 1, 1);
+        positioner.set_anchor(xdg_positioner::Anchor::TopLeft);
+        positioner.set_gravity(xdg_positioner::Gravity::BottomRight);
+        positioner.set_constraint_adjustment(
+            xdg_positioner::ConstraintAdjustment::SlideX
+                | xdg_positioner::ConstraintAdjustment::SlideY
+                | xdg_positioner::ConstraintAdjustment::FlipY,
+        );
+        positioner
+    }
+    /** Gives the surface its xdg-shell role. This is what maps the window on Wayland. */
+    fn create_shell_surface(&self) {
+        let windef = &self.window_definitions;
+        let xdg_surface =
+            self.globals
+                .wm_base
+                .get_xdg_surface(&self.surface, &self.queue_handle, ());
+
+        let parent = self.parent.as_ref().and_then(Weak::upgrade);
+        let parent_xdg_surface = parent.as_ref().and_then(|parent| {
+            parent
+                .shell_surface
+                .borrow()
+                .as_ref()
+                .map(|shell| shell.xdg_surface.clone())
+        });
+
+        let role = match parent_xdg_surface {
+            Some(parent_xdg_surface) if WaylandWindow::is_popup_type(windef) => {
+                let positioner = self.create_positioner();
+                let popup = xdg_surface.get_popup(
+                    Some(&parent_xdg_surface),
+                    &positioner,
+                    &self.queue_handle,
+                    (),
+                );
+                positioner.destroy();
+                // Menus take an explicit grab so they are dismissed by clicks elsewhere; tool-tips must not steal input
+                if windef.window_type == WindowType::Menu && windef.accepts_input {
+                    if let Some(seat) = &self.globals.seat {
+                        popup.grab(seat, self.globals.last_input_serial.get());
+                    }
+                }
+                WaylandRole::Popup { popup }
+            }
+            _ => {
+                let toplevel = xdg_surface.get_toplevel(&self.queue_handle, ());
+                toplevel.set_title(self.title.borrow().clone());
+                if let Some(parent_toplevel) =
+                    parent
+                        .as_ref()
+                        .and_then(|parent| match &*parent.shell_surface.borrow() {
+                            Some(WaylandShellSurface {
+                                role: WaylandRole::Toplevel { toplevel, .. },
+                                ..
+                            }) => Some(toplevel.clone()),
+                            _ => None,
+                        })
+                {
+                    toplevel.set_parent(Some(&parent_toplevel));
+                }
+
+                let size_limits = &windef.size_limits;
+                if windef.has_sizing_frame {
+                    // Zero means "no limit" to xdg_toplevel
+                    toplevel.set_min_size(
+                        size_limits.get_min_width().unwrap_or(0.0) as i32,
+                        size_limits.get_min_height().unwrap_or(0.0) as i32,
+                    );
+                    toplevel.set_max_size(
+                        size_limits.get_max_width().unwrap_or(0.0) as i32,
+                        size_limits.get_max_height().unwrap_or(0.0) as i32,
+                    );
+                } else {
+                    toplevel.set_min_size(self.width.get(), self.height.get());
+                    toplevel.set_max_size(self.width.get(), self.height.get());
+                }
+
+                let decoration = self.globals.decoration_manager.as_ref().map(|manager| {
+                    let decoration =
+                        manager.get_toplevel_decoration(&toplevel, &self.queue_handle, ());
+                    decoration.set_mode(if windef.has_os_window_border {
+                        zxdg_toplevel_decoration_v1::Mode::ServerSide
+                    } else {
+                        zxdg_toplevel_decoration_v1::Mode::ClientSide
+                    });
+                    decoration
+                });
+
+                if self.window_mode.get() != WindowMode::Windowed {
+                    toplevel.set_fullscreen(None);
+                } else if self.is_maximized.get() {
+                    toplevel.set_maximized();
+                }
+                WaylandRole::Toplevel {
+                    toplevel,
+                    decoration,
+                }
+            }
+        };
+
+        *self.shell_surface.borrow_mut() = Some(WaylandShellSurface { xdg_surface, role });
+        // The initial commit without a buffer asks the compositor for the first configure
+        self.surface.commit();
+    }
+    fn destroy_shell_surface(&self) {
+        if let Some(shell) = self.shell_surface.borrow_mut().take() {
+            match shell.role {
+                WaylandRole::Toplevel {
+                    toplevel,
+                    decoration,
+                } => {
+                    if let Some(decoration) = decoration {
+                        decoration.destroy();
+                    }
+                    toplevel.destroy();
+                }
+                WaylandRole::Popup { popup } => popup.destroy(),
+            }
+            shell.xdg_surface.destroy();
+            // Unmap by committing without a buffer
+            self.surface.attach(None, 0, 0);
+            self.surface.commit();
+        }
+    }
+}
+
+impl GenericWindow for WaylandWindow {
+    fn reshape_window(
+        &self,
+        new_x: &mut i32,
+        new_y: &mut i32,
+        new_width: &mut i32,
+        new_height: &mut i32,
+    ) {
+        if self.is_maximized() {
+            self.restore();
+        }
+        self.apply_size_limits(new_width, new_height, false);
+        self.width.set(*new_width);
+        self.height.set(*new_height);
+        // Rounding each configure to whole pixels would make a ratio taken from the current size drift
+        self.aspect_ratio
+            .set(*new_width as f32 / (*new_height).max(1) as f32);
+        self.save_restored_size();
+        self.move_window_to(new_x, new_y);
+    }
+    fn get_fullscreen_info(
+        &self,
+        x: &mut i32,
+        y: &mut i32,
+        width: &mut i32,
+        height: &mut i32,
+    ) -> bool {
+        // Only the compositor knows where the window is; all we know is the size it gave us while fullscreen
+        if self.window_mode.get() == WindowMode::Windowed {
+            return false;
+        }
+        *x = 0;
+        *y = 0;
+        *width = self.width.get();
+        *height = self.height.get();
+        true
+    }
+    fn move_window_to(&self, x: &mut i32, y: &mut i32) {
+        // Toplevels can not be positioned on Wayland. Popups are repositioned relative to their parent.
+        self.x.set(*x);
+        self.y.set(*y);
+        if let Some(WaylandShellSurface {
+            role: WaylandRole::Popup { popup },
+            ..
+        }) = &*self.shell_surface.borrow()
+        {
+            if popup.version() >= 3 {
+                let positioner = self.create_positioner();
+                popup.reposition(&positioner, 0);
+                positioner.destroy();
+            }
+        }
+    }
+    fn bring_to_front(&self, _force: bool) {
+        // The compositor owns the stacking order. A minimized window can at least be brought back.
+        if self.is_minimized() && self.window_definitions.is_regular_window {
+            self.restore();
+        }
+    }
+    fn destroy(&self) {
+        if !self.is_destroyed.get() {
+            self.destroy_shell_surface();
+            self.surface.destroy();
+            self.is_visible.set(false);
+            self.is_destroyed.set(true);
+        }
+    }
+    fn minimize(&self) {
+        self.save_restored_size();
+        self.with_toplevel(|toplevel| toplevel.set_minimized());
+        // Nothing tells us when the window comes back, so this is cleared the next time it is activated
+        self.is_minimized.set(true);
+        window_hierarchy::minimize_children(self);
+    }
+    fn maximize(&self) {
+        self.save_restored_size();
+        self.is_maximized.set(true);
+        self.with_toplevel(|toplevel| toplevel.set_maximized());
+    }
+    fn restore(&self) {
+        if self.is_minimized.get() {
+            // xdg-shell has no request to un-minimize. Remapping the window is the only way back.
+            self.is_minimized.set(false);
+            if self.is_visible.get() {
+                self.destroy_shell_surface();
+                self.create_shell_surface();
+            }
+            window_hierarchy::restore_children(self);
+        } else if self.is_maximized.get() {
+            self.is_maximized.set(false);
+            self.with_toplevel(|toplevel| toplevel.unset_maximized());
+        }
+    }
+    fn show(&self) {
+        if !self.is_visible.get() {
+            self.is_visible.set(true);
+            // Activation is up to the compositor; new toplevels are usually focused, popups never are
+            self.create_shell_surface();
+        }
+    }
+    fn hide(&self) {
+        if self.is_visible.get() {
+            self.is_visible.set(false);
+            self.is_activated.set(false);
+            self.destroy_shell_surface();
+        }
+    }
+    fn set_window_mode(&self, new_window_mode: WindowMode) {
+        if new_window_mode == self.window_mode.get() {
+            return;
+        }
+        if new_window_mode == WindowMode::Windowed {
+            self.window_mode.set(new_window_mode);
+            self.with_toplevel(|toplevel| toplevel.unset_fullscreen());
+        } else {
+            self.save_restored_size();
+            self.window_mode.set(new_window_mode);
+            // There is no exclusive fullscreen on Wayland; both modes ask the compositor for a fullscreen toplevel
+            self.with_toplevel(|toplevel| toplevel.set_fullscreen(None));
+        }
+    }
+    fn get_window_mode(&self) -> WindowMode {
+        self.window_mode.get()
+    }
+    fn is_maximized(&self) -> bool {
+        self.is_maximized.get()
+    }
+    fn is_minimized(&self) -> bool {
+        self.is_minimized.get()
+    }
+    fn is_visible(&self) -> bool {
+        self.is_visible.get()
+    }
+    fn get_restored_dimensions(
+        &self,
+        x: &mut i32,
+        y: &mut i32,
+        width: &mut i32,
+        height: &mut i32,
+    ) -> bool {
+        self.save_restored_size();
+        let (restored_width, restored_height) = self.restored_size.get();
+        *x = self.x.get();
+        *y = self.y.get();
+        *width = restored_width;
+        *height = restored_height;
+        true
+    }
+    fn set_window_focus(&self) {
+        // Focus stealing needs xdg-activation and a token from the compositor, which is not supported yet
+    }
+    fn set_opacity(&self, opacity: f32) {
+        // Wayland has no window opacity; the renderer has to apply it when it draws with an alpha channel
+        self.opacity.set(opacity.clamp(0.0, 1.0));
+    }
+    fn enable(&self, enable: bool) {
+        self.is_enabled.set(enable);
+    }
+    fn is_point_in_window(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width.get() && y < self.height.get()
+    }
+    fn get_window_border_size(&self) -> u32 {
+        // Server-side decorations are drawn outside the surface
+        0
+    }
+    fn get_window_title_bar_size(&self) -> i32 {
+        0
+    }
+    fn get_os_window_handle(&self) -> *const c_void {
+        self.surface.id().as_ptr() as *const c_void
+    }
+    fn is_foreground_window(&self) -> bool {
+        self.is_activated.get()
+    }
+    fn is_fullscreen_supported(&self) -> bool {
+        !WaylandWindow::is_popup_type(&self.window_definitions)
+    }
+    fn set_text(&self, text: &mut Vec<u16>) {
+        let len = text.iter().position(|&c| c == 0).unwrap_or(text.len());
+        let title = String::from_utf16_lossy(&text[..len]);
+        self.with_toplevel(|toplevel| toplevel.set_title(title.clone()));
+        *self.title.borrow_mut() = title;
+    }
+    fn get_definition(&self) -> &Rc<WindowDefinition> {
+        &self.window_definitions
+    }
+    fn is_definition_valid(&self) -> bool {
+        true
+    }
+    fn adjust_cached_size(&self, size: &mut (i32, i32)) {
+        *size = (self.width.get(), self.height.get());
+    }
+    fn get_dpi_scale_factor(&self) -> f32 {
+        self.dpi_scale_factor.get()
+    }
+    fn set_dpi_scale_factor(&self, factor: f32) {
+        self.dpi_scale_factor.set(factor);
+    }
+    fn is_manual_manage_dpi_change(&self) -> bool {
+        self.handle_manual_dpi_changes.get()
+    }
+    fn set_manual_manage_dpi_change(&self, manual_dpi_changes: bool) {
+        self.handle_manual_dpi_changes.set(manual_dpi_changes);
+    }
+    fn draw_attention(&self, parameters: WindowDrawAttentionRequestType) {
+        // Like set_window_focus this needs xdg-activation; remember the request so the application can show it
+        match parameters {
+            WindowDrawAttentionRequestType::UntilActivated => {
+                self.is_drawing_attention.set(!self.is_foreground_window())
+            }
+            WindowDrawAttentionRequestType::Stop => self.is_drawing_attention.set(false),
+        }
+    }
+    fn set_native_window_buttons_visibility(&self, visible: bool) {
+        // xdg-decoration has no per-button control; the best we can do is drop the server-side frame
+        self.native_window_buttons_visible.set(visible);
+        if let Some(WaylandShellSurface {
+            role:
+                WaylandRole::Toplevel {
+                    decoration: Some(decoration),
+                    ..
+                },
+            ..
+        }) = &*self.shell_surface.borrow()
+        {
+            if visible && self.window_definitions.has_os_window_border {
+                decoration.set_mode(zxdg_toplevel_decoration_v1::Mode::ServerSide);
+            } else {
+                decoration.set_mode(zxdg_toplevel_decoration_v1::Mode::ClientSide);
+            }
+        }
+    }
+    fn get_hierarchy(&self) -> &WindowHierarchy {
+        &self.hierarchy
+    }
+}
@@ -0,0 +1,655 @@
+use std::{
+    ffi::{CStr, CString},
+    fmt, io, mem,
+    os::{
+        fd::{AsRawFd, OwnedFd},
+        raw::{c_char, c_int, c_void},
+    },
+    ptr, slice,
+};
+
+/** xkb key codes are evdev codes plus 8, a leftover of the X11 key code range */
+const EVDEV_OFFSET: u32 = 8;
+
+const XKB_CONTEXT_NO_FLAGS: c_int = 0;
+const XKB_KEYMAP_FORMAT_TEXT_V1: c_int = 1;
+const XKB_KEYMAP_COMPILE_NO_FLAGS: c_int = 0;
+const XKB_STATE_MODS_EFFECTIVE: c_int = 1 << 3;
+const XKB_MOD_INVALID: u32 = 0xffff_ffff;
+const XKB_COMPOSE_COMPILE_NO_FLAGS: c_int = 0;
+const XKB_COMPOSE_STATE_NO_FLAGS: c_int = 0;
+const XKB_COMPOSE_FEED_ACCEPTED: c_int = 1;
+const XKB_COMPOSE_COMPOSING: c_int = 1;
+const XKB_COMPOSE_COMPOSED: c_int = 2;
+const XKB_COMPOSE_CANCELLED: c_int = 3;
+
+/** The names of the modifiers the backend reports, as every xkb keymap calls them */
+const XKB_MOD_NAME_SHIFT: &CStr = c"Shift";
+const XKB_MOD_NAME_CTRL: &CStr = c"Control";
+const XKB_MOD_NAME_ALT: &CStr = c"Mod1";
+const XKB_MOD_NAME_LOGO: &CStr = c"Mod4";
+const XKB_MOD_NAME_CAPS: &CStr = c"Lock";
+
+/** The libxkbcommon objects are only ever handled through pointers */
+#[repr(C)]
+struct RawContext {
+    _private: [u8; 0],
+}
+#[repr(C)]
+struct RawKeymap {
+    _private: [u8; 0],
+}
+#[repr(C)]
+struct RawState {
+    _private: [u8; 0],
+}
+#[repr(C)]
+struct RawComposeTable {
+    _private: [u8; 0],
+}
+#[repr(C)]
+struct RawComposeState {
+    _private: [u8; 0],
+}
+
+/** struct xkb_rule_names. A null name makes xkbcommon use $XKB_DEFAULT_* or its built-in default. */
+#[repr(C)]
+struct RawRuleNames {
+    rules: *const c_char,
+    model: *const c_char,
+    layout: *const c_char,
+    variant: *const c_char,
+    options: *const c_char,
+}
+
+macro_rules! xkbcommon_functions {
+    ($($name:ident: fn($($arg:ty),*) $(-> $ret:ty)?;)*) => {
+        /** The functions of libxkbcommon the backend uses, looked up when the library is loaded */
+        struct XkbCommonLibrary {
+            handle: *mut c_void,
+            $($name: unsafe extern "C" fn($($arg),*) $(-> $ret)?,)*
+        }
+
+        impl XkbCommonLibrary {
+            unsafe fn load_functions(handle: *mut c_void) -> io::Result<XkbCommonLibrary> {
+                Ok(XkbCommonLibrary {
+                    handle,
+                    $($name: {
+                        let name = concat!(stringify!($name), "\0");
+                        let symbol = libc::dlsym(handle, name.as_ptr() as *const c_char);
+                        if symbol.is_null() {
+                            return Err(io::Error::new(
+                                io::ErrorKind::NotFound,
+                                format!("libxkbcommon has no {}", stringify!($name)),
+                            ));
+                        }
+                        mem::transmute::<*mut c_void, unsafe extern "C" fn($($arg),*) $(-> $ret)?>(symbol)
+                    },)*
+                })
+            }
+        }
+    };
+}
+
+xkbcommon_functions! {
+    xkb_context_new: fn(c_int) -> *mut RawContext;
+    xkb_context_unref: fn(*mut RawContext);
+    xkb_keymap_new_from_string: fn(*mut RawContext, *const c_char, c_int, c_int) -> *mut RawKeymap;
+    xkb_keymap_new_from_names: fn(*mut RawContext, *const RawRuleNames, c_int) -> *mut RawKeymap;
+    xkb_keymap_unref: fn(*mut RawKeymap);
+    xkb_keymap_mod_get_index: fn(*mut RawKeymap, *const c_char) -> u32;
+    xkb_keymap_key_repeats: fn(*mut RawKeymap, u32) -> c_int;
+    xkb_state_new: fn(*mut RawKeymap) -> *mut RawState;
+    xkb_state_unref: fn(*mut RawState);
+    xkb_state_update_mask: fn(*mut RawState, u32, u32, u32, u32, u32, u32) -> c_int;
+    xkb_state_mod_index_is_active: fn(*mut RawState, u32, c_int) -> c_int;
+    xkb_state_key_get_one_sym: fn(*mut RawState, u32) -> u32;
+    xkb_state_key_get_utf32: fn(*mut RawState, u32) -> u32;
+    xkb_keysym_to_utf32: fn(u32) -> u32;
+    xkb_compose_table_new_from_locale: fn(*mut RawContext, *const c_char, c_int) -> *mut RawComposeTable;
+    xkb_compose_table_unref: fn(*mut RawComposeTable);
+    xkb_compose_state_new: fn(*mut RawComposeTable, c_int) -> *mut RawComposeState;
+    xkb_compose_state_unref: fn(*mut RawComposeState);
+    xkb_compose_state_feed: fn(*mut RawComposeState, u32) -> c_int;
+    xkb_compose_state_reset: fn(*mut RawComposeState);
+    xkb_compose_state_get_status: fn(*mut RawComposeState) -> c_int;
+    xkb_compose_state_get_utf8: fn(*mut RawComposeState, *mut c_char, usize) -> c_int;
+    xkb_compose_state_get_one_sym: fn(*mut RawComposeState) -> u32;
+}
+
+impl XkbCommonLibrary {
+    /** Loads libxkbcommon. Like Xlib for the X11 backend, it is loaded at runtime rather than linked. */
+    fn open() -> io::Result<XkbCommonLibrary> {
+        for name in [c"libxkbcommon.so.0", c"libxkbcommon.so"] {
+            unsafe {
+                let handle = libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+                if handle.is_null() {
+                    continue;
+                }
+                return XkbCommonLibrary::load_functions(handle).inspect_err(|_| {
+                    libc::dlclose(handle);
+                });
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "libxkbcommon could not be loaded",
+        ))
+    }
+}
+
+impl Drop for XkbCommonLibrary {
+    fn drop(&mut self) {
+        unsafe {
+            libc::dlclose(self.handle);
+        }
+    }
+}
+
+/** The modifiers that are in effect, whether held, latched or locked */
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct XkbModifiers {
+    pub is_shift_down: bool,
+    pub is_control_down: bool,
+    pub is_alt_down: bool,
+    pub is_command_down: bool,
+    pub are_caps_locked: bool,
+}
+
+/**
+ * Reads keys with the keymap the compositor sent, the way every Wayland client has to. The modifier state comes
+ * from wl_keyboard.modifiers, and dead keys and the compose key go through the compose table of the user's locale.
+ */
+pub struct XkbKeyboard {
+    library: XkbCommonLibrary,
+    context: *mut RawContext,
+    /** Null until the compositor sent a keymap */
+    keymap: *mut RawKeymap,
+    state: *mut RawState,
+    /** Null if the locale has no compose table, in which case dead keys type nothing */
+    compose_table: *mut RawComposeTable,
+    compose_state: *mut RawComposeState,
+    /** The indices of Shift, Control, Alt, Super and Caps Lock in the keymap */
+    modifier_indices: [u32; 5],
+}
+
+impl fmt::Debug for XkbKeyboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XkbKeyboard")
+            .field("has_keymap", &self.has_keymap())
+            .field("has_compose_table", &!self.compose_table.is_null())
+            .field("modifiers", &self.get_modifiers())
+            .finish()
+    }
+}
+
+/** The locale whose compose table is used, from the variables setlocale would read */
+fn get_compose_locale() -> CString {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| CString::new(value).ok())
+        .unwrap_or_else(|| c"C".to_owned())
+}
+
+impl XkbKeyboard {
+    /** Loads libxkbcommon and the compose table. Keys type nothing until a keymap is set. */
+    pub fn new() -> io::Result<XkbKeyboard> {
+        let library = XkbCommonLibrary::open()?;
+        unsafe {
+            let context = (library.xkb_context_new)(XKB_CONTEXT_NO_FLAGS);
+            if context.is_null() {
+                return Err(io::Error::other("the xkb context could not be created"));
+            }
+            let compose_table = (library.xkb_compose_table_new_from_locale)(
+                context,
+                get_compose_locale().as_ptr(),
+                XKB_COMPOSE_COMPILE_NO_FLAGS,
+            );
+            let compose_state = if compose_table.is_null() {
+                ptr::null_mut()
+            } else {
+                (library.xkb_compose_state_new)(compose_table, XKB_COMPOSE_STATE_NO_FLAGS)
+            };
+            Ok(XkbKeyboard {
+                library,
+                context,
+                keymap: ptr::null_mut(),
+                state: ptr::null_mut(),
+                compose_table,
+                compose_state,
+                modifier_indices: [XKB_MOD_INVALID; 5],
+            })
+        }
+    }
+    pub fn has_keymap(&self) -> bool {
+        !self.state.is_null()
+    }
+    /** Reads the keymap of wl_keyboard.keymap, which the compositor shares as a file of size bytes */
+    pub fn set_keymap_from_fd(&mut self, fd: OwnedFd, size: u32) -> io::Result<()> {
+        let text = unsafe {
+            let size = size as usize;
+            let data = libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                fd.as_raw_fd(),
+                0,
+            );
+            if data == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            let bytes = slice::from_raw_parts(data as *const u8, size);
+            // The keymap is a NUL-terminated string
+            let length = bytes.iter().position(|&byte| byte == 0).unwrap_or(size);
+            let text = CString::new(&bytes[..length]);
+            libc::munmap(data, size);
+            text
+        };
+        let text = text.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let keymap = unsafe {
+            (self.library.xkb_keymap_new_from_string)(
+                self.context,
+                text.as_ptr(),
+                XKB_KEYMAP_FORMAT_TEXT_V1,
+                XKB_KEYMAP_COMPILE_NO_FLAGS,
+            )
+        };
+        self.set_keymap(keymap)
+    }
+    /**
+     * Builds the keymap from the layout and variant names, e.g. "de" and "nodeadkeys". An empty name uses
+     * $XKB_DEFAULT_LAYOUT or $XKB_DEFAULT_VARIANT, and failing that xkbcommon's default, a US layout.
+     */
+    pub fn set_keymap_from_names(&mut self, layout: &str, variant: &str) -> io::Result<()> {
+        let to_name = |name: &str| {
+            CString::new(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+        };
+        let layout = to_name(layout)?;
+        let variant = to_name(variant)?;
+        let name_ptr = |name: &CString| {
+            if name.is_empty() {
+                ptr::null()
+            } else {
+                name.as_ptr()
+            }
+        };
+        let names = RawRuleNames {
+            rules: ptr::null(),
+            model: ptr::null(),
+            layout: name_ptr(&layout),
+            variant: name_ptr(&variant),
+            options: ptr::null(),
+        };
+        let keymap = unsafe {
+            (self.library.xkb_keymap_new_from_names)(
+                self.context,
+                &names,
+                XKB_KEYMAP_COMPILE_NO_FLAGS,
+            )
+        };
+        self.set_keymap(keymap)
+    }
+    fn set_keymap(&mut self, keymap: *mut RawKeymap) -> io::Result<()> {
+        if keymap.is_null() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the keymap could not be compiled",
+            ));
+        }
+        unsafe {
+            let state = (self.library.xkb_state_new)(keymap);
+            if state.is_null() {
+                (self.library.xkb_keymap_unref)(keymap);
+                return Err(io::Error::other("the xkb state could not be created"));
+            }
+            self.release_keymap();
+            self.keymap = keymap;
+            self.state = state;
+            self.modifier_indices = [
+                XKB_MOD_NAME_SHIFT,
+                XKB_MOD_NAME_CTRL,
+                XKB_MOD_NAME_ALT,
+                XKB_MOD_NAME_LOGO,
+                XKB_MOD_NAME_CAPS,
+            ]
+            .map(|name| (self.library.xkb_keymap_mod_get_index)(keymap, name.as_ptr()));
+        }
+        self.reset_compose();
+        Ok(())
+    }
+    fn release_keymap(&mut self) {
+        unsafe {
+            if !self.state.is_null() {
+                (self.library.xkb_state_unref)(self.state);
+                self.state = ptr::null_mut();
+            }
+            if !self.keymap.is_null() {
+                (self.library.xkb_keymap_unref)(self.keymap);
+                self.keymap = ptr::null_mut();
+            }
+        }
+    }
+    /** Applies wl_keyboard.modifiers. The masks are in the bits of the keymap's modifiers. */
+    pub fn update_modifiers(
+        &mut self,
+        mods_depressed: u32,
+        mods_latched: u32,
+        mods_locked: u32,
+        group: u32,
+    ) {
+        if self.has_keymap() {
+            unsafe {
+                (self.library.xkb_state_update_mask)(
+                    self.state,
+                    mods_depressed,
+                    mods_latched,
+                    mods_locked,
+                    0,
+                    0,
+                    group,
+                );
+            }
+        }
+    }
+    fn is_modifier_active(&self, modifier_index: u32) -> bool {
+        modifier_index != XKB_MOD_INVALID
+            && unsafe {
+                (self.library.xkb_state_mod_index_is_active)(
+                    self.state,
+                    modifier_index,
+                    XKB_STATE_MODS_EFFECTIVE,
+                ) > 0
+            }
+    }
+    pub fn get_modifiers(&self) -> XkbModifiers {
+        if !self.has_keymap() {
+            return XkbModifiers::default();
+        }
+        let [shift, control, alt, logo, caps] = self.modifier_indices;
+        XkbModifiers {
+            is_shift_down: self.is_modifier_active(shift),
+            is_control_down: self.is_modifier_active(control),
+            is_alt_down: self.is_modifier_active(alt),
+            is_command_down: self.is_modifier_active(logo),
+            are_caps_locked: self.is_modifier_active(caps),
+        }
+    }
+    /** The mask bit of a modifier, by its xkb name, for feeding update_modifiers made-up states */
+    pub fn get_modifier_mask(&self, name: &str) -> u32 {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return 0,
+        };
+        if self.keymap.is_null() {
+            return 0;
+        }
+        match unsafe { (self.library.xkb_keymap_mod_get_index)(self.keymap, name.as_ptr()) } {
+            XKB_MOD_INVALID => 0,
+            index => 1 << index,
+        }
+    }
+    /** Whether holding the key down repeats it. Modifiers and lock keys do not repeat. */
+    pub fn key_repeats(&self, key: u32) -> bool {
+        self.keymap.is_null()
+            || unsafe {
+                (self.library.xkb_keymap_key_repeats)(self.keymap, key + EVDEV_OFFSET) != 0
+            }
+    }
+    /**
+     * The character the evdev key types in the current modifier state, without going through compose. None for
+     * keys that type nothing, like the arrows or a dead key.
+     */
+    pub fn get_character(&self, key: u32) -> Option<char> {
+        if !self.has_keymap() {
+            return None;
+        }
+        let code_point =
+            unsafe { (self.library.xkb_state_key_get_utf32)(self.state, key + EVDEV_OFFSET) };
+        match code_point {
+            0 => None,
+            code_point => char::from_u32(code_point),
+        }
+    }
+    /**
+     * The text a press of the evdev key types. Keys of a compose sequence, like a dead accent, type nothing; the key
+     * that completes it types the composed text, and a key that breaks the sequence off is swallowed.
+     */
+    pub fn press_key(&mut self, key: u32) -> Option<String> {
+        if !self.has_keymap() {
+            return None;
+        }
+        if !self.compose_state.is_null() {
+            unsafe {
+                let keysym =
+                    (self.library.xkb_state_key_get_one_sym)(self.state, key + EVDEV_OFFSET);
+                if (self.library.xkb_compose_state_feed)(self.compose_state, keysym)
+                    == XKB_COMPOSE_FEED_ACCEPTED
+                {
+                    match (self.library.xkb_compose_state_get_status)(self.compose_state) {
+                        XKB_COMPOSE_COMPOSING => return None,
+                        XKB_COMPOSE_COMPOSED => {
+                            let text = self.get_composed_text();
+                            self.reset_compose();
+                            return text;
+                        }
+                        XKB_COMPOSE_CANCELLED => {
+                            self.reset_compose();
+                            return None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self.get_character(key).map(String::from)
+    }
+    /** The result of a finished compose sequence. Some sequences only name a keysym rather than spell out text. */
+    unsafe fn get_composed_text(&self) -> Option<String> {
+        let length =
+            (self.library.xkb_compose_state_get_utf8)(self.compose_state, ptr::null_mut(), 0);
+        if length > 0 {
+            let mut buffer = vec![0u8; length as usize + 1];
+            (self.library.xkb_compose_state_get_utf8)(
+                self.compose_state,
+                buffer.as_mut_ptr() as *mut c_char,
+                buffer.len(),
+            );
+            buffer.truncate(length as usize);
+            return String::from_utf8(buffer).ok();
+        }
+        let keysym = (self.library.xkb_compose_state_get_one_sym)(self.compose_state);
+        match (self.library.xkb_keysym_to_utf32)(keysym) {
+            0 => None,
+            code_point => char::from_u32(code_point).map(String::from),
+        }
+    }
+    /** Forgets a half-typed compose sequence, e.g. when the keyboard focus leaves */
+    pub fn reset_compose(&mut self) {
+        if !self.compose_state.is_null() {
+            unsafe {
+                (self.library.xkb_compose_state_reset)(self.compose_state);
+            }
+        }
+    }
+}
+
+impl Drop for XkbKeyboard {
+    fn drop(&mut self) {
+        self.release_keymap();
+        unsafe {
+            if !self.compose_state.is_null() {
+                (self.library.xkb_compose_state_unref)(self.compose_state);
+            }
+            if !self.compose_table.is_null() {
+                (self.library.xkb_compose_table_unref)(self.compose_table);
+            }
+            (self.library.xkb_context_unref)(self.context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    /** evdev codes of the keys the tests press, named by where they are on a US keyboard */
+    const KEY_2: u32 = 3;
+    const KEY_EQUAL: u32 = 13;
+    const KEY_Q: u32 = 16;
+    const KEY_E: u32 = 18;
+    const KEY_Y: u32 = 21;
+    const KEY_LEFTBRACE: u32 = 26;
+    const KEY_A: u32 = 30;
+    const KEY_LEFTSHIFT: u32 = 42;
+    const KEY_Z: u32 = 44;
+    const KEY_C: u32 = 46;
+    const KEY_SPACE: u32 = 57;
+
+    /** A keyboard without a keymap, or None to skip the test where libxkbcommon is not installed */
+    fn make_empty_keyboard() -> Option<XkbKeyboard> {
+        match XkbKeyboard::new() {
+            Ok(keyboard) => Some(keyboard),
+            Err(error) => {
+                eprintln!("skipped, libxkbcommon could not be loaded: {}", error);
+                None
+            }
+        }
+    }
+
+    fn make_keyboard(layout: &str, variant: &str) -> Option<XkbKeyboard> {
+        let mut keyboard = make_empty_keyboard()?;
+        keyboard
+            .set_keymap_from_names(layout, variant)
+            .expect("the layout is installed");
+        Some(keyboard)
+    }
+
+    #[test]
+    fn without_a_keymap_keys_type_nothing() {
+        let Some(mut keyboard) = make_empty_keyboard() else {
+            return;
+        };
+        assert!(!keyboard.has_keymap());
+        assert_eq!(keyboard.get_character(KEY_Q), None);
+        assert_eq!(keyboard.press_key(KEY_Q), None);
+        assert_eq!(keyboard.get_modifiers(), XkbModifiers::default());
+    }
+
+    #[test]
+    fn layouts_other_than_us_are_followed() {
+        let Some(keyboard) = make_keyboard("fr", "") else {
+            return;
+        };
+        assert_eq!(keyboard.get_character(KEY_Q), Some('a'));
+        assert_eq!(keyboard.get_character(KEY_A), Some('q'));
+        assert_eq!(keyboard.get_character(KEY_2), Some('é'));
+
+        let Some(keyboard) = make_keyboard("de", "") else {
+            return;
+        };
+        assert_eq!(keyboard.get_character(KEY_Y), Some('z'));
+        assert_eq!(keyboard.get_character(KEY_Z), Some('y'));
+    }
+
+    #[test]
+    fn modifiers_come_from_the_masks() {
+        let Some(mut keyboard) = make_keyboard("fr", "") else {
+            return;
+        };
+        let shift = keyboard.get_modifier_mask("Shift");
+        let lock = keyboard.get_modifier_mask("Lock");
+        let control = keyboard.get_modifier_mask("Control");
+        assert_ne!(shift, 0);
+
+        keyboard.update_modifiers(shift, 0, 0, 0);
+        assert!(keyboard.get_modifiers().is_shift_down);
+        assert_eq!(keyboard.get_character(KEY_2), Some('2'));
+        assert_eq!(keyboard.get_character(KEY_Q), Some('A'));
+
+        keyboard.update_modifiers(0, 0, lock, 0);
+        let modifiers = keyboard.get_modifiers();
+        assert!(!modifiers.is_shift_down);
+        assert!(modifiers.are_caps_locked);
+        assert_eq!(keyboard.get_character(KEY_Q), Some('A'));
+
+        keyboard.update_modifiers(control, 0, 0, 0);
+        assert!(keyboard.get_modifiers().is_control_down);
+        assert_eq!(keyboard.get_character(KEY_C), Some('\u{3}'));
+    }
+
+    #[test]
+    fn dead_keys_compose_with_the_next_key() {
+        let Some(mut keyboard) = make_keyboard("fr", "") else {
+            return;
+        };
+        // The key right of P is a dead circumflex on a French keyboard
+        assert_eq!(keyboard.get_character(KEY_LEFTBRACE), None);
+        assert_eq!(keyboard.press_key(KEY_LEFTBRACE), None);
+        assert_eq!(keyboard.press_key(KEY_E).as_deref(), Some("ê"));
+        assert_eq!(keyboard.press_key(KEY_E).as_deref(), Some("e"));
+
+        assert_eq!(keyboard.press_key(KEY_LEFTBRACE), None);
+        assert_eq!(keyboard.press_key(KEY_SPACE).as_deref(), Some("^"));
+
+        assert_eq!(keyboard.press_key(KEY_LEFTBRACE), None);
+        keyboard.reset_compose();
+        assert_eq!(keyboard.press_key(KEY_E).as_deref(), Some("e"));
+
+        // The variant without dead keys types the accent at once
+        let Some(mut keyboard) = make_keyboard("de", "") else {
+            return;
+        };
+        assert_eq!(keyboard.press_key(KEY_EQUAL), None);
+        assert_eq!(keyboard.press_key(KEY_E).as_deref(), Some("é"));
+        let Some(mut keyboard) = make_keyboard("de", "nodeadkeys") else {
+            return;
+        };
+        assert_eq!(keyboard.press_key(KEY_EQUAL).as_deref(), Some("´"));
+    }
+
+    #[test]
+    fn modifiers_do_not_repeat() {
+        let Some(keyboard) = make_keyboard("us", "") else {
+            return;
+        };
+        assert!(keyboard.key_repeats(KEY_A));
+        assert!(!keyboard.key_repeats(KEY_LEFTSHIFT));
+    }
+
+    #[test]
+    fn keymap_is_read_from_the_shared_file() {
+        let keymap = "xkb_keymap {\n\
+            xkb_keycodes { include \"evdev\" };\n\
+            xkb_types { include \"complete\" };\n\
+            xkb_compat { include \"complete\" };\n\
+            xkb_symbols { include \"pc+fr\" };\n\
+            };\n\0";
+        let path = std::env::temp_dir().join(format!("seraph-xkb-keymap-{}", std::process::id()));
+        File::create(&path)
+            .and_then(|mut file| file.write_all(keymap.as_bytes()))
+            .unwrap();
+        let fd = OwnedFd::from(File::open(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+
+        let Some(mut keyboard) = make_empty_keyboard() else {
+            return;
+        };
+        keyboard
+            .set_keymap_from_fd(fd, keymap.len() as u32)
+            .unwrap();
+        assert_eq!(keyboard.get_character(KEY_Q), Some('a'));
+
+        let path = std::env::temp_dir().join(format!("seraph-xkb-broken-{}", std::process::id()));
+        fs::write(&path, "xkb_keymap { nonsense };\0").unwrap();
+        let fd = OwnedFd::from(File::open(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(keyboard.set_keymap_from_fd(fd, 25).is_err());
+        // A keymap that does not compile leaves the previous one in place
+        assert_eq!(keyboard.get_character(KEY_Q), Some('a'));
+    }
+}
@@ -1,25 +1,40 @@
 use crate::generic::application::{
-    GenericApplication, MonitorInfo, PlatformRect, DEBUG_ACTION_ZONE_RATIO, DEBUG_SAFE_ZONE_RATIO,
+    DisplayMetrics, DisplayRotation, GenericApplication, ModifierKeysState, MonitorInfo,
+    PlatformRect, DEFAULT_DPI,
 };
 use crate::generic::application_message_handler::{
     ApplicationMessageHandler, WindowAction, WindowSizeLimits, WindowZone,
 };
-use glam::Point2;
+use crate::generic::application_proxy::{
+    ApplicationProxy, EventLoopWaker, ProxyReceiver, ProxyWindowId,
+};
+use crate::generic::edid::Edid;
+use glam::{Point2, Vec2};
 //use crate::generic::cursor::ICursor;
+use crate::generic::input::Key;
+use crate::generic::platform_event::{PlatformEvent, PlatformEventQueue};
+use crate::generic::timer::TimerQueue;
 use crate::generic::window::GenericWindow;
 use crate::generic::window_definition::{WindowDefinition, WindowTransparency, WindowType};
+use crate::generic::window_hierarchy;
+use crate::generic::window_hit_test;
+use crate::generic::window_sizing::{self, FrameInsets, SizingConstraints};
+use crate::generic::xinputinterface::XInputInterface;
 use crate::windows::cursor::WindowsCursor;
+use crate::windows::input;
 use crate::windows::utils;
 use crate::windows::utils::ToWide;
 use crate::windows::window::{WindowsWindow, APP_WINDOW_CLASS};
+use crate::windows::xinput::WindowsXInputStateSource;
 use lazy_static::lazy_static;
-use std::borrow::Borrow;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::BTreeMap;
-use std::io::Error;
+use std::fmt;
+use std::io::{self, Error};
+use std::mem::ManuallyDrop;
 use std::os::raw::c_void;
-use std::rc::Rc;
-use std::sync::{Arc, Once};
+use std::rc::{Rc, Weak};
+use std::time::Duration;
 use std::{mem, ptr};
 
 use windows::{
@@ -32,28 +47,34 @@ use windows::{
                 DIGCF_PRESENT, DIREG_DEV, GUID_DEVCLASS_MONITOR, HDEVINFO, MAX_DEVICE_ID_LEN,
                 SP_DEVINFO_DATA,
             },
-            HumanInterfaceDevice::{MOUSE_MOVE_ABSOLUTE, MOUSE_MOVE_RELATIVE},
+            HumanInterfaceDevice::{MOUSE_MOVE_ABSOLUTE, MOUSE_VIRTUAL_DESKTOP},
         },
         Foundation::{
-            ERROR_NO_MORE_ITEMS, HINSTANCE, HWND, INVALID_HANDLE_VALUE, LPARAM, LRESULT, POINT,
-            POINTL, RECT, WPARAM,
+            GetLastError, ERROR_CLASS_ALREADY_EXISTS, ERROR_NO_MORE_ITEMS, HINSTANCE, HWND,
+            INVALID_HANDLE_VALUE, LPARAM, LRESULT, POINT, POINTL, RECT, WAIT_TIMEOUT, WPARAM,
         },
         Graphics::{
             Dwm::DwmIsCompositionEnabled,
             Gdi::{
-                EnumDisplayDevicesW, GetMonitorInfoW, MonitorFromRect, DISPLAY_DEVICEW,
-                DISPLAY_DEVICE_ACTIVE, DISPLAY_DEVICE_ATTACHED_TO_DESKTOP,
-                DISPLAY_DEVICE_MIRRORING_DRIVER, DISPLAY_DEVICE_PRIMARY_DEVICE, HBRUSH, HMONITOR,
-                MONITORINFO, MONITOR_DEFAULTTONEAREST,
+                EnumDisplayDevicesW, EnumDisplaySettingsExW, GetMonitorInfoW, MonitorFromRect,
+                ScreenToClient, DEVMODEW, DISPLAY_DEVICEW, DISPLAY_DEVICE_ACTIVE,
+                DISPLAY_DEVICE_ATTACHED_TO_DESKTOP, DISPLAY_DEVICE_MIRRORING_DRIVER,
+                DISPLAY_DEVICE_PRIMARY_DEVICE, DMDO_180, DMDO_270, DMDO_90, ENUM_CURRENT_SETTINGS,
+                ENUM_DISPLAY_SETTINGS_FLAGS, HBRUSH, HMONITOR, MONITORINFO,
+                MONITOR_DEFAULTTONEAREST,
             },
         },
-        System::Registry::{HKEY, KEY_READ},
+        System::{
+            Registry::{HKEY, KEY_READ},
+            Threading::GetCurrentThreadId,
+        },
         UI::{
             Accessibility::{
                 FILTERKEYS, SKF_CONFIRMHOTKEY, SKF_HOTKEYACTIVE, SKF_STICKYKEYSON, STICKYKEYS,
                 TOGGLEKEYS,
             },
             Controls::{WM_MOUSEHOVER, WM_MOUSELEAVE},
+            HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
             Input::{
                 GetRawInputData, GetRawInputDeviceInfoA, GetRawInputDeviceList,
                 Ime::{
@@ -66,43 +87,45 @@ use windows::{
                     IMR_RECONVERTSTRING,
                 },
                 KeyboardAndMouse::{
-                    GetCapture, SetCapture, VK_CAPITAL, VK_F4, VK_LCONTROL, VK_LMENU, VK_LSHIFT,
-                    VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_SPACE,
+                    GetAsyncKeyState, GetCapture, GetKeyState, SetCapture, VIRTUAL_KEY, VK_CAPITAL,
+                    VK_F4, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_RCONTROL, VK_RMENU,
+                    VK_RSHIFT, VK_RWIN, VK_SPACE,
                 },
                 RegisterRawInputDevices, HRAWINPUT, RAWINPUT, RAWINPUTDEVICE, RAWINPUTDEVICELIST,
                 RAWINPUTDEVICE_FLAGS, RAWINPUTHEADER, RIDEV_REMOVE, RIDI_DEVICENAME, RID_INPUT,
                 RIM_TYPEMOUSE,
             },
             WindowsAndMessaging::{
-                AdjustWindowRectEx, DefWindowProcW, DispatchMessageW, GetCursorPos,
-                GetSystemMetrics, GetWindowInfo, GetWindowLongW, MessageBoxW, PeekMessageW,
-                RegisterClassW, SetCursorPos, SystemParametersInfoW, TranslateMessage,
+                AdjustWindowRectEx, DefWindowProcW, DispatchMessageW, GetClientRect, GetCursorPos,
+                GetSystemMetrics, GetWindowInfo, GetWindowLongW, KillTimer, MessageBoxW,
+                MsgWaitForMultipleObjectsEx, PeekMessageW, PostThreadMessageW, RegisterClassW,
+                SetCursorPos, SetTimer, SystemParametersInfoW, TranslateMessage, UnregisterClassW,
                 WindowFromPoint, CREATESTRUCTW, CS_DBLCLKS, DLGC_WANTALLKEYS, FKF_CONFIRMHOTKEY,
                 FKF_FILTERKEYSON, FKF_HOTKEYACTIVE, GWLP_USERDATA, GWL_EXSTYLE, GWL_STYLE, HCURSOR,
                 HICON, HTBOTTOM, HTBOTTOMLEFT, HTBOTTOMRIGHT, HTCAPTION, HTCLIENT, HTCLOSE, HTLEFT,
                 HTMAXBUTTON, HTMINBUTTON, HTNOWHERE, HTRIGHT, HTSYSMENU, HTTOP, HTTOPLEFT,
-                HTTOPRIGHT, MB_ICONEXCLAMATION, MB_OK, MINMAXINFO, MSG, NCCALCSIZE_PARAMS,
-                PM_REMOVE, SC_MAXIMIZE, SC_RESTORE, SM_CXSCREEN, SM_CXVIRTUALSCREEN, SM_CYSCREEN,
-                SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, SPI_GETWORKAREA,
-                SPI_SETFILTERKEYS, SPI_SETSTICKYKEYS, SPI_SETTOGGLEKEYS, SW_RESTORE,
+                HTTOPRIGHT, MB_ICONEXCLAMATION, MB_OK, MINMAXINFO, MSG, MWMO_INPUTAVAILABLE,
+                NCCALCSIZE_PARAMS, PM_REMOVE, QS_ALLINPUT, SC_MAXIMIZE, SC_RESTORE, SM_CXSCREEN,
+                SM_CXVIRTUALSCREEN, SM_CYSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+                SM_YVIRTUALSCREEN, SPI_GETWORKAREA, SPI_SETFILTERKEYS, SPI_SETSTICKYKEYS,
+                SPI_SETTOGGLEKEYS, SPI_SETWORKAREA, SW_PARENTCLOSING, SW_PARENTOPENING, SW_RESTORE,
                 SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, TKF_CONFIRMHOTKEY, TKF_HOTKEYACTIVE,
-                TKF_TOGGLEKEYSON, WINDOWINFO, WINDOW_EX_STYLE, WINDOW_STYLE, WMSZ_BOTTOM,
-                WMSZ_BOTTOMLEFT, WMSZ_BOTTOMRIGHT, WMSZ_LEFT, WMSZ_RIGHT, WMSZ_TOP, WMSZ_TOPLEFT,
-                WMSZ_TOPRIGHT, WM_ACTIVATE, WM_ACTIVATEAPP, WM_CHAR, WM_CLOSE, WM_CREATE,
-                WM_DESTROY, WM_DEVICECHANGE, WM_DISPLAYCHANGE, WM_DWMCOMPOSITIONCHANGED,
-                WM_ENTERSIZEMOVE, WM_ERASEBKGND, WM_EXITSIZEMOVE, WM_GETDLGCODE, WM_GETMINMAXINFO,
-                WM_IME_CHAR, WM_IME_COMPOSITION, WM_IME_ENDCOMPOSITION, WM_IME_NOTIFY,
-                WM_IME_REQUEST, WM_IME_SETCONTEXT, WM_IME_STARTCOMPOSITION, WM_INPUT,
-                WM_INPUTLANGCHANGE, WM_INPUTLANGCHANGEREQUEST, WM_INPUT_DEVICE_CHANGE, WM_KEYDOWN,
-                WM_KEYUP, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDBLCLK,
-                WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEACTIVATE, WM_MOUSEHWHEEL, WM_MOUSEMOVE,
-                WM_MOUSEWHEEL, WM_MOVE, WM_NCACTIVATE, WM_NCCALCSIZE, WM_NCCREATE, WM_NCHITTEST,
+                TKF_TOGGLEKEYSON, WINDOWINFO, WINDOW_EX_STYLE, WINDOW_STYLE, WM_ACTIVATE,
+                WM_ACTIVATEAPP, WM_CHAR, WM_CLOSE, WM_CREATE, WM_DESTROY, WM_DEVICECHANGE,
+                WM_DISPLAYCHANGE, WM_DPICHANGED, WM_DWMCOMPOSITIONCHANGED, WM_ENTERSIZEMOVE,
+                WM_ERASEBKGND, WM_EXITSIZEMOVE, WM_GETDLGCODE, WM_GETMINMAXINFO, WM_IME_CHAR,
+                WM_IME_COMPOSITION, WM_IME_ENDCOMPOSITION, WM_IME_NOTIFY, WM_IME_REQUEST,
+                WM_IME_SETCONTEXT, WM_IME_STARTCOMPOSITION, WM_INPUT, WM_INPUTLANGCHANGE,
+                WM_INPUTLANGCHANGEREQUEST, WM_INPUT_DEVICE_CHANGE, WM_KEYDOWN, WM_KEYUP,
+                WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDBLCLK, WM_MBUTTONDOWN,
+                WM_MBUTTONUP, WM_MOUSEACTIVATE, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL,
+                WM_MOVE, WM_NCACTIVATE, WM_NCCALCSIZE, WM_NCCREATE, WM_NCDESTROY, WM_NCHITTEST,
                 WM_NCLBUTTONDOWN, WM_NCMBUTTONDBLCLK, WM_NCMBUTTONDOWN, WM_NCMBUTTONUP,
                 WM_NCMOUSEHOVER, WM_NCMOUSELEAVE, WM_NCMOUSEMOVE, WM_NCPAINT, WM_NCRBUTTONDBLCLK,
                 WM_NCRBUTTONDOWN, WM_NCRBUTTONUP, WM_NCXBUTTONDBLCLK, WM_NCXBUTTONDOWN,
-                WM_NCXBUTTONUP, WM_PAINT, WM_RBUTTONDBLCLK, WM_RBUTTONDOWN, WM_RBUTTONUP,
+                WM_NCXBUTTONUP, WM_NULL, WM_PAINT, WM_RBUTTONDBLCLK, WM_RBUTTONDOWN, WM_RBUTTONUP,
                 WM_SETCURSOR, WM_SETTINGCHANGE, WM_SHOWWINDOW, WM_SIZE, WM_SIZING, WM_SYSCHAR,
-                WM_SYSCOMMAND, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_TOUCH, WM_XBUTTONDBLCLK,
+                WM_SYSCOMMAND, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_TIMER, WM_TOUCH, WM_XBUTTONDBLCLK,
                 WM_XBUTTONDOWN, WM_XBUTTONUP, WNDCLASSW, WVR_VALIDRECTS,
             },
         },
@@ -112,8 +135,20 @@ use winreg::RegKey;
 
 type IntPoint2 = Point2<i32>;
 
-pub static mut WINDOWS_APPLICATION: Option<&'static Arc<WindowsApplication>> = None;
-static INIT_APPLICATION: Once = Once::new();
+/** The timeout of a wait that never times out */
+const INFINITE: u32 = u32::MAX;
+
+/** What MsgWaitForMultipleObjectsEx returns when the wait itself failed */
+const WAIT_FAILED: u32 = u32::MAX;
+
+/** The ID of the Win32 timer that keeps the application ticking while the user drags or resizes a window */
+const MODAL_SIZE_LOOP_TIMER_ID: usize = 1;
+
+/**
+ * How often the modal size loop ticks when no timer is due sooner, so proxy messages and input still get through.
+ * Windows does not fire timers more often than every 10ms.
+ */
+const MODAL_SIZE_LOOP_IDLE_INTERVAL: Duration = Duration::from_millis(100);
 
 lazy_static! {
     static ref WINDOWS_MESSAGE_STRINGS: BTreeMap<u32, &'static str> = {
@@ -208,9 +243,28 @@ pub enum TaskbarProgressState {
     Paused = 0x8,
 }
 
+/**
+ * Wakes the thread that made the application by posting it a WM_NULL, which ends a GetMessageW or
+ * MsgWaitForMultipleObjects. The message itself is dispatched to no window and does nothing.
+ */
+#[derive(Debug)]
+struct ThreadMessageWaker {
+    thread_id: u32,
+}
+
+impl EventLoopWaker for ThreadMessageWaker {
+    fn wake(&self) {
+        unsafe {
+            PostThreadMessageW(self.thread_id, WM_NULL, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
 //TODO implement a TaskbarList struct that is built around the ITaskbarList3 COM interface
 
-/*pub struct DeferredWindowsMessage {
+/** A message queued by the wndproc, to be processed once the Win32 callback has returned */
+#[derive(Debug)]
+pub struct DeferredWindowsMessage {
     pub native_window: Weak<WindowsWindow>,
     pub hwnd: HWND,
     pub message: u32,
@@ -218,7 +272,10 @@ pub enum TaskbarProgressState {
     pub lparam: LPARAM,
     pub mouse_coord_x: i32,
     pub mouse_coord_y: i32,
+    /** RAWMOUSE.usFlags of a WM_INPUT, telling relative motion from absolute */
     pub raw_input_flags: u32,
+    /** RAWMOUSE.usButtonFlags of a WM_INPUT, the buttons that went down or up */
+    pub raw_button_flags: u16,
 }
 
 impl DeferredWindowsMessage {
@@ -230,19 +287,21 @@ impl DeferredWindowsMessage {
         lparam: LPARAM,
         x: i32,
         y: i32,
-        raw_input_flags: u32) -> DeferredWindowsMessage {
+        raw_input_flags: u32,
+    ) -> DeferredWindowsMessage {
         DeferredWindowsMessage {
             native_window: Rc::downgrade(native_window),
-            hwnd: hwnd,
-            message: message,
-            wparam: wparam,
-            lparam: lparam,
+            hwnd,
+            message,
+            wparam,
+            lparam,
             mouse_coord_x: x,
             mouse_coord_y: y,
-            raw_input_flags: raw_input_flags,
+            raw_input_flags,
+            raw_button_flags: 0,
         }
     }
-}*/
+}
 
 pub enum WindowsDragDropOperationType {
     DragEnter,
@@ -286,50 +345,100 @@ pub trait IWindowsMessageHandler {
     ) -> bool;
 }
 
-//This is internal to the WindowsApplication class in the Unreal Engine 4 code.
-pub enum ModifierKey {
-    LeftShift = 0,    // VK_LSHIFT
-    RightShift = 1,   // VK_RSHIFT
-    LeftControl = 2,  // VK_LCONTROL
-    RightControl = 3, // VK_RCONTROL
-    LeftAlt = 4,      // VK_LMENU
-    RightAlt = 5,     // VK_RMENU
-    CapsLock = 6,     // VK_CAPITAL
-    Count = 7,
-}
-
+/**
+ * Creates the Win32 application. The caller owns it: its windows only keep a weak reference, which the wndproc
+ * reaches through their GWLP_USERDATA, so dropping the application destroys its windows and a new one can be
+ * created afterwards.
+ */
 pub fn create_windows_application(
     hinstance: HINSTANCE,
     hicon: HICON,
-) -> &'static Arc<WindowsApplication> {
-    INIT_APPLICATION.call_once(|| unsafe { init_windows_application(hinstance, hicon) });
-    unsafe { WINDOWS_APPLICATION.unwrap() }
+    message_handler: &Rc<dyn ApplicationMessageHandler>,
+) -> Rc<WindowsApplication> {
+    WindowsApplication::new(hinstance, hicon, message_handler)
 }
 
-unsafe fn init_windows_application(hinstance: HINSTANCE, hicon: HICON) {
-    let mut app = utils::leak(Arc::new(WindowsApplication::new(hinstance, hicon)));
-    println!("app address is {:p}", &app);
-    WINDOWS_APPLICATION = Some(app);
-}
-
-//TODO implement GenericApplication trait. Also most likely trait based on IForceFeedbackSystem.
-#[derive(Debug)]
+//TODO most likely implement a trait based on IForceFeedbackSystem.
 pub struct WindowsApplication {
     cursor: Rc<WindowsCursor>,
     minimized_window_position: IntPoint2,
     instance_handle: HINSTANCE,
-    using_high_precision_mouse_input: bool,
+    using_high_precision_mouse_input: Cell<bool>,
+    /** Raw mouse motion received since the last poll_events, delivered as one on_raw_mouse_move per frame */
+    raw_mouse_delta: Cell<(i32, i32)>,
+    /** Virtual desktop position of the last absolute raw input, which the next one is a delta from */
+    last_absolute_mouse_pos: Cell<Option<(i32, i32)>>,
     is_mouse_attached: bool,
     force_activate_by_mouse: bool,
-    pub windows: RefCell<Vec<Rc<RefCell<WindowsWindow>>>>,
-    //modifier_key_state: [bool; ModifierKey::Count as usize],
-    in_modal_size_loop: bool,
-    pub display_metrics: DisplayMetrics,
+    pub windows: RefCell<Vec<Rc<WindowsWindow>>>,
+    modifier_keys: Cell<ModifierKeysState>,
+    /**
+     * Set between WM_ENTERSIZEMOVE and WM_EXITSIZEMOVE. Windows runs its own message loop while the user drags or
+     * resizes a window, so poll_events does not return until the user lets go; a Win32 timer ticks the application
+     * from inside that loop instead.
+     */
+    in_modal_size_loop: Cell<bool>,
+    initial_display_metrics: DisplayMetrics,
+    display_metrics: RefCell<DisplayMetrics>,
+    /** Set by WM_DISPLAYCHANGE and friends; the metrics are queried again once the message queue is empty */
+    display_metrics_dirty: Cell<bool>,
+    message_handler: Rc<dyn ApplicationMessageHandler>,
+    /** Input messages the wndproc queued since the last poll_events */
+    deferred_messages: RefCell<Vec<DeferredWindowsMessage>>,
+    /** The first half of a character that WM_CHAR delivered as a UTF-16 surrogate pair */
+    pending_high_surrogate: Cell<Option<u16>>,
+    events: PlatformEventQueue,
+    /** The application itself, handed to the windows it makes */
+    weak_self: Weak<WindowsApplication>,
+    proxy_receiver: ProxyReceiver<WindowsWindow>,
+    timers: TimerQueue,
+    xinput: XInputInterface,
     //startup_sticky_keys: STICKYKEYS,
     //startup_toggle_keys: TOGGLEKEYS,
     //startup_filter_keys: FILTERKEYS,
 }
 
+impl fmt::Debug for WindowsApplication {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowsApplication")
+            .field("cursor", &self.cursor)
+            .field("minimized_window_position", &self.minimized_window_position)
+            .field("instance_handle", &self.instance_handle)
+            .field(
+                "using_high_precision_mouse_input",
+                &self.using_high_precision_mouse_input,
+            )
+            .field("is_mouse_attached", &self.is_mouse_attached)
+            .field("force_activate_by_mouse", &self.force_activate_by_mouse)
+            .field("windows", &self.windows)
+            .field("modifier_keys", &self.modifier_keys)
+            .field("in_modal_size_loop", &self.in_modal_size_loop)
+            .field("display_metrics", &self.display_metrics)
+            .field("deferred_messages", &self.deferred_messages)
+            .field("num_queued_events", &self.events.len())
+            .field("proxy_receiver", &self.proxy_receiver)
+            .field("timers", &self.timers)
+            .field("xinput", &self.xinput)
+            .finish()
+    }
+}
+
+impl Drop for WindowsApplication {
+    fn drop(&mut self) {
+        // The wndproc can no longer reach the application, so these get the default handling
+        for window in self.windows.take() {
+            window.destroy();
+        }
+        unsafe {
+            // Fails if windows of the class are still open elsewhere, which leaves the class registered for them
+            UnregisterClassW(
+                PCWSTR::from_raw(APP_WINDOW_CLASS.to_wide_null().as_ptr()),
+                self.instance_handle,
+            );
+        }
+    }
+}
+
 impl WindowsApplication {
     /*fn allow_accessibility_shortcut_keys(&mut self, allow_keys: bool) {
         unsafe {
@@ -371,19 +480,48 @@ impl WindowsApplication {
             }
         }
     }*/
-    pub fn new(hinstance: HINSTANCE, hicon: HICON) -> WindowsApplication {
-        let display_metrics = DisplayMetrics::new();
+    pub fn new(
+        hinstance: HINSTANCE,
+        hicon: HICON,
+        message_handler: &Rc<dyn ApplicationMessageHandler>,
+    ) -> Rc<WindowsApplication> {
+        Rc::new_cyclic(|weak_self| {
+            WindowsApplication::new_with_weak_self(hinstance, hicon, message_handler, weak_self)
+        })
+    }
+    fn new_with_weak_self(
+        hinstance: HINSTANCE,
+        hicon: HICON,
+        message_handler: &Rc<dyn ApplicationMessageHandler>,
+        weak_self: &Weak<WindowsApplication>,
+    ) -> WindowsApplication {
+        let mut display_metrics = DisplayMetrics::default();
+        rebuild_display_metrics(&mut display_metrics);
         let mut winapp = WindowsApplication {
             cursor: Rc::new(WindowsCursor::new()),
             minimized_window_position: IntPoint2::new(-32000, -32000),
             instance_handle: hinstance,
-            using_high_precision_mouse_input: false,
+            using_high_precision_mouse_input: Cell::new(false),
+            raw_mouse_delta: Cell::new((0, 0)),
+            last_absolute_mouse_pos: Cell::new(None),
             is_mouse_attached: false,
             force_activate_by_mouse: false,
             windows: RefCell::new(vec![]),
-            //modifier_key_state: unsafe { mem::zeroed() },
-            in_modal_size_loop: false,
-            display_metrics: display_metrics,
+            modifier_keys: Cell::new(ModifierKeysState::default()),
+            in_modal_size_loop: Cell::new(false),
+            initial_display_metrics: display_metrics.clone(),
+            display_metrics: RefCell::new(display_metrics),
+            display_metrics_dirty: Cell::new(false),
+            message_handler: message_handler.clone(),
+            deferred_messages: RefCell::new(vec![]),
+            pending_high_surrogate: Cell::new(None),
+            events: PlatformEventQueue::new(),
+            weak_self: weak_self.clone(),
+            proxy_receiver: ProxyReceiver::new(Some(Box::new(ThreadMessageWaker {
+                thread_id: unsafe { GetCurrentThreadId() },
+            }))),
+            timers: TimerQueue::new(),
+            xinput: XInputInterface::new(Box::new(WindowsXInputStateSource), message_handler),
             //startup_sticky_keys: STICKYKEYS,
             //startup_toggle_keys: TOGGLEKEYS,
             //startup_filter_keys: FILTERKEYS,
@@ -392,16 +530,16 @@ impl WindowsApplication {
         let class_registered = winapp.register_class(hinstance, hicon);
         println!("Have we registered class? {}", class_registered);
         winapp.query_connected_mice();
+        winapp.update_all_modifier_key_states();
         println!("winapp debug is {:#?}", winapp);
         winapp
     }
-    pub fn make_window(&self) -> Rc<RefCell<WindowsWindow>> {
-        WindowsWindow::make()
+    pub fn make_window(&self, definition: &Rc<WindowDefinition>) -> Rc<WindowsWindow> {
+        WindowsWindow::make(&self.weak_self, definition)
     }
     pub fn initialize_window(
         &self,
-        window: &Rc<RefCell<WindowsWindow>>,
-        definition: &Rc<WindowDefinition>,
+        window: &Rc<WindowsWindow>,
         parent: Option<Rc<WindowsWindow>>,
         show_immediately: bool,
     ) {
@@ -409,19 +547,13 @@ impl WindowsApplication {
         self.windows.borrow_mut().push(window.clone());
         println!("window strong count is {}", Rc::strong_count(&window));
         println!("window weak count is {}", Rc::weak_count(&window));
-        println!(
-            "definition strong count is {}",
-            Rc::strong_count(&definition)
-        );
-        println!("definition weak count is {}", Rc::weak_count(&definition));
-        println!("about to initialize the window. Immutable borrow here.");
-        let borrowed_window: &RefCell<WindowsWindow> = Rc::borrow(&window);
-        borrowed_window.borrow_mut().initialize(
-            definition,
-            self.instance_handle,
-            parent,
-            show_immediately,
-        );
+        if let Some(parent) = &parent {
+            let generic_window: Rc<dyn GenericWindow> = window.clone();
+            let generic_parent: Rc<dyn GenericWindow> = parent.clone();
+            window_hierarchy::set_parent(&generic_window, Some(&generic_parent));
+        }
+        // The window is not borrowed while CreateWindowExW runs, so the wndproc can look it up
+        window.initialize(self.instance_handle, parent, show_immediately);
     }
     fn register_class(&self, hinstance: HINSTANCE, hicon: HICON) -> bool {
         unsafe {
@@ -438,7 +570,8 @@ impl WindowsApplication {
                 lpszClassName: PCWSTR::from_raw(APP_WINDOW_CLASS.to_wide_null().as_ptr()),
             };
 
-            if RegisterClassW(&wc) == 0 {
+            // The class outlives an application whose windows are still open, so the next one can reuse it
+            if RegisterClassW(&wc) == 0 && GetLastError() != ERROR_CLASS_ALREADY_EXISTS {
                 //ShowLastError();
 
                 // @todo Slate: Error message should be localized!
@@ -476,8 +609,8 @@ impl WindowsApplication {
     //TODO the return signature for this method feels wrong.
     pub fn find_window_by_hwnd(
         &self,
-        /*windows_to_search: &Vec<Rc<RefCell<WindowsWindow>>>,*/ handle_to_find: HWND,
-    ) -> Option<Rc<RefCell<WindowsWindow>>> {
+        /*windows_to_search: &Vec<Rc<WindowsWindow>>,*/ handle_to_find: HWND,
+    ) -> Option<Rc<WindowsWindow>> {
         println!(
             "Inside find_window_by_hwnd, handle_to_find is {:?}",
             handle_to_find
@@ -490,9 +623,8 @@ impl WindowsApplication {
             }
             let borrowed_window = &self.windows.borrow()[n];
             //println!("self.windows.borrow()[{}] is {:p} and {:#?}", n, borrowed_window, borrowed_window);
-            //println!("borrowed_window.get_hwnd() is {:p}", borrowed_window.borrow().get_hwnd());
-            let borrowed_window_borrow: &RefCell<WindowsWindow> = Rc::borrow(&borrowed_window);
-            if borrowed_window_borrow.borrow().get_hwnd() == handle_to_find {
+            //println!("borrowed_window.get_hwnd() is {:p}", borrowed_window.get_hwnd());
+            if borrowed_window.get_hwnd() == handle_to_find {
                 return Some(borrowed_window.clone());
             }
             n += 1;
@@ -525,11 +657,13 @@ impl WindowsApplication {
     pub fn get_capture(&self) -> HWND {
         unsafe { GetCapture() }
     }
-    pub fn set_high_precision_mouse_mode(&mut self, enable: bool, window: Rc<dyn GenericWindow>) {
+    pub fn set_high_precision_mouse_mode(&self, enable: bool, window: Rc<dyn GenericWindow>) {
         unsafe {
             let mut hwnd = HWND(0);
             let mut flags = RIDEV_REMOVE;
-            self.using_high_precision_mouse_input = enable;
+            self.using_high_precision_mouse_input.set(enable);
+            self.raw_mouse_delta.set((0, 0));
+            self.last_absolute_mouse_pos.set(None);
 
             if enable {
                 flags = RAWINPUTDEVICE_FLAGS(0);
@@ -552,6 +686,9 @@ impl WindowsApplication {
             RegisterRawInputDevices(&[raw_input_device], 1);
         }
     }
+    pub fn is_using_high_precision_mouse_mode(&self) -> bool {
+        self.using_high_precision_mouse_input.get()
+    }
     pub fn get_work_area(&self, current_window: &PlatformRect) -> PlatformRect {
         let mut windows_window_dim = RECT::default();
         windows_window_dim.left = current_window.left;
@@ -575,22 +712,24 @@ impl WindowsApplication {
             work_area
         }
     }
+    /** Queries the monitors again and reports the change, if there is one */
+    fn update_display_metrics(&self) {
+        let mut display_metrics = DisplayMetrics::default();
+        rebuild_display_metrics(&mut display_metrics);
+        let change = self.display_metrics.borrow().diff(&display_metrics);
+        if !change.is_empty() {
+            *self.display_metrics.borrow_mut() = display_metrics.clone();
+            self.events.push(PlatformEvent::DisplayMetricsChanged {
+                display_metrics,
+                change,
+            });
+        }
+    }
     pub fn process_message(&self, hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> i32 {
-        println!("Reached inside process_message");
         unsafe {
-            println!("hwnd is {:?}", hwnd);
-            println!("WindowsApplication self address is {:p}", self);
-            println!(
-                "self.windows.borrow().len() is {}. about to find window by hwnd.",
-                self.windows.borrow().len()
-            );
             let mut current_native_event_window_opt = self.find_window_by_hwnd(hwnd);
 
             if self.windows.borrow().len() != 0 && current_native_event_window_opt.is_some() {
-                println!(
-                    "current_native_event_window_opt is some. Don't believe me? Look! {:#?}",
-                    current_native_event_window_opt
-                );
                 let mut current_native_event_window = current_native_event_window_opt.unwrap();
 
                 match msg {
@@ -599,10 +738,7 @@ impl WindowsApplication {
                             let mmi = mem::transmute::<LPARAM, *const MINMAXINFO>(lparam);
                             *mmi
                         };
-                        let borrowed_window: &RefCell<WindowsWindow> =
-                            Rc::borrow(&current_native_event_window);
-                        let borrowed_window_borrow = borrowed_window.borrow();
-                        let windef = borrowed_window_borrow.get_definition();
+                        let windef = current_native_event_window.get_definition();
                         let ref size_limits: WindowSizeLimits = windef.size_limits;
 
                         // We need to inflate the max values if using an OS window border
@@ -669,50 +805,29 @@ impl WindowsApplication {
                             );
                             raw
                         };
-                        let raw_mouse = raw.data.mouse;
-
-                        if raw.header.dwType == 0 {
-                            let is_absolute_input = (raw_mouse.usFlags as u32
-                                & MOUSE_MOVE_ABSOLUTE)
-                                == MOUSE_MOVE_ABSOLUTE;
-                            if is_absolute_input {
-                                // Since the raw input is coming in as absolute it is likely the user is using a tablet
-                                // or perhaps is interacting through a virtual desktop
-                                self.defer_message(
-                                    &current_native_event_window,
-                                    hwnd,
-                                    msg,
-                                    wparam,
-                                    lparam,
-                                    0,
-                                    0,
-                                    MOUSE_MOVE_ABSOLUTE as u32,
-                                );
-                                return 1;
-                            }
-
-                            // Since raw input is coming in as relative it is likely a traditional mouse device
-                            let x_pos_relative = raw_mouse.lLastX;
-                            let y_pos_relative = raw_mouse.lLastY;
-
-                            self.defer_message(
+                        if raw.header.dwType == RIM_TYPEMOUSE.0 {
+                            let raw_mouse = raw.data.mouse;
+                            // The raw input handle is only valid during this message, so copy out what is needed.
+                            // Relative motion comes from a traditional mouse, absolute positions from a tablet
+                            // or a remote/virtual desktop. Both are turned into deltas once deferred.
+                            let mut deferred_message = DeferredWindowsMessage::new(
                                 &current_native_event_window,
                                 hwnd,
                                 msg,
                                 wparam,
                                 lparam,
-                                x_pos_relative,
-                                y_pos_relative,
-                                MOUSE_MOVE_RELATIVE as u32,
+                                raw_mouse.lLastX,
+                                raw_mouse.lLastY,
+                                raw_mouse.usFlags as u32,
                             );
+                            deferred_message.raw_button_flags =
+                                raw_mouse.Anonymous.Anonymous.usButtonFlags;
+                            self.deferred_messages.borrow_mut().push(deferred_message);
                             return 1;
                         }
                     }
                     WM_NCCALCSIZE => {
-                        let borrowed_window: &RefCell<WindowsWindow> =
-                            Rc::borrow(&current_native_event_window);
-                        let borrowed_window_borrow = borrowed_window.borrow();
-                        let windef = borrowed_window_borrow.get_definition();
+                        let windef = current_native_event_window.get_definition();
                         // Let windows absorb this message if using the standard border
                         if wparam.0 != 0 && !windef.has_os_window_border {
                             // Borderless game windows are not actually borderless, they have a thick border that we simply draw game content over (client
@@ -721,7 +836,7 @@ impl WindowsApplication {
                             // window rect (including the border) sits inside the monitor. The size adjustments here will be sent to WM_MOVE and
                             // WM_SIZE and the window will still be considered maximized.
                             if windef.window_type == WindowType::GameWindow
-                                && borrowed_window.borrow().is_maximized()
+                                && current_native_event_window.is_maximized()
                             {
                                 // Ask the system for the window border size as this is the amount that Windows will bleed our window over the edge
                                 // of our desired space. The value returned by current_native_event_window will be incorrect for our usage here as it
@@ -767,82 +882,170 @@ impl WindowsApplication {
                             return 0;
                         }
                     }
+                    WM_NCHITTEST => {
+                        // Windows with an OS border get their zones from DefWindowProc. Borderless windows draw their own
+                        // chrome, so tell Windows where it is or they can not be moved or resized.
+                        if !current_native_event_window
+                            .get_definition()
+                            .has_os_window_border
+                        {
+                            let mut cursor_point = input::get_cursor_pos(lparam);
+                            ScreenToClient(hwnd, &mut cursor_point);
+                            let mut client_rect: RECT = mem::zeroed();
+                            GetClientRect(hwnd, &mut client_rect);
+
+                            let generic_window: Rc<dyn GenericWindow> =
+                                current_native_event_window.clone();
+                            let zone = window_hit_test::get_window_zone_for_point(
+                                &self.message_handler,
+                                &generic_window,
+                                client_rect.right - client_rect.left,
+                                client_rect.bottom - client_rect.top,
+                                cursor_point.x,
+                                cursor_point.y,
+                            );
+                            return HIT_RESULTS[zone.to_usize()] as i32;
+                        }
+                    }
                     WM_SIZING => {
-                        let borrowed_window: &RefCell<WindowsWindow> =
-                            Rc::borrow(&current_native_event_window);
-                        let borrowed_window_borrow = borrowed_window.borrow();
-                        let windef = borrowed_window_borrow.get_definition();
-                        if windef.should_preserve_aspect_ratio {
-                            // The rect we get in lparam is window rect, but we need to preserve client's aspect ratio,
-                            // so we need to find what the border and title bar sizes are, if window has them and adjust the rect.
+                        let windef = current_native_event_window.get_definition();
+                        if let Some(edge) = input::get_resize_edge(wparam) {
+                            // The rect we get in lparam is window rect, but the limits and the aspect ratio are for the client area,
+                            // so we need to find what the border and title bar sizes are, if window has them.
                             let mut window_info: WINDOWINFO = mem::zeroed();
                             window_info.cbSize = mem::size_of::<WINDOWINFO>() as u32;
                             GetWindowInfo(hwnd, &mut window_info);
 
-                            let mut test_rect: RECT = mem::zeroed();
+                            // This adjusts a zero rect to give us the size of the border
+                            let mut border_rect: RECT = mem::zeroed();
                             AdjustWindowRectEx(
-                                &mut test_rect,
+                                &mut border_rect,
                                 WINDOW_STYLE(window_info.dwStyle),
                                 false,
                                 WINDOW_EX_STYLE(window_info.dwExStyle),
                             );
 
-                            let mut rect: RECT = {
-                                let lprect = mem::transmute::<LPARAM, *const RECT>(lparam);
-                                *lprect
+                            let constraints = SizingConstraints {
+                                frame: FrameInsets {
+                                    left: -border_rect.left,
+                                    top: -border_rect.top,
+                                    right: border_rect.right,
+                                    bottom: border_rect.bottom,
+                                },
+                                size_limits: windef.size_limits.clone(),
+                                aspect_ratio: if windef.should_preserve_aspect_ratio {
+                                    Some(current_native_event_window.get_aspect_ratio())
+                                } else {
+                                    None
+                                },
+                                dpi_scale: current_native_event_window.get_dpi_scale_factor(),
                             };
 
-                            rect.left -= test_rect.left;
-                            rect.right -= test_rect.right;
-                            rect.top -= test_rect.top;
-                            rect.bottom -= test_rect.bottom;
-
-                            let aspect_ratio = borrowed_window.borrow().get_aspect_ratio();
-                            let new_width = rect.right - rect.left;
-                            let new_height = rect.bottom - rect.top;
-
-                            let wparam0 = wparam.0;
-
-                            if wparam0 == WMSZ_LEFT as usize || wparam0 == WMSZ_RIGHT as usize {
-                                let adjusted_height: i32 = new_width / aspect_ratio as i32;
-                                rect.top -= (adjusted_height - new_height) / 2;
-                                rect.bottom += (adjusted_height - new_height) / 2;
-                                //break;
-                            } else if wparam0 == WMSZ_TOP as usize
-                                || wparam0 == WMSZ_BOTTOM as usize
-                            {
-                                let adjusted_width: i32 = new_height * aspect_ratio as i32;
-                                rect.left -= (adjusted_width - new_width) / 2;
-                                rect.right += (adjusted_width - new_width) / 2;
-                                //break;
-                            } else if wparam0 == WMSZ_TOPLEFT as usize {
-                                let adjusted_height: i32 = new_width / aspect_ratio as i32;
-                                rect.top -= adjusted_height - new_height;
-                                //break;
-                            } else if wparam0 == WMSZ_TOPRIGHT as usize {
-                                let adjusted_height: i32 = new_width / aspect_ratio as i32;
-                                rect.top -= adjusted_height - new_height;
-                                //break;
-                            } else if wparam0 == WMSZ_BOTTOMLEFT as usize {
-                                let adjusted_height: i32 = new_width / aspect_ratio as i32;
-                                rect.bottom += adjusted_height - new_height;
-                                //break;
-                            } else if wparam0 == WMSZ_BOTTOMRIGHT as usize {
-                                let adjusted_height: i32 = new_width / aspect_ratio as i32;
-                                rect.bottom += adjusted_height - new_height;
-                                //break;
-                            }
-
-                            AdjustWindowRectEx(
-                                &mut rect,
-                                WINDOW_STYLE(window_info.dwStyle),
-                                false,
-                                WINDOW_EX_STYLE(window_info.dwExStyle),
+                            // Windows uses the rect we write back through lparam
+                            let rect = &mut *mem::transmute::<LPARAM, *mut RECT>(lparam);
+                            let constrained_rect = window_sizing::constrain_window_rect(
+                                edge,
+                                &PlatformRect::new(rect.left, rect.top, rect.right, rect.bottom),
+                                &constraints,
                             );
+                            rect.left = constrained_rect.left;
+                            rect.top = constrained_rect.top;
+                            rect.right = constrained_rect.right;
+                            rect.bottom = constrained_rect.bottom;
 
                             return 1;
                         }
                     }
+                    WM_CHAR | WM_KEYDOWN | WM_KEYUP | WM_SYSKEYUP | WM_LBUTTONDBLCLK
+                    | WM_LBUTTONDOWN | WM_LBUTTONUP | WM_MBUTTONDBLCLK | WM_MBUTTONDOWN
+                    | WM_MBUTTONUP | WM_RBUTTONDBLCLK | WM_RBUTTONDOWN | WM_RBUTTONUP
+                    | WM_XBUTTONDBLCLK | WM_XBUTTONDOWN | WM_XBUTTONUP | WM_MOUSEMOVE
+                    | WM_NCMOUSEMOVE | WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+                        self.defer_message(
+                            &current_native_event_window,
+                            hwnd,
+                            msg,
+                            wparam,
+                            lparam,
+                            0,
+                            0,
+                            0,
+                        );
+                        // Handled
+                        return 0;
+                    }
+                    WM_SYSKEYDOWN => {
+                        // Alt-F4 or Alt+Space was pressed.
+                        // Allow alt+f4 to close the window and alt+space to open the window menu
+                        if wparam.0 != VK_F4.0 as usize && wparam.0 != VK_SPACE.0 as usize {
+                            self.defer_message(
+                                &current_native_event_window,
+                                hwnd,
+                                msg,
+                                wparam,
+                                lparam,
+                                0,
+                                0,
+                                0,
+                            );
+                        }
+                    }
+                    WM_SYSCHAR => {
+                        // Do not handle Alt+Space so that it passes through and opens the window system menu
+                        let is_alt_down = (lparam.0 >> 16) & 0x2000 != 0;
+                        if !is_alt_down || wparam.0 != VK_SPACE.0 as usize {
+                            return 0;
+                        }
+                    }
+                    // Windows hides and shows owned windows with their owner, and keeps them above it, by itself.
+                    // Remember where they were so they come back in the same place.
+                    WM_SHOWWINDOW => match lparam.0 as u32 {
+                        SW_PARENTCLOSING => {
+                            current_native_event_window.on_parent_window_minimized()
+                        }
+                        SW_PARENTOPENING => current_native_event_window.on_parent_window_restored(),
+                        _ => {}
+                    },
+                    WM_ACTIVATEAPP => {
+                        // Modifier keys may have been pressed or released while another application was active
+                        self.update_all_modifier_key_states();
+                    }
+                    // A monitor was added, removed or rotated, changed resolution, or the user changed its scale.
+                    // Every top-level window gets these, so only note it here and query once per poll_events.
+                    WM_DISPLAYCHANGE | WM_DPICHANGED => {
+                        self.display_metrics_dirty.set(true);
+                    }
+                    WM_SETTINGCHANGE if wparam.0 == SPI_SETWORKAREA.0 as usize => {
+                        // The task bar moved or changed size
+                        self.display_metrics_dirty.set(true);
+                    }
+                    // A controller may have been plugged in or removed, so look at the empty XInput slots again
+                    WM_DEVICECHANGE => {
+                        self.xinput.set_needs_controller_state_update();
+                    }
+                    WM_ENTERSIZEMOVE => {
+                        self.in_modal_size_loop.set(true);
+                        self.set_modal_size_loop_timer(hwnd);
+                    }
+                    WM_EXITSIZEMOVE => {
+                        self.in_modal_size_loop.set(false);
+                        KillTimer(hwnd, MODAL_SIZE_LOOP_TIMER_ID);
+                    }
+                    // The modal loop dispatches messages but never returns to poll_events, so do its work here,
+                    // which keeps timers firing and lets the application render while the window is resized
+                    WM_TIMER
+                        if wparam.0 == MODAL_SIZE_LOOP_TIMER_ID
+                            && self.in_modal_size_loop.get() =>
+                    {
+                        self.process_proxy_messages();
+                        self.process_deferred_messages();
+                        self.process_deferred_events(0.0);
+                        // The handler may have set or cancelled timers, so aim for the one that is due next
+                        if self.in_modal_size_loop.get() {
+                            self.set_modal_size_loop_timer(hwnd);
+                        }
+                        return 0;
+                    }
                     WM_DESTROY => {
                         println!("about to delete references to windows after WM_DESTROY. Mutable borrow here.");
                         self.windows
@@ -856,9 +1059,70 @@ impl WindowsApplication {
             DefWindowProcW(hwnd, msg, wparam, lparam).0 as i32
         }
     }
-    /*void FWindowsApplication::GetInitialDisplayMetrics( FDisplayMetrics& OutDisplayMetrics ) const {
-        OutDisplayMetrics = InitialDisplayMetrics;
-    }*/
+    /** Arms the Win32 timer of the modal size loop for when the next application timer is due */
+    fn set_modal_size_loop_timer(&self, hwnd: HWND) {
+        let interval = self
+            .timers
+            .get_wait_timeout(Some(MODAL_SIZE_LOOP_IDLE_INTERVAL))
+            .unwrap_or(MODAL_SIZE_LOOP_IDLE_INTERVAL);
+        unsafe {
+            // Setting a timer with the same ID again replaces it
+            SetTimer(
+                hwnd,
+                MODAL_SIZE_LOOP_TIMER_ID,
+                interval.as_micros().div_ceil(1000) as u32,
+                None,
+            );
+        }
+    }
+    /** Runs the window commands and user events the proxies sent since the last call */
+    fn process_proxy_messages(&self) {
+        self.proxy_receiver.process_messages(
+            &self.events,
+            |definition, parent, show_immediately| {
+                let window = self.make_window(definition);
+                self.initialize_window(&window, parent.cloned(), show_immediately);
+                // initialize_window leaves showing the window to the caller, like main does
+                if show_immediately {
+                    window.show();
+                }
+                window
+            },
+        );
+    }
+    /**
+     * Turns what the wndproc noted while messages were dispatched into events: deferred input, display changes,
+     * summed up raw mouse motion and expired timers.
+     */
+    fn process_deferred_messages(&self) {
+        // The wndproc only queued its input messages. Process them now that no Win32 callback is on the stack.
+        // Taking the queue first lets the handler cause new messages without a double borrow.
+        let deferred_messages = mem::take(&mut *self.deferred_messages.borrow_mut());
+        for deferred_message in &deferred_messages {
+            self.process_deferred_message(deferred_message);
+        }
+
+        if self.display_metrics_dirty.take() {
+            self.update_display_metrics();
+        }
+        // WM_DESTROY takes a window out of the list, which is how the proxies learn it is gone
+        self.proxy_receiver.retain_windows(|window| {
+            self.windows
+                .borrow()
+                .iter()
+                .any(|open_window| Rc::ptr_eq(open_window, window))
+        });
+
+        // Raw motion arrives at the mouse's polling rate, far more often than frames, so it is summed up
+        let (delta_x, delta_y) = self.raw_mouse_delta.take();
+        if delta_x != 0 || delta_y != 0 {
+            self.events.push(PlatformEvent::RawMouseMove {
+                x: delta_x,
+                y: delta_y,
+            });
+        }
+        self.timers.fire_expired_timers(&self.events);
+    }
     fn is_keyboard_input_message(&self, msg: u32) -> bool {
         match msg {
             // Keyboard input notification messages...
@@ -957,42 +1221,75 @@ impl WindowsApplication {
             self.is_mouse_attached = mouse_count > 0;
         }
     }
-    /*fn update_all_modifier_key_states(&mut self) {
-        unsafe {
-            self.modifier_key_state[ModifierKey::LeftShift as usize]       = (GetAsyncKeyState(VK_LSHIFT) & 0x8000) != 0;
-            self.modifier_key_state[ModifierKey::RightShift as usize]      = (GetAsyncKeyState(VK_RSHIFT) & 0x8000) != 0;
-            self.modifier_key_state[ModifierKey::LeftControl as usize]     = (GetAsyncKeyState(VK_LCONTROL) & 0x8000) != 0;
-            self.modifier_key_state[ModifierKey::RightControl as usize]    = (GetAsyncKeyState(VK_RCONTROL) & 0x8000) != 0;
-            self.modifier_key_state[ModifierKey::LeftAlt as usize]         = (GetAsyncKeyState(VK_LMENU) & 0x8000) != 0;
-            self.modifier_key_state[ModifierKey::RightAlt as usize]        = (GetAsyncKeyState(VK_RMENU) & 0x8000) != 0;
-            self.modifier_key_state[ModifierKey::CapsLock as usize]        = (GetKeyState(VK_CAPITAL) & 0x0001) != 0;
+    /** Reads every modifier key from Windows, for when key messages may have been missed */
+    fn update_all_modifier_key_states(&self) {
+        // The high bit of GetAsyncKeyState is set while the key is down, so a down key reads as negative
+        let is_key_down =
+            |virtual_key: VIRTUAL_KEY| unsafe { GetAsyncKeyState(virtual_key.0 as i32) < 0 };
+        self.modifier_keys.set(ModifierKeysState {
+            is_left_shift_down: is_key_down(VK_LSHIFT),
+            is_right_shift_down: is_key_down(VK_RSHIFT),
+            is_left_control_down: is_key_down(VK_LCONTROL),
+            is_right_control_down: is_key_down(VK_RCONTROL),
+            is_left_alt_down: is_key_down(VK_LMENU),
+            is_right_alt_down: is_key_down(VK_RMENU),
+            is_left_command_down: is_key_down(VK_LWIN),
+            is_right_command_down: is_key_down(VK_RWIN),
+            are_caps_locked: unsafe { GetKeyState(VK_CAPITAL.0 as i32) & 0x0001 != 0 },
+        });
+    }
+    /** Records a key going down or up. Caps lock is read back from Windows since a press toggles it. */
+    fn update_modifier_key_state(&self, key: Key, is_down: bool) -> ModifierKeysState {
+        let mut modifier_keys = self.modifier_keys.get();
+        modifier_keys.set_key_down(key, is_down);
+        if key == Key::CapsLock {
+            modifier_keys.are_caps_locked =
+                unsafe { GetKeyState(VK_CAPITAL.0 as i32) & 0x0001 != 0 };
         }
-    }*/
-    // Defined as a global so that it can be extern'd by UELibrary
-    fn windows_application_wnd_proc(
+        self.modifier_keys.set(modifier_keys);
+        modifier_keys
+    }
+    /**
+     * Routes a message to the application that made the window. WM_NCCREATE stores the weak reference the window
+     * passed to CreateWindowExW in GWLP_USERDATA, and WM_NCDESTROY releases it. Messages that arrive before
+     * WM_NCCREATE, or after the application is dropped, get the default handling.
+     */
+    unsafe fn windows_application_wnd_proc(
         hwnd: HWND,
         msg: u32,
         wparam: WPARAM,
         lparam: LPARAM,
     ) -> LRESULT {
-        unsafe {
-            LRESULT(
-                WINDOWS_APPLICATION
-                    .unwrap()
-                    .process_message(hwnd, msg, wparam, lparam) as isize,
-            )
+        if msg == WM_NCCREATE {
+            let create_struct = &*(lparam.0 as *const CREATESTRUCTW);
+            let _ = utils::set_window_long_ptr(
+                hwnd,
+                GWLP_USERDATA.0,
+                create_struct.lpCreateParams as *const WindowsApplication,
+            );
         }
-    }
-    pub fn pump_messages(&self, time_delta: f32) {
-        unsafe {
-            let mut message: MSG = mem::zeroed();
-
-            // standard Windows message handling
-            while PeekMessageW(&mut message, HWND(0), 0, 0, PM_REMOVE).0 != 0 {
-                TranslateMessage(&message);
-                DispatchMessageW(&message);
+        let application_ptr =
+            utils::get_window_long_ptr::<WindowsApplication>(hwnd, GWLP_USERDATA.0)
+                .unwrap_or(ptr::null());
+        if application_ptr.is_null() {
+            return DefWindowProcW(hwnd, msg, wparam, lparam);
+        }
+        if msg == WM_NCDESTROY {
+            let _ = utils::set_window_long_ptr::<WindowsApplication>(
+                hwnd,
+                GWLP_USERDATA.0,
+                ptr::null(),
+            );
+            drop(Weak::from_raw(application_ptr));
+            return DefWindowProcW(hwnd, msg, wparam, lparam);
+        }
+        // The window keeps owning its reference until WM_NCDESTROY
+        let application = ManuallyDrop::new(Weak::from_raw(application_ptr));
+        match application.upgrade() {
+            Some(application) => {
+                LRESULT(application.process_message(hwnd, msg, wparam, lparam) as isize)
             }
-            println!("In pump_messages, return value was 0");
+            None => DefWindowProcW(hwnd, msg, wparam, lparam),
         }
     }
     unsafe extern "system" fn app_wnd_proc(
@@ -1001,20 +1298,19 @@ impl WindowsApplication {
         wparam: WPARAM,
         lparam: LPARAM,
     ) -> LRESULT {
-        println!(
-            "in app_wnd_proc, message is {0}, {:#06x} ({})",
+        log::trace!(
+            "app_wnd_proc: message {:#06x} ({})",
             msg,
-            if let Some(msg_str) = WINDOWS_MESSAGE_STRINGS.get(&msg) {
-                msg_str
-            } else {
-                "Not found"
-            }
+            WINDOWS_MESSAGE_STRINGS
+                .get(&msg)
+                .copied()
+                .unwrap_or("unknown")
         );
         Self::windows_application_wnd_proc(hwnd, msg, wparam, lparam)
     }
     fn defer_message(
         &self,
-        native_window: &Rc<RefCell<WindowsWindow>>,
+        native_window: &Rc<WindowsWindow>,
         in_hwnd: HWND,
         in_message: u32,
         in_wparam: WPARAM,
@@ -1023,37 +1319,283 @@ impl WindowsApplication {
         mouse_y: i32,
         raw_input_flags: u32,
     ) {
+        self.deferred_messages
+            .borrow_mut()
+            .push(DeferredWindowsMessage::new(
+                native_window,
+                in_hwnd,
+                in_message,
+                in_wparam,
+                in_lparam,
+                mouse_x,
+                mouse_y,
+                raw_input_flags,
+            ));
+    }
+    /**
+     * Adds the motion of an absolute raw input to the frame's delta. The position is normalized to 0-65535 across
+     * the virtual desktop, or across the primary monitor without MOUSE_VIRTUAL_DESKTOP.
+     */
+    fn accumulate_absolute_raw_mouse_move(&self, raw_input_flags: u32, x: i32, y: i32) {
+        let (left, top, width, height) = unsafe {
+            if raw_input_flags & MOUSE_VIRTUAL_DESKTOP as u32 != 0 {
+                (
+                    GetSystemMetrics(SM_XVIRTUALSCREEN),
+                    GetSystemMetrics(SM_YVIRTUALSCREEN),
+                    GetSystemMetrics(SM_CXVIRTUALSCREEN),
+                    GetSystemMetrics(SM_CYVIRTUALSCREEN),
+                )
+            } else {
+                (
+                    0,
+                    0,
+                    GetSystemMetrics(SM_CXSCREEN),
+                    GetSystemMetrics(SM_CYSCREEN),
+                )
+            }
+        };
+        let absolute_x = left + (x as f32 / 65535.0 * width as f32) as i32;
+        let absolute_y = top + (y as f32 / 65535.0 * height as f32) as i32;
+
+        // The first position only sets where the following deltas are measured from
+        if let Some((last_x, last_y)) = self
+            .last_absolute_mouse_pos
+            .replace(Some((absolute_x, absolute_y)))
+        {
+            let (delta_x, delta_y) = self.raw_mouse_delta.get();
+            self.raw_mouse_delta
+                .set((delta_x + absolute_x - last_x, delta_y + absolute_y - last_y));
+        }
+    }
+    /** Turns a message the wndproc deferred into events for the message handler */
+    fn process_deferred_message(&self, deferred_message: &DeferredWindowsMessage) {
+        let native_window = match deferred_message.native_window.upgrade() {
+            Some(native_window) => native_window,
+            // The window was destroyed after the message was queued
+            None => return,
+        };
+        let generic_window: Rc<dyn GenericWindow> = native_window;
+        let msg = deferred_message.message;
+
+        if self.is_input_message(msg)
+            && !window_hierarchy::should_process_user_input(&self.message_handler, &generic_window)
+        {
+            return;
+        }
+
+        match msg {
+            WM_CHAR => {
+                let code_unit = deferred_message.wparam.0 as u16;
+                let mut pending_high_surrogate = self.pending_high_surrogate.get();
+                let character = input::decode_wm_char(&mut pending_high_surrogate, code_unit);
+                self.pending_high_surrogate.set(pending_high_surrogate);
+                if let Some(character) = character {
+                    self.events.push(PlatformEvent::KeyChar {
+                        character,
+                        is_repeat: input::is_repeat(deferred_message.lparam),
+                        modifier_keys: self.modifier_keys.get(),
+                    });
+                }
+            }
+            WM_KEYDOWN | WM_SYSKEYDOWN => {
+                let virtual_key = VIRTUAL_KEY(deferred_message.wparam.0 as u16);
+                let key = input::translate_key(virtual_key, deferred_message.lparam);
+                let modifier_keys = self.update_modifier_key_state(key, true);
+                self.events.push(PlatformEvent::KeyDown {
+                    key,
+                    character_code: input::get_character_code(virtual_key),
+                    is_repeat: input::is_repeat(deferred_message.lparam),
+                    modifier_keys,
+                });
+            }
+            WM_KEYUP | WM_SYSKEYUP => {
+                let virtual_key = VIRTUAL_KEY(deferred_message.wparam.0 as u16);
+                let key = input::translate_key(virtual_key, deferred_message.lparam);
+                let modifier_keys = self.update_modifier_key_state(key, false);
+                self.events.push(PlatformEvent::KeyUp {
+                    key,
+                    character_code: input::get_character_code(virtual_key),
+                    is_repeat: false,
+                    modifier_keys,
+                });
+            }
+            WM_LBUTTONDBLCLK | WM_LBUTTONDOWN | WM_LBUTTONUP | WM_MBUTTONDBLCLK
+            | WM_MBUTTONDOWN | WM_MBUTTONUP | WM_RBUTTONDBLCLK | WM_RBUTTONDOWN | WM_RBUTTONUP
+            | WM_XBUTTONDBLCLK | WM_XBUTTONDOWN | WM_XBUTTONUP => {
+                let button = input::translate_mouse_button(msg, deferred_message.wparam);
+                // Client-area button messages carry client coordinates
+                let cursor_point = input::get_cursor_pos(deferred_message.lparam);
+                let cursor_pos = Some(Vec2::new(cursor_point.x as f32, cursor_point.y as f32));
+                let modifier_keys = self.modifier_keys.get();
+                self.events.push(match msg {
+                    WM_LBUTTONDBLCLK | WM_MBUTTONDBLCLK | WM_RBUTTONDBLCLK | WM_XBUTTONDBLCLK => {
+                        PlatformEvent::MouseDoubleClick {
+                            window: generic_window,
+                            button,
+                            cursor_pos,
+                            modifier_keys,
+                        }
+                    }
+                    WM_LBUTTONDOWN | WM_MBUTTONDOWN | WM_RBUTTONDOWN | WM_XBUTTONDOWN => {
+                        PlatformEvent::MouseDown {
+                            window: generic_window,
+                            button,
+                            cursor_pos,
+                            modifier_keys,
+                        }
+                    }
+                    _ => PlatformEvent::MouseUp {
+                        button,
+                        cursor_pos,
+                        modifier_keys,
+                    },
+                });
+            }
+            WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+                let delta = input::get_wheel_delta(deferred_message.wparam);
+                // Unlike the button messages, wheel messages carry screen coordinates
+                let mut cursor_point = input::get_cursor_pos(deferred_message.lparam);
+                unsafe { ScreenToClient(deferred_message.hwnd, &mut cursor_point) };
+                let cursor_pos = Some(Vec2::new(cursor_point.x as f32, cursor_point.y as f32));
+                let modifier_keys = self.modifier_keys.get();
+                self.events.push(if msg == WM_MOUSEWHEEL {
+                    PlatformEvent::MouseWheel {
+                        delta,
+                        cursor_pos,
+                        modifier_keys,
+                    }
+                } else {
+                    PlatformEvent::MouseHWheel {
+                        delta,
+                        cursor_pos,
+                        modifier_keys,
+                    }
+                });
+            }
+            WM_MOUSEMOVE | WM_NCMOUSEMOVE => {
+                // In high precision mode movement comes from WM_INPUT instead
+                if !self.using_high_precision_mouse_input.get() {
+                    self.events.push(PlatformEvent::MouseMove);
+                }
+            }
+            WM_INPUT => {
+                let (x, y) = (
+                    deferred_message.mouse_coord_x,
+                    deferred_message.mouse_coord_y,
+                );
+                if deferred_message.raw_input_flags & MOUSE_MOVE_ABSOLUTE as u32 != 0 {
+                    self.accumulate_absolute_raw_mouse_move(deferred_message.raw_input_flags, x, y);
+                } else {
+                    let (delta_x, delta_y) = self.raw_mouse_delta.get();
+                    self.raw_mouse_delta.set((delta_x + x, delta_y + y));
+                    // The device went relative, so the next absolute sample starts over rather than jumping
+                    self.last_absolute_mouse_pos.set(None);
+                }
+                for (button, is_pressed) in
+                    input::get_raw_mouse_button_changes(deferred_message.raw_button_flags)
+                {
+                    self.events
+                        .push(PlatformEvent::RawMouseButton { button, is_pressed });
+                }
+            }
+            _ => {}
+        }
     }
 }
 
-fn get_monitor_size_from_edid(
-    h_dev_reg_key: RegKey,
-    out_width: &mut i32,
-    out_height: &mut i32,
-) -> bool {
-    for (name, value) in h_dev_reg_key.enum_values().map(|x| x.unwrap()) {
-        if &name[..] != "EDID" {
-            continue;
-        } else {
-            // EDID data format documented here:
-            // http://en.wikipedia.org/wiki/EDID
-
-            let detail_timing_descriptor_start_index: usize = 54;
-
-            *out_width = ((value.bytes[detail_timing_descriptor_start_index + 4] as i32 >> 4) << 8)
-                | value.bytes[detail_timing_descriptor_start_index + 2] as i32;
-            *out_height = ((value.bytes[detail_timing_descriptor_start_index + 7] as i32 >> 4)
-                << 8)
-                | value.bytes[detail_timing_descriptor_start_index + 5] as i32;
-
-            return true; // valid EDID found
+impl GenericApplication for WindowsApplication {
+    type Cursor = HCURSOR;
+    type Window = WindowsWindow;
+
+    fn set_message_handler(&mut self, in_message_handler: &Rc<dyn ApplicationMessageHandler>) {
+        self.message_handler = in_message_handler.clone();
+        self.xinput.set_message_handler(in_message_handler);
+    }
+    fn get_message_handler(&self) -> &Rc<dyn ApplicationMessageHandler> {
+        &self.message_handler
+    }
+    fn get_event_queue(&self) -> &PlatformEventQueue {
+        &self.events
+    }
+    fn get_timer_queue(&self) -> &TimerQueue {
+        &self.timers
+    }
+    fn get_modifier_keys(&self) -> ModifierKeysState {
+        self.modifier_keys.get()
+    }
+    fn get_initial_display_metrics(&self) -> DisplayMetrics {
+        self.initial_display_metrics.clone()
+    }
+    fn get_display_metrics(&self) -> DisplayMetrics {
+        self.display_metrics.borrow().clone()
+    }
+    fn create_proxy(&self) -> ApplicationProxy {
+        self.proxy_receiver.create_proxy()
+    }
+    fn get_proxy_window(&self, id: ProxyWindowId) -> Option<Rc<WindowsWindow>> {
+        self.proxy_receiver.get_window(id)
+    }
+    fn wait_for_events(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        if !self.events.is_empty()
+            || !self.deferred_messages.borrow().is_empty()
+            || self.proxy_receiver.has_pending_messages()
+            || self.timers.has_expired_timers()
+        {
+            return Ok(true);
+        }
+        let timeout = self.timers.get_wait_timeout(timeout);
+        // Rounded up, so the wait does not end just before the timeout and leave the caller to spin
+        let timeout_ms = timeout.map_or(INFINITE, |timeout| {
+            timeout
+                .as_micros()
+                .div_ceil(1000)
+                .min((INFINITE - 1) as u128) as u32
+        });
+        unsafe {
+            // MWMO_INPUTAVAILABLE returns at once for messages that are already queued but were not looked at yet.
+            // A proxy posts a thread message, which ends the wait like any other.
+            let result = MsgWaitForMultipleObjectsEx(
+                0,
+                ptr::null(),
+                timeout_ms,
+                QS_ALLINPUT,
+                MWMO_INPUTAVAILABLE,
+            );
+            if result == WAIT_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(result != WAIT_TIMEOUT.0 || self.timers.has_expired_timers())
         }
     }
+    fn poll_game_device_state(&self, _time_delta: f32) {
+        self.xinput.send_controller_events();
+    }
+    fn is_gamepad_attached(&self) -> bool {
+        self.xinput.is_gamepad_attached()
+    }
+    fn poll_events(&self, _time_delta: f32) -> io::Result<()> {
+        self.process_proxy_messages();
+        unsafe {
+            let mut message: MSG = mem::zeroed();
+
+            // standard Windows message handling
+            while PeekMessageW(&mut message, HWND(0), 0, 0, PM_REMOVE).0 != 0 {
+                TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+        }
+        self.process_deferred_messages();
+        Ok(())
+    }
+}
 
-    false
+/** Reads and parses the EDID Windows keeps in the monitor's device registry key */
+fn get_edid_from_registry(h_dev_reg_key: RegKey) -> Option<Edid> {
+    let value = h_dev_reg_key.get_raw_value("EDID").ok()?;
+    Edid::parse(&value.bytes).ok()
 }
 
-fn get_size_for_dev_id(target_dev_id: &String, width: &mut i32, height: &mut i32) -> bool {
+fn get_edid_for_dev_id(target_dev_id: &String) -> Option<Edid> {
     unsafe {
         let dev_info = SetupDiGetClassDevsExW(
             Some(&GUID_DEVCLASS_MONITOR), //class GUID
@@ -1066,14 +1608,14 @@ fn get_size_for_dev_id(target_dev_id: &String, width: &mut i32, height: &mut i32
         );
 
         if dev_info.is_err() {
-            return false;
+            return None;
         }
         let dev_info = dev_info.unwrap();
         if dev_info.is_invalid() {
-            return false;
+            return None;
         }
 
-        let mut res = false;
+        let mut res = None;
         let mut monitor_index = 0;
         loop {
             let err = Error::last_os_error();
@@ -1100,11 +1642,7 @@ fn get_size_for_dev_id(target_dev_id: &String, width: &mut i32, height: &mut i32
                         );
 
                         if h_dev_reg_key != 0 && h_dev_reg_key != INVALID_HANDLE_VALUE.0 {
-                            res = get_monitor_size_from_edid(
-                                RegKey::predef(h_dev_reg_key as *mut _),
-                                width,
-                                height,
-                            );
+                            res = get_edid_from_registry(RegKey::predef(h_dev_reg_key as *mut _));
                             //advapi32::RegCloseKey(h_dev_reg_key);
                             break;
                         }
@@ -1115,20 +1653,83 @@ fn get_size_for_dev_id(target_dev_id: &String, width: &mut i32, height: &mut i32
         }
 
         if SetupDiDestroyDeviceInfoList(dev_info).0 == 0 {
-            res = false;
+            res = None;
         }
 
         res
     }
 }
 
+/**
+ * Fills in where a display adapter's output sits on the desktop, its refresh rate and rotation from its current
+ * display settings, then the work area and DPI of the HMONITOR that covers it.
+ */
+unsafe fn get_monitor_placement(adapter_name: PCWSTR, info: &mut MonitorInfo) {
+    let mut dev_mode = DEVMODEW::default();
+    dev_mode.dmSize = mem::size_of::<DEVMODEW>() as u16;
+    if EnumDisplaySettingsExW(
+        adapter_name,
+        ENUM_CURRENT_SETTINGS,
+        &mut dev_mode,
+        ENUM_DISPLAY_SETTINGS_FLAGS(0),
+    )
+    .0 == 0
+    {
+        return;
+    }
+    let position: POINTL = dev_mode.Anonymous1.Anonymous2.dmPosition;
+    info.display_rect = PlatformRect::new(
+        position.x,
+        position.y,
+        position.x + dev_mode.dmPelsWidth as i32,
+        position.y + dev_mode.dmPelsHeight as i32,
+    );
+    // 0 and 1 both mean "whatever the hardware defaults to"
+    if dev_mode.dmDisplayFrequency > 1 {
+        info.refresh_rate = dev_mode.dmDisplayFrequency as f32;
+    }
+    info.rotation = match dev_mode.Anonymous1.Anonymous2.dmDisplayOrientation {
+        DMDO_90 => DisplayRotation::Rotate90,
+        DMDO_180 => DisplayRotation::Rotate180,
+        DMDO_270 => DisplayRotation::Rotate270,
+        _ => DisplayRotation::Rotate0,
+    };
+
+    let display_rect = RECT {
+        left: info.display_rect.left,
+        top: info.display_rect.top,
+        right: info.display_rect.right,
+        bottom: info.display_rect.bottom,
+    };
+    let monitor = MonitorFromRect(&display_rect, MONITOR_DEFAULTTONEAREST);
+    let mut monitor_info = MONITORINFO::default();
+    monitor_info.cbSize = mem::size_of::<MONITORINFO>() as u32;
+    info.work_area = if GetMonitorInfoW(monitor, &mut monitor_info).0 != 0 {
+        PlatformRect::new(
+            monitor_info.rcWork.left,
+            monitor_info.rcWork.top,
+            monitor_info.rcWork.right,
+            monitor_info.rcWork.bottom,
+        )
+    } else {
+        info.display_rect
+    };
+
+    let mut dpi_x = 0;
+    let mut dpi_y = 0;
+    info.dpi = if GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y).is_ok() {
+        dpi_x as i32
+    } else {
+        DEFAULT_DPI
+    };
+}
+
 fn get_monitor_info(out_monitor_info: &mut Vec<MonitorInfo>) {
     unsafe {
         let mut display_device = DISPLAY_DEVICEW::default();
         display_device.cb = mem::size_of::<DISPLAY_DEVICEW>() as u32;
         let mut device_index = 0; // device index
 
-        let mut primary_device: *mut MonitorInfo = ptr::null_mut();
         out_monitor_info.reserve(2); // Reserve two slots, as that will be the most common maximum
 
         while EnumDisplayDevicesW(PCWSTR::null(), device_index, &mut display_device, 0).0 != 0 {
@@ -1153,25 +1754,22 @@ fn get_monitor_info(out_monitor_info: &mut Vec<MonitorInfo>) {
                         let temp_str = String::from_utf16_lossy(&monitor.DeviceID[..]);
                         let idx = &temp_str[9..].find("\\").unwrap();
                         info.name += &temp_str[8..9 + *idx];
-                        info.id = temp_str;
                         //info.name = info.id.Mid (8, Info.ID.Find (TEXT("\\"), ESearchCase::CaseSensitive, ESearchDir::FromStart, 9) - 8);
+                        info.id = temp_str.trim_end_matches('\0').to_string();
+                        info.is_primary =
+                            (display_device.StateFlags & DISPLAY_DEVICE_PRIMARY_DEVICE) > 0;
 
-                        if get_size_for_dev_id(
-                            &info.name,
-                            &mut info.native_width,
-                            &mut info.native_height,
-                        ) {
-                            let temp_str = String::from_utf16_lossy(&monitor.DeviceID[..]);
-                            info.id = temp_str.trim_end_matches('\0').to_string();
-                            info.is_primary =
-                                (display_device.StateFlags & DISPLAY_DEVICE_PRIMARY_DEVICE) > 0;
-                            out_monitor_info.push(info);
-
-                            let len = out_monitor_info.len();
-                            if primary_device.is_null() && out_monitor_info[len - 1].is_primary {
-                                primary_device = &mut out_monitor_info[len - 1];
-                            }
+                        get_monitor_placement(
+                            PCWSTR(display_device.DeviceName.as_ptr()),
+                            &mut info,
+                        );
+                        // Without an EDID the current mode is the best guess at the native one
+                        info.native_width = info.display_rect.get_width();
+                        info.native_height = info.display_rect.get_height();
+                        if let Some(edid) = get_edid_for_dev_id(&info.name) {
+                            info.apply_edid(&edid);
                         }
+                        out_monitor_info.push(info);
                     }
                     monitor_index += 1;
 
@@ -1187,89 +1785,46 @@ fn get_monitor_info(out_monitor_info: &mut Vec<MonitorInfo>) {
     }
 }
 
-//TODO this struct has cross-platform applications, so it shouldn't be implemented within the Windows-specific files.
-#[derive(PartialEq, Debug, Clone, Default)]
-pub struct DisplayMetrics {
-    primary_display_width: i32,
-    primary_display_height: i32,
-    monitor_info: Vec<MonitorInfo>,
-    pub primary_display_work_area_rect: PlatformRect,
-    pub virtual_display_rect: PlatformRect,
-    //TODO: The following should be a Vector2D
-    title_safe_padding_size: (f32, f32),
-    //TODO: The following should be a Vector2D
-    action_safe_padding_size: (f32, f32),
-}
-
-impl DisplayMetrics {
-    pub fn new() -> DisplayMetrics {
-        unsafe {
-            let mut out_display_metrics = DisplayMetrics::default();
-            // Total screen size of the primary monitor
-            out_display_metrics.primary_display_width = GetSystemMetrics(SM_CXSCREEN);
-            out_display_metrics.primary_display_height = GetSystemMetrics(SM_CYSCREEN);
-
-            // Get the screen rect of the primary monitor, excluding taskbar etc.
-            let mut work_area_rect: RECT = mem::zeroed();
-            if SystemParametersInfoW(
-                SPI_GETWORKAREA,
-                0,
-                Some(&mut work_area_rect as *mut RECT as *mut _),
-                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-            )
-            .0 == 0
-            {
-                work_area_rect.top = 0;
-                work_area_rect.bottom = 0;
-                work_area_rect.left = 0;
-                work_area_rect.right = 0;
-            }
-
-            out_display_metrics.primary_display_work_area_rect.left = work_area_rect.left;
-            out_display_metrics.primary_display_work_area_rect.top = work_area_rect.top;
-            out_display_metrics.primary_display_work_area_rect.right = work_area_rect.right;
-            out_display_metrics.primary_display_work_area_rect.bottom = work_area_rect.bottom;
-
-            // Virtual desktop area
-            out_display_metrics.virtual_display_rect.left = GetSystemMetrics(SM_XVIRTUALSCREEN);
-            out_display_metrics.virtual_display_rect.top = GetSystemMetrics(SM_YVIRTUALSCREEN);
-            out_display_metrics.virtual_display_rect.right =
-                out_display_metrics.virtual_display_rect.left
-                    + GetSystemMetrics(SM_CXVIRTUALSCREEN);
-            out_display_metrics.virtual_display_rect.bottom =
-                out_display_metrics.virtual_display_rect.top + GetSystemMetrics(SM_CYVIRTUALSCREEN);
-
-            // Get connected monitor information
-            get_monitor_info(&mut out_display_metrics.monitor_info);
-
-            // Apply the debug safe zones
-            out_display_metrics.apply_default_safe_zones();
-            out_display_metrics
-        }
-    }
-    pub fn get_debug_title_safe_zone_ratio(&self) -> f32 {
-        unsafe { DEBUG_SAFE_ZONE_RATIO }
-    }
-    pub fn get_debug_action_safe_zone_ratio(&self) -> f32 {
-        unsafe { DEBUG_ACTION_ZONE_RATIO }
-    }
-    pub fn apply_default_safe_zones(&mut self) {
-        let safe_zone_ratio = self.get_debug_title_safe_zone_ratio();
-        if safe_zone_ratio < 1.0 {
-            let half_unsafe_ratio = (1.0 - safe_zone_ratio) * 0.5;
-            self.title_safe_padding_size = (
-                self.primary_display_width as f32 * half_unsafe_ratio,
-                self.primary_display_height as f32 * half_unsafe_ratio,
-            );
+pub fn rebuild_display_metrics(out_display_metrics: &mut DisplayMetrics) {
+    unsafe {
+        // Total screen size of the primary monitor
+        out_display_metrics.primary_display_width = GetSystemMetrics(SM_CXSCREEN);
+        out_display_metrics.primary_display_height = GetSystemMetrics(SM_CYSCREEN);
+
+        // Get the screen rect of the primary monitor, excluding taskbar etc.
+        let mut work_area_rect: RECT = mem::zeroed();
+        if SystemParametersInfoW(
+            SPI_GETWORKAREA,
+            0,
+            Some(&mut work_area_rect as *mut RECT as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+        .0 == 0
+        {
+            work_area_rect.top = 0;
+            work_area_rect.bottom = 0;
+            work_area_rect.left = 0;
+            work_area_rect.right = 0;
         }
 
-        let action_safe_zone_ratio = self.get_debug_action_safe_zone_ratio();
-        if action_safe_zone_ratio < 1.0 {
-            let half_unsafe_ratio = (1.0 - action_safe_zone_ratio) * 0.5;
-            self.action_safe_padding_size = (
-                self.primary_display_width as f32 * half_unsafe_ratio,
-                self.primary_display_height as f32 * half_unsafe_ratio,
-            );
-        }
+        out_display_metrics.primary_display_work_area_rect.left = work_area_rect.left;
+        out_display_metrics.primary_display_work_area_rect.top = work_area_rect.top;
+        out_display_metrics.primary_display_work_area_rect.right = work_area_rect.right;
+        out_display_metrics.primary_display_work_area_rect.bottom = work_area_rect.bottom;
+
+        // Virtual desktop area
+        out_display_metrics.virtual_display_rect.left = GetSystemMetrics(SM_XVIRTUALSCREEN);
+        out_display_metrics.virtual_display_rect.top = GetSystemMetrics(SM_YVIRTUALSCREEN);
+        out_display_metrics.virtual_display_rect.right =
+            out_display_metrics.virtual_display_rect.left + GetSystemMetrics(SM_CXVIRTUALSCREEN);
+        out_display_metrics.virtual_display_rect.bottom =
+            out_display_metrics.virtual_display_rect.top + GetSystemMetrics(SM_CYVIRTUALSCREEN);
+
+        // Get connected monitor information
+        out_display_metrics.monitor_info.clear();
+        get_monitor_info(&mut out_display_metrics.monitor_info);
+
+        // Apply the debug safe zones
+        out_display_metrics.apply_default_safe_zones();
     }
 }
@@ -0,0 +1,374 @@
+use crate::generic::application_message_handler::MouseButtons;
+use crate::generic::input::Key;
+use crate::generic::window_sizing::ResizeEdge;
+use windows::Win32::{
+    Foundation::{LPARAM, POINT, WPARAM},
+    UI::Input::KeyboardAndMouse::{
+        MapVirtualKeyW, MAPVK_VK_TO_CHAR, VIRTUAL_KEY, VK_ADD, VK_APPS, VK_BACK, VK_CAPITAL,
+        VK_CONTROL, VK_DECIMAL, VK_DELETE, VK_DIVIDE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_F10,
+        VK_F11, VK_F12, VK_F2, VK_F3, VK_F4, VK_F5, VK_F6, VK_F7, VK_F8, VK_F9, VK_HOME, VK_INSERT,
+        VK_LCONTROL, VK_LEFT, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MENU, VK_MULTIPLY, VK_NEXT,
+        VK_NUMLOCK, VK_NUMPAD0, VK_NUMPAD1, VK_NUMPAD2, VK_NUMPAD3, VK_NUMPAD4, VK_NUMPAD5,
+        VK_NUMPAD6, VK_NUMPAD7, VK_NUMPAD8, VK_NUMPAD9, VK_OEM_1, VK_OEM_102, VK_OEM_2, VK_OEM_3,
+        VK_OEM_4, VK_OEM_5, VK_OEM_6, VK_OEM_7, VK_OEM_COMMA, VK_OEM_MINUS, VK_OEM_PERIOD,
+        VK_OEM_PLUS, VK_PAUSE, VK_PRIOR, VK_RCONTROL, VK_RETURN, VK_RIGHT, VK_RMENU, VK_RSHIFT,
+        VK_RWIN, VK_SCROLL, VK_SHIFT, VK_SNAPSHOT, VK_SPACE, VK_SUBTRACT, VK_TAB, VK_UP,
+    },
+    UI::WindowsAndMessaging::{
+        WHEEL_DELTA, WMSZ_BOTTOM, WMSZ_BOTTOMLEFT, WMSZ_BOTTOMRIGHT, WMSZ_LEFT, WMSZ_RIGHT,
+        WMSZ_TOP, WMSZ_TOPLEFT, WMSZ_TOPRIGHT, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MBUTTONDBLCLK, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_RBUTTONDBLCLK, WM_RBUTTONDOWN,
+        WM_RBUTTONUP, WM_XBUTTONDBLCLK, WM_XBUTTONDOWN, WM_XBUTTONUP,
+    },
+};
+
+/** Bit 24 of a key message's lparam is set for the keys sent with an 0xE0 prefix */
+const KF_EXTENDED_BIT: isize = 1 << 24;
+/** Bit 30 of a key message's lparam is set if the key was already down */
+const KF_REPEAT_BIT: isize = 1 << 30;
+/** The RI_MOUSE_*_DOWN and RI_MOUSE_*_UP bits of RAWMOUSE.usButtonFlags, from winuser.h */
+const RAW_MOUSE_BUTTON_FLAGS: [(u16, MouseButtons, bool); 10] = [
+    (0x0001, MouseButtons::Left, true),
+    (0x0002, MouseButtons::Left, false),
+    (0x0004, MouseButtons::Right, true),
+    (0x0008, MouseButtons::Right, false),
+    (0x0010, MouseButtons::Middle, true),
+    (0x0020, MouseButtons::Middle, false),
+    (0x0040, MouseButtons::Thumb01, true),
+    (0x0080, MouseButtons::Thumb01, false),
+    (0x0100, MouseButtons::Thumb02, true),
+    (0x0200, MouseButtons::Thumb02, false),
+];
+/** The high word of an XBUTTON message's wparam is XBUTTON1 for the back button and XBUTTON2 for forward */
+const XBUTTON1: u16 = 0x0001;
+
+/**
+ * Virtual keys of the keys a scan code may be missing for, such as input injected with SendInput.
+ * The letters and digits have no VK_ constants, their virtual keys are their ASCII codes.
+ */
+const VIRTUAL_KEYS: [(VIRTUAL_KEY, Key); 107] = [
+    (VK_ESCAPE, Key::Escape),
+    (VK_F1, Key::F1),
+    (VK_F2, Key::F2),
+    (VK_F3, Key::F3),
+    (VK_F4, Key::F4),
+    (VK_F5, Key::F5),
+    (VK_F6, Key::F6),
+    (VK_F7, Key::F7),
+    (VK_F8, Key::F8),
+    (VK_F9, Key::F9),
+    (VK_F10, Key::F10),
+    (VK_F11, Key::F11),
+    (VK_F12, Key::F12),
+    (VK_SNAPSHOT, Key::PrintScreen),
+    (VK_SCROLL, Key::ScrollLock),
+    (VK_PAUSE, Key::Pause),
+    (VK_OEM_3, Key::Backquote),
+    (VIRTUAL_KEY(b'1' as u16), Key::Digit1),
+    (VIRTUAL_KEY(b'2' as u16), Key::Digit2),
+    (VIRTUAL_KEY(b'3' as u16), Key::Digit3),
+    (VIRTUAL_KEY(b'4' as u16), Key::Digit4),
+    (VIRTUAL_KEY(b'5' as u16), Key::Digit5),
+    (VIRTUAL_KEY(b'6' as u16), Key::Digit6),
+    (VIRTUAL_KEY(b'7' as u16), Key::Digit7),
+    (VIRTUAL_KEY(b'8' as u16), Key::Digit8),
+    (VIRTUAL_KEY(b'9' as u16), Key::Digit9),
+    (VIRTUAL_KEY(b'0' as u16), Key::Digit0),
+    (VK_OEM_MINUS, Key::Minus),
+    (VK_OEM_PLUS, Key::Equal),
+    (VK_BACK, Key::Backspace),
+    (VK_TAB, Key::Tab),
+    (VIRTUAL_KEY(b'Q' as u16), Key::KeyQ),
+    (VIRTUAL_KEY(b'W' as u16), Key::KeyW),
+    (VIRTUAL_KEY(b'E' as u16), Key::KeyE),
+    (VIRTUAL_KEY(b'R' as u16), Key::KeyR),
+    (VIRTUAL_KEY(b'T' as u16), Key::KeyT),
+    (VIRTUAL_KEY(b'Y' as u16), Key::KeyY),
+    (VIRTUAL_KEY(b'U' as u16), Key::KeyU),
+    (VIRTUAL_KEY(b'I' as u16), Key::KeyI),
+    (VIRTUAL_KEY(b'O' as u16), Key::KeyO),
+    (VIRTUAL_KEY(b'P' as u16), Key::KeyP),
+    (VK_OEM_4, Key::BracketLeft),
+    (VK_OEM_6, Key::BracketRight),
+    (VK_OEM_5, Key::Backslash),
+    (VK_CAPITAL, Key::CapsLock),
+    (VIRTUAL_KEY(b'A' as u16), Key::KeyA),
+    (VIRTUAL_KEY(b'S' as u16), Key::KeyS),
+    (VIRTUAL_KEY(b'D' as u16), Key::KeyD),
+    (VIRTUAL_KEY(b'F' as u16), Key::KeyF),
+    (VIRTUAL_KEY(b'G' as u16), Key::KeyG),
+    (VIRTUAL_KEY(b'H' as u16), Key::KeyH),
+    (VIRTUAL_KEY(b'J' as u16), Key::KeyJ),
+    (VIRTUAL_KEY(b'K' as u16), Key::KeyK),
+    (VIRTUAL_KEY(b'L' as u16), Key::KeyL),
+    (VK_OEM_1, Key::Semicolon),
+    (VK_OEM_7, Key::Quote),
+    (VK_RETURN, Key::Enter),
+    (VK_SHIFT, Key::ShiftLeft),
+    (VK_LSHIFT, Key::ShiftLeft),
+    (VK_OEM_102, Key::IntlBackslash),
+    (VIRTUAL_KEY(b'Z' as u16), Key::KeyZ),
+    (VIRTUAL_KEY(b'X' as u16), Key::KeyX),
+    (VIRTUAL_KEY(b'C' as u16), Key::KeyC),
+    (VIRTUAL_KEY(b'V' as u16), Key::KeyV),
+    (VIRTUAL_KEY(b'B' as u16), Key::KeyB),
+    (VIRTUAL_KEY(b'N' as u16), Key::KeyN),
+    (VIRTUAL_KEY(b'M' as u16), Key::KeyM),
+    (VK_OEM_COMMA, Key::Comma),
+    (VK_OEM_PERIOD, Key::Period),
+    (VK_OEM_2, Key::Slash),
+    (VK_RSHIFT, Key::ShiftRight),
+    (VK_CONTROL, Key::ControlLeft),
+    (VK_LCONTROL, Key::ControlLeft),
+    (VK_LWIN, Key::MetaLeft),
+    (VK_MENU, Key::AltLeft),
+    (VK_LMENU, Key::AltLeft),
+    (VK_SPACE, Key::Space),
+    (VK_RMENU, Key::AltRight),
+    (VK_RWIN, Key::MetaRight),
+    (VK_APPS, Key::ContextMenu),
+    (VK_RCONTROL, Key::ControlRight),
+    (VK_INSERT, Key::Insert),
+    (VK_DELETE, Key::Delete),
+    (VK_HOME, Key::Home),
+    (VK_END, Key::End),
+    (VK_PRIOR, Key::PageUp),
+    (VK_NEXT, Key::PageDown),
+    (VK_UP, Key::ArrowUp),
+    (VK_DOWN, Key::ArrowDown),
+    (VK_LEFT, Key::ArrowLeft),
+    (VK_RIGHT, Key::ArrowRight),
+    (VK_NUMLOCK, Key::NumLock),
+    (VK_DIVIDE, Key::NumpadDivide),
+    (VK_MULTIPLY, Key::NumpadMultiply),
+    (VK_SUBTRACT, Key::NumpadSubtract),
+    (VK_ADD, Key::NumpadAdd),
+    (VK_DECIMAL, Key::NumpadDecimal),
+    (VK_NUMPAD0, Key::Numpad0),
+    (VK_NUMPAD1, Key::Numpad1),
+    (VK_NUMPAD2, Key::Numpad2),
+    (VK_NUMPAD3, Key::Numpad3),
+    (VK_NUMPAD4, Key::Numpad4),
+    (VK_NUMPAD5, Key::Numpad5),
+    (VK_NUMPAD6, Key::Numpad6),
+    (VK_NUMPAD7, Key::Numpad7),
+    (VK_NUMPAD8, Key::Numpad8),
+    (VK_NUMPAD9, Key::Numpad9),
+];
+
+pub fn key_from_virtual_key(virtual_key: VIRTUAL_KEY) -> Key {
+    VIRTUAL_KEYS
+        .iter()
+        .find(|(vk, _)| *vk == virtual_key)
+        .map_or(Key::Unknown, |&(_, key)| key)
+}
+
+/**
+ * Works out the physical key of a WM_KEYDOWN/WM_KEYUP from the scan code in its lparam.
+ * The virtual key is only used when there is no scan code to go by.
+ */
+pub fn translate_key(virtual_key: VIRTUAL_KEY, lparam: LPARAM) -> Key {
+    // Windows swaps these two: Pause comes with NumLock's scan code, and NumLock comes as an extended key
+    if virtual_key == VK_PAUSE {
+        return Key::Pause;
+    }
+    if virtual_key == VK_NUMLOCK {
+        return Key::NumLock;
+    }
+
+    let scan_code = ((lparam.0 >> 16) & 0xFF) as u32;
+    if scan_code != 0 {
+        let key = if lparam.0 & KF_EXTENDED_BIT != 0 {
+            Key::from_scan_code(0xE000 | scan_code)
+        } else {
+            Key::from_scan_code(scan_code)
+        };
+        if key != Key::Unknown {
+            return key;
+        }
+    }
+    key_from_virtual_key(virtual_key)
+}
+
+/** Whether a WM_KEYDOWN or WM_CHAR was generated by the keyboard's auto-repeat */
+pub fn is_repeat(lparam: LPARAM) -> bool {
+    lparam.0 & KF_REPEAT_BIT != 0
+}
+
+/**
+ * Turns the UTF-16 code unit of a WM_CHAR into a character. Characters outside the Basic Multilingual Plane come as
+ * two WM_CHARs, one per surrogate, so a high surrogate waits in pending_high_surrogate for the low one. A surrogate
+ * without its other half is dropped, but the code unit that broke the pair is still decoded.
+ */
+pub fn decode_wm_char(pending_high_surrogate: &mut Option<u16>, code_unit: u16) -> Option<char> {
+    match code_unit {
+        0xD800..=0xDBFF => {
+            *pending_high_surrogate = Some(code_unit);
+            None
+        }
+        0xDC00..=0xDFFF => {
+            let high_surrogate = pending_high_surrogate.take()?;
+            char::decode_utf16([high_surrogate, code_unit]).next()?.ok()
+        }
+        _ => {
+            *pending_high_surrogate = None;
+            char::from_u32(code_unit as u32)
+        }
+    }
+}
+
+/** The unshifted character the key types on the current layout, or 0 if it types none */
+pub fn get_character_code(virtual_key: VIRTUAL_KEY) -> u32 {
+    unsafe { MapVirtualKeyW(virtual_key.0 as u32, MAPVK_VK_TO_CHAR) }
+}
+
+/** The button a client-area WM_*BUTTONDOWN, WM_*BUTTONUP or WM_*BUTTONDBLCLK is about */
+pub fn translate_mouse_button(msg: u32, wparam: WPARAM) -> MouseButtons {
+    match msg {
+        WM_LBUTTONDOWN | WM_LBUTTONUP | WM_LBUTTONDBLCLK => MouseButtons::Left,
+        WM_MBUTTONDOWN | WM_MBUTTONUP | WM_MBUTTONDBLCLK => MouseButtons::Middle,
+        WM_RBUTTONDOWN | WM_RBUTTONUP | WM_RBUTTONDBLCLK => MouseButtons::Right,
+        WM_XBUTTONDOWN | WM_XBUTTONUP | WM_XBUTTONDBLCLK => {
+            if (wparam.0 >> 16) as u16 == XBUTTON1 {
+                MouseButtons::Thumb01
+            } else {
+                MouseButtons::Thumb02
+            }
+        }
+        _ => MouseButtons::Invalid,
+    }
+}
+
+/**
+ * The position packed into a mouse message's lparam, like GET_X_LPARAM/GET_Y_LPARAM.
+ * The coordinates are signed, they are negative left of and above the window or the primary monitor.
+ */
+pub fn get_cursor_pos(lparam: LPARAM) -> POINT {
+    POINT {
+        x: (lparam.0 & 0xFFFF) as i16 as i32,
+        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+    }
+}
+
+/** The edge or corner being dragged according to a WM_SIZING wparam, None for anything else */
+pub fn get_resize_edge(wparam: WPARAM) -> Option<ResizeEdge> {
+    match wparam.0 as u32 {
+        WMSZ_LEFT => Some(ResizeEdge::Left),
+        WMSZ_RIGHT => Some(ResizeEdge::Right),
+        WMSZ_TOP => Some(ResizeEdge::Top),
+        WMSZ_BOTTOM => Some(ResizeEdge::Bottom),
+        WMSZ_TOPLEFT => Some(ResizeEdge::TopLeft),
+        WMSZ_TOPRIGHT => Some(ResizeEdge::TopRight),
+        WMSZ_BOTTOMLEFT => Some(ResizeEdge::BottomLeft),
+        WMSZ_BOTTOMRIGHT => Some(ResizeEdge::BottomRight),
+        _ => None,
+    }
+}
+
+/** How many notches a WM_MOUSEWHEEL or WM_MOUSEHWHEEL turned. Smooth-scrolling devices send fractions of one. */
+pub fn get_wheel_delta(wparam: WPARAM) -> f32 {
+    ((wparam.0 >> 16) & 0xFFFF) as u16 as i16 as f32 / WHEEL_DELTA as f32
+}
+
+/** The buttons that went down (true) or up (false) according to RAWMOUSE.usButtonFlags */
+pub fn get_raw_mouse_button_changes(
+    button_flags: u16,
+) -> impl Iterator<Item = (MouseButtons, bool)> {
+    RAW_MOUSE_BUTTON_FLAGS
+        .iter()
+        .filter(move |(flag, _, _)| button_flags & flag != 0)
+        .map(|&(_, button, is_pressed)| (button, is_pressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(code_units: &[u16]) -> String {
+        let mut pending_high_surrogate = None;
+        code_units
+            .iter()
+            .filter_map(|&code_unit| decode_wm_char(&mut pending_high_surrogate, code_unit))
+            .collect()
+    }
+
+    #[test]
+    fn surrogate_pairs_are_joined() {
+        // U+1F600 is D83D DE00 in UTF-16
+        assert_eq!(decode_all(&[0x61, 0xD83D, 0xDE00, 0x62]), "a\u{1F600}b");
+    }
+
+    #[test]
+    fn a_high_surrogate_without_its_low_half_does_not_swallow_the_next_character() {
+        assert_eq!(decode_all(&[0xD83D, 0x61]), "a");
+        assert_eq!(decode_all(&[0xD83D, 0xD83D, 0xDE00]), "\u{1F600}");
+    }
+
+    #[test]
+    fn a_low_surrogate_without_a_high_one_is_dropped() {
+        assert_eq!(decode_all(&[0xDE00, 0x61]), "a");
+        assert_eq!(decode_all(&[0xD83D, 0x61, 0xDE00]), "a");
+    }
+
+    /** The lparam of a WM_KEYDOWN for a key with the given scan code, with or without the 0xE0 prefix */
+    fn make_key_lparam(scan_code: isize, is_extended: bool) -> LPARAM {
+        let extended_bit = if is_extended { KF_EXTENDED_BIT } else { 0 };
+        LPARAM(1 | (scan_code << 16) | extended_bit)
+    }
+
+    #[test]
+    fn extended_keys_are_told_apart_by_the_extended_bit() {
+        for (virtual_key, scan_code, is_extended, expected_key) in [
+            (VK_CONTROL, 0x1D, false, Key::ControlLeft),
+            (VK_CONTROL, 0x1D, true, Key::ControlRight),
+            (VK_MENU, 0x38, false, Key::AltLeft),
+            (VK_MENU, 0x38, true, Key::AltRight),
+            (VK_RETURN, 0x1C, false, Key::Enter),
+            (VK_RETURN, 0x1C, true, Key::NumpadEnter),
+            (VK_UP, 0x48, true, Key::ArrowUp),
+            (VK_LEFT, 0x4B, true, Key::ArrowLeft),
+            (VK_RIGHT, 0x4D, true, Key::ArrowRight),
+            (VK_DOWN, 0x50, true, Key::ArrowDown),
+            // With NumLock off the keypad sends arrow virtual keys, but it is still the keypad
+            (VK_UP, 0x48, false, Key::Numpad8),
+            (VK_DELETE, 0x53, false, Key::NumpadDecimal),
+        ] {
+            assert_eq!(
+                translate_key(virtual_key, make_key_lparam(scan_code, is_extended)),
+                expected_key,
+                "{:#X} extended {}",
+                scan_code,
+                is_extended
+            );
+        }
+    }
+
+    #[test]
+    fn pause_and_num_lock_go_by_the_virtual_key() {
+        // Pause arrives with NumLock's scan code and NumLock arrives as an extended key
+        assert_eq!(
+            translate_key(VK_PAUSE, make_key_lparam(0x45, false)),
+            Key::Pause
+        );
+        assert_eq!(
+            translate_key(VK_NUMLOCK, make_key_lparam(0x45, true)),
+            Key::NumLock
+        );
+        assert_eq!(translate_key(VK_PAUSE, LPARAM(0)), Key::Pause);
+    }
+
+    #[test]
+    fn virtual_key_is_used_when_the_scan_code_does_not_decide() {
+        assert_eq!(translate_key(VK_F5, LPARAM(1)), Key::F5);
+        assert_eq!(
+            translate_key(VK_RCONTROL, make_key_lparam(0, true)),
+            Key::ControlRight
+        );
+        // A scan code no key of the table has
+        assert_eq!(
+            translate_key(VIRTUAL_KEY(b'A' as u16), make_key_lparam(0x70, false)),
+            Key::KeyA
+        );
+        assert_eq!(translate_key(VIRTUAL_KEY(0xFF), LPARAM(1)), Key::Unknown);
+    }
+}
@@ -4,11 +4,12 @@ use std::{io, process};
 pub mod application;
 pub mod cursor;
 pub mod dialog;
+pub mod input;
 #[macro_use]
 pub mod macros;
 pub mod utils;
 pub mod window;
-pub mod xinputinterface;
+pub mod xinput;
 
 /*static WINDOWS_MESSAGE_STRINGS: Lazy<Mutex<BTreeMap<u32, &'static str>>> = Lazy::new(|| {
     let mut result: BTreeMap<u32, &'static str> = BTreeMap::new();
@@ -1,6 +1,6 @@
 use std::ffi::OsStr;
+use std::io;
 use std::os::windows::ffi::OsStrExt;
-use std::{io, mem};
 use windows::Win32::{
     Foundation::{GetLastError, SetLastError, HWND, WIN32_ERROR},
     UI::WindowsAndMessaging::{
@@ -29,16 +29,6 @@ pub fn other_error<T>(msg: &str) -> io::Result<T> {
     Err(io::Error::new(io::ErrorKind::Other, msg))
 }
 
-pub fn leak<T>(v: T) -> &'static T {
-    unsafe {
-        println!("Inside utils::leak");
-        let b = Box::new(v);
-        let p: *const T = &*b;
-        mem::forget(b); // leak our reference, so that `b` is never freed
-        &*p
-    }
-}
-
 pub unsafe fn get_window_long_ptr<T>(wnd: HWND, index: i32) -> io::Result<*const T> {
     #[cfg(target_pointer_width = "32")]
     use GetWindowLongW as GetWindowLongPtr;
@@ -4,20 +4,17 @@ use crate::{
         window_definition::{
             WindowActivationPolicy, WindowDefinition, WindowTransparency, WindowType,
         },
+        window_hierarchy::WindowHierarchy,
     },
-    windows::{
-        application::{WindowsApplication, WINDOWS_APPLICATION},
-        utils::ToWide,
-    },
+    windows::{application::WindowsApplication, utils::ToWide},
 };
 use std::{
-    borrow::{Borrow, BorrowMut},
+    borrow::Borrow,
     cell::{Cell, RefCell},
     cmp, fmt, io, mem,
     os::raw::c_void,
     ptr,
-    rc::Rc,
-    sync::{Arc, Weak},
+    rc::{Rc, Weak},
 };
 use windows::{
     core::PCWSTR,
@@ -70,10 +67,10 @@ pub struct WindowsWindow {
     hwnd: Cell<HWND>,
     region_height: Cell<i32>,
     region_width: Cell<i32>,
-    window_mode: WindowMode,
+    window_mode: Cell<WindowMode>,
     ole_reference_count: u32,
-    pre_fullscreen_window_placement: WINDOWPLACEMENT,
-    pre_parent_minimized_window_placement: WINDOWPLACEMENT,
+    pre_fullscreen_window_placement: RefCell<WINDOWPLACEMENT>,
+    pre_parent_minimized_window_placement: RefCell<WINDOWPLACEMENT>,
     virtual_height: Cell<i32>,
     virtual_width: Cell<i32>,
     aspect_ratio: Cell<f32>,
@@ -81,15 +78,18 @@ pub struct WindowsWindow {
     is_first_time_visible: Cell<bool>,
     initially_minimized: Cell<bool>,
     initially_maximized: Cell<bool>,
-    dpi_scale_factor: f32,
-    handle_manual_dpi_changes: bool,
+    dpi_scale_factor: Cell<f32>,
+    handle_manual_dpi_changes: Cell<bool>,
     window_definitions: Rc<WindowDefinition>,
+    hierarchy: WindowHierarchy,
 }
 
 impl fmt::Debug for WindowsWindow {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let pre_fullscreen_window_placement = format!("WINDOWPLACEMENT {{ length: {}, flags: {}, showCmd: {}, ptMinPosition: POINT {{ x: {}, y: {} }}, ptMaxPosition: POINT {{ x: {}, y: {} }}, rcNormalPosition: RECT {{ left: {}, top: {}, right: {}, bottom: {} }} }}", &self.pre_fullscreen_window_placement.length, &self.pre_fullscreen_window_placement.flags.0, &self.pre_fullscreen_window_placement.showCmd.0, &self.pre_fullscreen_window_placement.ptMinPosition.x, &self.pre_fullscreen_window_placement.ptMinPosition.y, &self.pre_fullscreen_window_placement.ptMaxPosition.x, &self.pre_fullscreen_window_placement.ptMaxPosition.y, &self.pre_fullscreen_window_placement.rcNormalPosition.left, &self.pre_fullscreen_window_placement.rcNormalPosition.top, &self.pre_fullscreen_window_placement.rcNormalPosition.right, &self.pre_fullscreen_window_placement.rcNormalPosition.bottom);
-        let pre_parent_minimized_window_placement = format!("WINDOWPLACEMENT {{ length: {}, flags: {}, showCmd: {}, ptMinPosition: POINT {{ x: {}, y: {} }}, ptMaxPosition: POINT {{ x: {}, y: {} }}, rcNormalPosition: RECT {{ left: {}, top: {}, right: {}, bottom: {} }} }}", &self.pre_parent_minimized_window_placement.length, &self.pre_parent_minimized_window_placement.flags.0, &self.pre_parent_minimized_window_placement.showCmd.0, &self.pre_parent_minimized_window_placement.ptMinPosition.x, &self.pre_parent_minimized_window_placement.ptMinPosition.y, &self.pre_parent_minimized_window_placement.ptMaxPosition.x, &self.pre_parent_minimized_window_placement.ptMaxPosition.y, &self.pre_parent_minimized_window_placement.rcNormalPosition.left, &self.pre_parent_minimized_window_placement.rcNormalPosition.top, &self.pre_parent_minimized_window_placement.rcNormalPosition.right, &self.pre_parent_minimized_window_placement.rcNormalPosition.bottom);
+        let pre_fullscreen = self.pre_fullscreen_window_placement.borrow();
+        let pre_fullscreen_window_placement = format!("WINDOWPLACEMENT {{ length: {}, flags: {}, showCmd: {}, ptMinPosition: POINT {{ x: {}, y: {} }}, ptMaxPosition: POINT {{ x: {}, y: {} }}, rcNormalPosition: RECT {{ left: {}, top: {}, right: {}, bottom: {} }} }}", &pre_fullscreen.length, &pre_fullscreen.flags.0, &pre_fullscreen.showCmd.0, &pre_fullscreen.ptMinPosition.x, &pre_fullscreen.ptMinPosition.y, &pre_fullscreen.ptMaxPosition.x, &pre_fullscreen.ptMaxPosition.y, &pre_fullscreen.rcNormalPosition.left, &pre_fullscreen.rcNormalPosition.top, &pre_fullscreen.rcNormalPosition.right, &pre_fullscreen.rcNormalPosition.bottom);
+        let pre_parent_minimized = self.pre_parent_minimized_window_placement.borrow();
+        let pre_parent_minimized_window_placement = format!("WINDOWPLACEMENT {{ length: {}, flags: {}, showCmd: {}, ptMinPosition: POINT {{ x: {}, y: {} }}, ptMaxPosition: POINT {{ x: {}, y: {} }}, rcNormalPosition: RECT {{ left: {}, top: {}, right: {}, bottom: {} }} }}", &pre_parent_minimized.length, &pre_parent_minimized.flags.0, &pre_parent_minimized.showCmd.0, &pre_parent_minimized.ptMinPosition.x, &pre_parent_minimized.ptMinPosition.y, &pre_parent_minimized.ptMaxPosition.x, &pre_parent_minimized.ptMaxPosition.y, &pre_parent_minimized.rcNormalPosition.left, &pre_parent_minimized.rcNormalPosition.top, &pre_parent_minimized.rcNormalPosition.right, &pre_parent_minimized.rcNormalPosition.bottom);
         f.debug_struct("WindowsWindow")
             .field("app_window_class", &self.app_window_class)
             .field("owning_application", &self.owning_application)
@@ -116,12 +116,16 @@ impl fmt::Debug for WindowsWindow {
             .field("dpi_scale_factor", &self.dpi_scale_factor)
             .field("handle_manual_dpi_changes", &self.handle_manual_dpi_changes)
             .field("window_definitions", &self.window_definitions)
+            .field("hierarchy", &self.hierarchy)
             .finish()
     }
 }
 
 impl WindowsWindow {
-    pub fn new() -> WindowsWindow {
+    pub fn new(
+        application: &Weak<WindowsApplication>,
+        definition: &Rc<WindowDefinition>,
+    ) -> WindowsWindow {
         unsafe {
             let mut wnd_plcment: WINDOWPLACEMENT = mem::zeroed();
             let mut wnd_plcment1: WINDOWPLACEMENT = mem::zeroed();
@@ -129,14 +133,14 @@ impl WindowsWindow {
             wnd_plcment1.length = mem::size_of::<WINDOWPLACEMENT>() as u32;
             WindowsWindow {
                 app_window_class: APP_WINDOW_CLASS,
-                owning_application: Arc::downgrade(WINDOWS_APPLICATION.unwrap()),
+                owning_application: application.clone(),
                 hwnd: Cell::new(HWND(ptr::null_mut())),
                 region_height: Cell::new(-1),
                 region_width: Cell::new(-1),
-                window_mode: WindowMode::Windowed,
+                window_mode: Cell::new(WindowMode::Windowed),
                 ole_reference_count: 0,
-                pre_fullscreen_window_placement: wnd_plcment,
-                pre_parent_minimized_window_placement: wnd_plcment1,
+                pre_fullscreen_window_placement: RefCell::new(wnd_plcment),
+                pre_parent_minimized_window_placement: RefCell::new(wnd_plcment1),
                 virtual_height: Cell::new(0),
                 virtual_width: Cell::new(0),
                 aspect_ratio: Cell::new(1.0f32),
@@ -144,32 +148,25 @@ impl WindowsWindow {
                 is_first_time_visible: Cell::new(true),
                 initially_minimized: Cell::new(false),
                 initially_maximized: Cell::new(false),
-                dpi_scale_factor: 1.0,
-                handle_manual_dpi_changes: false,
-                window_definitions: Rc::new(WindowDefinition::default()),
+                dpi_scale_factor: Cell::new(1.0),
+                handle_manual_dpi_changes: Cell::new(false),
+                window_definitions: definition.clone(),
+                hierarchy: WindowHierarchy::new(),
             }
         }
     }
-    pub fn make() -> Rc<RefCell<WindowsWindow>> {
-        println!("WindowsWindow::make");
-        Rc::new(RefCell::new(WindowsWindow::new()))
+    pub fn make(
+        application: &Weak<WindowsApplication>,
+        definition: &Rc<WindowDefinition>,
+    ) -> Rc<WindowsWindow> {
+        Rc::new(WindowsWindow::new(application, definition))
     }
     pub fn initialize(
-        &mut self,
-        definition: &Rc<WindowDefinition>,
+        &self,
         instance: HINSTANCE,
         parent: Option<Rc<WindowsWindow>>,
         show_immediately: bool,
     ) {
-        println!("Just reach in initialize");
-
-        self.window_definitions.borrow_mut().clone_from(definition);
-        println!(
-            "definition strong count is {}",
-            Rc::strong_count(&definition)
-        );
-        println!("definition weak count is {}", Rc::weak_count(&definition));
-
         let mut window_ex_style: u32 = 0;
         let mut window_style: u32 = 0;
 
@@ -190,11 +187,12 @@ impl WindowsWindow {
         let mut window_width = client_width;
         let mut window_height = client_height;
 
-        let application_supports_per_pixel_blending = unsafe {
-            WINDOWS_APPLICATION
-                .unwrap()
-                .get_window_transparency_support()
-        } == WindowTransparency::PerPixel;
+        let application_supports_per_pixel_blending =
+            self.owning_application
+                .upgrade()
+                .map_or(false, |application| {
+                    application.get_window_transparency_support() == WindowTransparency::PerPixel
+                });
 
         if !windef_borrow.has_os_window_border {
             window_ex_style = WS_EX_WINDOWEDGE.0;
@@ -264,15 +262,6 @@ impl WindowsWindow {
         }
 
         //TODO: parent window may be null, but I'm using Rc to hold parent window, which I think implies that parent window can't be null. Fix.
-        println!("WindowsWindow self is {:p}", self);
-        //let len = Weak::upgrade(&self.owning_application).unwrap().borrow().windows.len();
-        //println!("The vec len is {}", len);
-        println!("self debug is {:#?}", self);
-        println!("self.hwnd is {:?}", self.hwnd.get());
-        println!(
-            "self.owning_application is {:p}",
-            &Weak::upgrade(&self.owning_application)
-        );
         self.hwnd.set(
             match create_window(
                 WINDOW_EX_STYLE(window_ex_style),
@@ -290,7 +279,8 @@ impl WindowsWindow {
                 },
                 HMENU(ptr::null_mut()),
                 instance,
-                None,
+                // The wndproc keeps this in GWLP_USERDATA and gives it back on WM_NCDESTROY
+                Some(Weak::into_raw(self.owning_application.clone()) as *const c_void),
             ) {
                 Ok(hwnd) => hwnd,
                 Err(err) => {
@@ -303,9 +293,6 @@ impl WindowsWindow {
                 }
             },
         );
-        println!("self.hwnd is now {:?}", self.hwnd.get());
-
-        println!("CreateWindowExW called");
         self.virtual_width.set(client_width);
         self.virtual_height.set(client_height);
 
@@ -442,7 +429,7 @@ impl WindowsWindow {
                     }
                 }
             } else {
-                let use_corner_radius = self.window_mode == WindowMode::Windowed
+                let use_corner_radius = self.window_mode.get() == WindowMode::Windowed
                     && windef_borrow.transparency_support != WindowTransparency::PerPixel
                     && windef_borrow.corner_radius > 0;
                 if use_corner_radius {
@@ -487,21 +474,24 @@ impl WindowsWindow {
             }
         }
     }
-    pub fn on_parent_window_minimized(&mut self) {
+    pub fn on_parent_window_minimized(&self) {
         // This function is called from SW_PARENTCLOSING, because there's a bug in Win32 that causes the equivalent SW_PARENTOPENING
         // message to restore in an incorrect state (eg, it will lose the maximized status of the window)
         // To work around this, we cache our window placement here so that we can restore it later (see OnParentWindowRestored)
         unsafe {
             GetWindowPlacement(
                 self.hwnd.get(),
-                &mut self.pre_parent_minimized_window_placement,
+                &mut *self.pre_parent_minimized_window_placement.borrow_mut(),
             );
         }
     }
     pub fn on_parent_window_restored(&self) {
         // This function is called from SW_PARENTOPENING so that we can restore the window placement that was cached in OnParentWindowMinimized
         unsafe {
-            SetWindowPlacement(self.hwnd.get(), &self.pre_parent_minimized_window_placement);
+            SetWindowPlacement(
+                self.hwnd.get(),
+                &*self.pre_parent_minimized_window_placement.borrow(),
+            );
         }
     }
     pub fn is_enabled(&self) -> bool {
@@ -650,7 +640,7 @@ impl GenericWindow for WindowsWindow {
                 *new_height,
                 SWP_NOZORDER
                     | SWP_NOACTIVATE
-                    | if self.window_mode == WindowMode::Fullscreen {
+                    | if self.window_mode.get() == WindowMode::Fullscreen {
                         SWP_NOSENDCHANGING
                     } else {
                         SET_WINDOW_POS_FLAGS(0)
@@ -671,7 +661,7 @@ impl GenericWindow for WindowsWindow {
         width: &mut i32,
         height: &mut i32,
     ) -> bool {
-        let true_fullscreen = self.window_mode == WindowMode::Fullscreen;
+        let true_fullscreen = self.window_mode.get() == WindowMode::Fullscreen;
 
         unsafe {
             let monitor = MonitorFromWindow(
@@ -817,12 +807,12 @@ impl GenericWindow for WindowsWindow {
         }
     }
     fn get_dpi_scale_factor(&self) -> f32 {
-        self.dpi_scale_factor
+        self.dpi_scale_factor.get()
     }
-    fn set_window_mode(&mut self, new_window_mode: WindowMode) {
+    fn set_window_mode(&self, new_window_mode: WindowMode) {
         let windef_borrow: &WindowDefinition = Rc::borrow(&self.window_definitions);
-        if new_window_mode != self.window_mode {
-            self.window_mode = new_window_mode;
+        if new_window_mode != self.window_mode.get() {
+            self.window_mode.set(new_window_mode);
 
             let true_fullscreen = new_window_mode == WindowMode::Fullscreen;
 
@@ -852,7 +842,10 @@ impl GenericWindow for WindowsWindow {
                 let is_borderless_game_window = windef_borrow.window_type == WindowType::GameWindow
                     && !windef_borrow.has_os_window_border;
                 unsafe {
-                    GetWindowPlacement(self.hwnd.get(), &mut self.pre_fullscreen_window_placement);
+                    GetWindowPlacement(
+                        self.hwnd.get(),
+                        &mut *self.pre_fullscreen_window_placement.borrow_mut(),
+                    );
                 }
 
                 // Setup Win32 flags for fullscreen window
@@ -947,13 +940,16 @@ impl GenericWindow for WindowsWindow {
                         SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_FRAMECHANGED,
                     );
 
-                    SetWindowPlacement(self.hwnd.get(), &self.pre_fullscreen_window_placement);
+                    SetWindowPlacement(
+                        self.hwnd.get(),
+                        &*self.pre_fullscreen_window_placement.borrow(),
+                    );
                 }
             }
         }
     }
     fn get_window_mode(&self) -> WindowMode {
-        self.window_mode
+        self.window_mode.get()
     }
     fn is_maximized(&self) -> bool {
         let zoomed = unsafe { !!IsZoomed(self.hwnd.get()) };
@@ -991,7 +987,7 @@ impl GenericWindow for WindowsWindow {
         }
     }
     /** Sets focus on the native window */
-    fn set_window_focus(&mut self) {
+    fn set_window_focus(&self) {
         unsafe {
             if GetFocus() != self.hwnd.get() {
                 SetFocus(self.hwnd.get());
@@ -1076,12 +1072,12 @@ impl GenericWindow for WindowsWindow {
         }
     }
     fn is_manual_manage_dpi_change(&self) -> bool {
-        self.handle_manual_dpi_changes
+        self.handle_manual_dpi_changes.get()
     }
-    fn set_manual_manage_dpi_change(&mut self, manual_dpi_changes: bool) {
-        self.handle_manual_dpi_changes = manual_dpi_changes;
+    fn set_manual_manage_dpi_change(&self, manual_dpi_changes: bool) {
+        self.handle_manual_dpi_changes.set(manual_dpi_changes);
     }
-    fn destroy(&mut self) {
+    fn destroy(&self) {
         unsafe {
             if self.ole_reference_count > 0 && IsWindow(self.hwnd.get()).0 != 0 {
                 let res = RevokeDragDrop(self.hwnd.get());
@@ -1101,8 +1097,8 @@ impl GenericWindow for WindowsWindow {
         todo!()
     }
 
-    fn set_dpi_scale_factor(&mut self, factor: f32) {
-        self.dpi_scale_factor = factor;
+    fn set_dpi_scale_factor(&self, factor: f32) {
+        self.dpi_scale_factor.set(factor);
     }
 
     fn draw_attention(&self, parameters: WindowDrawAttentionRequestType) {
@@ -1122,9 +1118,12 @@ impl GenericWindow for WindowsWindow {
         unsafe { FlashWindowEx(&flash_info) };
     }
 
-    fn set_native_window_buttons_visibility(&mut self, visible: bool) {
+    fn set_native_window_buttons_visibility(&self, visible: bool) {
         todo!()
     }
+    fn get_hierarchy(&self) -> &WindowHierarchy {
+        &self.hierarchy
+    }
 }
 
 fn create_window(
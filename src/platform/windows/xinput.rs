@@ -0,0 +1,33 @@
+use crate::generic::xinputinterface::{XInputGamepad, XInputState, XInputStateSource};
+use std::mem;
+use windows::Win32::{
+    Foundation::ERROR_SUCCESS,
+    UI::Input::XboxController::{XInputGetState, XINPUT_STATE},
+};
+
+/** Reads the controllers through XInputGetState */
+#[derive(Debug, Default)]
+pub struct WindowsXInputStateSource;
+
+impl XInputStateSource for WindowsXInputStateSource {
+    fn get_state(&self, user_index: u32) -> Option<XInputState> {
+        unsafe {
+            let mut state: XINPUT_STATE = mem::zeroed();
+            if XInputGetState(user_index, &mut state) != ERROR_SUCCESS.0 {
+                return None;
+            }
+            Some(XInputState {
+                packet_number: state.dwPacketNumber,
+                gamepad: XInputGamepad {
+                    buttons: state.Gamepad.wButtons.0,
+                    left_trigger: state.Gamepad.bLeftTrigger,
+                    right_trigger: state.Gamepad.bRightTrigger,
+                    thumb_lx: state.Gamepad.sThumbLX,
+                    thumb_ly: state.Gamepad.sThumbLY,
+                    thumb_rx: state.Gamepad.sThumbRX,
+                    thumb_ry: state.Gamepad.sThumbRY,
+                },
+            })
+        }
+    }
+}
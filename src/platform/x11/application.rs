@@ -0,0 +1,633 @@
+use crate::generic::application::{DisplayMetrics, GenericApplication, ModifierKeysState};
+use crate::generic::application_message_handler::{
+    ApplicationMessageHandler, MouseButtons, WindowActivation, WindowZone,
+};
+use crate::generic::application_proxy::{ApplicationProxy, ProxyReceiver, ProxyWindowId};
+use crate::generic::cursor::ICursor;
+use crate::generic::input::Key;
+use crate::generic::platform_event::{PlatformEvent, PlatformEventQueue};
+use crate::generic::timer::TimerQueue;
+use crate::generic::wakeup_pipe::WakeupPipe;
+use crate::generic::window::GenericWindow;
+use crate::generic::window_definition::WindowDefinition;
+use crate::generic::window_hierarchy;
+use crate::generic::window_hit_test;
+use crate::x11::cursor::X11Cursor;
+use crate::x11::display::{self, X11Display};
+use crate::x11::window::X11Window;
+use glam::Vec2;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    fmt, io, mem,
+    os::raw::{c_char, c_int, c_uint},
+    ptr,
+    rc::Rc,
+    time::Duration,
+};
+use x11_dl::xlib;
+
+/** X has no system double-click time, so use the Windows default */
+const DOUBLE_CLICK_TIME_MS: xlib::Time = 500;
+/** How far, in pixels, the second click of a double-click may be from the first */
+const DOUBLE_CLICK_DISTANCE: i32 = 4;
+
+/** X keycodes are evdev codes offset by the 8 codes the X protocol reserves */
+const EVDEV_KEYCODE_OFFSET: c_uint = 8;
+
+/** Tilting the wheel left and right is reported as presses of buttons 6 and 7 */
+const BUTTON_SCROLL_LEFT: c_uint = 6;
+const BUTTON_SCROLL_RIGHT: c_uint = 7;
+/** The back and forward buttons that most mice report as buttons 8 and 9 */
+const BUTTON_BACK: c_uint = 8;
+const BUTTON_FORWARD: c_uint = 9;
+
+/** The button, time, root position and window of the last click, used to detect double-clicks */
+type LastClick = (c_uint, xlib::Time, i32, i32, xlib::Window);
+
+/** Maps a keysym to the character it produces. Falls back to the Latin-1 text from XLookupString. */
+fn keysym_to_char(keysym: xlib::KeySym, text: &[c_char]) -> Option<char> {
+    // Keysyms 0x01000000 - 0x0110FFFF are Unicode code points
+    if keysym & 0xff00_0000 == 0x0100_0000 {
+        return char::from_u32((keysym & 0x00ff_ffff) as u32);
+    }
+    text.first().map(|&byte| byte as u8 as char)
+}
+
+fn translate_mouse_button(button: c_uint) -> MouseButtons {
+    match button {
+        xlib::Button1 => MouseButtons::Left,
+        xlib::Button2 => MouseButtons::Middle,
+        xlib::Button3 => MouseButtons::Right,
+        BUTTON_BACK => MouseButtons::Thumb01,
+        BUTTON_FORWARD => MouseButtons::Thumb02,
+        _ => MouseButtons::Invalid,
+    }
+}
+
+pub struct X11Application {
+    display: Rc<X11Display>,
+    cursor: Rc<RefCell<X11Cursor>>,
+    pub windows: RefCell<Vec<Rc<X11Window>>>,
+    message_handler: Rc<dyn ApplicationMessageHandler>,
+    events: PlatformEventQueue,
+    /** Keycodes that are currently held. X repeats by sending more KeyPress events without a KeyRelease. */
+    pressed_keys: RefCell<HashSet<c_uint>>,
+    last_click: Cell<Option<LastClick>>,
+    is_active: Cell<bool>,
+    modifier_keys: Cell<ModifierKeysState>,
+    initial_display_metrics: DisplayMetrics,
+    display_metrics: RefCell<DisplayMetrics>,
+    /** Set when the X server reported a monitor change; the metrics are queried again once the queue is empty */
+    display_metrics_dirty: Cell<bool>,
+    proxy_receiver: ProxyReceiver<X11Window>,
+    /** Polled next to the display connection, so a proxy can wake the application while it waits */
+    wakeup_pipe: WakeupPipe,
+    timers: TimerQueue,
+}
+
+impl fmt::Debug for X11Application {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X11Application")
+            .field("display", &self.display)
+            .field("cursor", &self.cursor)
+            .field("windows", &self.windows)
+            .field("is_active", &self.is_active)
+            .field("modifier_keys", &self.modifier_keys)
+            .field("display_metrics", &self.display_metrics)
+            .field("proxy_receiver", &self.proxy_receiver)
+            .field("timers", &self.timers)
+            .finish()
+    }
+}
+
+impl X11Application {
+    /** Connects to the X server named by $DISPLAY */
+    pub fn new(message_handler: &Rc<dyn ApplicationMessageHandler>) -> io::Result<X11Application> {
+        let display = Rc::new(X11Display::open()?);
+        unsafe {
+            // Without this, a held key produces KeyRelease/KeyPress pairs and repeats look like separate presses
+            let mut supported = xlib::False;
+            (display.xlib.XkbSetDetectableAutoRepeat)(display.display, xlib::True, &mut supported);
+        }
+        display.select_display_change_input();
+        let initial_display_metrics = DisplayMetrics::from_monitors(display.get_monitor_info());
+        let (wakeup_pipe, waker) = WakeupPipe::new()?;
+        Ok(X11Application {
+            cursor: Rc::new(RefCell::new(X11Cursor::new(&display))),
+            display,
+            windows: RefCell::new(vec![]),
+            message_handler: message_handler.clone(),
+            events: PlatformEventQueue::new(),
+            pressed_keys: RefCell::new(HashSet::new()),
+            last_click: Cell::new(None),
+            is_active: Cell::new(false),
+            modifier_keys: Cell::new(ModifierKeysState::default()),
+            display_metrics: RefCell::new(initial_display_metrics.clone()),
+            initial_display_metrics,
+            display_metrics_dirty: Cell::new(false),
+            proxy_receiver: ProxyReceiver::new(Some(Box::new(waker))),
+            wakeup_pipe,
+            timers: TimerQueue::new(),
+        })
+    }
+    /** Creates a window from the definition and registers it with the application. parent becomes its owner. */
+    pub fn make_window(
+        &self,
+        definition: &Rc<WindowDefinition>,
+        parent: Option<&Rc<X11Window>>,
+        show_immediately: bool,
+    ) -> Rc<X11Window> {
+        let window = Rc::new(X11Window::new(&self.display, definition));
+        if let Some(parent) = parent {
+            window.set_transient_for(parent);
+            let generic_parent: Rc<dyn GenericWindow> = parent.clone();
+            let generic_window: Rc<dyn GenericWindow> = window.clone();
+            window_hierarchy::set_parent(&generic_window, Some(&generic_parent));
+        }
+        self.windows.borrow_mut().push(window.clone());
+        self.cursor.borrow_mut().add_window(window.get_handle());
+        if show_immediately {
+            window.show();
+        }
+        window
+    }
+    pub fn find_window_by_handle(&self, handle_to_find: xlib::Window) -> Option<Rc<X11Window>> {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|window| window.get_handle() == handle_to_find)
+            .cloned()
+    }
+    pub fn get_cursor(&self) -> &Rc<RefCell<X11Cursor>> {
+        &self.cursor
+    }
+    pub fn get_display(&self) -> &Rc<X11Display> {
+        &self.display
+    }
+    fn should_process_user_input(&self, window: &Rc<X11Window>) -> bool {
+        let generic_window: Rc<dyn GenericWindow> = window.clone();
+        window.is_enabled()
+            && window_hierarchy::should_process_user_input(&self.message_handler, &generic_window)
+    }
+    /**
+     * Brings the modifier keys up to date with the state mask of a key or button event. The mask holds the
+     * modifiers from before the event and does not tell left from right, which the key events fill in.
+     */
+    fn sync_modifier_keys(&self, state: c_uint) -> ModifierKeysState {
+        let mut modifier_keys = self.modifier_keys.get();
+        // Mod1 is Alt and Mod4 is Super with every stock keymap
+        modifier_keys.sync_with(
+            state & xlib::ShiftMask != 0,
+            state & xlib::ControlMask != 0,
+            state & xlib::Mod1Mask != 0,
+            state & xlib::Mod4Mask != 0,
+            state & xlib::LockMask != 0,
+        );
+        self.modifier_keys.set(modifier_keys);
+        modifier_keys
+    }
+    /** Queries the monitors again and reports the change, if there is one */
+    fn update_display_metrics(&self) {
+        let display_metrics = DisplayMetrics::from_monitors(self.display.get_monitor_info());
+        let change = self.display_metrics.borrow().diff(&display_metrics);
+        if !change.is_empty() {
+            *self.display_metrics.borrow_mut() = display_metrics.clone();
+            self.events.push(PlatformEvent::DisplayMetricsChanged {
+                display_metrics,
+                change,
+            });
+        }
+    }
+    fn process_event(&self, event: &xlib::XEvent) {
+        if self.display.is_display_change_event(event) {
+            self.display_metrics_dirty.set(true);
+            return;
+        }
+        let window_handle = unsafe { event.any.window };
+        let window = match self.find_window_by_handle(window_handle) {
+            Some(window) => window,
+            None => return,
+        };
+        let generic_window: Rc<dyn GenericWindow> = window.clone();
+        let events = &self.events;
+
+        match event.get_type() {
+            xlib::KeyPress | xlib::KeyRelease => {
+                if !self.should_process_user_input(&window) {
+                    return;
+                }
+                let mut key_event = unsafe { event.key };
+                let mut text = [0 as c_char; 32];
+                let mut keysym: xlib::KeySym = 0;
+                let text_len = unsafe {
+                    (self.display.xlib.XLookupString)(
+                        &mut key_event,
+                        text.as_mut_ptr(),
+                        text.len() as c_int,
+                        &mut keysym,
+                        ptr::null_mut(),
+                    )
+                };
+                let text = &text[..text_len.max(0) as usize];
+                let character = keysym_to_char(keysym, text);
+                let character_code = character.map_or(0, |character| character as u32);
+                let key =
+                    Key::from_evdev_code(key_event.keycode.saturating_sub(EVDEV_KEYCODE_OFFSET));
+                let is_press = event.get_type() == xlib::KeyPress;
+
+                let mut modifier_keys = self.sync_modifier_keys(key_event.state);
+                modifier_keys.set_key_down(key, is_press);
+                // The mask of a press still has the lock state from before the press toggled it
+                if key == Key::CapsLock && is_press {
+                    modifier_keys.are_caps_locked = !modifier_keys.are_caps_locked;
+                }
+                self.modifier_keys.set(modifier_keys);
+
+                if is_press {
+                    let is_repeat = !self.pressed_keys.borrow_mut().insert(key_event.keycode);
+                    events.push(PlatformEvent::KeyDown {
+                        key,
+                        character_code,
+                        is_repeat,
+                        modifier_keys,
+                    });
+                    // Only keys that produce text get a character message, like WM_CHAR
+                    if let (Some(character), false) = (character, text.is_empty()) {
+                        events.push(PlatformEvent::KeyChar {
+                            character,
+                            is_repeat,
+                            modifier_keys,
+                        });
+                    }
+                } else {
+                    self.pressed_keys.borrow_mut().remove(&key_event.keycode);
+                    events.push(PlatformEvent::KeyUp {
+                        key,
+                        character_code,
+                        is_repeat: false,
+                        modifier_keys,
+                    });
+                }
+            }
+            xlib::ButtonPress => {
+                if !self.should_process_user_input(&window) {
+                    return;
+                }
+                let button_event = unsafe { event.button };
+                let cursor_pos = Vec2::new(button_event.x as f32, button_event.y as f32);
+                let modifier_keys = self.sync_modifier_keys(button_event.state);
+                match button_event.button {
+                    xlib::Button4 => {
+                        events.push(PlatformEvent::MouseWheel {
+                            delta: 1.0,
+                            cursor_pos: Some(cursor_pos),
+                            modifier_keys,
+                        });
+                    }
+                    xlib::Button5 => {
+                        events.push(PlatformEvent::MouseWheel {
+                            delta: -1.0,
+                            cursor_pos: Some(cursor_pos),
+                            modifier_keys,
+                        });
+                    }
+                    BUTTON_SCROLL_LEFT => {
+                        events.push(PlatformEvent::MouseHWheel {
+                            delta: -1.0,
+                            cursor_pos: Some(cursor_pos),
+                            modifier_keys,
+                        });
+                    }
+                    BUTTON_SCROLL_RIGHT => {
+                        events.push(PlatformEvent::MouseHWheel {
+                            delta: 1.0,
+                            cursor_pos: Some(cursor_pos),
+                            modifier_keys,
+                        });
+                    }
+                    button => {
+                        let click = (
+                            button,
+                            button_event.time,
+                            button_event.x_root,
+                            button_event.y_root,
+                            window_handle,
+                        );
+                        let is_double_click = match self.last_click.get() {
+                            Some((last_button, last_time, last_x, last_y, last_window)) => {
+                                last_button == button
+                                    && last_window == window_handle
+                                    && button_event.time.wrapping_sub(last_time)
+                                        <= DOUBLE_CLICK_TIME_MS
+                                    && (button_event.x_root - last_x).abs() <= DOUBLE_CLICK_DISTANCE
+                                    && (button_event.y_root - last_y).abs() <= DOUBLE_CLICK_DISTANCE
+                            }
+                            None => false,
+                        };
+                        // Windows without an OS border draw their own title bar and border, which the window manager
+                        // only knows about if we tell it when a drag starts on them
+                        if button == xlib::Button1 && !window.get_definition().has_os_window_border
+                        {
+                            let (width, height) = window.get_size();
+                            let zone = window_hit_test::get_window_zone_for_point(
+                                &self.message_handler,
+                                &generic_window,
+                                width,
+                                height,
+                                button_event.x,
+                                button_event.y,
+                            );
+                            // A double-click on the title bar goes to the handler, which may maximize the window
+                            let is_title_bar = zone == WindowZone::TitleBar;
+                            if !(is_title_bar && is_double_click)
+                                && self.display.begin_move_resize(
+                                    window_handle,
+                                    zone,
+                                    button_event.x_root,
+                                    button_event.y_root,
+                                    button,
+                                )
+                            {
+                                self.last_click.set(is_title_bar.then_some(click));
+                                return;
+                            }
+                        }
+                        if is_double_click {
+                            self.last_click.set(None);
+                            events.push(PlatformEvent::MouseDoubleClick {
+                                window: generic_window,
+                                button: translate_mouse_button(button),
+                                cursor_pos: Some(cursor_pos),
+                                modifier_keys,
+                            });
+                        } else {
+                            self.last_click.set(Some(click));
+                            events.push(PlatformEvent::MouseDown {
+                                window: generic_window,
+                                button: translate_mouse_button(button),
+                                cursor_pos: Some(cursor_pos),
+                                modifier_keys,
+                            });
+                        }
+                    }
+                }
+            }
+            xlib::ButtonRelease => {
+                if !self.should_process_user_input(&window) {
+                    return;
+                }
+                let button_event = unsafe { event.button };
+                // Wheel "buttons" are reported as a press/release pair, the press already produced the wheel message
+                if (xlib::Button4..=BUTTON_SCROLL_RIGHT).contains(&button_event.button) {
+                    return;
+                }
+                let cursor_pos = Vec2::new(button_event.x as f32, button_event.y as f32);
+                events.push(PlatformEvent::MouseUp {
+                    button: translate_mouse_button(button_event.button),
+                    cursor_pos: Some(cursor_pos),
+                    modifier_keys: self.sync_modifier_keys(button_event.state),
+                });
+            }
+            xlib::MotionNotify => {
+                let motion_event = unsafe { event.motion };
+                // Keep the pointer inside the rect the cursor is locked to
+                let mut cursor = self.cursor.borrow_mut();
+                if let Some(clip_rect) = cursor.get_clip_rect() {
+                    if motion_event.x_root < clip_rect.left
+                        || motion_event.x_root >= clip_rect.right
+                        || motion_event.y_root < clip_rect.top
+                        || motion_event.y_root >= clip_rect.bottom
+                    {
+                        cursor.set_position(motion_event.x_root, motion_event.y_root);
+                    }
+                }
+                drop(cursor);
+                if self.should_process_user_input(&window) {
+                    events.push(PlatformEvent::MouseMove);
+                }
+            }
+            xlib::EnterNotify => {
+                events.push(PlatformEvent::CursorSet);
+            }
+            xlib::ConfigureNotify => {
+                let configure_event = unsafe { event.configure };
+                // The event is relative to the window manager's frame when the window is reparented
+                let (mut x, mut y) = (0, 0);
+                let mut child: xlib::Window = 0;
+                unsafe {
+                    (self.display.xlib.XTranslateCoordinates)(
+                        self.display.display,
+                        window_handle,
+                        self.display.root,
+                        0,
+                        0,
+                        &mut x,
+                        &mut y,
+                        &mut child,
+                    );
+                }
+                let (moved, resized) =
+                    window.on_configure(x, y, configure_event.width, configure_event.height);
+                if resized {
+                    events.push(PlatformEvent::SizeChanged {
+                        window: generic_window.clone(),
+                        width: configure_event.width,
+                        height: configure_event.height,
+                        was_minimized: false,
+                    });
+                }
+                if moved {
+                    events.push(PlatformEvent::MovedWindow {
+                        window: generic_window,
+                        x,
+                        y,
+                    });
+                }
+            }
+            xlib::UnmapNotify if window.on_unmapped() => {
+                let mut size = (0, 0);
+                window.adjust_cached_size(&mut size);
+                events.push(PlatformEvent::SizeChanged {
+                    window: generic_window,
+                    width: size.0,
+                    height: size.1,
+                    was_minimized: true,
+                });
+            }
+            xlib::DestroyNotify => {
+                window.on_destroyed();
+                self.cursor.borrow_mut().remove_window(window_handle);
+            }
+            xlib::ClientMessage => {
+                let client_message = unsafe { event.client_message };
+                if client_message.message_type != self.display.atoms.wm_protocols {
+                    return;
+                }
+                let protocol = client_message.data.get_long(0) as xlib::Atom;
+                if protocol == self.display.atoms.wm_delete_window {
+                    events.push(PlatformEvent::WindowClose {
+                        window: generic_window,
+                    });
+                } else if protocol == self.display.atoms.net_wm_ping {
+                    // Answer pings so the window manager does not offer to kill us
+                    let mut reply = *event;
+                    reply.client_message.window = self.display.root;
+                    unsafe {
+                        (self.display.xlib.XSendEvent)(
+                            self.display.display,
+                            self.display.root,
+                            xlib::False,
+                            xlib::SubstructureNotifyMask | xlib::SubstructureRedirectMask,
+                            &mut reply,
+                        );
+                    }
+                    self.display.flush();
+                }
+            }
+            xlib::FocusIn | xlib::FocusOut => {
+                let focus_event = unsafe { event.focus_change };
+                if focus_event.mode == xlib::NotifyGrab
+                    || focus_event.mode == xlib::NotifyUngrab
+                    || focus_event.detail == xlib::NotifyInferior
+                {
+                    return;
+                }
+                let activation_type = if event.get_type() == xlib::FocusIn {
+                    WindowActivation::Activate
+                } else {
+                    // Releases are not delivered to a window that lost focus, so forget what was held
+                    self.pressed_keys.borrow_mut().clear();
+                    let mut modifier_keys = self.modifier_keys.get();
+                    let are_caps_locked = modifier_keys.are_caps_locked;
+                    modifier_keys.sync_with(false, false, false, false, are_caps_locked);
+                    self.modifier_keys.set(modifier_keys);
+                    WindowActivation::Deactivate
+                };
+                events.push(PlatformEvent::WindowActivationChanged {
+                    window: generic_window,
+                    activation_type,
+                });
+            }
+            xlib::Expose => {
+                let expose_event = unsafe { event.expose };
+                if expose_event.count == 0 {
+                    events.push(PlatformEvent::OsPaint {
+                        window: generic_window,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    /** The application is active while one of its windows has keyboard focus */
+    fn update_application_activation(&self) {
+        let focus = self.display.get_input_focus();
+        let is_active = self
+            .windows
+            .borrow()
+            .iter()
+            .any(|window| window.get_handle() == focus);
+        if is_active != self.is_active.get() {
+            self.is_active.set(is_active);
+            self.events
+                .push(PlatformEvent::ApplicationActivationChanged { is_active });
+        }
+    }
+}
+
+impl GenericApplication for X11Application {
+    type Cursor = X11Cursor;
+    type Window = X11Window;
+
+    fn set_message_handler(&mut self, in_message_handler: &Rc<dyn ApplicationMessageHandler>) {
+        self.message_handler = in_message_handler.clone();
+    }
+    fn get_message_handler(&self) -> &Rc<dyn ApplicationMessageHandler> {
+        &self.message_handler
+    }
+    fn get_event_queue(&self) -> &PlatformEventQueue {
+        &self.events
+    }
+    fn get_timer_queue(&self) -> &TimerQueue {
+        &self.timers
+    }
+    fn get_modifier_keys(&self) -> ModifierKeysState {
+        self.modifier_keys.get()
+    }
+    fn get_initial_display_metrics(&self) -> DisplayMetrics {
+        self.initial_display_metrics.clone()
+    }
+    fn get_display_metrics(&self) -> DisplayMetrics {
+        self.display_metrics.borrow().clone()
+    }
+    fn create_proxy(&self) -> ApplicationProxy {
+        self.proxy_receiver.create_proxy()
+    }
+    fn get_proxy_window(&self, id: ProxyWindowId) -> Option<Rc<X11Window>> {
+        self.proxy_receiver.get_window(id)
+    }
+    fn wait_for_events(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        if self.display.is_connection_lost() {
+            return Err(self.display.get_connection_lost_error());
+        }
+        if !self.events.is_empty()
+            || self.proxy_receiver.has_pending_messages()
+            || self.timers.has_expired_timers()
+        {
+            return Ok(true);
+        }
+        let fd = unsafe {
+            // XPending also flushes the requests, which the X server may have to answer before anything happens
+            if (self.display.xlib.XPending)(self.display.display) > 0 {
+                return Ok(true);
+            }
+            (self.display.xlib.XConnectionNumber)(self.display.display)
+        };
+        if self.display.is_connection_lost() {
+            return Err(self.display.get_connection_lost_error());
+        }
+        let is_ready = self
+            .wakeup_pipe
+            .wait(fd, self.timers.get_wait_timeout(timeout))?;
+        Ok(is_ready || self.timers.has_expired_timers())
+    }
+    fn poll_events(&self, _time_delta: f32) -> io::Result<()> {
+        self.wakeup_pipe.drain();
+        self.proxy_receiver.process_messages(
+            &self.events,
+            |definition, parent, show_immediately| {
+                self.make_window(definition, parent, show_immediately)
+            },
+        );
+        unsafe {
+            while !self.display.is_connection_lost()
+                && (self.display.xlib.XPending)(self.display.display) > 0
+            {
+                let mut event: xlib::XEvent = mem::zeroed();
+                (self.display.xlib.XNextEvent)(self.display.display, &mut event);
+                self.process_event(&event);
+            }
+        }
+        for x_error in display::take_x_errors() {
+            log::warn!("{}", x_error);
+        }
+        // The events read before the connection failed stay queued for process_deferred_events
+        if self.display.is_connection_lost() {
+            return Err(self.display.get_connection_lost_error());
+        }
+        // A hot-plug sends a burst of XRandR events; query the monitors once for all of them
+        if self.display_metrics_dirty.take() {
+            self.update_display_metrics();
+        }
+
+        self.windows
+            .borrow_mut()
+            .retain(|window| !window.is_destroyed());
+        self.proxy_receiver
+            .retain_windows(|window| !window.is_destroyed());
+        self.update_application_activation();
+        self.timers.fire_expired_timers(&self.events);
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    core::math::color::Color,
+    generic::{
+        application::PlatformRect,
+        cursor::{ICursor, MouseCursor},
+    },
+    x11::display::X11Display,
+};
+use glam::Vec2;
+use std::{
+    cell::Cell,
+    error::Error,
+    ffi::c_void,
+    io,
+    os::raw::{c_char, c_int, c_uint},
+    path::Path,
+    rc::Rc,
+};
+use x11_dl::xlib;
+
+/** Shapes from X11/cursorfont.h. x11-dl does not export them. */
+const XC_X_CURSOR: c_uint = 0;
+const XC_BOTTOM_LEFT_CORNER: c_uint = 12;
+const XC_BOTTOM_RIGHT_CORNER: c_uint = 14;
+const XC_CROSSHAIR: c_uint = 34;
+const XC_FLEUR: c_uint = 52;
+const XC_HAND1: c_uint = 58;
+const XC_HAND2: c_uint = 60;
+const XC_LEFT_PTR: c_uint = 68;
+const XC_PENCIL: c_uint = 86;
+const XC_SB_H_DOUBLE_ARROW: c_uint = 108;
+const XC_SB_V_DOUBLE_ARROW: c_uint = 116;
+const XC_XTERM: c_uint = 152;
+
+/** The X11 cursor. Shapes are applied to every window of the application, since X cursors are per window. */
+#[derive(Debug)]
+pub struct X11Cursor {
+    display: Rc<X11Display>,
+    pub current_type: MouseCursor,
+    pub cursor_handles: [xlib::Cursor; 15],
+    pub cursor_override_handles: [xlib::Cursor; 15],
+    /** A 1x1 transparent cursor. X has no ShowCursor, so hiding swaps this in. */
+    blank_cursor: xlib::Cursor,
+    is_visible: Cell<bool>,
+    clip_rect: Cell<Option<PlatformRect>>,
+    windows: Vec<xlib::Window>,
+}
+
+impl X11Cursor {
+    pub fn new(display: &Rc<X11Display>) -> X11Cursor {
+        let mut cursor_handles = [0 as xlib::Cursor; 15];
+        let mut blank_cursor = 0;
+        unsafe {
+            for (i, cursor_handle) in cursor_handles.iter_mut().enumerate() {
+                let shape = match MouseCursor::from_usize(i) {
+                    MouseCursor::None | MouseCursor::Custom => continue,
+                    MouseCursor::Default => XC_LEFT_PTR,
+                    MouseCursor::TextEditBeam => XC_XTERM,
+                    MouseCursor::ResizeLeftRight => XC_SB_H_DOUBLE_ARROW,
+                    MouseCursor::ResizeUpDown => XC_SB_V_DOUBLE_ARROW,
+                    MouseCursor::ResizeSouthEast => XC_BOTTOM_RIGHT_CORNER,
+                    MouseCursor::ResizeSouthWest => XC_BOTTOM_LEFT_CORNER,
+                    MouseCursor::CardinalCross => XC_FLEUR,
+                    MouseCursor::Crosshairs => XC_CROSSHAIR,
+                    MouseCursor::Hand => XC_HAND2,
+                    MouseCursor::GrabHand => XC_HAND1,
+                    MouseCursor::GrabHandClosed => XC_FLEUR,
+                    MouseCursor::SlashedCircle => XC_X_CURSOR,
+                    MouseCursor::EyeDropper => XC_PENCIL,
+                };
+                *cursor_handle = (display.xlib.XCreateFontCursor)(display.display, shape);
+            }
+
+            let data: c_char = 0;
+            let pixmap =
+                (display.xlib.XCreateBitmapFromData)(display.display, display.root, &data, 1, 1);
+            if pixmap != 0 {
+                let mut color: xlib::XColor = std::mem::zeroed();
+                blank_cursor = (display.xlib.XCreatePixmapCursor)(
+                    display.display,
+                    pixmap,
+                    pixmap,
+                    &mut color,
+                    &mut color,
+                    0,
+                    0,
+                );
+                (display.xlib.XFreePixmap)(display.display, pixmap);
+            }
+        }
+        X11Cursor {
+            display: display.clone(),
+            current_type: MouseCursor::Default,
+            cursor_handles,
+            cursor_override_handles: [0; 15],
+            blank_cursor,
+            is_visible: Cell::new(true),
+            clip_rect: Cell::new(None),
+            windows: vec![],
+        }
+    }
+    /** Registers a window that should follow the cursor shape */
+    pub fn add_window(&mut self, window: xlib::Window) {
+        self.windows.push(window);
+        self.update_window_cursors();
+    }
+    pub fn remove_window(&mut self, window: xlib::Window) {
+        self.windows.retain(|&handle| handle != window);
+    }
+    /** The rect the cursor is currently locked to, if any */
+    pub fn get_clip_rect(&self) -> Option<PlatformRect> {
+        self.clip_rect.get()
+    }
+    /** The cursor handle for the current type, honoring overrides */
+    fn get_current_handle(&self) -> xlib::Cursor {
+        let index = self.current_type.to_usize();
+        if self.cursor_override_handles[index] != 0 {
+            self.cursor_override_handles[index]
+        } else {
+            self.cursor_handles[index]
+        }
+    }
+    fn update_window_cursors(&self) {
+        let cursor = if self.is_visible.get() && self.current_type != MouseCursor::None {
+            self.get_current_handle()
+        } else {
+            self.blank_cursor
+        };
+        unsafe {
+            for window in &self.windows {
+                (self.display.xlib.XDefineCursor)(self.display.display, *window, cursor);
+            }
+        }
+        self.display.flush();
+    }
+}
+
+impl ICursor for X11Cursor {
+    type Rect = PlatformRect;
+
+    fn create_cursor_from_file<P: AsRef<Path>>(
+        path_to_cursor_without_extension: P,
+        _hotspot: Vec2,
+    ) -> Result<Self, Box<dyn Error>> {
+        let msg = format!(
+            "loading cursors from file is not supported by the X11 backend ({})",
+            path_to_cursor_without_extension.as_ref().display()
+        );
+        Err(Box::new(io::Error::new(io::ErrorKind::Unsupported, msg)))
+    }
+
+    fn is_create_cursor_from_rgba_buffer_supported() -> bool {
+        false
+    }
+
+    fn create_cursor_from_rgba_buffer(
+        _pixels: Color,
+        _width: i32,
+        _height: i32,
+        _hotspot: Vec2,
+    ) -> Option<Self> {
+        None
+    }
+
+    fn get_position(&self) -> Vec2 {
+        let display = &self.display;
+        let (mut root, mut child) = (0, 0);
+        let (mut root_x, mut root_y, mut win_x, mut win_y): (c_int, c_int, c_int, c_int) =
+            (0, 0, 0, 0);
+        let mut mask: c_uint = 0;
+        unsafe {
+            (display.xlib.XQueryPointer)(
+                display.display,
+                display.root,
+                &mut root,
+                &mut child,
+                &mut root_x,
+                &mut root_y,
+                &mut win_x,
+                &mut win_y,
+                &mut mask,
+            );
+        }
+        Vec2::new(root_x as f32, root_y as f32)
+    }
+
+    fn set_position(&mut self, x: i32, y: i32) {
+        let (mut x, mut y) = (x, y);
+        if let Some(clip_rect) = self.clip_rect.get() {
+            x = x.max(clip_rect.left).min(clip_rect.right - 1);
+            y = y.max(clip_rect.top).min(clip_rect.bottom - 1);
+        }
+        let display = &self.display;
+        unsafe {
+            (display.xlib.XWarpPointer)(display.display, 0, display.root, 0, 0, 0, 0, x, y);
+        }
+        display.flush();
+    }
+
+    fn set_type(&mut self, new_cursor: MouseCursor) {
+        self.current_type = new_cursor;
+        self.update_window_cursors();
+    }
+
+    fn get_type(&self) -> &MouseCursor {
+        &self.current_type
+    }
+
+    fn get_size(&self, width: &mut i32, height: &mut i32) {
+        *width = 16;
+        *height = 16;
+    }
+
+    fn show(&self, show: bool) {
+        self.is_visible.set(show);
+        self.update_window_cursors();
+    }
+
+    /** X11 can only confine the pointer with an active grab, so the application warps it back inside the rect on motion instead */
+    fn lock(&self, bounds: Option<*const Self::Rect>) {
+        match bounds {
+            Some(rect) if !rect.is_null() => {
+                self.clip_rect.set(Some(unsafe { *rect }));
+            }
+            _ => self.clip_rect.set(None),
+        }
+    }
+
+    fn set_type_shape(&mut self, cursor_type: MouseCursor, cursor_handle: *const c_void) {
+        self.cursor_override_handles[cursor_type.to_usize()] = cursor_handle as xlib::Cursor;
+        self.update_window_cursors();
+    }
+}
+
+impl Drop for X11Cursor {
+    fn drop(&mut self) {
+        let display = &self.display;
+        unsafe {
+            for cursor_handle in self.cursor_handles.iter().chain([&self.blank_cursor]) {
+                if *cursor_handle != 0 {
+                    (display.xlib.XFreeCursor)(display.display, *cursor_handle);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,874 @@
+use crate::generic::application::{DisplayRotation, MonitorInfo, PlatformRect, DEFAULT_DPI};
+use crate::generic::application_message_handler::WindowZone;
+use crate::generic::edid::Edid;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    ffi::{CStr, CString},
+    fmt, io, mem,
+    os::raw::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_void},
+    ptr, slice,
+    sync::Mutex,
+};
+use x11_dl::{xlib, xrandr};
+
+/** Atoms that the backend needs, interned once when the display is opened */
+#[derive(Debug)]
+pub struct X11Atoms {
+    pub wm_protocols: xlib::Atom,
+    pub wm_delete_window: xlib::Atom,
+    pub wm_state: xlib::Atom,
+    pub net_wm_ping: xlib::Atom,
+    pub net_wm_name: xlib::Atom,
+    pub utf8_string: xlib::Atom,
+    pub motif_wm_hints: xlib::Atom,
+    pub net_active_window: xlib::Atom,
+    pub net_frame_extents: xlib::Atom,
+    pub net_wm_user_time: xlib::Atom,
+    pub net_wm_window_opacity: xlib::Atom,
+    pub net_wm_state: xlib::Atom,
+    pub net_wm_state_maximized_horz: xlib::Atom,
+    pub net_wm_state_maximized_vert: xlib::Atom,
+    pub net_wm_state_hidden: xlib::Atom,
+    pub net_wm_state_fullscreen: xlib::Atom,
+    pub net_wm_state_demands_attention: xlib::Atom,
+    pub net_wm_state_above: xlib::Atom,
+    pub net_wm_state_skip_taskbar: xlib::Atom,
+    pub net_wm_window_type: xlib::Atom,
+    pub net_wm_window_type_normal: xlib::Atom,
+    pub net_wm_window_type_popup_menu: xlib::Atom,
+    pub net_wm_window_type_tooltip: xlib::Atom,
+    pub net_wm_window_type_notification: xlib::Atom,
+    pub net_wm_window_type_dnd: xlib::Atom,
+    pub net_workarea: xlib::Atom,
+    pub net_current_desktop: xlib::Atom,
+    pub edid: xlib::Atom,
+    pub net_wm_moveresize: xlib::Atom,
+}
+
+const ATOM_NAMES: [&str; 29] = [
+    "WM_PROTOCOLS",
+    "WM_DELETE_WINDOW",
+    "WM_STATE",
+    "_NET_WM_PING",
+    "_NET_WM_NAME",
+    "UTF8_STRING",
+    "_MOTIF_WM_HINTS",
+    "_NET_ACTIVE_WINDOW",
+    "_NET_FRAME_EXTENTS",
+    "_NET_WM_USER_TIME",
+    "_NET_WM_WINDOW_OPACITY",
+    "_NET_WM_STATE",
+    "_NET_WM_STATE_MAXIMIZED_HORZ",
+    "_NET_WM_STATE_MAXIMIZED_VERT",
+    "_NET_WM_STATE_HIDDEN",
+    "_NET_WM_STATE_FULLSCREEN",
+    "_NET_WM_STATE_DEMANDS_ATTENTION",
+    "_NET_WM_STATE_ABOVE",
+    "_NET_WM_STATE_SKIP_TASKBAR",
+    "_NET_WM_WINDOW_TYPE",
+    "_NET_WM_WINDOW_TYPE_NORMAL",
+    "_NET_WM_WINDOW_TYPE_POPUP_MENU",
+    "_NET_WM_WINDOW_TYPE_TOOLTIP",
+    "_NET_WM_WINDOW_TYPE_NOTIFICATION",
+    "_NET_WM_WINDOW_TYPE_DND",
+    "_NET_WORKAREA",
+    "_NET_CURRENT_DESKTOP",
+    "EDID",
+    "_NET_WM_MOVERESIZE",
+];
+
+/** The _NET_WM_STATE client message actions from the EWMH spec */
+pub const NET_WM_STATE_REMOVE: c_long = 0;
+pub const NET_WM_STATE_ADD: c_long = 1;
+
+/** The _NET_WM_MOVERESIZE directions from the EWMH spec. The borders go clockwise from the top left corner. */
+const NET_WM_MOVERESIZE_SIZE_TOPLEFT: c_long = 0;
+const NET_WM_MOVERESIZE_SIZE_TOP: c_long = 1;
+const NET_WM_MOVERESIZE_SIZE_TOPRIGHT: c_long = 2;
+const NET_WM_MOVERESIZE_SIZE_RIGHT: c_long = 3;
+const NET_WM_MOVERESIZE_SIZE_BOTTOMRIGHT: c_long = 4;
+const NET_WM_MOVERESIZE_SIZE_BOTTOM: c_long = 5;
+const NET_WM_MOVERESIZE_SIZE_BOTTOMLEFT: c_long = 6;
+const NET_WM_MOVERESIZE_SIZE_LEFT: c_long = 7;
+const NET_WM_MOVERESIZE_MOVE: c_long = 8;
+
+/** WM_STATE value of an iconified window. Xlib.h does not define it. */
+pub const ICONIC_STATE: c_long = 3;
+
+/** Millimeters per inch, for turning the physical size of an output into a DPI */
+const MM_PER_INCH: f32 = 25.4;
+
+/** The most X errors take_x_errors hands out; older ones are dropped when more arrive */
+const MAX_QUEUED_X_ERRORS: usize = 64;
+
+type XGetErrorTextFn = unsafe extern "C" fn(*mut xlib::Display, c_int, *mut c_char, c_int) -> c_int;
+
+type XIOErrorExitHandler = unsafe extern "C" fn(*mut xlib::Display, *mut c_void);
+type XSetIOErrorExitHandlerFn =
+    unsafe extern "C" fn(*mut xlib::Display, Option<XIOErrorExitHandler>, *mut c_void);
+
+/**
+ * XGetErrorText of the Xlib that installed the error handler. X11Display::open sets it every time, since Xlib may
+ * have been unloaded and loaded again at another address since the last display was opened. The handler must not
+ * load Xlib itself.
+ */
+static X_GET_ERROR_TEXT: Mutex<Option<XGetErrorTextFn>> = Mutex::new(None);
+
+/** The errors on_x_error caught that take_x_errors has not handed out yet */
+static X_ERRORS: Mutex<VecDeque<X11Error>> = Mutex::new(VecDeque::new());
+
+/** The addresses of the open displays whose connection on_x_io_error saw fail */
+static LOST_DISPLAYS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+/** An error the X server sent for a request, as caught by the error handler */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct X11Error {
+    pub error_code: u8,
+    /** The major opcode of the request that failed */
+    pub request_code: u8,
+    pub minor_code: u8,
+    /** The window, pixmap or other resource the request was about */
+    pub resource_id: xlib::XID,
+    pub serial: c_ulong,
+    /** The description XGetErrorText gives for the error code */
+    pub text: String,
+}
+
+impl fmt::Display for X11Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "X error: {} (request {}, resource {:#x})",
+            self.text, self.request_code, self.resource_id
+        )
+    }
+}
+
+impl Error for X11Error {}
+
+/**
+ * Takes the X errors caught since the last call, oldest first. They come from every open display. Most of them come
+ * from racing the window manager, like configuring a window it just destroyed, and are harmless, so
+ * X11Application::poll_events only logs them as warnings.
+ */
+pub fn take_x_errors() -> Vec<X11Error> {
+    X_ERRORS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .drain(..)
+        .collect()
+}
+
+/** The X error handler only queues the error for take_x_errors; Xlib's default one would exit the process */
+unsafe extern "C" fn on_x_error(
+    display: *mut xlib::Display,
+    error_event: *mut xlib::XErrorEvent,
+) -> c_int {
+    let error_event = &*error_event;
+    let x_get_error_text = *X_GET_ERROR_TEXT
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let text = match x_get_error_text {
+        Some(x_get_error_text) => {
+            let mut buffer = [0 as c_char; 256];
+            x_get_error_text(
+                display,
+                error_event.error_code as c_int,
+                buffer.as_mut_ptr(),
+                buffer.len() as c_int,
+            );
+            CStr::from_ptr(buffer.as_ptr())
+                .to_string_lossy()
+                .into_owned()
+        }
+        None => format!("error code {}", error_event.error_code),
+    };
+    let mut errors = X_ERRORS.lock().unwrap_or_else(|err| err.into_inner());
+    if errors.len() == MAX_QUEUED_X_ERRORS {
+        errors.pop_front();
+    }
+    errors.push_back(X11Error {
+        error_code: error_event.error_code,
+        request_code: error_event.request_code,
+        minor_code: error_event.minor_code,
+        resource_id: error_event.resourceid,
+        serial: error_event.serial,
+        text,
+    });
+    0
+}
+
+/**
+ * The X IO error handler, called when the connection to the server fails. It marks the display lost, so the
+ * application can return an error instead of calling into the dead connection again.
+ */
+unsafe extern "C" fn on_x_io_error(display: *mut xlib::Display) -> c_int {
+    let mut lost_displays = LOST_DISPLAYS.lock().unwrap_or_else(|err| err.into_inner());
+    if !lost_displays.contains(&(display as usize)) {
+        lost_displays.push(display as usize);
+    }
+    0
+}
+
+/**
+ * Called by Xlib after on_x_io_error. Xlib's own exit handler ends the process; returning from this one leaves the
+ * display marked as failed instead, so every later call on it returns at once.
+ */
+unsafe extern "C" fn on_x_io_error_exit(_display: *mut xlib::Display, _user_data: *mut c_void) {}
+
+/**
+ * XSetIOErrorExitHandler, which Xlib has had since 1.7. x11-dl does not bind it, so it is looked up in the libX11
+ * that is already loaded. Older Xlibs do not have it and still exit the process after on_x_io_error.
+ */
+fn get_x_set_io_error_exit_handler() -> Option<XSetIOErrorExitHandlerFn> {
+    unsafe {
+        let handle = libc::dlopen(c"libX11.so.6".as_ptr(), libc::RTLD_LAZY | libc::RTLD_NOLOAD);
+        if handle.is_null() {
+            return None;
+        }
+        let symbol = libc::dlsym(handle, c"XSetIOErrorExitHandler".as_ptr());
+        // Only drops the reference taken above; x11-dl keeps the library loaded
+        libc::dlclose(handle);
+        if symbol.is_null() {
+            None
+        } else {
+            Some(mem::transmute::<*mut c_void, XSetIOErrorExitHandlerFn>(
+                symbol,
+            ))
+        }
+    }
+}
+
+/** A connection to the X server that is shared by the application, its windows and the cursor */
+pub struct X11Display {
+    pub xlib: xlib::Xlib,
+    pub display: *mut xlib::Display,
+    pub screen: c_int,
+    pub root: xlib::Window,
+    pub atoms: X11Atoms,
+    /** libXrandr, None if it could not be loaded or the server does not have the extension */
+    pub xrandr: Option<xrandr::Xrandr>,
+    /** The type of the first XRandR event. Its events are numbered from here. */
+    pub xrandr_event_base: c_int,
+}
+
+impl fmt::Debug for X11Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X11Display")
+            .field("display", &self.display)
+            .field("screen", &self.screen)
+            .field("root", &self.root)
+            .field("atoms", &self.atoms)
+            .field("has_xrandr", &self.xrandr.is_some())
+            .finish()
+    }
+}
+
+impl X11Display {
+    /** Connects to the display named by $DISPLAY */
+    pub fn open() -> io::Result<X11Display> {
+        let xlib =
+            xlib::Xlib::open().map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))?;
+        unsafe {
+            let display = (xlib.XOpenDisplay)(ptr::null());
+            if display.is_null() {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    "could not open the X display",
+                ));
+            }
+            *X_GET_ERROR_TEXT
+                .lock()
+                .unwrap_or_else(|err| err.into_inner()) = Some(xlib.XGetErrorText);
+            (xlib.XSetErrorHandler)(Some(on_x_error));
+            (xlib.XSetIOErrorHandler)(Some(on_x_io_error));
+            if let Some(x_set_io_error_exit_handler) = get_x_set_io_error_exit_handler() {
+                x_set_io_error_exit_handler(display, Some(on_x_io_error_exit), ptr::null_mut());
+            }
+
+            let screen = (xlib.XDefaultScreen)(display);
+            let root = (xlib.XRootWindow)(display, screen);
+
+            let names: Vec<CString> = ATOM_NAMES
+                .iter()
+                .map(|name| CString::new(*name).unwrap())
+                .collect();
+            let mut name_ptrs: Vec<*mut c_char> = names
+                .iter()
+                .map(|name| name.as_ptr() as *mut c_char)
+                .collect();
+            let mut atoms = [0 as xlib::Atom; ATOM_NAMES.len()];
+            (xlib.XInternAtoms)(
+                display,
+                name_ptrs.as_mut_ptr(),
+                name_ptrs.len() as c_int,
+                xlib::False,
+                atoms.as_mut_ptr(),
+            );
+
+            let mut xrandr_event_base = 0;
+            let xrandr = xrandr::Xrandr::open().ok().filter(|xrandr| {
+                let mut error_base = 0;
+                (xrandr.XRRQueryExtension)(display, &mut xrandr_event_base, &mut error_base) != 0
+            });
+
+            Ok(X11Display {
+                xlib,
+                display,
+                screen,
+                root,
+                xrandr,
+                xrandr_event_base,
+                atoms: X11Atoms {
+                    wm_protocols: atoms[0],
+                    wm_delete_window: atoms[1],
+                    wm_state: atoms[2],
+                    net_wm_ping: atoms[3],
+                    net_wm_name: atoms[4],
+                    utf8_string: atoms[5],
+                    motif_wm_hints: atoms[6],
+                    net_active_window: atoms[7],
+                    net_frame_extents: atoms[8],
+                    net_wm_user_time: atoms[9],
+                    net_wm_window_opacity: atoms[10],
+                    net_wm_state: atoms[11],
+                    net_wm_state_maximized_horz: atoms[12],
+                    net_wm_state_maximized_vert: atoms[13],
+                    net_wm_state_hidden: atoms[14],
+                    net_wm_state_fullscreen: atoms[15],
+                    net_wm_state_demands_attention: atoms[16],
+                    net_wm_state_above: atoms[17],
+                    net_wm_state_skip_taskbar: atoms[18],
+                    net_wm_window_type: atoms[19],
+                    net_wm_window_type_normal: atoms[20],
+                    net_wm_window_type_popup_menu: atoms[21],
+                    net_wm_window_type_tooltip: atoms[22],
+                    net_wm_window_type_notification: atoms[23],
+                    net_wm_window_type_dnd: atoms[24],
+                    net_workarea: atoms[25],
+                    net_current_desktop: atoms[26],
+                    edid: atoms[27],
+                    net_wm_moveresize: atoms[28],
+                },
+            })
+        }
+    }
+    /** Whether the connection to the X server failed. Nothing can be read from or sent to it any more. */
+    pub fn is_connection_lost(&self) -> bool {
+        LOST_DISPLAYS
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .contains(&(self.display as usize))
+    }
+    /** The error the event loop returns once the connection is lost */
+    pub fn get_connection_lost_error(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            "the connection to the X server was lost",
+        )
+    }
+    pub fn flush(&self) {
+        unsafe {
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+    /** The size of the default screen in pixels */
+    pub fn get_screen_size(&self) -> (i32, i32) {
+        unsafe {
+            (
+                (self.xlib.XDisplayWidth)(self.display, self.screen),
+                (self.xlib.XDisplayHeight)(self.display, self.screen),
+            )
+        }
+    }
+    /** The window that currently has keyboard focus, 0 if none */
+    pub fn get_input_focus(&self) -> xlib::Window {
+        let mut focus: xlib::Window = 0;
+        let mut revert_to: c_int = 0;
+        unsafe {
+            (self.xlib.XGetInputFocus)(self.display, &mut focus, &mut revert_to);
+        }
+        focus
+    }
+    /** Reads Xft.dpi from the resource database, 96 if it is not set */
+    pub fn get_dpi(&self) -> f32 {
+        self.get_xft_dpi().unwrap_or(DEFAULT_DPI as f32)
+    }
+    /** Xft.dpi from the resource database. Desktops set it to the scale the user picked. */
+    fn get_xft_dpi(&self) -> Option<f32> {
+        unsafe {
+            let resources = (self.xlib.XResourceManagerString)(self.display);
+            if resources.is_null() {
+                return None;
+            }
+            CStr::from_ptr(resources)
+                .to_string_lossy()
+                .lines()
+                .find_map(|line| {
+                    line.strip_prefix("Xft.dpi:")
+                        .and_then(|value| value.trim().parse::<f32>().ok())
+                })
+        }
+    }
+    /**
+     * The work area of the current desktop from _NET_WORKAREA. EWMH only gives one rect for the whole screen, so
+     * a panel on one monitor of several is not accounted for.
+     */
+    fn get_net_work_area(&self) -> Option<PlatformRect> {
+        let current_desktop = self
+            .get_long_property(self.root, self.atoms.net_current_desktop, xlib::XA_CARDINAL)
+            .first()
+            .map_or(0, |&desktop| desktop as usize);
+        let work_areas =
+            self.get_long_property(self.root, self.atoms.net_workarea, xlib::XA_CARDINAL);
+        let work_area = work_areas
+            .chunks_exact(4)
+            .nth(current_desktop)
+            .or_else(|| work_areas.chunks_exact(4).next())?;
+        let (x, y, width, height) = (
+            work_area[0] as i32,
+            work_area[1] as i32,
+            work_area[2] as i32,
+            work_area[3] as i32,
+        );
+        Some(PlatformRect::new(x, y, x + width, y + height))
+    }
+    /**
+     * Describes every output that is lit, using XRandR. Without XRandR the whole screen is one monitor. The work
+     * area of a monitor is the part of _NET_WORKAREA that is on it, and its DPI is Xft.dpi when the desktop set
+     * one, else the one its physical size gives.
+     */
+    pub fn get_monitor_info(&self) -> Vec<MonitorInfo> {
+        let net_work_area = self.get_net_work_area();
+        let xft_dpi = self.get_xft_dpi();
+        let mut monitor_info = self.get_xrandr_monitor_info();
+        if monitor_info.is_empty() {
+            let (width, height) = self.get_screen_size();
+            monitor_info.push(MonitorInfo {
+                name: "Screen".to_string(),
+                id: format!("Screen{}", self.screen),
+                native_width: width,
+                native_height: height,
+                is_primary: true,
+                display_rect: PlatformRect::new(0, 0, width, height),
+                ..MonitorInfo::default()
+            });
+        }
+        for monitor in monitor_info.iter_mut() {
+            monitor.work_area = net_work_area
+                .and_then(|work_area| work_area.intersect(&monitor.display_rect))
+                .unwrap_or(monitor.display_rect);
+            monitor.dpi = match xft_dpi {
+                Some(dpi) => dpi.round() as i32,
+                None if monitor.physical_width_mm > 0 => {
+                    // The physical size is for the unrotated panel
+                    let width = match monitor.rotation {
+                        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                            monitor.display_rect.get_height()
+                        }
+                        _ => monitor.display_rect.get_width(),
+                    };
+                    (width as f32 * MM_PER_INCH / monitor.physical_width_mm as f32).round() as i32
+                }
+                None => DEFAULT_DPI,
+            };
+        }
+        monitor_info
+    }
+    fn get_xrandr_monitor_info(&self) -> Vec<MonitorInfo> {
+        let Some(xrandr) = &self.xrandr else {
+            return vec![];
+        };
+        let mut monitor_info = vec![];
+        unsafe {
+            let resources = (xrandr.XRRGetScreenResourcesCurrent)(self.display, self.root);
+            if resources.is_null() {
+                return monitor_info;
+            }
+            let primary_output = (xrandr.XRRGetOutputPrimary)(self.display, self.root);
+            let outputs =
+                slice::from_raw_parts((*resources).outputs, (*resources).noutput as usize);
+            let modes = slice::from_raw_parts((*resources).modes, (*resources).nmode as usize);
+            for &output in outputs {
+                let output_info = (xrandr.XRRGetOutputInfo)(self.display, resources, output);
+                if output_info.is_null() {
+                    continue;
+                }
+                if (*output_info).connection == xrandr::RR_Connected as u16
+                    && (*output_info).crtc != 0
+                {
+                    let crtc_info =
+                        (xrandr.XRRGetCrtcInfo)(self.display, resources, (*output_info).crtc);
+                    if !crtc_info.is_null() {
+                        let name = String::from_utf8_lossy(slice::from_raw_parts(
+                            (*output_info).name as *const u8,
+                            (*output_info).nameLen as usize,
+                        ))
+                        .into_owned();
+                        let mut info = MonitorInfo {
+                            id: name.clone(),
+                            name,
+                            is_primary: output == primary_output,
+                            physical_width_mm: (*output_info).mm_width as i32,
+                            physical_height_mm: (*output_info).mm_height as i32,
+                            display_rect: PlatformRect::new(
+                                (*crtc_info).x,
+                                (*crtc_info).y,
+                                (*crtc_info).x + (*crtc_info).width as i32,
+                                (*crtc_info).y + (*crtc_info).height as i32,
+                            ),
+                            rotation: get_rotation((*crtc_info).rotation),
+                            ..MonitorInfo::default()
+                        };
+                        if let Some(mode) = modes.iter().find(|mode| mode.id == (*crtc_info).mode) {
+                            info.refresh_rate = get_refresh_rate(mode);
+                        }
+                        // The first preferred mode is the panel's native resolution
+                        let output_modes = slice::from_raw_parts(
+                            (*output_info).modes,
+                            (*output_info).nmode as usize,
+                        );
+                        if let Some(native_mode) = output_modes
+                            .first()
+                            .filter(|_| (*output_info).npreferred > 0)
+                            .and_then(|&native_mode| {
+                                modes.iter().find(|mode| mode.id == native_mode)
+                            })
+                        {
+                            info.native_width = native_mode.width as i32;
+                            info.native_height = native_mode.height as i32;
+                        } else {
+                            info.native_width = info.display_rect.get_width();
+                            info.native_height = info.display_rect.get_height();
+                        }
+                        if let Some(edid) = self.get_output_edid(xrandr, output) {
+                            info.apply_edid(&edid);
+                        }
+                        monitor_info.push(info);
+                        (xrandr.XRRFreeCrtcInfo)(crtc_info);
+                    }
+                }
+                (xrandr.XRRFreeOutputInfo)(output_info);
+            }
+            (xrandr.XRRFreeScreenResources)(resources);
+        }
+        monitor_info
+    }
+    /** Asks for the events that is_display_change_event looks for: XRandR changes and root property changes */
+    pub fn select_display_change_input(&self) {
+        unsafe {
+            if let Some(xrandr) = &self.xrandr {
+                (xrandr.XRRSelectInput)(
+                    self.display,
+                    self.root,
+                    xrandr::RRScreenChangeNotifyMask
+                        | xrandr::RRCrtcChangeNotifyMask
+                        | xrandr::RROutputChangeNotifyMask,
+                );
+            }
+            (self.xlib.XSelectInput)(self.display, self.root, xlib::PropertyChangeMask);
+        }
+    }
+    /**
+     * Whether the event says the monitors or the work area changed: an output was plugged in or out, a CRTC
+     * changed mode, position or rotation, or the window manager updated _NET_WORKAREA.
+     */
+    pub fn is_display_change_event(&self, event: &xlib::XEvent) -> bool {
+        let event_type = event.get_type();
+        if event_type == xlib::PropertyNotify {
+            let property_event = unsafe { event.property };
+            return property_event.window == self.root
+                && (property_event.atom == self.atoms.net_workarea
+                    || property_event.atom == self.atoms.net_current_desktop);
+        }
+        match &self.xrandr {
+            Some(xrandr) if event_type == self.xrandr_event_base + xrandr::RRScreenChangeNotify => {
+                // Lets Xlib's idea of the screen size catch up
+                let mut event = *event;
+                unsafe { (xrandr.XRRUpdateConfiguration)(&mut event) };
+                true
+            }
+            Some(_) => event_type == self.xrandr_event_base + xrandr::RRNotify,
+            None => false,
+        }
+    }
+    /** Reads the EDID the driver publishes as an output property, if there is one and it parses */
+    fn get_output_edid(&self, xrandr: &xrandr::Xrandr, output: xrandr::RROutput) -> Option<Edid> {
+        let mut actual_type: xlib::Atom = 0;
+        let mut actual_format: c_int = 0;
+        let mut num_items: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = ptr::null_mut();
+        unsafe {
+            let status = (xrandr.XRRGetOutputProperty)(
+                self.display,
+                output,
+                self.atoms.edid,
+                0,
+                // In 32-bit units, enough for the base block and three extensions
+                128,
+                xlib::False,
+                xlib::False,
+                xlib::AnyPropertyType as xlib::Atom,
+                &mut actual_type,
+                &mut actual_format,
+                &mut num_items,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
+            }
+            let edid = if actual_format == 8 {
+                Edid::parse(slice::from_raw_parts(data, num_items as usize)).ok()
+            } else {
+                None
+            };
+            (self.xlib.XFree)(data as *mut _);
+            edid
+        }
+    }
+    /** Sends a 32-bit client message to the root window, the way EWMH asks clients to talk to the window manager */
+    pub fn send_client_message_to_root(
+        &self,
+        window: xlib::Window,
+        message_type: xlib::Atom,
+        data: [c_long; 5],
+    ) {
+        unsafe {
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            event.client_message.type_ = xlib::ClientMessage;
+            event.client_message.window = window;
+            event.client_message.message_type = message_type;
+            event.client_message.format = 32;
+            for (index, value) in data.iter().enumerate() {
+                event.client_message.data.set_long(index, *value);
+            }
+            (self.xlib.XSendEvent)(
+                self.display,
+                self.root,
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+        }
+    }
+    /** Asks the window manager to add or remove up to two _NET_WM_STATE atoms */
+    pub fn send_net_wm_state(
+        &self,
+        window: xlib::Window,
+        add: bool,
+        first: xlib::Atom,
+        second: xlib::Atom,
+    ) {
+        let action = if add {
+            NET_WM_STATE_ADD
+        } else {
+            NET_WM_STATE_REMOVE
+        };
+        // Source indication 1 means "normal application"
+        self.send_client_message_to_root(
+            window,
+            self.atoms.net_wm_state,
+            [action, first as c_long, second as c_long, 1, 0],
+        );
+    }
+    /**
+     * Hands a drag that started with a button press on a custom title bar or border over to the window manager,
+     * which moves or resizes the window until the button is released. Returns false for the zones that neither
+     * move nor resize, which the application handles itself.
+     */
+    pub fn begin_move_resize(
+        &self,
+        window: xlib::Window,
+        zone: WindowZone,
+        x_root: c_int,
+        y_root: c_int,
+        button: c_uint,
+    ) -> bool {
+        let direction = match zone {
+            WindowZone::TitleBar => NET_WM_MOVERESIZE_MOVE,
+            WindowZone::TopLeftBorder => NET_WM_MOVERESIZE_SIZE_TOPLEFT,
+            WindowZone::TopBorder => NET_WM_MOVERESIZE_SIZE_TOP,
+            WindowZone::TopRightBorder => NET_WM_MOVERESIZE_SIZE_TOPRIGHT,
+            WindowZone::RightBorder => NET_WM_MOVERESIZE_SIZE_RIGHT,
+            WindowZone::BottomRightBorder => NET_WM_MOVERESIZE_SIZE_BOTTOMRIGHT,
+            WindowZone::BottomBorder => NET_WM_MOVERESIZE_SIZE_BOTTOM,
+            WindowZone::BottomLeftBorder => NET_WM_MOVERESIZE_SIZE_BOTTOMLEFT,
+            WindowZone::LeftBorder => NET_WM_MOVERESIZE_SIZE_LEFT,
+            _ => return false,
+        };
+        unsafe {
+            // The press gave us an implicit pointer grab, which the window manager needs to take over the drag
+            (self.xlib.XUngrabPointer)(self.display, xlib::CurrentTime);
+        }
+        // Source indication 1 means "normal application"
+        self.send_client_message_to_root(
+            window,
+            self.atoms.net_wm_moveresize,
+            [
+                x_root as c_long,
+                y_root as c_long,
+                direction,
+                button as c_long,
+                1,
+            ],
+        );
+        self.flush();
+        true
+    }
+    /** Reads a 32-bit property. Xlib hands format 32 data back as longs. */
+    pub fn get_long_property(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+        property_type: xlib::Atom,
+    ) -> Vec<c_long> {
+        let mut actual_type: xlib::Atom = 0;
+        let mut actual_format: c_int = 0;
+        let mut num_items: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = ptr::null_mut();
+        unsafe {
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                window,
+                property,
+                0,
+                1024,
+                xlib::False,
+                property_type,
+                &mut actual_type,
+                &mut actual_format,
+                &mut num_items,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != xlib::Success as c_int || data.is_null() {
+                return vec![];
+            }
+            let values = if actual_format == 32 {
+                slice::from_raw_parts(data as *const c_long, num_items as usize).to_vec()
+            } else {
+                vec![]
+            };
+            (self.xlib.XFree)(data as *mut _);
+            values
+        }
+    }
+    /** Replaces a 32-bit property */
+    pub fn set_long_property(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+        property_type: xlib::Atom,
+        values: &[c_long],
+    ) {
+        unsafe {
+            (self.xlib.XChangeProperty)(
+                self.display,
+                window,
+                property,
+                property_type,
+                32,
+                xlib::PropModeReplace,
+                values.as_ptr() as *const c_uchar,
+                values.len() as c_int,
+            );
+        }
+    }
+    /** Whether the window's _NET_WM_STATE contains the atom */
+    pub fn has_net_wm_state(&self, window: xlib::Window, state: xlib::Atom) -> bool {
+        self.get_long_property(window, self.atoms.net_wm_state, xlib::XA_ATOM)
+            .iter()
+            .any(|&atom| atom as xlib::Atom == state)
+    }
+}
+
+fn get_rotation(rotation: xrandr::Rotation) -> DisplayRotation {
+    match rotation as c_int & 0xF {
+        xrandr::RR_Rotate_90 => DisplayRotation::Rotate90,
+        xrandr::RR_Rotate_180 => DisplayRotation::Rotate180,
+        xrandr::RR_Rotate_270 => DisplayRotation::Rotate270,
+        _ => DisplayRotation::Rotate0,
+    }
+}
+
+/** The vertical refresh rate of a mode, from its pixel clock and total size the way xrandr(1) computes it */
+fn get_refresh_rate(mode: &xrandr::XRRModeInfo) -> f32 {
+    let mut v_total = mode.vTotal as f64;
+    if mode.modeFlags as c_int & xrandr::RR_DoubleScan != 0 {
+        v_total *= 2.0;
+    }
+    if mode.modeFlags as c_int & xrandr::RR_Interlace != 0 {
+        v_total /= 2.0;
+    }
+    if mode.hTotal == 0 || v_total == 0.0 {
+        return 0.0;
+    }
+    (mode.dotClock as f64 / (mode.hTotal as f64 * v_total)) as f32
+}
+
+impl Drop for X11Display {
+    fn drop(&mut self) {
+        unsafe {
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+        // Another display may be opened at the same address
+        LOST_DISPLAYS
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .retain(|&display| display != self.display as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_error_handler_marks_only_that_display_lost() {
+        // Never dereferenced, the handler only records the address
+        let display = 0x1000 as *mut xlib::Display;
+        let other_display = 0x2000 as *mut xlib::Display;
+        unsafe {
+            assert_eq!(on_x_io_error(display), 0);
+            on_x_io_error(display);
+        }
+        let lost_displays = LOST_DISPLAYS.lock().unwrap().clone();
+        assert_eq!(
+            lost_displays
+                .iter()
+                .filter(|&&lost| lost == display as usize)
+                .count(),
+            1
+        );
+        assert!(!lost_displays.contains(&(other_display as usize)));
+        LOST_DISPLAYS
+            .lock()
+            .unwrap()
+            .retain(|&lost| lost != display as usize);
+    }
+
+    #[test]
+    fn finds_the_io_error_exit_handler_of_the_loaded_xlib() {
+        // Like the backend, do nothing when Xlib is not installed
+        let Ok(_xlib) = xlib::Xlib::open() else {
+            return;
+        };
+        let has_exit_handler = unsafe {
+            let handle = libc::dlopen(c"libX11.so.6".as_ptr(), libc::RTLD_LAZY | libc::RTLD_NOLOAD);
+            let has_exit_handler = !handle.is_null()
+                && !libc::dlsym(handle, c"XSetIOErrorExitHandler".as_ptr()).is_null();
+            if !handle.is_null() {
+                libc::dlclose(handle);
+            }
+            has_exit_handler
+        };
+        assert_eq!(
+            get_x_set_io_error_exit_handler().is_some(),
+            has_exit_handler
+        );
+    }
+}
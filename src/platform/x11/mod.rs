@@ -0,0 +1,4 @@
+pub mod application;
+pub mod cursor;
+pub mod display;
+pub mod window;
@@ -0,0 +1,724 @@
+use crate::generic::{
+    window::{GenericWindow, WindowDrawAttentionRequestType, WindowMode},
+    window_definition::{WindowActivationPolicy, WindowDefinition, WindowType},
+    window_hierarchy::{self, WindowHierarchy},
+};
+use crate::x11::display::{X11Display, ICONIC_STATE};
+use std::{
+    cell::Cell,
+    ffi::CString,
+    os::raw::{c_int, c_long, c_uint, c_void},
+    ptr,
+    rc::Rc,
+};
+use x11_dl::xlib;
+
+/** _MOTIF_WM_HINTS flags and values. Most window managers still honor these for decorations. */
+const MWM_HINTS_FUNCTIONS: c_long = 1 << 0;
+const MWM_HINTS_DECORATIONS: c_long = 1 << 1;
+const MWM_FUNC_RESIZE: c_long = 1 << 1;
+const MWM_FUNC_MOVE: c_long = 1 << 2;
+const MWM_FUNC_MINIMIZE: c_long = 1 << 3;
+const MWM_FUNC_MAXIMIZE: c_long = 1 << 4;
+const MWM_FUNC_CLOSE: c_long = 1 << 5;
+const MWM_DECOR_ALL: c_long = 1 << 0;
+
+/** A top-level X11 window. The geometry is cached from ConfigureNotify so queries don't need a round trip. */
+#[derive(Debug)]
+pub struct X11Window {
+    display: Rc<X11Display>,
+    handle: xlib::Window,
+    x: Cell<i32>,
+    y: Cell<i32>,
+    width: Cell<i32>,
+    height: Cell<i32>,
+    /** Client rect to return to when leaving the maximized or fullscreen state */
+    restored_rect: Cell<(i32, i32, i32, i32)>,
+    window_mode: Cell<WindowMode>,
+    is_visible: Cell<bool>,
+    is_first_time_visible: Cell<bool>,
+    is_enabled: Cell<bool>,
+    is_destroyed: Cell<bool>,
+    native_window_buttons_visible: Cell<bool>,
+    dpi_scale_factor: Cell<f32>,
+    handle_manual_dpi_changes: Cell<bool>,
+    window_definitions: Rc<WindowDefinition>,
+    hierarchy: WindowHierarchy,
+}
+
+impl X11Window {
+    pub fn new(display: &Rc<X11Display>, definition: &Rc<WindowDefinition>) -> X11Window {
+        let x = definition.x_desired_position_on_screen as i32;
+        let y = definition.y_desired_position_on_screen as i32;
+        let width = (definition.width_desired_on_screen as i32).max(1);
+        let height = (definition.height_desired_on_screen as i32).max(1);
+
+        let xlib = &display.xlib;
+        let handle = unsafe {
+            let mut attributes: xlib::XSetWindowAttributes = std::mem::zeroed();
+            attributes.event_mask = xlib::ExposureMask
+                | xlib::StructureNotifyMask
+                | xlib::FocusChangeMask
+                | xlib::PropertyChangeMask
+                | xlib::KeyPressMask
+                | xlib::KeyReleaseMask
+                | xlib::ButtonPressMask
+                | xlib::ButtonReleaseMask
+                | xlib::PointerMotionMask
+                | xlib::EnterWindowMask
+                | xlib::LeaveWindowMask;
+            // Windows that draw their own border and are transient bypass the window manager entirely, like WS_POPUP
+            attributes.override_redirect = if X11Window::is_override_redirect(definition) {
+                xlib::True
+            } else {
+                xlib::False
+            };
+            (xlib.XCreateWindow)(
+                display.display,
+                display.root,
+                x,
+                y,
+                width as c_uint,
+                height as c_uint,
+                0,
+                xlib::CopyFromParent,
+                xlib::InputOutput as c_uint,
+                ptr::null_mut(),
+                xlib::CWEventMask | xlib::CWOverrideRedirect,
+                &mut attributes,
+            )
+        };
+
+        let window = X11Window {
+            display: display.clone(),
+            handle,
+            x: Cell::new(x),
+            y: Cell::new(y),
+            width: Cell::new(width),
+            height: Cell::new(height),
+            restored_rect: Cell::new((x, y, width, height)),
+            window_mode: Cell::new(WindowMode::Windowed),
+            is_visible: Cell::new(false),
+            is_first_time_visible: Cell::new(true),
+            is_enabled: Cell::new(true),
+            is_destroyed: Cell::new(false),
+            native_window_buttons_visible: Cell::new(true),
+            dpi_scale_factor: Cell::new(display.get_dpi() / 96.0),
+            handle_manual_dpi_changes: Cell::new(definition.manual_dpi),
+            window_definitions: definition.clone(),
+            hierarchy: WindowHierarchy::new(),
+        };
+        window.initialize();
+        window
+    }
+    fn is_override_redirect(definition: &WindowDefinition) -> bool {
+        !definition.has_os_window_border
+            && matches!(
+                definition.window_type,
+                WindowType::Menu | WindowType::ToolTip | WindowType::CursorDecorator
+            )
+    }
+    /** Sets up the properties the window manager reads before the window is first mapped */
+    fn initialize(&self) {
+        let windef = &self.window_definitions;
+        let display = &self.display;
+        let atoms = &display.atoms;
+        unsafe {
+            let mut protocols = [atoms.wm_delete_window, atoms.net_wm_ping];
+            (display.xlib.XSetWMProtocols)(
+                display.display,
+                self.handle,
+                protocols.as_mut_ptr(),
+                protocols.len() as c_int,
+            );
+
+            let wm_hints = (display.xlib.XAllocWMHints)();
+            if !wm_hints.is_null() {
+                (*wm_hints).flags = xlib::InputHint;
+                (*wm_hints).input = if windef.accepts_input {
+                    xlib::True
+                } else {
+                    xlib::False
+                };
+                (display.xlib.XSetWMHints)(display.display, self.handle, wm_hints);
+                (display.xlib.XFree)(wm_hints as *mut _);
+            }
+        }
+
+        let window_type = match windef.window_type {
+            WindowType::Normal | WindowType::GameWindow => atoms.net_wm_window_type_normal,
+            WindowType::Menu => atoms.net_wm_window_type_popup_menu,
+            WindowType::ToolTip => atoms.net_wm_window_type_tooltip,
+            WindowType::Notification => atoms.net_wm_window_type_notification,
+            WindowType::CursorDecorator => atoms.net_wm_window_type_dnd,
+        };
+        display.set_long_property(
+            self.handle,
+            atoms.net_wm_window_type,
+            xlib::XA_ATOM,
+            &[window_type as c_long],
+        );
+
+        // Before mapping, the window manager reads _NET_WM_STATE straight from the property
+        let mut states = vec![];
+        if windef.is_topmost_window {
+            states.push(atoms.net_wm_state_above as c_long);
+        }
+        if !windef.appears_in_taskbar {
+            states.push(atoms.net_wm_state_skip_taskbar as c_long);
+        }
+        display.set_long_property(self.handle, atoms.net_wm_state, xlib::XA_ATOM, &states);
+
+        self.update_motif_hints();
+        self.update_size_hints();
+
+        let mut title = windef.title.encode_utf16().collect::<Vec<u16>>();
+        self.set_text(&mut title);
+        if windef.opacity < 1.0 {
+            self.set_opacity(windef.opacity);
+        }
+    }
+    fn update_motif_hints(&self) {
+        let windef = &self.window_definitions;
+        let mut functions = MWM_FUNC_MOVE;
+        if windef.has_sizing_frame {
+            functions |= MWM_FUNC_RESIZE;
+        }
+        if self.native_window_buttons_visible.get() {
+            if windef.has_close_button {
+                functions |= MWM_FUNC_CLOSE;
+            }
+            if windef.supports_minimize {
+                functions |= MWM_FUNC_MINIMIZE;
+            }
+            if windef.supports_maximize {
+                functions |= MWM_FUNC_MAXIMIZE;
+            }
+        }
+        let decorations =
+            if windef.has_os_window_border && self.window_mode.get() == WindowMode::Windowed {
+                MWM_DECOR_ALL
+            } else {
+                0
+            };
+        let hints: [c_long; 5] = [
+            MWM_HINTS_FUNCTIONS | MWM_HINTS_DECORATIONS,
+            functions,
+            decorations,
+            0,
+            0,
+        ];
+        let motif_wm_hints = self.display.atoms.motif_wm_hints;
+        self.display
+            .set_long_property(self.handle, motif_wm_hints, motif_wm_hints, &hints);
+    }
+    /** Publishes the size limits, aspect ratio and requested position in WM_NORMAL_HINTS */
+    fn update_size_hints(&self) {
+        let windef = &self.window_definitions;
+        let size_limits = &windef.size_limits;
+        unsafe {
+            let size_hints = (self.display.xlib.XAllocSizeHints)();
+            if size_hints.is_null() {
+                return;
+            }
+            (*size_hints).flags = xlib::PPosition | xlib::USPosition;
+            (*size_hints).x = self.x.get();
+            (*size_hints).y = self.y.get();
+
+            if !windef.has_sizing_frame {
+                (*size_hints).flags |= xlib::PMinSize | xlib::PMaxSize;
+                (*size_hints).min_width = self.width.get();
+                (*size_hints).min_height = self.height.get();
+                (*size_hints).max_width = self.width.get();
+                (*size_hints).max_height = self.height.get();
+            } else {
+                if size_limits.get_min_width().is_some() || size_limits.get_min_height().is_some() {
+                    (*size_hints).flags |= xlib::PMinSize;
+                    (*size_hints).min_width = size_limits.get_min_width().unwrap_or(1.0) as c_int;
+                    (*size_hints).min_height = size_limits.get_min_height().unwrap_or(1.0) as c_int;
+                }
+                if size_limits.get_max_width().is_some() || size_limits.get_max_height().is_some() {
+                    (*size_hints).flags |= xlib::PMaxSize;
+                    (*size_hints).max_width =
+                        size_limits.get_max_width().unwrap_or(i32::MAX as f32) as c_int;
+                    (*size_hints).max_height =
+                        size_limits.get_max_height().unwrap_or(i32::MAX as f32) as c_int;
+                }
+            }
+            if windef.should_preserve_aspect_ratio {
+                (*size_hints).flags |= xlib::PAspect;
+                (*size_hints).min_aspect.x = self.width.get();
+                (*size_hints).min_aspect.y = self.height.get();
+                (*size_hints).max_aspect = (*size_hints).min_aspect;
+            }
+
+            (self.display.xlib.XSetWMNormalHints)(self.display.display, self.handle, size_hints);
+            (self.display.xlib.XFree)(size_hints as *mut _);
+        }
+    }
+    fn set_urgency_hint(&self, urgent: bool) {
+        unsafe {
+            let wm_hints = (self.display.xlib.XGetWMHints)(self.display.display, self.handle);
+            if wm_hints.is_null() {
+                return;
+            }
+            if urgent {
+                (*wm_hints).flags |= xlib::XUrgencyHint;
+            } else {
+                (*wm_hints).flags &= !xlib::XUrgencyHint;
+            }
+            (self.display.xlib.XSetWMHints)(self.display.display, self.handle, wm_hints);
+            (self.display.xlib.XFree)(wm_hints as *mut _);
+        }
+    }
+    /** Adds or removes _NET_WM_STATE atoms. Unmapped windows are not managed yet, so the property is written directly. */
+    fn change_net_wm_state(&self, add: bool, first: xlib::Atom, second: xlib::Atom) {
+        let display = &self.display;
+        if self.is_visible.get() {
+            display.send_net_wm_state(self.handle, add, first, second);
+        } else {
+            let mut states: Vec<c_long> =
+                display.get_long_property(self.handle, display.atoms.net_wm_state, xlib::XA_ATOM);
+            for atom in [first, second] {
+                if atom == 0 {
+                    continue;
+                }
+                states.retain(|&state| state as xlib::Atom != atom);
+                if add {
+                    states.push(atom as c_long);
+                }
+            }
+            display.set_long_property(
+                self.handle,
+                display.atoms.net_wm_state,
+                xlib::XA_ATOM,
+                &states,
+            );
+        }
+        display.flush();
+    }
+    /** _NET_FRAME_EXTENTS as (left, right, top, bottom), zeroes if the window manager does not publish it */
+    fn get_frame_extents(&self) -> (i32, i32, i32, i32) {
+        let extents = self.display.get_long_property(
+            self.handle,
+            self.display.atoms.net_frame_extents,
+            xlib::XA_CARDINAL,
+        );
+        if extents.len() == 4 {
+            (
+                extents[0] as i32,
+                extents[1] as i32,
+                extents[2] as i32,
+                extents[3] as i32,
+            )
+        } else {
+            (0, 0, 0, 0)
+        }
+    }
+    fn save_restored_rect(&self) {
+        if !self.is_maximized()
+            && !self.is_minimized()
+            && self.window_mode.get() == WindowMode::Windowed
+        {
+            self.restored_rect.set((
+                self.x.get(),
+                self.y.get(),
+                self.width.get(),
+                self.height.get(),
+            ));
+        }
+    }
+    pub fn get_handle(&self) -> xlib::Window {
+        self.handle
+    }
+    /**
+     * Marks the window as owned by parent. Window managers keep transient windows above their owner and minimize
+     * them along with it.
+     */
+    pub fn set_transient_for(&self, parent: &X11Window) {
+        unsafe {
+            (self.display.xlib.XSetTransientForHint)(
+                self.display.display,
+                self.handle,
+                parent.handle,
+            );
+        }
+    }
+    /** The size of the client area from the last ConfigureNotify */
+    pub fn get_size(&self) -> (i32, i32) {
+        (self.width.get(), self.height.get())
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled.get()
+    }
+    pub fn is_destroyed(&self) -> bool {
+        self.is_destroyed.get()
+    }
+    /** Updates the cached geometry from a ConfigureNotify. Returns whether the window moved and whether it was resized. */
+    pub fn on_configure(&self, x: i32, y: i32, width: i32, height: i32) -> (bool, bool) {
+        let moved = x != self.x.get() || y != self.y.get();
+        let resized = width != self.width.get() || height != self.height.get();
+        self.x.set(x);
+        self.y.set(y);
+        self.width.set(width);
+        self.height.set(height);
+        self.save_restored_rect();
+        (moved, resized)
+    }
+    /** Called when the X server tells us the window is gone, whether or not we destroyed it */
+    pub fn on_destroyed(&self) {
+        self.is_destroyed.set(true);
+        self.is_visible.set(false);
+    }
+    /** Called on UnmapNotify. Returns true if the window was minimized rather than hidden by us. */
+    pub fn on_unmapped(&self) -> bool {
+        self.is_visible.get() && !self.is_destroyed.get()
+    }
+}
+
+impl GenericWindow for X11Window {
+    fn reshape_window(
+        &self,
+        new_x: &mut i32,
+        new_y: &mut i32,
+        new_width: &mut i32,
+        new_height: &mut i32,
+    ) {
+        if self.is_maximized() {
+            self.restore();
+        }
+        *new_width = (*new_width).max(1);
+        *new_height = (*new_height).max(1);
+        unsafe {
+            (self.display.xlib.XMoveResizeWindow)(
+                self.display.display,
+                self.handle,
+                *new_x,
+                *new_y,
+                *new_width as c_uint,
+                *new_height as c_uint,
+            );
+        }
+        self.display.flush();
+    }
+    fn get_fullscreen_info(
+        &self,
+        x: &mut i32,
+        y: &mut i32,
+        width: &mut i32,
+        height: &mut i32,
+    ) -> bool {
+        let (screen_width, screen_height) = self.display.get_screen_size();
+        *x = 0;
+        *y = 0;
+        *width = screen_width;
+        *height = screen_height;
+        true
+    }
+    fn move_window_to(&self, x: &mut i32, y: &mut i32) {
+        unsafe {
+            (self.display.xlib.XMoveWindow)(self.display.display, self.handle, *x, *y);
+        }
+        self.display.flush();
+    }
+    fn bring_to_front(&self, force: bool) {
+        if self.is_minimized() && self.window_definitions.is_regular_window {
+            self.restore();
+        }
+        unsafe {
+            (self.display.xlib.XRaiseWindow)(self.display.display, self.handle);
+        }
+        // Override-redirect windows are not stacked by the window manager, so their transient hint does nothing
+        window_hierarchy::bring_children_to_front(self);
+        if force {
+            self.set_window_focus();
+        }
+        self.display.flush();
+    }
+    fn destroy(&self) {
+        if !self.is_destroyed.get() {
+            unsafe {
+                (self.display.xlib.XDestroyWindow)(self.display.display, self.handle);
+            }
+            self.on_destroyed();
+            self.display.flush();
+        }
+    }
+    fn minimize(&self) {
+        self.save_restored_rect();
+        unsafe {
+            (self.display.xlib.XIconifyWindow)(
+                self.display.display,
+                self.handle,
+                self.display.screen,
+            );
+        }
+        // Not every window manager iconifies transient windows with their owner
+        window_hierarchy::minimize_children(self);
+        self.display.flush();
+    }
+    fn maximize(&self) {
+        self.save_restored_rect();
+        if self.is_minimized() {
+            self.restore();
+        }
+        let atoms = &self.display.atoms;
+        self.change_net_wm_state(
+            true,
+            atoms.net_wm_state_maximized_horz,
+            atoms.net_wm_state_maximized_vert,
+        );
+    }
+    fn restore(&self) {
+        // Like SW_RESTORE, a minimized window goes back to being maximized if it was maximized before
+        if self.is_minimized() {
+            unsafe {
+                (self.display.xlib.XMapWindow)(self.display.display, self.handle);
+            }
+            window_hierarchy::restore_children(self);
+            self.set_window_focus();
+            self.display.flush();
+        } else if self.is_maximized() {
+            let atoms = &self.display.atoms;
+            self.change_net_wm_state(
+                false,
+                atoms.net_wm_state_maximized_horz,
+                atoms.net_wm_state_maximized_vert,
+            );
+        }
+    }
+    fn show(&self) {
+        if !self.is_visible.get() {
+            self.is_visible.set(true);
+
+            // Do not activate windows that do not take input; e.g. tool-tips and cursor decorators
+            let windef = &self.window_definitions;
+            let mut should_activate = false;
+            if windef.accepts_input {
+                should_activate = windef.activation_policy == WindowActivationPolicy::Always;
+                if self.is_first_time_visible.get()
+                    && windef.activation_policy == WindowActivationPolicy::FirstShown
+                {
+                    should_activate = true;
+                }
+            }
+            self.is_first_time_visible.set(false);
+
+            // A user time of zero tells EWMH window managers not to give the window focus when it is mapped
+            if !should_activate {
+                self.display.set_long_property(
+                    self.handle,
+                    self.display.atoms.net_wm_user_time,
+                    xlib::XA_CARDINAL,
+                    &[0],
+                );
+            }
+            unsafe {
+                if should_activate {
+                    (self.display.xlib.XMapRaised)(self.display.display, self.handle);
+                } else {
+                    (self.display.xlib.XMapWindow)(self.display.display, self.handle);
+                }
+            }
+            self.display.flush();
+        }
+    }
+    fn hide(&self) {
+        if self.is_visible.get() {
+            self.is_visible.set(false);
+            unsafe {
+                (self.display.xlib.XWithdrawWindow)(
+                    self.display.display,
+                    self.handle,
+                    self.display.screen,
+                );
+            }
+            self.display.flush();
+        }
+    }
+    fn set_window_mode(&self, new_window_mode: WindowMode) {
+        if new_window_mode == self.window_mode.get() {
+            return;
+        }
+        let fullscreen = self.display.atoms.net_wm_state_fullscreen;
+        if new_window_mode == WindowMode::Windowed {
+            self.window_mode.set(new_window_mode);
+            self.change_net_wm_state(false, fullscreen, 0);
+            self.update_motif_hints();
+        } else {
+            self.save_restored_rect();
+            self.window_mode.set(new_window_mode);
+            // X11 has no exclusive mode. Both fullscreen modes cover the screen without decorations.
+            self.update_motif_hints();
+            self.change_net_wm_state(true, fullscreen, 0);
+        }
+    }
+    fn get_window_mode(&self) -> WindowMode {
+        self.window_mode.get()
+    }
+    fn is_maximized(&self) -> bool {
+        let atoms = &self.display.atoms;
+        self.display
+            .has_net_wm_state(self.handle, atoms.net_wm_state_maximized_horz)
+            && self
+                .display
+                .has_net_wm_state(self.handle, atoms.net_wm_state_maximized_vert)
+    }
+    fn is_minimized(&self) -> bool {
+        let wm_state = self.display.atoms.wm_state;
+        let state = self
+            .display
+            .get_long_property(self.handle, wm_state, wm_state);
+        state.first() == Some(&ICONIC_STATE)
+            || self
+                .display
+                .has_net_wm_state(self.handle, self.display.atoms.net_wm_state_hidden)
+    }
+    fn is_visible(&self) -> bool {
+        self.is_visible.get()
+    }
+    fn get_restored_dimensions(
+        &self,
+        x: &mut i32,
+        y: &mut i32,
+        width: &mut i32,
+        height: &mut i32,
+    ) -> bool {
+        self.save_restored_rect();
+        let (restored_x, restored_y, restored_width, restored_height) = self.restored_rect.get();
+        *x = restored_x;
+        *y = restored_y;
+        *width = restored_width;
+        *height = restored_height;
+        true
+    }
+    fn set_window_focus(&self) {
+        if !self.is_visible.get() {
+            return;
+        }
+        // Override-redirect windows are invisible to the window manager, so they have to take focus themselves
+        if X11Window::is_override_redirect(&self.window_definitions) {
+            unsafe {
+                (self.display.xlib.XSetInputFocus)(
+                    self.display.display,
+                    self.handle,
+                    xlib::RevertToParent,
+                    xlib::CurrentTime,
+                );
+            }
+        } else {
+            self.display.send_client_message_to_root(
+                self.handle,
+                self.display.atoms.net_active_window,
+                [1, xlib::CurrentTime as c_long, 0, 0, 0],
+            );
+        }
+        self.display.flush();
+    }
+    fn set_opacity(&self, opacity: f32) {
+        let opacity = (opacity.clamp(0.0, 1.0) as f64 * u32::MAX as f64) as u32;
+        self.display.set_long_property(
+            self.handle,
+            self.display.atoms.net_wm_window_opacity,
+            xlib::XA_CARDINAL,
+            &[opacity as c_long],
+        );
+        self.display.flush();
+    }
+    fn enable(&self, enable: bool) {
+        self.is_enabled.set(enable);
+    }
+    fn is_point_in_window(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width.get() && y < self.height.get()
+    }
+    fn get_window_border_size(&self) -> u32 {
+        if !self.window_definitions.has_os_window_border {
+            return 0;
+        }
+        let (left, _, _, _) = self.get_frame_extents();
+        left as u32
+    }
+    fn get_window_title_bar_size(&self) -> i32 {
+        if !self.window_definitions.has_os_window_border {
+            return 0;
+        }
+        let (left, _, top, _) = self.get_frame_extents();
+        (top - left).max(0)
+    }
+    fn get_os_window_handle(&self) -> *const c_void {
+        self.handle as *const c_void
+    }
+    fn is_foreground_window(&self) -> bool {
+        self.display.get_input_focus() == self.handle
+    }
+    fn is_fullscreen_supported(&self) -> bool {
+        true
+    }
+    fn set_text(&self, text: &mut Vec<u16>) {
+        let len = text.iter().position(|&c| c == 0).unwrap_or(text.len());
+        let title = String::from_utf16_lossy(&text[..len]);
+        let title_bytes = title.replace('\0', "").into_bytes();
+        let display = &self.display;
+        unsafe {
+            // WM_NAME for old window managers, _NET_WM_NAME for everything that understands UTF-8
+            if let Ok(c_title) = CString::new(title_bytes.clone()) {
+                (display.xlib.XStoreName)(display.display, self.handle, c_title.as_ptr());
+            }
+            (display.xlib.XChangeProperty)(
+                display.display,
+                self.handle,
+                display.atoms.net_wm_name,
+                display.atoms.utf8_string,
+                8,
+                xlib::PropModeReplace,
+                title_bytes.as_ptr(),
+                title_bytes.len() as c_int,
+            );
+        }
+        display.flush();
+    }
+    fn get_definition(&self) -> &Rc<WindowDefinition> {
+        &self.window_definitions
+    }
+    fn is_definition_valid(&self) -> bool {
+        true
+    }
+    fn adjust_cached_size(&self, size: &mut (i32, i32)) {
+        *size = (self.width.get(), self.height.get());
+    }
+    fn get_dpi_scale_factor(&self) -> f32 {
+        self.dpi_scale_factor.get()
+    }
+    fn set_dpi_scale_factor(&self, factor: f32) {
+        self.dpi_scale_factor.set(factor);
+    }
+    fn is_manual_manage_dpi_change(&self) -> bool {
+        self.handle_manual_dpi_changes.get()
+    }
+    fn set_manual_manage_dpi_change(&self, manual_dpi_changes: bool) {
+        self.handle_manual_dpi_changes.set(manual_dpi_changes);
+    }
+    fn draw_attention(&self, parameters: WindowDrawAttentionRequestType) {
+        let demands_attention = self.display.atoms.net_wm_state_demands_attention;
+        match parameters {
+            WindowDrawAttentionRequestType::UntilActivated => {
+                if !self.is_foreground_window() {
+                    self.set_urgency_hint(true);
+                    self.change_net_wm_state(true, demands_attention, 0);
+                }
+            }
+            WindowDrawAttentionRequestType::Stop => {
+                self.set_urgency_hint(false);
+                self.change_net_wm_state(false, demands_attention, 0);
+            }
+        }
+    }
+    fn set_native_window_buttons_visibility(&self, visible: bool) {
+        self.native_window_buttons_visible.set(visible);
+        self.update_motif_hints();
+        self.display.flush();
+    }
+    fn get_hierarchy(&self) -> &WindowHierarchy {
+        &self.hierarchy
+    }
+}